@@ -76,12 +76,37 @@ impl ApiClient {
         &self,
         request: &CreateMessageRequest,
     ) -> Result<MessageStream, ApiError> {
-        let url = format!("{}/v1/messages", self.base_url);
-        let headers = self.build_headers()?;
-
         let body = serde_json::to_string(request).map_err(|e| ApiError::BadRequest {
             message: format!("Failed to serialize request: {e}"),
         })?;
+        let response = self.post("/v1/messages", body).await?;
+        Ok(MessageStream::new(response.bytes_stream()))
+    }
+
+    /// Count the input tokens `request` would use, without sending it to the
+    /// model. Covers the system prompt, tool definitions and messages.
+    pub async fn count_tokens(&self, request: &CreateMessageRequest) -> Result<u64, ApiError> {
+        #[derive(serde::Deserialize)]
+        struct CountResponse {
+            input_tokens: u64,
+        }
+
+        let body = count_tokens_body(request)?;
+        let response = self.post("/v1/messages/count_tokens", body).await?;
+        let text = response
+            .text()
+            .await
+            .map_err(|e| ApiError::Network(e.to_string()))?;
+        serde_json::from_str::<CountResponse>(&text)
+            .map(|r| r.input_tokens)
+            .map_err(|e| ApiError::StreamParse(format!("Invalid count_tokens response: {e}")))
+    }
+
+    /// POST `body` to `path`, retrying transient errors. Returns the
+    /// successful response.
+    async fn post(&self, path: &str, body: String) -> Result<reqwest::Response, ApiError> {
+        let url = format!("{}{path}", self.base_url);
+        let headers = self.build_headers()?;
         let body_bytes = bytes::Bytes::from(body);
 
         for attempt in 0..=self.retry_config.max_retries {
@@ -103,7 +128,7 @@ impl ApiClient {
                 Ok(response) => {
                     let status = response.status();
                     if status.is_success() {
-                        return Ok(MessageStream::new(response.bytes_stream()));
+                        return Ok(response);
                     }
 
                     let retry_after = parse_retry_after(response.headers());
@@ -149,6 +174,19 @@ impl ApiClient {
     }
 }
 
+/// The count_tokens endpoint accepts only the fields that affect the input,
+/// and rejects the rest (`max_tokens`, `stream`, ...).
+fn count_tokens_body(request: &CreateMessageRequest) -> Result<String, ApiError> {
+    const FIELDS: &[&str] = &["model", "messages", "system", "tools", "thinking"];
+    let mut value = serde_json::to_value(request).map_err(|e| ApiError::BadRequest {
+        message: format!("Failed to serialize request: {e}"),
+    })?;
+    if let Some(map) = value.as_object_mut() {
+        map.retain(|key, _| FIELDS.contains(&key.as_str()));
+    }
+    Ok(value.to_string())
+}
+
 /// Parse the `retry-after` header value as seconds and convert to milliseconds.
 fn parse_retry_after(headers: &HeaderMap) -> Option<u64> {
     headers
@@ -212,6 +250,27 @@ mod tests {
         assert_eq!(parse_retry_after(&headers), None);
     }
 
+    #[test]
+    fn count_tokens_body_drops_generation_fields() {
+        let request = CreateMessageRequest {
+            model: "claude-sonnet-4-5".into(),
+            max_tokens: 1024,
+            messages: vec![],
+            system: None,
+            tools: None,
+            stop_sequences: None,
+            temperature: Some(1.0),
+            thinking: None,
+            stream: true,
+        };
+        let body: serde_json::Value =
+            serde_json::from_str(&count_tokens_body(&request).unwrap()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({"model": "claude-sonnet-4-5", "messages": []})
+        );
+    }
+
     #[test]
     fn parse_retry_after_invalid() {
         let mut headers = HeaderMap::new();
@@ -40,6 +40,13 @@ impl Provider for AnthropicProvider {
         })
     }
 
+    fn count_tokens<'a>(
+        &'a self,
+        request: &'a CreateMessageRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<u64>, ApiError>> + Send + 'a>> {
+        Box::pin(async move { self.client.count_tokens(request).await.map(Some) })
+    }
+
     fn name(&self) -> &str {
         "anthropic"
     }
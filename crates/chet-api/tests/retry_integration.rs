@@ -290,3 +290,26 @@ async fn test_no_retry_on_401() {
         "should have made only 1 request (no retry)"
     );
 }
+
+/// count_tokens shares the retry path and returns the counted input tokens.
+#[tokio::test]
+#[ignore]
+async fn test_count_tokens_retries_then_counts() {
+    let body = r#"{"input_tokens":1234}"#;
+    let ok = format!(
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         \r\n\
+         {}",
+        body.len(),
+        body
+    );
+    let (base_url, counter) = start_test_server(vec![http_500_response(), ok]).await;
+
+    let client = make_client(&base_url);
+    let tokens = client.count_tokens(&test_request()).await.unwrap();
+    assert_eq!(tokens, 1234);
+    assert_eq!(counter.load(Ordering::SeqCst), 2);
+}
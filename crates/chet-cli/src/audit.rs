@@ -0,0 +1,118 @@
+//! `chet audit` — query the permission audit log.
+
+use anyhow::Result;
+use chet_config::ChetConfig;
+use chet_permissions::{AuditEntry, AuditQuery};
+use std::path::Path;
+
+/// Filters and output options for `chet audit`.
+#[derive(clap::Args)]
+pub(crate) struct AuditArgs {
+    /// Only entries from sessions whose ID starts with this prefix
+    #[arg(long)]
+    session: Option<String>,
+    /// Only entries for this tool
+    #[arg(long)]
+    tool: Option<String>,
+    /// Only entries with this decision (permit, block, prompt, approved,
+    /// denied, allow_once, always_allow, deny, ...)
+    #[arg(long)]
+    decision: Option<String>,
+    /// Search every project, not just the current one
+    #[arg(long)]
+    all_projects: bool,
+    /// Show at most this many of the most recent entries (0 = all)
+    #[arg(long, default_value_t = 50)]
+    limit: usize,
+    /// Print raw JSONL instead of a table
+    #[arg(long)]
+    json: bool,
+}
+
+pub(crate) async fn run(config: &ChetConfig, cwd: &Path, args: &AuditArgs) -> Result<()> {
+    let project = if args.all_projects {
+        None
+    } else {
+        Some(crate::runner::project_id(cwd).await)
+    };
+    let query = AuditQuery {
+        project,
+        session: args.session.clone(),
+        tool: args.tool.clone(),
+        decision: args.decision.clone(),
+    };
+    let entries = chet_permissions::audit::query(&config.config_dir.join("audit"), &query)?;
+    let skip = if args.limit == 0 {
+        0
+    } else {
+        entries.len().saturating_sub(args.limit)
+    };
+
+    if entries.is_empty() {
+        eprintln!("No matching audit entries.");
+        return Ok(());
+    }
+    for entry in &entries[skip..] {
+        if args.json {
+            println!("{}", serde_json::to_string(entry)?);
+        } else {
+            println!("{}", format_entry(entry));
+        }
+    }
+    Ok(())
+}
+
+/// One table row: time, session, kind, tool, decision, input, then details.
+fn format_entry(entry: &AuditEntry) -> String {
+    let session = entry
+        .session
+        .as_deref()
+        .map(|s| chet_types::truncate_str(s, 8))
+        .unwrap_or("-");
+    let mut line = format!(
+        "{}  {session:<8}  {:<10}  {:<12}  {:<12}",
+        entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        entry.kind.as_str(),
+        entry.tool.as_deref().unwrap_or("-"),
+        entry.decision,
+    );
+    if let Some(input) = &entry.input {
+        line.push_str(&format!("  {input}"));
+    }
+    let details: Vec<String> = [
+        entry.event.as_ref().map(|e| format!("event: {e}")),
+        entry.source.as_ref().map(|s| format!("by: {s}")),
+        entry.rule.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !details.is_empty() {
+        line.push_str(&format!("  [{}]", details.join("; ")));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chet_permissions::{AuditKind, AuditLog};
+
+    #[test]
+    fn format_entry_includes_details() {
+        // Entries are only written on append
+        let log = AuditLog::new(Path::new("/nonexistent"), "proj");
+        log.set_session("0123456789abcdef");
+        let mut entry = log.entry(AuditKind::Permission, "block");
+        entry.tool = Some("Bash".into());
+        entry.input = Some("command: rm -rf /".into());
+        entry.source = Some("config".into());
+        entry.rule = Some("rule: Bash(command:rm *) -> block".into());
+
+        let line = format_entry(&entry);
+        assert!(line.contains("01234567  permission"), "{line}");
+        assert!(line.contains("Bash"));
+        assert!(line.contains("command: rm -rf /"));
+        assert!(line.ends_with("[by: config; rule: Bash(command:rm *) -> block]"));
+    }
+}
@@ -1,7 +1,7 @@
 //! Slash command dispatch and handlers.
 
 use chet_permissions::PermissionEngine;
-use chet_session::{MemoryManager, Session, SessionStore, compact};
+use chet_session::{MemoryManager, MemoryScope, Session, SessionStore, compact};
 use chet_terminal::StatusLine;
 use chrono::Utc;
 use std::sync::{Arc, Mutex};
@@ -13,6 +13,8 @@ pub(crate) enum SlashResult {
     Continue,
     Break,
     Unknown,
+    /// Continue, with this text pre-filled in the editor.
+    Prefill(String),
 }
 
 pub(crate) async fn handle_slash_command(
@@ -22,6 +24,7 @@ pub(crate) async fn handle_slash_command(
     let CommandContext {
         session,
         store,
+        agent,
         context_tracker,
         system_prompt,
         mcp_manager,
@@ -29,6 +32,8 @@ pub(crate) async fn handle_slash_command(
         project_id,
         status_line,
         hooks_engine,
+        listed_sessions,
+        color,
     } = ctx;
     if !input.starts_with('/') {
         return None;
@@ -58,6 +63,10 @@ pub(crate) async fn handle_slash_command(
             handle_mcp_command(args, mcp_manager).await;
             Some(SlashResult::Continue)
         }
+        "/hooks" => {
+            handle_hooks_command(hooks_engine);
+            Some(SlashResult::Continue)
+        }
         "/memory" => {
             handle_memory_command(args, memory_manager, project_id, status_line).await;
             Some(SlashResult::Continue)
@@ -68,7 +77,18 @@ pub(crate) async fn handle_slash_command(
             Some(SlashResult::Continue)
         }
         "/context" => {
-            let info = context_tracker.estimate(&session.messages, Some(system_prompt));
+            // An exact count, where the provider supports it, calibrates the estimate
+            if !session.messages.is_empty() {
+                match agent.count_tokens(&session.messages).await {
+                    Ok(Some(tokens)) => {
+                        context_tracker.calibrate(&session.messages, Some(system_prompt), tokens)
+                    }
+                    Ok(None) => {}
+                    Err(e) => tracing::debug!("Token count failed, using estimate: {e}"),
+                }
+            }
+            let mut info = context_tracker.estimate(&session.messages, Some(system_prompt));
+            info.reclaimed_tokens = session.micro_compaction.tokens_reclaimed;
             eprintln!("{}", context_tracker.format_detailed(&info));
             Some(SlashResult::Continue)
         }
@@ -81,14 +101,40 @@ pub(crate) async fn handle_slash_command(
             Some(SlashResult::Continue)
         }
         "/sessions" => {
-            handle_sessions_list(store).await;
+            handle_sessions(args.unwrap_or(""), session, store, listed_sessions, color).await;
+            Some(SlashResult::Continue)
+        }
+        "/rewind" => Some(handle_rewind(args.unwrap_or(""), session, store).await),
+        "/fork" => {
+            let parent = session.short_id();
+            *session = session.fork();
+            if let Some(label) = args.filter(|a| !a.is_empty()) {
+                session.metadata.label = Some(label.to_string());
+            }
+            if let Err(e) = store.save(session).await {
+                eprintln!("Warning: failed to save session: {e}");
+            }
+            eprintln!(
+                "Forked session {parent} as {}. /resume {parent} returns to the original.",
+                session.short_id()
+            );
+            Some(SlashResult::Continue)
+        }
+        "/export" => {
+            handle_export(args.unwrap_or(""), session);
             Some(SlashResult::Continue)
         }
         "/resume" => {
-            if let Some(prefix) = args {
-                handle_resume(session, store, prefix).await;
-            } else {
-                eprintln!("Usage: /resume <session-id-prefix>");
+            match args {
+                // A number picks from the last /sessions listing or search
+                Some(arg) => match arg.parse::<usize>() {
+                    Ok(n) if (1..=listed_sessions.len()).contains(&n) => {
+                        let id = listed_sessions[n - 1].clone();
+                        handle_resume(session, store, &id).await;
+                    }
+                    _ => handle_resume(session, store, arg).await,
+                },
+                None => eprintln!("Usage: /resume <session-id-prefix | number from /sessions>"),
             }
             Some(SlashResult::Continue)
         }
@@ -160,36 +206,61 @@ async fn handle_compact(
     }
 }
 
-async fn handle_sessions_list(store: &SessionStore) {
+/// `/sessions [--here] [query]`: list saved sessions, or search them.
+async fn handle_sessions(
+    args: &str,
+    session: &Session,
+    store: &SessionStore,
+    listed: &mut Vec<String>,
+    color: bool,
+) {
+    let mut here = false;
+    let mut query = Vec::new();
+    for word in args.split_whitespace() {
+        match word {
+            "--here" => here = true,
+            _ => query.push(word),
+        }
+    }
+    let in_scope = |cwd: &str| !here || cwd == session.metadata.cwd;
+
+    if !query.is_empty() {
+        let hits = match store.search(&query.join(" ")).await {
+            Ok(hits) => hits,
+            Err(e) => {
+                eprintln!("Failed to search sessions: {e}");
+                return;
+            }
+        };
+        let hits: Vec<_> = hits
+            .into_iter()
+            .filter(|hit| in_scope(&hit.summary.cwd))
+            .collect();
+        if hits.is_empty() {
+            eprintln!("No matching sessions.");
+            return;
+        }
+        for (i, hit) in hits.iter().enumerate() {
+            eprintln!("{}", crate::sessions::format_hit(i + 1, hit, color));
+        }
+        *listed = hits.iter().map(|hit| hit.summary.id.to_string()).collect();
+        eprintln!("\nUse /resume <number> to open one.");
+        return;
+    }
+
     match store.list().await {
         Ok(summaries) => {
+            let summaries: Vec<_> = summaries.into_iter().filter(|s| in_scope(&s.cwd)).collect();
             if summaries.is_empty() {
                 eprintln!("No saved sessions.");
                 return;
             }
             eprintln!("Saved sessions:");
-            for s in &summaries {
-                let label = s.label.as_deref().unwrap_or("");
-                let label_str = if label.is_empty() {
-                    String::new()
-                } else {
-                    format!(" [{label}]")
-                };
-                let model_short = chet_terminal::statusline::shorten_model_name(&s.model);
-                eprintln!(
-                    "  {} {:>8}  {:>3} msgs  {}{}  {}",
-                    s.short_id(),
-                    s.age(),
-                    s.message_count,
-                    model_short,
-                    label_str,
-                    if s.preview.is_empty() {
-                        "(empty)"
-                    } else {
-                        &s.preview
-                    }
-                );
+            let tree = crate::sessions::fork_tree(&summaries);
+            for (i, (depth, s)) in tree.iter().enumerate() {
+                eprintln!("{}", crate::sessions::format_summary(i + 1, *depth, s));
             }
+            *listed = tree.iter().map(|(_, s)| s.id.to_string()).collect();
         }
         Err(e) => {
             eprintln!("Failed to list sessions: {e}");
@@ -197,6 +268,83 @@ async fn handle_sessions_list(store: &SessionStore) {
     }
 }
 
+/// `/rewind` lists the prompts; `/rewind <n> [--drop]` removes prompt `n` and
+/// everything after it, putting the prompt back in the editor unless `--drop`.
+async fn handle_rewind(args: &str, session: &mut Session, store: &SessionStore) -> SlashResult {
+    let prompts = session.user_prompts();
+    if prompts.is_empty() {
+        eprintln!("Nothing to rewind.");
+        return SlashResult::Continue;
+    }
+    let mut words = args.split_whitespace();
+    let n = match words.next().map(str::parse::<usize>) {
+        Some(Ok(n)) if (1..=prompts.len()).contains(&n) => n,
+        None => {
+            eprintln!("Prompts:");
+            for (n, (_, text)) in prompts.iter().enumerate() {
+                let line = text.lines().next().unwrap_or("");
+                eprintln!("  {:>2}. {}", n + 1, chet_types::truncate_str(line, 72));
+            }
+            eprintln!(
+                "\nUse /rewind <n> to go back to before prompt n; it is put back in the editor (--drop to discard it)."
+            );
+            return SlashResult::Continue;
+        }
+        Some(_) => {
+            eprintln!("Usage: /rewind [1-{}] [--drop]", prompts.len());
+            return SlashResult::Continue;
+        }
+    };
+    let drop = words.any(|w| w == "--drop");
+
+    let (index, _) = prompts[n - 1];
+    let removed = session.messages.len() - index;
+    let Some(prompt) = session.rewind_to(index) else {
+        return SlashResult::Continue;
+    };
+    if let Err(e) = store.save(session).await {
+        eprintln!("Warning: failed to save session: {e}");
+    }
+    eprintln!(
+        "Rewound {removed} messages; {} remaining.",
+        session.messages.len()
+    );
+    if drop {
+        SlashResult::Continue
+    } else {
+        SlashResult::Prefill(prompt)
+    }
+}
+
+/// `/export [md|html|json] [--redact] [path]`: write the session to a file,
+/// by default `chet-<id>.<ext>` in the working directory.
+fn handle_export(args: &str, session: &Session) {
+    let mut format = chet_session::ExportFormat::Markdown;
+    let mut redact = false;
+    let mut path = None;
+    for word in args.split_whitespace() {
+        if word == "--redact" {
+            redact = true;
+        } else if let Ok(f) = word.parse() {
+            format = f;
+        } else {
+            path = Some(std::path::PathBuf::from(word));
+        }
+    }
+    let path = path.unwrap_or_else(|| {
+        std::path::Path::new(&session.metadata.cwd).join(format!(
+            "chet-{}.{}",
+            session.short_id(),
+            format.extension()
+        ))
+    });
+    let rendered = crate::sessions::export(session, format, redact);
+    match std::fs::write(&path, rendered) {
+        Ok(()) => eprintln!("Exported session to {}", path.display()),
+        Err(e) => eprintln!("Failed to export session: {e}"),
+    }
+}
+
 async fn handle_resume(session: &mut Session, store: &SessionStore, prefix: &str) {
     match store.load_by_prefix(prefix).await {
         Ok(loaded) => {
@@ -232,12 +380,27 @@ async fn handle_mcp_command(args: Option<&str>, mcp_manager: &mut Option<chet_mc
         _ => {
             // Default: show status
             match mcp_manager {
-                Some(manager) if manager.client_count() > 0 => {
-                    eprintln!("MCP servers ({} connected):", manager.client_count());
-                    for (name, tool_count) in manager.server_summary() {
-                        eprintln!("  {name}: {tool_count} tools");
+                Some(manager) if manager.server_count() > 0 => {
+                    eprintln!(
+                        "MCP servers ({}/{} ready):",
+                        manager.client_count(),
+                        manager.server_count()
+                    );
+                    for status in manager.status() {
+                        let restarts = if status.restarts > 0 {
+                            format!(", {} restarts", status.restarts)
+                        } else {
+                            String::new()
+                        };
+                        eprintln!(
+                            "  {}: {} ({} tools{restarts})",
+                            status.name, status.state, status.tool_count
+                        );
+                        for line in &status.stderr_tail {
+                            eprintln!("    | {line}");
+                        }
                     }
-                    eprintln!("\nUse /mcp reconnect [name] to reconnect servers.");
+                    eprintln!("\nUse /mcp reconnect [name] to restart servers.");
                 }
                 _ => {
                     eprintln!("No MCP servers connected.");
@@ -247,6 +410,39 @@ async fn handle_mcp_command(args: Option<&str>, mcp_manager: &mut Option<chet_mc
     }
 }
 
+fn handle_hooks_command(engine: &PermissionEngine) {
+    let hooks = engine.hook_status();
+    if hooks.is_empty() {
+        eprintln!("No hooks configured.");
+        return;
+    }
+    eprintln!("Hooks ({}):", hooks.len());
+    for status in hooks {
+        let hook = &status.hook;
+        let matcher = match (&hook.tool, &hook.args) {
+            (None, None) => String::new(),
+            (tool, args) => {
+                let tool = tool.as_deref().unwrap_or("*");
+                match args {
+                    Some(args) => format!(" [{tool} {args}]"),
+                    None => format!(" [{tool}]"),
+                }
+            }
+        };
+        let managed = if status.managed { " [managed]" } else { "" };
+        let last = match &status.last_result {
+            Some(result) => format!(", last: {result}"),
+            None => String::new(),
+        };
+        eprintln!(
+            "  {}{matcher}{managed}: {} ({} matches{last})",
+            hook.event.as_str(),
+            hook.command,
+            status.matched
+        );
+    }
+}
+
 async fn handle_memory_command(
     args: Option<&str>,
     memory_manager: &MemoryManager,
@@ -255,24 +451,29 @@ async fn handle_memory_command(
 ) {
     match args {
         None => {
-            // /memory — show both
-            let global = memory_manager.load_global().await;
-            let project = match project_id {
-                Some(id) => memory_manager.load_project(id).await,
-                None => String::new(),
-            };
-            if global.is_empty() && project.is_empty() {
-                eprintln!("No memory saved yet.");
-                eprintln!("Tip: Ask the model to remember something, or use /memory edit.");
-                return;
+            // /memory — show each layer and the file it comes from
+            let mut layers = vec![("Global Memory", MemoryScope::Global)];
+            if let Some(id) = project_id {
+                layers.push(("Project Memory", MemoryScope::Project(id)));
             }
-            if !global.is_empty() {
-                eprintln!("=== Global Memory ===");
-                eprintln!("{global}");
+            if let Some(root) = memory_manager.team_root() {
+                layers.push(("Team Memory", MemoryScope::Team(root)));
             }
-            if !project.is_empty() {
-                eprintln!("=== Project Memory ===");
-                eprintln!("{project}");
+            let mut any = false;
+            for (heading, scope) in layers {
+                let path = memory_manager.path(scope);
+                let content = memory_manager.load_doc(scope).await.to_prompt();
+                eprintln!("=== {heading} ({}) ===", path.display());
+                if content.is_empty() {
+                    eprintln!("(empty)");
+                } else {
+                    any = true;
+                    eprintln!("{content}");
+                }
+            }
+            if !any {
+                eprintln!("No memory saved yet.");
+                eprintln!("Tip: Ask the model to remember something, or use /memory edit.");
             }
         }
         Some(sub) if sub == "edit" || sub == "edit global" => {
@@ -287,6 +488,14 @@ async fn handle_memory_command(
                 eprintln!("No project context available.");
             }
         }
+        Some("edit team") => {
+            if let Some(root) = memory_manager.team_root() {
+                let path = memory_manager.path(MemoryScope::Team(root));
+                open_editor(&path, status_line).await;
+            } else {
+                eprintln!("No repository available for team memory.");
+            }
+        }
         Some("reset") => {
             if let Err(e) = memory_manager.reset_global().await {
                 eprintln!("Failed to reset global memory: {e}");
@@ -318,7 +527,7 @@ async fn handle_memory_command(
         }
         Some(other) => {
             eprintln!("Unknown /memory subcommand: {other}");
-            eprintln!("Usage: /memory [edit [global|project] | reset [global|project]]");
+            eprintln!("Usage: /memory [edit [global|project|team] | reset [global|project]]");
         }
     }
 }
@@ -453,19 +662,28 @@ fn print_help() {
     eprintln!("  /help     — Show this help");
     eprintln!("  /effort   — Show or set effort level (low, medium, high, xhigh, auto)");
     eprintln!("  /plan     — Toggle plan mode (read-only exploration)");
-    eprintln!("  /mcp      — Show connected MCP servers and tools");
+    eprintln!("  /mode     — Show or set permission mode (default, acceptEdits, plan, bypass)");
+    eprintln!("  /mcp      — Show MCP server state, tools and recent stderr");
+    eprintln!("  /hooks    — List hooks with match counts and last results");
     eprintln!("  /memory   — View/edit/reset persistent memory");
     eprintln!("  /copy     — Copy last response to clipboard");
     eprintln!("  /model    — Show current model");
     eprintln!("  /cost     — Show token usage");
     eprintln!("  /context  — Show detailed context window usage");
     eprintln!("  /compact  — Compact conversation (archive + summarize)");
-    eprintln!("  /sessions — List saved sessions");
-    eprintln!("  /resume   — Resume a saved session by ID prefix");
+    eprintln!("  /sessions — List saved sessions; --here for this directory, words to search");
+    eprintln!("  /resume   — Resume a saved session by ID prefix or /sessions number");
+    eprintln!("  /export   — Export the session (md, html, json; --redact hides secrets)");
+    eprintln!("  /rewind   — Go back to before an earlier prompt");
+    eprintln!("  /fork     — Continue in a copy of this session");
     eprintln!("  /clear    — Clear conversation (starts new session)");
     eprintln!("  /quit     — Exit");
     eprintln!();
     eprintln!("Flags:");
     eprintln!("  --effort <level>       — Set effort level (low, medium, high, xhigh)");
     eprintln!("  --thinking-budget <N>  — Enable extended thinking (token budget)");
+    eprintln!("  --permission-mode <m>  — Start in a permission mode");
+    eprintln!();
+    eprintln!("Keys:");
+    eprintln!("  Shift+Tab              — Cycle permission mode");
 }
@@ -0,0 +1,213 @@
+//! `chet config show` — the effective configuration, with settings locked by
+//! managed policy marked.
+
+use chet_config::{ChetConfig, RuleFile};
+use chet_permissions::{HookConfig, OutsideWorkspace, PermissionRule};
+use std::fmt::Write;
+
+const LOCKED: &str = "[locked]";
+
+pub(crate) fn show(config: &ChetConfig) {
+    print!("{}", render(config));
+}
+
+fn render(config: &ChetConfig) -> String {
+    let managed = &config.managed;
+    let mut out = String::new();
+
+    let mut files = vec![config.config_dir.join("config.toml")];
+    if let Some(dir) = &config.project_dir {
+        files.push(RuleFile::Project.path(dir));
+        files.push(RuleFile::Local.path(dir));
+        files.push(dir.join(".mcp.json"));
+    }
+    files.retain(|f| f.exists());
+    let _ = writeln!(out, "Config files:");
+    for file in &files {
+        let _ = writeln!(out, "  {}", file.display());
+    }
+    if files.is_empty() {
+        let _ = writeln!(out, "  (none)");
+    }
+    match &managed.path {
+        Some(path) => {
+            let _ = writeln!(out, "Managed policy: {}", path.display());
+        }
+        None => {
+            let _ = writeln!(
+                out,
+                "Managed policy: none ({} not found)",
+                chet_config::managed_policy_path().display()
+            );
+        }
+    }
+    let _ = writeln!(out);
+
+    let _ = writeln!(
+        out,
+        "Model:      {}{}",
+        config.model,
+        allowlist(managed.api.allowed_models.as_deref())
+    );
+    let _ = writeln!(out, "Max tokens: {}", config.max_tokens);
+    if let Some(effort) = config.effort {
+        let _ = writeln!(out, "Effort:     {effort}");
+    }
+    if let Some(budget) = config.thinking_budget {
+        let _ = writeln!(out, "Thinking:   {budget} tokens");
+    }
+    let _ = writeln!(
+        out,
+        "Providers:  {}",
+        match &managed.api.allowed_providers {
+            Some(providers) => format!("{} {LOCKED}", providers.join(", ")),
+            None => "any".to_string(),
+        }
+    );
+    let _ = writeln!(
+        out,
+        "Ludicrous:  {}",
+        if managed.ludicrous_allowed() {
+            "allowed"
+        } else {
+            "not allowed [locked]"
+        }
+    );
+    let _ = writeln!(
+        out,
+        "Outside workspace: {}",
+        match config.outside_workspace {
+            OutsideWorkspace::Prompt => "prompt",
+            OutsideWorkspace::Block => "block",
+        }
+    );
+    for dir in &config.additional_directories {
+        let _ = writeln!(out, "  + {}", dir.display());
+    }
+    let sessions = &config.sessions;
+    let _ = writeln!(
+        out,
+        "Sessions:   {}",
+        match (sessions.retention_days, sessions.max_count) {
+            (None, None) => "kept forever".to_string(),
+            (Some(days), None) => format!("kept {days} days"),
+            (None, Some(max)) => format!("at most {max}"),
+            (Some(days), Some(max)) => format!("kept {days} days, at most {max}"),
+        }
+    );
+
+    let _ = writeln!(out, "\nPermission rules:");
+    let rules = managed
+        .permissions
+        .rules
+        .iter()
+        .map(|r| (true, r))
+        .chain(config.permission_rules.iter().map(|r| (false, r)));
+    let mut any = false;
+    for (locked, rule) in rules {
+        any = true;
+        let _ = writeln!(out, "  {}{}", lock_prefix(locked), format_rule(rule));
+    }
+    if !any {
+        let _ = writeln!(out, "  (none)");
+    }
+
+    let _ = writeln!(out, "\nHooks:");
+    let hooks = managed
+        .hooks
+        .iter()
+        .map(|h| (true, h))
+        .chain(config.hooks.iter().map(|h| (false, h)));
+    let mut any = false;
+    for (locked, hook) in hooks {
+        any = true;
+        let _ = writeln!(out, "  {}{}", lock_prefix(locked), format_hook(hook));
+    }
+    if !any {
+        let _ = writeln!(out, "  (none)");
+    }
+
+    let _ = writeln!(out, "\nMCP servers:");
+    let mut servers: Vec<(&String, &str)> = config
+        .mcp
+        .servers
+        .keys()
+        .map(|name| (name, "user"))
+        .chain(
+            config
+                .project_mcp
+                .servers
+                .keys()
+                .map(|name| (name, "project")),
+        )
+        .collect();
+    servers.sort();
+    for (name, source) in &servers {
+        let _ = writeln!(out, "  {name} ({source})");
+    }
+    if servers.is_empty() {
+        let _ = writeln!(out, "  (none)");
+    }
+    if let Some(allowed) = &managed.mcp.allowed_servers {
+        let allowed: Vec<String> = allowed
+            .iter()
+            .map(|s| format!("{} ({})", s.name, s.command))
+            .collect();
+        let _ = writeln!(out, "  allowed: {} {LOCKED}", allowed.join(", "));
+    }
+    out
+}
+
+fn lock_prefix(locked: bool) -> &'static str {
+    if locked { "[locked] " } else { "" }
+}
+
+fn allowlist(patterns: Option<&[String]>) -> String {
+    match patterns {
+        Some(patterns) => format!("  (allowed: {}) {LOCKED}", patterns.join(", ")),
+        None => String::new(),
+    }
+}
+
+/// `Bash [command:rm *] -> block`
+fn format_rule(rule: &PermissionRule) -> String {
+    match &rule.args {
+        Some(args) => format!("{} [{args}] -> {}", rule.tool, rule.level.as_str()),
+        None => format!("{} -> {}", rule.tool, rule.level.as_str()),
+    }
+}
+
+/// `before_tool [Bash]: ./check.sh`
+fn format_hook(hook: &HookConfig) -> String {
+    let matcher = match (&hook.tool, &hook.args) {
+        (None, None) => String::new(),
+        (tool, Some(args)) => format!(" [{} {args}]", tool.as_deref().unwrap_or("*")),
+        (Some(tool), None) => format!(" [{tool}]"),
+    };
+    format!("{}{matcher}: {}", hook.event.as_str(), hook.command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chet_permissions::{HookEvent, PermissionLevel};
+
+    #[test]
+    fn formats_rules_and_hooks() {
+        let rule = PermissionRule {
+            tool: "Bash".into(),
+            args: Some("command:rm *".into()),
+            level: PermissionLevel::Block,
+        };
+        assert_eq!(format_rule(&rule), "Bash [command:rm *] -> block");
+        let hook = HookConfig {
+            event: HookEvent::BeforeTool,
+            command: "./check.sh".into(),
+            timeout_ms: 5000,
+            tool: Some("Bash".into()),
+            args: None,
+        };
+        assert_eq!(format_hook(&hook), "before_tool [Bash]: ./check.sh");
+        assert_eq!(lock_prefix(true), "[locked] ");
+    }
+}
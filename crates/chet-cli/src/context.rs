@@ -1,6 +1,7 @@
 //! Context structs that bundle related parameters for CLI entry points.
 
 use chet_config::ChetConfig;
+use chet_core::Agent;
 use chet_permissions::PermissionEngine;
 use chet_session::{ContextTracker, MemoryManager, Session, SessionStore};
 use chet_terminal::StatusLine;
@@ -18,13 +19,17 @@ pub(crate) struct UIContext {
 pub(crate) struct CommandContext<'a> {
     pub session: &'a mut Session,
     pub store: &'a SessionStore,
-    pub context_tracker: &'a ContextTracker,
+    pub agent: &'a Agent,
+    pub context_tracker: &'a mut ContextTracker,
     pub system_prompt: &'a str,
     pub mcp_manager: &'a mut Option<chet_mcp::McpManager>,
     pub memory_manager: &'a MemoryManager,
     pub project_id: Option<&'a str>,
     pub status_line: &'a Option<Arc<Mutex<StatusLine>>>,
     pub hooks_engine: &'a Arc<PermissionEngine>,
+    /// Full IDs from the last `/sessions` listing, for `/resume <n>`.
+    pub listed_sessions: &'a mut Vec<String>,
+    pub color: bool,
 }
 
 /// Long-lived state for the REPL loop.
@@ -42,6 +47,7 @@ pub(crate) struct ReplContext<'a> {
 
 /// One-time startup options for REPL session initialization.
 pub(crate) struct ReplStartup {
-    pub resume_id: Option<String>,
+    /// Session picked by `--resume` or `--continue`.
+    pub resumed: Option<Session>,
     pub session_name: Option<String>,
 }
@@ -1,19 +1,25 @@
 //! Chet CLI — an AI-powered coding assistant.
 
+mod audit;
 mod commands;
+mod config;
 mod context;
+mod mcp_handler;
+mod mode;
 mod plan;
 mod prompt;
 mod prompts;
 mod repl;
 mod runner;
+mod serve;
+mod sessions;
 
 use anyhow::{Context, Result};
 use chet_api::AnthropicProvider;
 use chet_config::{ChetConfig, CliOverrides};
 use chet_core::ManagedWorktree;
-use chet_permissions::PermissionEngine;
-use chet_session::MemoryManager;
+use chet_permissions::PermissionMode;
+use chet_session::{MemoryManager, Session, SessionStore};
 use chet_types::{Effort, provider::Provider};
 use clap::Parser;
 use std::io;
@@ -26,10 +32,14 @@ struct Cli {
     #[arg(short, long)]
     print: Option<String>,
 
-    /// Resume a previous session by ID or prefix
-    #[arg(long)]
+    /// Resume a previous session by ID or prefix; without one, pick from a list
+    #[arg(long, value_name = "ID", num_args = 0..=1, default_missing_value = "")]
     resume: Option<String>,
 
+    /// Continue the most recent session started in this directory or its git root
+    #[arg(short = 'c', long = "continue", conflicts_with = "resume")]
+    continue_session: bool,
+
     /// Model to use
     #[arg(long)]
     model: Option<String>,
@@ -59,9 +69,14 @@ struct Cli {
     verbose: bool,
 
     /// Skip all permission checks — auto-permit every tool call
+    /// (same as --permission-mode bypass)
     #[arg(long)]
     ludicrous: bool,
 
+    /// Permission mode to start in: default, acceptEdits, plan, bypass
+    #[arg(long, value_name = "MODE")]
+    permission_mode: Option<PermissionMode>,
+
     /// Run in an isolated git worktree
     #[arg(long)]
     worktree: bool,
@@ -98,6 +113,42 @@ struct Cli {
 enum Commands {
     /// List configured agent profiles
     Agents,
+    /// Query the permission audit log
+    Audit(audit::AuditArgs),
+    /// Inspect the effective configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Search and export saved sessions
+    Sessions {
+        #[command(subcommand)]
+        command: sessions::SessionsCommands,
+    },
+    /// Model Context Protocol commands
+    Mcp {
+        #[command(subcommand)]
+        command: McpCommands,
+    },
+}
+
+#[derive(clap::Subcommand)]
+enum ConfigCommands {
+    /// Show the effective settings, marking those locked by managed policy
+    Show,
+}
+
+#[derive(clap::Subcommand)]
+enum McpCommands {
+    /// Run chet as an MCP server exposing its built-in tools (stdio by default)
+    Serve {
+        /// Serve over HTTP on this address instead of stdio (e.g. 127.0.0.1:8765)
+        #[arg(long, value_name = "ADDR")]
+        http: Option<String>,
+        /// Allow --http to bind a non-loopback address (exposes tools to the network)
+        #[arg(long, requires = "http")]
+        allow_remote: bool,
+    },
 }
 
 #[tokio::main]
@@ -142,26 +193,69 @@ async fn main() -> Result<()> {
     )
     .map_err(|e| anyhow::anyhow!("{e}"))?;
 
+    let permission_mode = if cli.ludicrous {
+        PermissionMode::Bypass
+    } else {
+        cli.permission_mode.unwrap_or_default()
+    };
+    let bypass = permission_mode == PermissionMode::Bypass;
+    if bypass && !config.managed.ludicrous_allowed() {
+        anyhow::bail!(
+            "{}",
+            config.managed.violation(
+                "permission_mode",
+                "--ludicrous / bypass mode is not allowed"
+            )
+        );
+    }
+
     // Handle subcommands that don't need the full agent stack
-    if let Some(Commands::Agents) = cli.command {
-        print_agents(&config);
-        return Ok(());
+    match &cli.command {
+        Some(Commands::Agents) => {
+            print_agents(&config);
+            return Ok(());
+        }
+        Some(Commands::Audit(args)) => {
+            return audit::run(&config, &cwd, args).await;
+        }
+        Some(Commands::Config {
+            command: ConfigCommands::Show,
+        }) => {
+            config::show(&config);
+            return Ok(());
+        }
+        Some(Commands::Sessions { command }) => {
+            return sessions::run(&config, &cwd, command).await;
+        }
+        Some(Commands::Mcp {
+            command: McpCommands::Serve { http, allow_remote },
+        }) => {
+            return serve::run(
+                &config,
+                cwd,
+                permission_mode,
+                http.as_deref(),
+                *allow_remote,
+            )
+            .await;
+        }
+        None => {}
     }
 
+    let resumed =
+        sessions::resume_target(&config, cli.continue_session, cli.resume.as_deref(), &cwd).await?;
+
     let provider: Arc<dyn Provider> = create_provider(&cli, &config).await?;
 
-    let is_interactive = cli.print.is_none() && !cli.ludicrous;
+    let is_interactive = cli.print.is_none() && !bypass;
 
     // Set up worktree isolation if requested
     let worktree_requested = cli.worktree || cli.worktree_branch.is_some();
     let mut managed_worktree: Option<ManagedWorktree> = None;
     let effective_cwd = if worktree_requested {
         // Create a temporary permission engine for hooks during worktree setup
-        let setup_engine = Arc::new(if cli.ludicrous {
-            PermissionEngine::ludicrous()
-        } else {
-            PermissionEngine::new(config.permission_rules.clone(), config.hooks.clone(), None)
-        });
+        let setup_engine =
+            Arc::new(runner::permission_engine(&config, None).with_mode(permission_mode));
         match chet_core::create_worktree(&cwd, cli.worktree_branch.as_deref(), Some(setup_engine))
             .await
         {
@@ -180,24 +274,57 @@ async fn main() -> Result<()> {
         cwd.clone()
     };
 
-    // Start MCP servers if configured
-    let mcp_manager = runner::start_mcp_servers(&config).await;
-
     // Compute project_id from original cwd (not worktree) so all worktrees share memory
-    let project_id: Option<String> = match chet_core::worktree::git_repo_root(&cwd).await {
-        Ok(ref root) => Some(MemoryManager::project_id(root)),
-        Err(_) => Some(MemoryManager::project_id(&cwd)),
-    };
+    let project_id: Option<String> = Some(runner::project_id(&cwd).await);
+    let audit_project = project_id.clone().unwrap_or_default();
+
+    // Start MCP servers if configured. Sampling requests need approval through
+    // the same terminal prompt as tools; elicitation needs a user at the REPL.
+    let mcp_handler = mcp_handler::CliMcpHandler::new(
+        Arc::clone(&provider),
+        config.model.clone(),
+        if is_interactive {
+            Some(Arc::new(prompt::TerminalPromptHandler::default()))
+        } else {
+            None
+        },
+        bypass,
+        cli.print.is_none(),
+    );
+    let mcp_prompt = prompt::TerminalPromptHandler::default();
+    let mcp_manager = runner::start_mcp_servers(
+        &config,
+        Arc::new(mcp_handler),
+        project_id.as_deref(),
+        if is_interactive {
+            Some(&mcp_prompt)
+        } else {
+            None
+        },
+        bypass,
+    )
+    .await;
 
-    let memory_manager = MemoryManager::new(config.memory_dir.clone());
+    let memory_manager = MemoryManager::new(config.memory_dir.clone())
+        .with_team_root(runner::repo_root(&effective_cwd).await);
 
     let result = if let Some(prompt) = cli.print {
-        // Print mode: single prompt, no session persistence
-        let engine = Arc::new(if cli.ludicrous {
-            PermissionEngine::ludicrous()
-        } else {
-            PermissionEngine::new(config.permission_rules.clone(), config.hooks.clone(), None)
+        // Print mode: a single prompt, saved as a session (or added to the
+        // resumed one) so a later `-p --continue` can pick it up
+        let store = SessionStore::new(config.config_dir.clone()).await?;
+        let mut session = resumed.unwrap_or_else(|| {
+            Session::new(config.model.clone(), effective_cwd.display().to_string())
         });
+        if let Some(name) = cli.name {
+            session.metadata.label = Some(name);
+        }
+        let engine = Arc::new(
+            runner::permission_engine(&config, None)
+                .with_workspace(runner::workspace(&config, &effective_cwd).await)
+                .with_mode(permission_mode)
+                .with_audit_log(runner::audit_log(&config, &audit_project)),
+        );
+        engine.set_audit_session(&session.id.to_string());
         let memory_section = memory_manager.load_combined(project_id.as_deref()).await;
         let mut agent = runner::create_agent(
             Arc::clone(&provider),
@@ -206,12 +333,19 @@ async fn main() -> Result<()> {
             &effective_cwd,
             &mcp_manager,
             project_id,
+            memory_manager.team_root().map(Into::into),
         );
-        agent.set_system_prompt(prompts::system_prompt(&effective_cwd, &memory_section));
-        let mut messages = vec![prompts::user_message(&prompt)];
+        if permission_mode == PermissionMode::Plan {
+            agent.set_read_only_mode(true);
+            agent.set_system_prompt(prompts::plan_system_prompt(&effective_cwd, &memory_section));
+        } else {
+            agent.set_system_prompt(prompts::system_prompt(&effective_cwd, &memory_section));
+        }
+        agent.set_tool_output_dir(store.tool_output_dir(session.id));
+        session.messages.push(prompts::user_message(&prompt));
         let usage = runner::run_agent(
             &agent,
-            &mut messages,
+            &mut session.messages,
             context::UIContext {
                 stdout_is_tty,
                 stderr_is_tty,
@@ -219,28 +353,34 @@ async fn main() -> Result<()> {
             },
         )
         .await?;
+        session.updated_at = chrono::Utc::now();
+        session.record_turn(&usage, session.updated_at);
+        session.auto_label();
+        if let Err(e) = store.save(&session).await {
+            eprintln!("Warning: failed to save session: {e}");
+        }
         prompts::print_usage(&usage);
         if let Some(manager) = mcp_manager {
             manager.shutdown().await;
         }
         Ok(())
     } else {
-        // Interactive REPL mode
-        let engine = Arc::new(if cli.ludicrous {
-            PermissionEngine::ludicrous()
-        } else {
-            let prompt_handler: Option<Arc<dyn chet_permissions::PromptHandler>> = if is_interactive
-            {
-                Some(Arc::new(prompt::TerminalPromptHandler))
+        // Interactive REPL mode. The prompt handler is kept even when
+        // starting in bypass, so switching modes later can still ask.
+        let prompt_handler: Option<Arc<dyn chet_permissions::PromptHandler>> =
+            if cli.print.is_none() {
+                Some(Arc::new(prompt::TerminalPromptHandler::new(
+                    config.project_dir.clone(),
+                )))
             } else {
                 None
             };
-            PermissionEngine::new(
-                config.permission_rules.clone(),
-                config.hooks.clone(),
-                prompt_handler,
-            )
-        });
+        let engine = Arc::new(
+            runner::permission_engine(&config, prompt_handler)
+                .with_workspace(runner::workspace(&config, &effective_cwd).await)
+                .with_mode(permission_mode)
+                .with_audit_log(runner::audit_log(&config, &audit_project)),
+        );
 
         let original_cwd = if worktree_requested {
             Some(cwd.clone())
@@ -260,7 +400,7 @@ async fn main() -> Result<()> {
                 project_id,
             },
             context::ReplStartup {
-                resume_id: cli.resume,
+                resumed,
                 session_name: cli.name,
             },
         )
@@ -323,6 +463,15 @@ fn print_agents(config: &ChetConfig) {
 /// Priority: --provider flag > CLAUDE_CODE_USE_BEDROCK/VERTEX env > CHET_USE_BEDROCK/VERTEX env > "anthropic"
 async fn create_provider(cli: &Cli, config: &ChetConfig) -> Result<Arc<dyn Provider>> {
     let provider_name = resolve_provider_name(cli);
+    if !config.managed.provider_allowed(&provider_name) {
+        anyhow::bail!(
+            "{}",
+            config.managed.violation(
+                "provider",
+                format!("provider '{provider_name}' is not allowed")
+            )
+        );
+    }
 
     match provider_name.as_str() {
         "anthropic" => {
@@ -0,0 +1,605 @@
+//! Handles MCP server-initiated requests: sampling via the active provider
+//! (behind a permission prompt) and elicitation via a terminal form.
+
+use chet_mcp::{
+    ElicitationAction, ElicitationRequest, ElicitationResult, McpRequestHandler, SamplingContent,
+    SamplingError, SamplingRequest, SamplingResult,
+};
+use chet_permissions::{PromptHandler, PromptResponse};
+use chet_types::provider::Provider;
+use chet_types::{
+    ContentBlock, ContentDelta, CreateMessageRequest, ImageSource, ImageSourceType, Message, Role,
+    StopReason, StreamEvent, SystemContent,
+};
+use futures_util::StreamExt;
+use std::collections::HashSet;
+use std::future::Future;
+use std::io::{self, BufRead, Write};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// Number of attempts the user gets to enter a valid value for a field.
+const MAX_FIELD_ATTEMPTS: usize = 3;
+
+/// Serves MCP sampling and elicitation requests for the CLI.
+pub struct CliMcpHandler {
+    provider: Arc<dyn Provider>,
+    model: String,
+    /// Prompt handler for sampling approval. `None` denies every request.
+    prompt_handler: Option<Arc<dyn PromptHandler>>,
+    /// Approve sampling without asking (--ludicrous mode).
+    auto_approve: bool,
+    /// Whether a user is present to answer elicitation forms.
+    interactive: bool,
+    /// Servers the user chose to "always allow" for sampling this session.
+    approved_servers: Mutex<HashSet<String>>,
+}
+
+impl CliMcpHandler {
+    pub fn new(
+        provider: Arc<dyn Provider>,
+        model: String,
+        prompt_handler: Option<Arc<dyn PromptHandler>>,
+        auto_approve: bool,
+        interactive: bool,
+    ) -> Self {
+        Self {
+            provider,
+            model,
+            prompt_handler,
+            auto_approve,
+            interactive,
+            approved_servers: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Ask the user whether `server` may run this sampling request.
+    async fn approve_sampling(&self, server: &str, request: &SamplingRequest) -> bool {
+        if self.auto_approve || self.approved_servers.lock().unwrap().contains(server) {
+            return true;
+        }
+        let Some(handler) = &self.prompt_handler else {
+            return false;
+        };
+        let tool_name = format!("mcp__{server}__sampling");
+        let description = describe_sampling(server, request);
+        let input = serde_json::json!({ "max_tokens": request.max_tokens });
+        match handler
+            .prompt_permission(&tool_name, &input, &description, None, &[])
+            .await
+        {
+            PromptResponse::AllowOnce => true,
+            PromptResponse::AlwaysAllow | PromptResponse::AlwaysAllowSaved(_) => {
+                self.approved_servers
+                    .lock()
+                    .unwrap()
+                    .insert(server.to_string());
+                true
+            }
+            PromptResponse::Deny => false,
+        }
+    }
+
+    /// Send the sampling request to the provider and collect the text reply.
+    async fn sample(&self, request: SamplingRequest) -> Result<SamplingResult, SamplingError> {
+        let api_request = CreateMessageRequest {
+            model: self.model.clone(),
+            max_tokens: request.max_tokens,
+            messages: request.messages.iter().map(to_message).collect(),
+            system: request.system_prompt.map(|text| {
+                vec![SystemContent {
+                    content_type: "text",
+                    text,
+                    cache_control: None,
+                }]
+            }),
+            tools: None,
+            stop_sequences: request.stop_sequences,
+            temperature: request.temperature.map(|t| t as f32),
+            thinking: None,
+            stream: true,
+        };
+
+        let mut stream = self
+            .provider
+            .create_message_stream(&api_request)
+            .await
+            .map_err(|e| SamplingError::Failed(format!("Sampling failed: {e}")))?;
+
+        let mut text = String::new();
+        let mut model = self.model.clone();
+        let mut stop_reason = None;
+        while let Some(event) = stream.next().await {
+            match event.map_err(|e| SamplingError::Failed(format!("Sampling failed: {e}")))? {
+                StreamEvent::MessageStart { message } => model = message.model,
+                StreamEvent::ContentBlockDelta {
+                    delta: ContentDelta::TextDelta { text: delta },
+                    ..
+                } => text.push_str(&delta),
+                StreamEvent::MessageDelta { delta, .. } => stop_reason = delta.stop_reason,
+                StreamEvent::Error { error } => {
+                    return Err(SamplingError::Failed(format!(
+                        "Sampling failed: {}",
+                        error.message
+                    )));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(SamplingResult {
+            role: "assistant".to_string(),
+            content: SamplingContent::Text { text },
+            model,
+            stop_reason: stop_reason.map(|r| {
+                match r {
+                    StopReason::EndTurn | StopReason::ToolUse => "endTurn",
+                    StopReason::MaxTokens => "maxTokens",
+                    StopReason::StopSequence => "stopSequence",
+                }
+                .to_string()
+            }),
+        })
+    }
+}
+
+impl McpRequestHandler for CliMcpHandler {
+    fn create_message<'a>(
+        &'a self,
+        server: &'a str,
+        request: SamplingRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<SamplingResult, SamplingError>> + Send + 'a>> {
+        Box::pin(async move {
+            if !self.approve_sampling(server, &request).await {
+                return Err(SamplingError::Rejected(
+                    "User rejected sampling request".to_string(),
+                ));
+            }
+            self.sample(request).await
+        })
+    }
+
+    fn elicit<'a>(
+        &'a self,
+        server: &'a str,
+        request: ElicitationRequest,
+    ) -> Pin<Box<dyn Future<Output = ElicitationResult> + Send + 'a>> {
+        Box::pin(async move {
+            if !self.interactive {
+                return ElicitationResult::decline();
+            }
+            let server = server.to_string();
+            tokio::task::spawn_blocking(move || elicit_in_terminal(&server, &request))
+                .await
+                .unwrap_or_else(|_| ElicitationResult::decline())
+        })
+    }
+}
+
+/// Convert an MCP sampling message to an API message.
+fn to_message(msg: &chet_mcp::SamplingMessage) -> Message {
+    let role = if msg.role == "assistant" {
+        Role::Assistant
+    } else {
+        Role::User
+    };
+    let block = match &msg.content {
+        SamplingContent::Text { text } => ContentBlock::Text { text: text.clone() },
+        SamplingContent::Image { data, mime_type } => ContentBlock::Image {
+            source: ImageSource {
+                source_type: ImageSourceType::Base64,
+                media_type: mime_type.clone(),
+                data: data.clone(),
+            },
+        },
+    };
+    Message {
+        role,
+        content: vec![block],
+    }
+}
+
+/// Describe a sampling request for the permission prompt, showing each message.
+fn describe_sampling(server: &str, request: &SamplingRequest) -> String {
+    let mut out = format!("MCP server '{server}' wants to send a request to the model:");
+    if let Some(system) = &request.system_prompt {
+        out.push_str(&format!("\n    [system] {}", preview(system)));
+    }
+    for msg in &request.messages {
+        let body = match &msg.content {
+            SamplingContent::Text { text } => preview(text),
+            SamplingContent::Image { mime_type, .. } => format!("<image {mime_type}>"),
+        };
+        out.push_str(&format!("\n    [{}] {body}", msg.role));
+    }
+    out
+}
+
+/// Single-line preview of a message body, truncated for display.
+fn preview(text: &str) -> String {
+    const MAX_LEN: usize = 200;
+    let flat = text.replace('\n', " ");
+    if flat.len() > MAX_LEN {
+        format!("{}...", chet_types::truncate_str(&flat, MAX_LEN))
+    } else {
+        flat
+    }
+}
+
+/// Walk the user through the requested schema, one property at a time.
+fn elicit_in_terminal(server: &str, request: &ElicitationRequest) -> ElicitationResult {
+    let stderr = io::stderr();
+    let mut err = stderr.lock();
+    let _ = writeln!(err);
+    let _ = writeln!(err, "  MCP server '{server}' requests input:");
+    let _ = writeln!(err, "  {}", request.message);
+    let _ = write!(err, "  [y] Respond  [n] Decline  [c] Cancel  > ");
+    let _ = err.flush();
+
+    match read_stdin_line().as_deref().map(str::trim) {
+        Some("y" | "yes" | "") => {}
+        Some("c" | "cancel") | None => {
+            return ElicitationResult {
+                action: ElicitationAction::Cancel,
+                content: None,
+            };
+        }
+        _ => return ElicitationResult::decline(),
+    }
+
+    let schema = &request.requested_schema;
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    let mut content = serde_json::Map::new();
+
+    if let Some(properties) = schema["properties"].as_object() {
+        for (name, prop) in properties {
+            let is_required = required.contains(&name.as_str());
+            let mut attempts = 0;
+            loop {
+                let _ = write!(err, "  {}: ", field_label(name, prop, is_required));
+                let _ = err.flush();
+                let Some(raw) = read_stdin_line() else {
+                    return ElicitationResult {
+                        action: ElicitationAction::Cancel,
+                        content: None,
+                    };
+                };
+                match parse_field(prop, raw.trim(), is_required) {
+                    Ok(Some(value)) => {
+                        content.insert(name.clone(), value);
+                        break;
+                    }
+                    Ok(None) => break,
+                    Err(msg) => {
+                        attempts += 1;
+                        let _ = writeln!(err, "  {msg}");
+                        if attempts >= MAX_FIELD_ATTEMPTS {
+                            return ElicitationResult {
+                                action: ElicitationAction::Cancel,
+                                content: None,
+                            };
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    ElicitationResult {
+        action: ElicitationAction::Accept,
+        content: Some(content),
+    }
+}
+
+fn read_stdin_line() -> Option<String> {
+    let mut input = String::new();
+    match io::stdin().lock().read_line(&mut input) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(input),
+    }
+}
+
+/// Build the prompt label for a schema property, e.g. `email (string, required)`.
+fn field_label(name: &str, prop: &serde_json::Value, required: bool) -> String {
+    let title = prop["title"].as_str().unwrap_or(name);
+    let mut hints = Vec::new();
+    if let Some(options) = prop["enum"].as_array() {
+        let opts: Vec<String> = options
+            .iter()
+            .map(|o| o.as_str().map_or_else(|| o.to_string(), str::to_string))
+            .collect();
+        hints.push(opts.join("/"));
+    } else if let Some(ty) = prop["type"].as_str() {
+        hints.push(ty.to_string());
+    }
+    if required {
+        hints.push("required".to_string());
+    }
+    let mut label = format!("{title} ({})", hints.join(", "));
+    if let Some(desc) = prop["description"].as_str() {
+        label.push_str(&format!(" — {desc}"));
+    }
+    label
+}
+
+/// Parse a raw answer against a primitive schema property.
+///
+/// Returns `Ok(None)` for an empty optional field and `Err` with a message
+/// to show when the answer doesn't fit the schema.
+fn parse_field(
+    prop: &serde_json::Value,
+    raw: &str,
+    required: bool,
+) -> Result<Option<serde_json::Value>, String> {
+    if raw.is_empty() {
+        if let Some(default) = prop.get("default") {
+            return Ok(Some(default.clone()));
+        }
+        return if required {
+            Err("A value is required".to_string())
+        } else {
+            Ok(None)
+        };
+    }
+
+    if let Some(options) = prop["enum"].as_array() {
+        return options
+            .iter()
+            .find(|o| o.as_str() == Some(raw))
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| "Choose one of the listed options".to_string());
+    }
+
+    match prop["type"].as_str().unwrap_or("string") {
+        "boolean" => match raw.to_lowercase().as_str() {
+            "y" | "yes" | "true" => Ok(Some(serde_json::Value::Bool(true))),
+            "n" | "no" | "false" => Ok(Some(serde_json::Value::Bool(false))),
+            _ => Err("Enter yes or no".to_string()),
+        },
+        "integer" => raw
+            .parse::<i64>()
+            .map(|n| Some(serde_json::json!(n)))
+            .map_err(|_| "Enter a whole number".to_string()),
+        "number" => raw
+            .parse::<f64>()
+            .map(|n| Some(serde_json::json!(n)))
+            .map_err(|_| "Enter a number".to_string()),
+        _ => Ok(Some(serde_json::Value::String(raw.to_string()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_string_field() {
+        let prop = json!({"type": "string"});
+        assert_eq!(
+            parse_field(&prop, "abc", false).unwrap(),
+            Some(json!("abc"))
+        );
+        assert_eq!(parse_field(&prop, "", false).unwrap(), None);
+        assert!(parse_field(&prop, "", true).is_err());
+    }
+
+    #[test]
+    fn parse_typed_fields() {
+        assert_eq!(
+            parse_field(&json!({"type": "integer"}), "42", true).unwrap(),
+            Some(json!(42))
+        );
+        assert!(parse_field(&json!({"type": "integer"}), "4.2", true).is_err());
+        assert_eq!(
+            parse_field(&json!({"type": "number"}), "4.5", true).unwrap(),
+            Some(json!(4.5))
+        );
+        assert_eq!(
+            parse_field(&json!({"type": "boolean"}), "yes", true).unwrap(),
+            Some(json!(true))
+        );
+        assert!(parse_field(&json!({"type": "boolean"}), "maybe", true).is_err());
+    }
+
+    #[test]
+    fn parse_enum_and_default() {
+        let prop = json!({"type": "string", "enum": ["a", "b"], "default": "b"});
+        assert_eq!(parse_field(&prop, "a", true).unwrap(), Some(json!("a")));
+        assert!(parse_field(&prop, "c", true).is_err());
+        assert_eq!(parse_field(&prop, "", true).unwrap(), Some(json!("b")));
+    }
+
+    #[test]
+    fn field_label_shows_hints() {
+        let prop = json!({"type": "string", "title": "Email", "description": "work address"});
+        assert_eq!(
+            field_label("email", &prop, true),
+            "Email (string, required) — work address"
+        );
+        let prop = json!({"enum": ["low", "high"]});
+        assert_eq!(field_label("level", &prop, false), "level (low/high)");
+    }
+
+    #[test]
+    fn describe_sampling_lists_messages() {
+        let request: SamplingRequest = serde_json::from_value(json!({
+            "messages": [
+                {"role": "user", "content": {"type": "text", "text": "Summarize\nthis"}},
+                {"role": "user", "content": {"type": "image", "data": "xx", "mimeType": "image/png"}}
+            ],
+            "systemPrompt": "Be brief",
+            "maxTokens": 50
+        }))
+        .unwrap();
+        let desc = describe_sampling("docs", &request);
+        assert!(desc.contains("MCP server 'docs'"));
+        assert!(desc.contains("[system] Be brief"));
+        assert!(desc.contains("[user] Summarize this"));
+        assert!(desc.contains("<image image/png>"));
+    }
+
+    /// Provider that always replies "sampled" and ends the turn.
+    struct StubProvider;
+
+    impl Provider for StubProvider {
+        fn create_message_stream<'a>(
+            &'a self,
+            _request: &'a CreateMessageRequest,
+        ) -> Pin<
+            Box<
+                dyn Future<Output = Result<chet_types::provider::EventStream, chet_types::ApiError>>
+                    + Send
+                    + 'a,
+            >,
+        > {
+            Box::pin(async {
+                let events = vec![
+                    Ok(StreamEvent::ContentBlockDelta {
+                        index: 0,
+                        delta: ContentDelta::TextDelta {
+                            text: "sampled".to_string(),
+                        },
+                    }),
+                    Ok(StreamEvent::MessageDelta {
+                        delta: chet_types::MessageDelta {
+                            stop_reason: Some(StopReason::EndTurn),
+                        },
+                        usage: None,
+                    }),
+                ];
+                Ok(Box::pin(futures_util::stream::iter(events))
+                    as chet_types::provider::EventStream)
+            })
+        }
+
+        fn name(&self) -> &str {
+            "stub"
+        }
+    }
+
+    /// Prompt handler that returns a fixed response and counts calls.
+    struct StubPrompt {
+        response: PromptResponse,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl StubPrompt {
+        fn new(response: PromptResponse) -> Arc<Self> {
+            Arc::new(Self {
+                response,
+                calls: std::sync::atomic::AtomicUsize::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    impl PromptHandler for StubPrompt {
+        fn prompt_permission(
+            &self,
+            _tool_name: &str,
+            _tool_input: &serde_json::Value,
+            _description: &str,
+            _preview: Option<&str>,
+            _suggested_rules: &[chet_permissions::PermissionRule],
+        ) -> Pin<Box<dyn Future<Output = PromptResponse> + Send + '_>> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let response = self.response.clone();
+            Box::pin(async move { response })
+        }
+    }
+
+    fn handler(
+        prompt: Option<Arc<dyn PromptHandler>>,
+        auto_approve: bool,
+        interactive: bool,
+    ) -> CliMcpHandler {
+        CliMcpHandler::new(
+            Arc::new(StubProvider),
+            "test-model".to_string(),
+            prompt,
+            auto_approve,
+            interactive,
+        )
+    }
+
+    fn sampling_request() -> SamplingRequest {
+        serde_json::from_value(json!({
+            "messages": [{"role": "user", "content": {"type": "text", "text": "hi"}}],
+            "maxTokens": 10
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn ludicrous_auto_approves_sampling() {
+        let prompt = StubPrompt::new(PromptResponse::Deny);
+        let handler = handler(Some(prompt.clone()), true, false);
+        let result = handler
+            .create_message("srv", sampling_request())
+            .await
+            .unwrap();
+        assert!(matches!(result.content, SamplingContent::Text { text } if text == "sampled"));
+        assert_eq!(result.stop_reason.as_deref(), Some("endTurn"));
+        assert_eq!(prompt.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn sampling_rejected_without_prompt_handler() {
+        let handler = handler(None, false, false);
+        let err = handler
+            .create_message("srv", sampling_request())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SamplingError::Rejected(_)));
+    }
+
+    #[tokio::test]
+    async fn sampling_denied_by_user_is_rejected() {
+        let prompt = StubPrompt::new(PromptResponse::Deny);
+        let handler = handler(Some(prompt.clone()), false, true);
+        let err = handler
+            .create_message("srv", sampling_request())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SamplingError::Rejected(_)));
+        assert_eq!(prompt.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn always_allow_remembers_server() {
+        let prompt = StubPrompt::new(PromptResponse::AlwaysAllow);
+        let handler = handler(Some(prompt.clone()), false, true);
+        for _ in 0..2 {
+            handler
+                .create_message("srv", sampling_request())
+                .await
+                .unwrap();
+        }
+        assert_eq!(prompt.calls(), 1);
+
+        // Approval is per server
+        handler
+            .create_message("other", sampling_request())
+            .await
+            .unwrap();
+        assert_eq!(prompt.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn elicit_declines_when_not_interactive() {
+        let handler = handler(None, true, false);
+        let request: ElicitationRequest =
+            serde_json::from_value(json!({"message": "Name?"})).unwrap();
+        let result = handler.elicit("srv", request).await;
+        assert_eq!(result.action, ElicitationAction::Decline);
+        assert!(result.content.is_none());
+    }
+}
@@ -0,0 +1,118 @@
+//! Permission modes in the REPL: switching, prompts and status line badges.
+
+use chet_core::Agent;
+use chet_permissions::{PermissionEngine, PermissionMode};
+use chet_terminal::StatusLine;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::prompts::{plan_system_prompt, system_prompt};
+
+/// Switch the engine and agent to `mode`. Plan mode also restricts the agent
+/// to read-only tools and uses the planning system prompt. Returns the new
+/// system prompt.
+pub(crate) fn apply_mode(
+    mode: PermissionMode,
+    agent: &mut Agent,
+    engine: &PermissionEngine,
+    cwd: &Path,
+    memory_section: &str,
+    status_line: &Option<Arc<Mutex<StatusLine>>>,
+) -> String {
+    engine.set_mode(mode);
+    let plan = mode == PermissionMode::Plan;
+    agent.set_read_only_mode(plan);
+    let system = if plan {
+        plan_system_prompt(cwd, memory_section)
+    } else {
+        system_prompt(cwd, memory_section)
+    };
+    agent.set_system_prompt(system.clone());
+    if let Some(sl) = status_line {
+        sl.lock().unwrap().update_field(|d| d.mode = badge(mode));
+    }
+    system
+}
+
+/// Parse a `/mode` argument. Bypass is only reachable when the session
+/// started in it (`allow_bypass`) and managed policy permits it, the same
+/// as for Shift+Tab.
+pub(crate) fn parse_switch(
+    arg: &str,
+    allow_bypass: bool,
+    policy_allows_bypass: bool,
+) -> Result<PermissionMode, String> {
+    match arg.parse::<PermissionMode>()? {
+        PermissionMode::Bypass if !policy_allows_bypass => {
+            Err("Bypass mode is not allowed by managed policy.".to_string())
+        }
+        PermissionMode::Bypass if !allow_bypass => Err(
+            "Bypass mode can only be used in sessions started with --permission-mode bypass."
+                .to_string(),
+        ),
+        mode => Ok(mode),
+    }
+}
+
+/// Status line badge; the default mode has none.
+pub(crate) fn badge(mode: PermissionMode) -> Option<String> {
+    match mode {
+        PermissionMode::Default => None,
+        PermissionMode::AcceptEdits => Some("ACCEPT EDITS".to_string()),
+        PermissionMode::Plan => Some("PLAN".to_string()),
+        PermissionMode::Bypass => Some("BYPASS".to_string()),
+    }
+}
+
+/// REPL prompt for the mode.
+pub(crate) fn prompt(mode: PermissionMode) -> &'static str {
+    match mode {
+        PermissionMode::Default => "> ",
+        PermissionMode::AcceptEdits => "edits> ",
+        PermissionMode::Plan => "plan> ",
+        PermissionMode::Bypass => "bypass> ",
+    }
+}
+
+/// One-line description printed when switching to the mode.
+pub(crate) fn banner(mode: PermissionMode, color: bool) -> String {
+    match mode {
+        PermissionMode::Plan => chet_terminal::style::plan_mode_banner(color),
+        PermissionMode::Default => {
+            "Permission mode: default — mutating tools ask first".to_string()
+        }
+        PermissionMode::AcceptEdits => {
+            "Permission mode: acceptEdits — Write/Edit inside the workspace run without asking"
+                .to_string()
+        }
+        PermissionMode::Bypass => {
+            "Permission mode: bypass — every tool call runs without asking".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_mode_but_default_has_a_badge() {
+        for mode in PermissionMode::ALL {
+            assert_eq!(badge(mode).is_none(), mode == PermissionMode::Default);
+            assert!(prompt(mode).ends_with("> "));
+            assert!(!banner(mode, false).contains('\x1b'));
+        }
+    }
+
+    #[test]
+    fn bypass_switch_needs_a_bypass_session() {
+        assert_eq!(parse_switch("plan", false, true), Ok(PermissionMode::Plan));
+        assert!(parse_switch("bypass", false, true).is_err());
+        assert_eq!(
+            parse_switch("bypass", true, true),
+            Ok(PermissionMode::Bypass)
+        );
+        assert!(parse_switch("bypass", true, false).is_err());
+        assert!(parse_switch("nonsense", true, true).is_err());
+    }
+}
@@ -1,12 +1,29 @@
 //! Terminal-based prompt handler for interactive permission prompts.
 
-use chet_permissions::{PromptHandler, PromptResponse};
+use chet_config::RuleFile;
+use chet_permissions::{PermissionRule, PromptHandler, PromptResponse};
 use std::future::Future;
 use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 
+/// Most lines of a tool's preview shown in a prompt.
+const MAX_PREVIEW_LINES: usize = 40;
+
 /// Prompts the user in the terminal for permission decisions.
-pub struct TerminalPromptHandler;
+///
+/// With a project dir, "always allow" can also be saved to the project's
+/// `.chet/config.toml` or `.chet/config.local.toml`.
+#[derive(Default)]
+pub struct TerminalPromptHandler {
+    project_dir: Option<PathBuf>,
+}
+
+impl TerminalPromptHandler {
+    pub fn new(project_dir: Option<PathBuf>) -> Self {
+        Self { project_dir }
+    }
+}
 
 impl PromptHandler for TerminalPromptHandler {
     fn prompt_permission(
@@ -14,10 +31,17 @@ impl PromptHandler for TerminalPromptHandler {
         tool_name: &str,
         tool_input: &serde_json::Value,
         description: &str,
+        preview: Option<&str>,
+        suggested_rules: &[PermissionRule],
     ) -> Pin<Box<dyn Future<Output = PromptResponse> + Send + '_>> {
         let tool_name = tool_name.to_string();
         let input_summary = summarize_input(tool_input);
         let description = description.to_string();
+        let preview = preview.map(str::to_string);
+        let rules = suggested_rules.to_vec();
+        let project_dir = self.project_dir.clone().filter(|_| !rules.is_empty());
+        // An absolute path means nothing on a teammate's machine
+        let shareable = !rules.iter().any(names_absolute_path);
 
         Box::pin(async move {
             // Use spawn_blocking since we read from stdin
@@ -30,20 +54,63 @@ impl PromptHandler for TerminalPromptHandler {
                 if !description.is_empty() {
                     let _ = writeln!(err, "  {description}");
                 }
+                if let Some(preview) = &preview {
+                    for line in preview_lines(preview) {
+                        let _ = writeln!(err, "    {line}");
+                    }
+                }
                 if !input_summary.is_empty() {
                     let _ = writeln!(err, "  {input_summary}");
                 }
-                let _ = write!(err, "  [y] Allow once  [a] Always allow  [n] Deny  > ");
+                if !rules.is_empty() {
+                    let _ = writeln!(err, "  Always allow: {}", describe_rules(&rules));
+                }
+                let save_options = match (&project_dir, shareable) {
+                    (Some(_), true) => "  [p] Save to project  [l] Save locally",
+                    (Some(_), false) => "  [l] Save locally",
+                    (None, _) => "",
+                };
+                let _ = write!(
+                    err,
+                    "  [y] Allow once  [a] Always allow (session){save_options}  [n] Deny  > "
+                );
                 let _ = err.flush();
 
-                let mut input = String::new();
-                let stdin = io::stdin();
-                let _ = stdin.lock().read_line(&mut input);
+                let answer = read_answer();
+                let file = match answer.as_str() {
+                    "y" | "yes" | "" => return PromptResponse::AllowOnce,
+                    "a" | "always" => return PromptResponse::AlwaysAllow,
+                    "p" | "project" if shareable => RuleFile::Project,
+                    "l" | "local" => RuleFile::Local,
+                    _ => return PromptResponse::Deny,
+                };
+                let Some(project_dir) = project_dir else {
+                    return PromptResponse::Deny;
+                };
 
-                match input.trim().to_lowercase().as_str() {
-                    "y" | "yes" | "" => PromptResponse::AllowOnce,
-                    "a" | "always" => PromptResponse::AlwaysAllow,
-                    _ => PromptResponse::Deny,
+                // Show exactly what will be written before saving
+                let path = file.path(&project_dir);
+                let _ = writeln!(err, "  Append to {}:", path.display());
+                for line in chet_config::format_permission_rules(&rules).lines() {
+                    let _ = writeln!(err, "    {line}");
+                }
+                let _ = write!(err, "  Save? [y] Yes  [n] No, allow once  > ");
+                let _ = err.flush();
+                if !matches!(read_answer().as_str(), "y" | "yes" | "") {
+                    return PromptResponse::AllowOnce;
+                }
+                match chet_config::save_permission_rules(&project_dir, file, &rules) {
+                    Ok(path) => {
+                        let _ = writeln!(err, "  Saved to {}", path.display());
+                        PromptResponse::AlwaysAllowSaved(rules)
+                    }
+                    Err(e) => {
+                        let _ = writeln!(
+                            err,
+                            "  Could not save rule ({e}); allowing for this session"
+                        );
+                        PromptResponse::AlwaysAllow
+                    }
                 }
             })
             .await;
@@ -53,6 +120,50 @@ impl PromptHandler for TerminalPromptHandler {
     }
 }
 
+fn read_answer() -> String {
+    let mut input = String::new();
+    let _ = io::stdin().lock().read_line(&mut input);
+    input.trim().to_lowercase()
+}
+
+/// Rules as `Tool(args)`, e.g. `Bash(command:cargo test *)`.
+fn describe_rules(rules: &[PermissionRule]) -> String {
+    rules
+        .iter()
+        .map(|r| match &r.args {
+            Some(args) => format!("{}({args})", r.tool),
+            None => r.tool.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A tool's preview, cut to [`MAX_PREVIEW_LINES`] so a large change doesn't
+/// push the question off screen.
+fn preview_lines(preview: &str) -> Vec<String> {
+    let lines: Vec<&str> = preview.lines().collect();
+    if lines.len() <= MAX_PREVIEW_LINES {
+        return lines.into_iter().map(str::to_string).collect();
+    }
+    let mut shown: Vec<String> = lines[..MAX_PREVIEW_LINES]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+    shown.push(format!(
+        "... {} more lines",
+        lines.len() - MAX_PREVIEW_LINES
+    ));
+    shown
+}
+
+/// Whether a rule is scoped to an absolute path, e.g. `Read(path:/etc/hosts)`.
+fn names_absolute_path(rule: &PermissionRule) -> bool {
+    rule.args
+        .as_deref()
+        .and_then(|args| args.split_once(':'))
+        .is_some_and(|(_, value)| value.starts_with('/') || Path::new(value).is_absolute())
+}
+
 /// Create a brief summary of tool input for display.
 fn summarize_input(input: &serde_json::Value) -> String {
     match input {
@@ -86,3 +197,34 @@ fn summarize_input(input: &serde_json::Value) -> String {
         _ => String::new(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chet_permissions::PermissionLevel;
+
+    fn rule(args: &str) -> PermissionRule {
+        PermissionRule {
+            tool: "Read".into(),
+            args: Some(args.into()),
+            level: PermissionLevel::Permit,
+        }
+    }
+
+    #[test]
+    fn long_previews_are_cut() {
+        let preview: String = (0..100).map(|i| format!("+line {i}\n")).collect();
+        let lines = preview_lines(&preview);
+        assert_eq!(lines.len(), MAX_PREVIEW_LINES + 1);
+        assert_eq!(lines[0], "+line 0");
+        assert_eq!(lines[MAX_PREVIEW_LINES], "... 60 more lines");
+        assert_eq!(preview_lines("-a\n+b").len(), 2);
+    }
+
+    #[test]
+    fn absolute_path_rules_are_not_shareable() {
+        assert!(names_absolute_path(&rule("path:/home/u/.ssh/id_rsa")));
+        assert!(!names_absolute_path(&rule("path:src/**")));
+        assert!(!names_absolute_path(&rule("command:ls /tmp")));
+    }
+}
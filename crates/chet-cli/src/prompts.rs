@@ -18,9 +18,13 @@ pub(crate) fn append_memory_instructions(prompt: &mut String, memory: &str) {
         "\n\n# Persistent Memory\n\n\
         You have access to persistent memory that survives across sessions via the \
         MemoryRead and MemoryWrite tools.\n\
-        - Use MemoryRead to check existing memory before writing.\n\
-        - When writing, provide the complete updated content for the scope (global or project).\n\
-        - Use 'global' scope for cross-project preferences, 'project' for project-specific notes.\n\
+        - Memory is a list of titled entries, each with an id and optional tags. Use MemoryRead \
+        with a query to search them, or with an id to read one in full.\n\
+        - Save one fact per entry: MemoryWrite 'add' with a title, 'update' or 'delete' with an id. \
+        Check for an existing entry before adding a similar one.\n\
+        - Use 'global' scope for cross-project preferences, 'project' for your own project-specific \
+        notes, and 'team' for project conventions every contributor should know. Team memory is \
+        the repo's .chet/memory.md, committed and shared, so the user approves each change.\n\
         - Save things worth remembering: user preferences, project conventions, key decisions.\n\
         - When the user explicitly asks you to remember something, save it immediately.",
     );
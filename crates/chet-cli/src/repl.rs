@@ -1,7 +1,7 @@
 //! Interactive REPL loop.
 
 use anyhow::Result;
-use chet_permissions::PermissionEngine;
+use chet_permissions::{PermissionEngine, PermissionMode};
 use chet_session::{ContextTracker, Session};
 use chet_terminal::{
     LineEditor, ReadLineResult, SlashCommandCompleter, StatusLine, StatusLineData,
@@ -13,6 +13,7 @@ use std::sync::{Arc, Mutex};
 
 use crate::commands::{self, SlashResult};
 use crate::context::{CommandContext, ReplContext, ReplStartup, UIContext};
+use crate::mode::{self, apply_mode};
 use crate::plan::{self, PlanApproval};
 use crate::prompts::{plan_system_prompt, print_usage, system_prompt, user_message};
 use crate::runner::{self, create_agent};
@@ -45,11 +46,12 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
         cwd,
         &mcp_manager,
         project_id.clone(),
+        memory_manager.team_root().map(Into::into),
     );
     let store = chet_session::SessionStore::new(config.config_dir.clone())
         .await
         .map_err(|e| anyhow::anyhow!("{e}"))?;
-    let context_tracker = ContextTracker::new(&config.model);
+    let mut context_tracker = ContextTracker::new(&config.model);
     let mut memory_section = memory_manager.load_combined(project_id.as_deref()).await;
     let mut system = system_prompt(cwd, &memory_section);
     agent.set_system_prompt(system.clone());
@@ -81,18 +83,28 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
     );
 
     // Load or create session
-    let mut session = match &startup.resume_id {
-        Some(prefix) => {
-            let s = store
-                .load_by_prefix(prefix)
-                .await
-                .map_err(|e| anyhow::anyhow!("{e}"))?;
+    let mut session = match startup.resumed {
+        Some(s) => {
             eprintln!("Resumed session {}", s.short_id());
             s
         }
         None => Session::new(config.model.clone(), cwd.display().to_string()),
     };
 
+    let mut listed_sessions = Vec::new();
+
+    // Apply [sessions] retention, sparing the session being resumed
+    let retention = crate::sessions::retention(config);
+    if !retention.is_unlimited() {
+        match store.gc(&retention, &[session.id], false).await {
+            Ok(removed) if !removed.is_empty() => {
+                tracing::info!("Deleted {} expired sessions", removed.len());
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Session cleanup failed: {e}"),
+        }
+    }
+
     // Apply --name flag (overrides auto-label, even on resumed sessions)
     if let Some(name) = startup.session_name {
         session.metadata.label = Some(name);
@@ -107,14 +119,19 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
         "/cost",
         "/effort",
         "/help",
+        "/hooks",
         "/mcp",
         "/memory",
         "/model",
         "/context",
         "/compact",
         "/sessions",
+        "/export",
+        "/rewind",
+        "/fork",
         "/resume",
         "/plan",
+        "/mode",
     ])));
 
     let thinking_info = match (config.effort, config.thinking_budget) {
@@ -123,9 +140,9 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
         (None, None) => String::new(),
     };
     let mcp_info = match &mcp_manager {
-        Some(m) if m.client_count() > 0 => {
+        Some(m) if m.server_count() > 0 => {
             let tool_count: usize = m.server_summary().iter().map(|(_, c)| c).sum();
-            format!(", mcp: {} servers/{} tools", m.client_count(), tool_count)
+            format!(", mcp: {} servers/{} tools", m.server_count(), tool_count)
         }
         _ => String::new(),
     };
@@ -145,7 +162,7 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
             input_tokens: session.total_usage.input_tokens,
             output_tokens: session.total_usage.output_tokens,
             effort: config.effort,
-            plan_mode: false,
+            mode: mode::badge(hooks_engine.mode()),
             active_tool: None,
         };
         let mut sl = StatusLine::new(data);
@@ -165,21 +182,35 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
         mcp_info,
         session.short_id()
     );
-    eprintln!("Type your message. Press Ctrl+D to exit.\n");
+    eprintln!("Type your message. Press Shift+Tab to switch permission mode, Ctrl+D to exit.\n");
 
     // Set terminal title (TTY only)
     if stderr_is_tty {
         set_terminal_title(&format!("chet — {}", session.short_id()));
     }
 
-    let mut plan_mode = false;
+    // Shift+Tab only cycles into bypass if the session started there
+    let allow_bypass = hooks_engine.mode() == PermissionMode::Bypass;
+    let mut mode_before_plan = PermissionMode::Default;
+    if hooks_engine.mode() != PermissionMode::Default {
+        system = apply_mode(
+            hooks_engine.mode(),
+            &mut agent,
+            &hooks_engine,
+            cwd,
+            &memory_section,
+            &status_line,
+        );
+        eprintln!("{}", mode::banner(hooks_engine.mode(), stderr_is_tty));
+    }
     let mut auto_compact_failures: u32 = 0;
+    const MICRO_COMPACT_THRESHOLD: f64 = 60.0;
     const AUTO_COMPACT_THRESHOLD: f64 = 80.0;
     const AUTO_COMPACT_MAX_FAILURES: u32 = 3;
     let mut first_iteration = true;
 
     loop {
-        let prompt = if plan_mode { "plan> " } else { "> " };
+        let prompt = mode::prompt(hooks_engine.mode());
 
         // Suspend status line before line editor (skip first — already suspended above)
         if first_iteration {
@@ -209,6 +240,27 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
                 }
                 continue;
             }
+            ReadLineResult::CycleMode(line) => {
+                if let Some(sl) = &status_line {
+                    resume_status_line(sl);
+                }
+                let current = hooks_engine.mode();
+                let next = current.next(allow_bypass);
+                if next == PermissionMode::Plan {
+                    mode_before_plan = current;
+                }
+                system = apply_mode(
+                    next,
+                    &mut agent,
+                    &hooks_engine,
+                    cwd,
+                    &memory_section,
+                    &status_line,
+                );
+                eprintln!("{}", mode::banner(next, stderr_is_tty));
+                editor.set_pending(line);
+                continue;
+            }
         };
 
         let input = input.trim();
@@ -219,26 +271,32 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
         // Handle /plan toggle before other slash commands
         if input == "/plan" || input.starts_with("/plan ") {
             let plan_desc = input.strip_prefix("/plan").unwrap().trim();
+            let plan_mode = hooks_engine.mode() == PermissionMode::Plan;
             if plan_mode && plan_desc.is_empty() {
                 // Exit plan mode
-                plan_mode = false;
-                agent.set_read_only_mode(false);
-                agent.set_system_prompt(system_prompt(cwd, &memory_section));
+                system = apply_mode(
+                    mode_before_plan,
+                    &mut agent,
+                    &hooks_engine,
+                    cwd,
+                    &memory_section,
+                    &status_line,
+                );
                 eprintln!("Exited plan mode.");
-                if let Some(sl) = &status_line {
-                    sl.lock().unwrap().update_field(|d| d.plan_mode = false);
-                }
                 continue;
             }
             if !plan_mode {
                 // Enter plan mode
-                plan_mode = true;
-                agent.set_read_only_mode(true);
-                agent.set_system_prompt(plan_system_prompt(cwd, &memory_section));
-                eprintln!("{}", chet_terminal::style::plan_mode_banner(stderr_is_tty));
-                if let Some(sl) = &status_line {
-                    sl.lock().unwrap().update_field(|d| d.plan_mode = true);
-                }
+                mode_before_plan = hooks_engine.mode();
+                system = apply_mode(
+                    PermissionMode::Plan,
+                    &mut agent,
+                    &hooks_engine,
+                    cwd,
+                    &memory_section,
+                    &status_line,
+                );
+                eprintln!("{}", mode::banner(PermissionMode::Plan, stderr_is_tty));
             }
             if plan_desc.is_empty() {
                 continue;
@@ -247,6 +305,36 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
             session.messages.push(user_message(plan_desc));
         }
 
+        // Handle /mode inline (needs mutable access to agent)
+        if input == "/mode" || input.starts_with("/mode ") {
+            let arg = input.strip_prefix("/mode").unwrap().trim();
+            if arg.is_empty() {
+                eprintln!("Current permission mode: {}", hooks_engine.mode());
+                let names: Vec<&str> = PermissionMode::ALL.iter().map(|m| m.as_str()).collect();
+                eprintln!("Available: {} (Shift+Tab cycles)", names.join(", "));
+                continue;
+            }
+            match mode::parse_switch(arg, allow_bypass, config.managed.ludicrous_allowed()) {
+                Ok(next) => {
+                    let current = hooks_engine.mode();
+                    if next == PermissionMode::Plan && current != PermissionMode::Plan {
+                        mode_before_plan = current;
+                    }
+                    system = apply_mode(
+                        next,
+                        &mut agent,
+                        &hooks_engine,
+                        cwd,
+                        &memory_section,
+                        &status_line,
+                    );
+                    eprintln!("{}", mode::banner(next, stderr_is_tty));
+                }
+                Err(msg) => eprintln!("{msg}"),
+            }
+            continue;
+        }
+
         // Handle /effort inline (needs mutable access to agent)
         if input.starts_with("/effort") {
             let arg = input.strip_prefix("/effort").unwrap().trim();
@@ -293,19 +381,26 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
             CommandContext {
                 session: &mut session,
                 store: &store,
-                context_tracker: &context_tracker,
+                agent: &agent,
+                context_tracker: &mut context_tracker,
                 system_prompt: &system,
                 mcp_manager: &mut mcp_manager,
                 memory_manager: &memory_manager,
                 project_id: project_id.as_deref(),
                 status_line: &status_line,
                 hooks_engine: &hooks_engine,
+                listed_sessions: &mut listed_sessions,
+                color: stderr_is_tty,
             },
         )
         .await
         {
             // Update status line for commands that change session state
-            if input == "/clear" || input.starts_with("/resume") {
+            if input == "/clear"
+                || input.starts_with("/resume")
+                || input.starts_with("/rewind")
+                || input.starts_with("/fork")
+            {
                 if let Some(sl) = &status_line {
                     let info = context_tracker.estimate(&session.messages, Some(&system));
                     sl.lock().unwrap().update_field(|d| {
@@ -324,6 +419,10 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
                     eprintln!("Unknown command: {input}. Type /help for available commands.");
                     continue;
                 }
+                SlashResult::Prefill(text) => {
+                    editor.set_pending(text);
+                    continue;
+                }
             }
         }
 
@@ -332,6 +431,8 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
             session.messages.push(user_message(input));
         }
 
+        hooks_engine.set_audit_session(&session.id.to_string());
+        agent.set_tool_output_dir(store.tool_output_dir(session.id));
         match runner::run_agent(
             &agent,
             &mut session.messages,
@@ -344,9 +445,16 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
         .await
         {
             Ok(usage) => {
-                session.total_usage.add(&usage);
                 session.updated_at = Utc::now();
+                session.record_turn(&usage, session.updated_at);
                 session.auto_label();
+                if let Some(prompt) = agent.last_prompt_size() {
+                    context_tracker.calibrate(
+                        &session.messages[..prompt.messages],
+                        Some(&system),
+                        prompt.tokens,
+                    );
+                }
 
                 // Update terminal title with session label
                 if stderr_is_tty {
@@ -356,7 +464,7 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
                 }
 
                 // In plan mode, save plan to file and prompt for approval
-                if plan_mode {
+                if hooks_engine.mode() == PermissionMode::Plan {
                     if let Some(plan_text) = plan::extract_last_assistant_text(&session.messages) {
                         let plan_path =
                             plan::save_plan_file(&config.config_dir, &session, &plan_text).await;
@@ -383,9 +491,14 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
                                     }
                                 }
                             }
-                            plan_mode = false;
-                            agent.set_read_only_mode(false);
-                            agent.set_system_prompt(system_prompt(cwd, &memory_section));
+                            system = apply_mode(
+                                mode_before_plan,
+                                &mut agent,
+                                &hooks_engine,
+                                cwd,
+                                &memory_section,
+                                &status_line,
+                            );
                             eprintln!("Plan approved. Exiting plan mode.");
                         }
                         PlanApproval::Refine => {
@@ -393,15 +506,17 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
                         }
                         PlanApproval::Discard => {
                             plan::pop_last_turn(&mut session.messages);
-                            plan_mode = false;
-                            agent.set_read_only_mode(false);
-                            agent.set_system_prompt(system_prompt(cwd, &memory_section));
+                            system = apply_mode(
+                                mode_before_plan,
+                                &mut agent,
+                                &hooks_engine,
+                                cwd,
+                                &memory_section,
+                                &status_line,
+                            );
                             eprintln!("Plan discarded. Exiting plan mode.");
                         }
                     }
-                    if let Some(sl) = &status_line {
-                        sl.lock().unwrap().update_field(|d| d.plan_mode = plan_mode);
-                    }
                 }
 
                 // Auto-save
@@ -413,7 +528,7 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
                 let new_memory = memory_manager.load_combined(project_id.as_deref()).await;
                 if new_memory != memory_section {
                     memory_section = new_memory;
-                    system = if plan_mode {
+                    system = if hooks_engine.mode() == PermissionMode::Plan {
                         plan_system_prompt(cwd, &memory_section)
                     } else {
                         system_prompt(cwd, &memory_section)
@@ -421,15 +536,32 @@ pub(crate) async fn repl(ctx: ReplContext<'_>, startup: ReplStartup) -> Result<(
                     agent.set_system_prompt(system.clone());
                 }
 
+                // Elide stale tool results before full compaction is considered
+                let mut info = context_tracker.estimate(&session.messages, Some(&system));
+                if info.usage_percent() > MICRO_COMPACT_THRESHOLD {
+                    if let Some(pass) = chet_session::micro_compact(&mut session.messages) {
+                        session.micro_compaction.add(&pass);
+                        session.updated_at = Utc::now();
+                        if let Err(e) = store.save(&session).await {
+                            tracing::warn!("Failed to save after micro-compaction: {e}");
+                        }
+                        eprintln!(
+                            "(micro-compacted: elided {} old tool results, ~{:.1}k tokens reclaimed)",
+                            pass.results_elided,
+                            pass.tokens_reclaimed as f64 / 1000.0
+                        );
+                        info = context_tracker.estimate(&session.messages, Some(&system));
+                    }
+                }
+
                 // Update status line with latest context info
-                let info = context_tracker.estimate(&session.messages, Some(&system));
                 if let Some(sl) = &status_line {
                     sl.lock().unwrap().update_field(|d| {
                         d.context_tokens_k = info.estimated_tokens as f64 / 1000.0;
                         d.context_percent = info.usage_percent();
                         d.input_tokens = session.total_usage.input_tokens;
                         d.output_tokens = session.total_usage.output_tokens;
-                        d.plan_mode = plan_mode;
+                        d.mode = mode::badge(hooks_engine.mode());
                         d.effort = agent.effort();
                     });
                 } else {
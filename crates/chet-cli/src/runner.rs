@@ -3,8 +3,9 @@
 use anyhow::Result;
 use chet_config::ChetConfig;
 use chet_core::{Agent, AgentEvent, SubagentTool};
-use chet_mcp::{McpManager, McpTool};
-use chet_permissions::PermissionEngine;
+use chet_mcp::{McpConfig, McpManager, McpRequestHandler, McpStartOptions, McpTool, McpTrustStore};
+use chet_permissions::{AuditLog, PermissionEngine, PromptHandler, PromptResponse, Workspace};
+use chet_session::MemoryManager;
 use chet_terminal::StreamingMarkdownRenderer;
 use chet_tools::ToolRegistry;
 use chet_types::{Message, Usage, provider::Provider};
@@ -22,6 +23,7 @@ pub(crate) fn create_agent(
     cwd: &std::path::Path,
     mcp_manager: &Option<McpManager>,
     project_id: Option<String>,
+    team_root: Option<std::path::PathBuf>,
 ) -> Agent {
     let mut registry = ToolRegistry::with_builtins();
     registry.register(Arc::new(SubagentTool::new(
@@ -33,20 +35,20 @@ pub(crate) fn create_agent(
     )));
 
     // Register memory tools
-    registry.register(Arc::new(chet_tools::MemoryReadTool::new(
-        config.memory_dir.clone(),
-        project_id.clone(),
-    )));
-    registry.register(Arc::new(chet_tools::MemoryWriteTool::new(
-        config.memory_dir.clone(),
-        project_id,
-    )));
+    let mut memory_read =
+        chet_tools::MemoryReadTool::new(config.memory_dir.clone(), project_id.clone());
+    let mut memory_write = chet_tools::MemoryWriteTool::new(config.memory_dir.clone(), project_id);
+    if let Some(root) = team_root {
+        memory_read = memory_read.with_team_root(root.clone());
+        memory_write = memory_write.with_team_root(root);
+    }
+    registry.register(Arc::new(memory_read));
+    registry.register(Arc::new(memory_write));
 
     // Register MCP tools
     if let Some(manager) = mcp_manager {
-        for (client, tool_info) in manager.tools() {
-            let server_name = client.server_name().to_string();
-            registry.register(Arc::new(McpTool::new(&server_name, tool_info, client)));
+        for (server, tool_info) in manager.tools() {
+            registry.register(Arc::new(McpTool::new(tool_info, server)));
         }
     }
 
@@ -215,6 +217,15 @@ pub(crate) async fn run_agent(
                     chet_terminal::style::tool_blocked(&name, &reason, stderr_is_tty)
                 );
             }
+            AgentEvent::StopBlocked { reason } => {
+                spinner.set_active(false);
+                chet_terminal::spinner::clear_line(stderr_is_tty);
+                renderer.finish();
+                let _ = writeln!(io::stderr(), "Stop hook: {reason} (continuing)");
+                spinner.set_message(&thinking_msg);
+                spinner.set_active(true);
+                first_text = true;
+            }
             AgentEvent::Cancelled => {
                 spinner.set_active(false);
                 chet_terminal::spinner::clear_line(stderr_is_tty);
@@ -253,15 +264,136 @@ pub(crate) async fn run_agent(
     }
 }
 
+/// A permission engine with the configured rules and hooks, under the
+/// managed policy's rules and hooks.
+pub(crate) fn permission_engine(
+    config: &ChetConfig,
+    prompt_handler: Option<Arc<dyn PromptHandler>>,
+) -> PermissionEngine {
+    PermissionEngine::new(
+        config.permission_rules.clone(),
+        config.hooks.clone(),
+        prompt_handler,
+    )
+    .with_managed_rules(config.managed.permissions.rules.clone())
+    .with_managed_hooks(config.managed.hooks.clone())
+}
+
+/// The workspace trust boundary for `cwd`: its git root (or `cwd` itself)
+/// plus the configured `additional_directories`.
+pub(crate) async fn workspace(config: &ChetConfig, cwd: &std::path::Path) -> Workspace {
+    let root = repo_root(cwd).await;
+    Workspace::new(&root, &config.additional_directories)
+        .with_cwd(cwd)
+        .with_outside_access(config.outside_workspace)
+}
+
+/// Project ID for `cwd`, derived from its git root (or `cwd` itself) so
+/// every worktree and subdirectory of a repo shares memory and audit logs.
+pub(crate) async fn project_id(cwd: &std::path::Path) -> String {
+    MemoryManager::project_id(&repo_root(cwd).await)
+}
+
+/// The git root of `cwd`, or `cwd` itself outside a repo. Team memory lives
+/// in its `.chet/memory.md`.
+pub(crate) async fn repo_root(cwd: &std::path::Path) -> std::path::PathBuf {
+    chet_core::worktree::git_repo_root(cwd)
+        .await
+        .unwrap_or_else(|_| cwd.to_path_buf())
+}
+
+/// The permission audit log for a project.
+pub(crate) fn audit_log(config: &ChetConfig, project_id: &str) -> AuditLog {
+    AuditLog::new(&config.config_dir.join("audit"), project_id)
+}
+
 /// Start MCP servers from config. Returns None if no servers configured.
-pub(crate) async fn start_mcp_servers(config: &ChetConfig) -> Option<McpManager> {
-    if config.mcp.servers.is_empty() {
+/// `handler` serves the servers' sampling and elicitation requests.
+///
+/// Project-defined servers are only included once trusted; see
+/// [`trusted_project_servers`].
+pub(crate) async fn start_mcp_servers(
+    config: &ChetConfig,
+    handler: Arc<dyn McpRequestHandler>,
+    project_id: Option<&str>,
+    prompt_handler: Option<&dyn PromptHandler>,
+    auto_approve: bool,
+) -> Option<McpManager> {
+    let mut mcp = config.mcp.clone();
+    if let Some(project_id) = project_id {
+        let trust_path = McpTrustStore::path_for(&config.config_dir, project_id);
+        let trusted =
+            trusted_project_servers(config, trust_path, prompt_handler, auto_approve).await;
+        mcp.servers.extend(trusted.servers);
+    }
+    if mcp.servers.is_empty() {
         return None;
     }
-    let manager = McpManager::start(&config.mcp).await;
-    if manager.client_count() > 0 {
+    let options = McpStartOptions {
+        handler: Some(handler),
+        tool_cache_dir: Some(config.config_dir.join("mcp-cache")),
+    };
+    let manager = McpManager::start_with_options(&mcp, options).await;
+    if manager.server_count() > 0 {
         Some(manager)
     } else {
         None
     }
 }
+
+/// Filter `config.project_mcp` down to the servers the user has trusted.
+///
+/// Servers approved before (with the same command line) start silently.
+/// Others are prompted for: "always allow" persists the approval for this
+/// project, "allow once" starts the server for this session only. Without a
+/// prompt handler, untrusted servers are skipped with a warning.
+pub(crate) async fn trusted_project_servers(
+    config: &ChetConfig,
+    trust_path: std::path::PathBuf,
+    prompt_handler: Option<&dyn PromptHandler>,
+    auto_approve: bool,
+) -> McpConfig {
+    let mut trusted = McpConfig::default();
+    if config.project_mcp.servers.is_empty() {
+        return trusted;
+    }
+    let mut store = McpTrustStore::load(trust_path);
+
+    let mut names: Vec<&String> = config.project_mcp.servers.keys().collect();
+    names.sort();
+    for name in names {
+        let server = &config.project_mcp.servers[name];
+        let approved = if auto_approve || store.is_trusted(name, server) {
+            true
+        } else if let Some(handler) = prompt_handler {
+            let description = format!(
+                "Project MCP server '{name}' wants to run: {} {}",
+                server.command,
+                server.args.join(" ")
+            );
+            let input = serde_json::json!({ "command": server.command, "args": server.args });
+            match handler
+                .prompt_permission(&format!("mcp__{name}"), &input, &description, None, &[])
+                .await
+            {
+                PromptResponse::AllowOnce => true,
+                PromptResponse::AlwaysAllow | PromptResponse::AlwaysAllowSaved(_) => {
+                    if let Err(e) = store.trust(name, server) {
+                        eprintln!("Warning: failed to save MCP trust decision: {e}");
+                    }
+                    true
+                }
+                PromptResponse::Deny => false,
+            }
+        } else {
+            eprintln!(
+                "Warning: skipping untrusted project MCP server '{name}' (run chet interactively to approve it)"
+            );
+            false
+        };
+        if approved {
+            trusted.servers.insert(name.clone(), server.clone());
+        }
+    }
+    trusted
+}
@@ -0,0 +1,114 @@
+//! `chet mcp serve` — expose chet's built-in tools as an MCP server.
+
+use anyhow::Result;
+use chet_config::ChetConfig;
+use chet_mcp::{HttpAccess, McpServer, ToolBackend};
+use chet_permissions::{PermissionEngine, PermissionMode};
+use chet_tools::ToolRegistry;
+use chet_types::{ToolContext, ToolDefinition, ToolOutput};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Runs MCP tool calls through the built-in registry and the same
+/// permission/hook pipeline the agent uses.
+struct BuiltinToolBackend {
+    registry: ToolRegistry,
+    permissions: Arc<PermissionEngine>,
+    cwd: PathBuf,
+}
+
+impl ToolBackend for BuiltinToolBackend {
+    fn definitions(&self) -> Vec<ToolDefinition> {
+        self.registry
+            .definitions()
+            .into_iter()
+            .filter(|d| !self.permissions.is_tool_blocked(&d.name))
+            .collect()
+    }
+
+    fn is_read_only(&self, name: &str) -> bool {
+        self.registry.is_read_only(name).unwrap_or(false)
+    }
+
+    fn call_tool<'a>(
+        &'a self,
+        name: &'a str,
+        arguments: serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = ToolOutput> + Send + 'a>> {
+        Box::pin(async move {
+            let ctx = ToolContext {
+                cwd: self.cwd.clone(),
+                env: std::env::vars().collect(),
+                sandboxed: false,
+            };
+            chet_core::execute_tool_call(
+                &self.registry,
+                &self.permissions,
+                ctx,
+                name,
+                arguments,
+                false,
+            )
+            .await
+        })
+    }
+}
+
+/// Serve the built-in tools over stdio, or HTTP when `http_addr` is set.
+///
+/// The engine has no prompt handler — there is no terminal to ask on — so
+/// every call that would normally prompt (any non-read-only tool without a
+/// `permit` rule, or file access outside the workspace) is denied. Use
+/// `permit` rules, `--permission-mode acceptEdits` (for edits) or
+/// `--ludicrous` to allow them.
+///
+/// Over HTTP, clients must send `Authorization: Bearer <token>`. The token is
+/// read from `CHET_MCP_TOKEN`, or generated and printed to stderr at startup.
+pub(crate) async fn run(
+    config: &ChetConfig,
+    cwd: PathBuf,
+    mode: PermissionMode,
+    http_addr: Option<&str>,
+    allow_remote: bool,
+) -> Result<()> {
+    let audit = crate::runner::audit_log(config, &crate::runner::project_id(&cwd).await);
+    let permissions = Arc::new(
+        crate::runner::permission_engine(config, None)
+            .with_workspace(crate::runner::workspace(config, &cwd).await)
+            .with_mode(mode)
+            .with_audit_log(audit),
+    );
+    // Large results are written where only this user can read them
+    permissions.set_tool_output_dir(&config.config_dir.join("serve").join("tool-output"));
+    let backend = BuiltinToolBackend {
+        registry: ToolRegistry::with_builtins(),
+        permissions,
+        cwd,
+    };
+    let server = Arc::new(McpServer::new(Arc::new(backend)));
+
+    match http_addr {
+        Some(addr) => {
+            let access = match std::env::var("CHET_MCP_TOKEN") {
+                Ok(token) if !token.is_empty() => HttpAccess {
+                    token,
+                    allow_remote,
+                },
+                _ => {
+                    let access = HttpAccess {
+                        allow_remote,
+                        ..HttpAccess::with_random_token()
+                    };
+                    eprintln!("Bearer token: {}", access.token);
+                    access
+                }
+            };
+            eprintln!("chet MCP server listening on http://{addr}");
+            server.serve_http(addr, access).await?;
+        }
+        None => server.serve_stdio().await?,
+    }
+    Ok(())
+}
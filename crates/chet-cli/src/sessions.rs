@@ -0,0 +1,470 @@
+//! `chet sessions` and the session listings shared with `/sessions`.
+
+use anyhow::Result;
+use chet_config::ChetConfig;
+use chet_session::{
+    ExportFormat, Redaction, Retention, SearchHit, Session, SessionStore, SessionSummary,
+};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+
+#[derive(clap::Subcommand)]
+pub(crate) enum SessionsCommands {
+    /// Search saved sessions' messages, tool inputs and labels
+    Search {
+        /// Words to find; each must start a word in the session
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// Only sessions started in the current directory
+        #[arg(long)]
+        here: bool,
+        /// Show at most this many results (0 = all)
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Export a session as Markdown, HTML or JSON
+    Export {
+        /// Session ID or prefix
+        id: String,
+        /// md, html or json
+        #[arg(long, default_value = "md")]
+        format: ExportFormat,
+        /// Write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+        /// Redact secrets and replace home directory paths with ~
+        #[arg(long)]
+        redact: bool,
+    },
+    /// Delete sessions beyond the [sessions] retention_days / max_count limits
+    Gc {
+        /// Delete sessions not updated for this many days (overrides config)
+        #[arg(long)]
+        retention_days: Option<u32>,
+        /// Keep at most this many sessions (overrides config)
+        #[arg(long)]
+        max_count: Option<usize>,
+        /// List what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show disk used by saved sessions, per project
+    Du,
+}
+
+pub(crate) async fn run(config: &ChetConfig, cwd: &Path, command: &SessionsCommands) -> Result<()> {
+    let store = SessionStore::new(config.config_dir.clone()).await?;
+    match command {
+        SessionsCommands::Search { query, here, limit } => {
+            let cwd = cwd.display().to_string();
+            let mut hits = store.search(&query.join(" ")).await?;
+            hits.retain(|hit| !here || hit.summary.cwd == cwd);
+            if *limit > 0 {
+                hits.truncate(*limit);
+            }
+            if hits.is_empty() {
+                eprintln!("No matching sessions.");
+                return Ok(());
+            }
+            use std::io::IsTerminal;
+            let color = std::io::stdout().is_terminal();
+            for (i, hit) in hits.iter().enumerate() {
+                println!("{}", format_hit(i + 1, hit, color));
+            }
+            eprintln!("\nResume with: chet --resume <id>");
+        }
+        SessionsCommands::Export {
+            id,
+            format,
+            output,
+            redact,
+        } => {
+            let session = store.load_by_prefix(id).await?;
+            let rendered = export(&session, *format, *redact);
+            match output {
+                Some(path) => {
+                    std::fs::write(path, rendered)?;
+                    eprintln!(
+                        "Exported session {} to {}",
+                        session.short_id(),
+                        path.display()
+                    );
+                }
+                None => print!("{rendered}"),
+            }
+        }
+        SessionsCommands::Gc {
+            retention_days,
+            max_count,
+            dry_run,
+        } => {
+            let configured = retention(config);
+            let retention = Retention {
+                retention_days: retention_days.or(configured.retention_days),
+                max_count: max_count.or(configured.max_count),
+            };
+            if retention.is_unlimited() {
+                eprintln!(
+                    "No retention limits set. Add retention_days or max_count under [sessions] in config.toml, or pass --retention-days / --max-count."
+                );
+                return Ok(());
+            }
+            let removed = store.gc(&retention, &[], *dry_run).await?;
+            for (i, s) in removed.iter().enumerate() {
+                println!("{}", format_summary(i + 1, 0, s));
+            }
+            let verb = if *dry_run { "Would delete" } else { "Deleted" };
+            eprintln!("{verb} {} sessions.", removed.len());
+        }
+        SessionsCommands::Du => {
+            let usage = store.disk_usage().await?;
+            if usage.is_empty() {
+                eprintln!("No saved sessions.");
+                return Ok(());
+            }
+            let mut roots = HashMap::new();
+            for (s, _) in &usage {
+                if !roots.contains_key(&s.cwd) {
+                    let root = chet_core::worktree::git_repo_root(Path::new(&s.cwd))
+                        .await
+                        .map(|r| r.display().to_string())
+                        .unwrap_or_else(|_| s.cwd.clone());
+                    roots.insert(s.cwd.clone(), root);
+                }
+            }
+            let projects = by_project(&usage, |cwd| roots[cwd].clone());
+            let total: u64 = projects.iter().map(|p| p.bytes).sum();
+            for p in &projects {
+                println!(
+                    "{:>10}  {:>4} sessions  {}",
+                    format_bytes(p.bytes),
+                    p.sessions,
+                    p.path
+                );
+            }
+            println!(
+                "{:>10}  {:>4} sessions  total in {}",
+                format_bytes(total),
+                usage.len(),
+                SessionStore::dir_in(&config.config_dir).display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The `[sessions]` retention limits from config.
+pub(crate) fn retention(config: &ChetConfig) -> Retention {
+    Retention {
+        retention_days: config.sessions.retention_days,
+        max_count: config.sessions.max_count,
+    }
+}
+
+/// Disk usage of one project's sessions.
+#[derive(Debug, PartialEq)]
+struct ProjectUsage {
+    path: String,
+    sessions: usize,
+    bytes: u64,
+}
+
+/// Sum session sizes per project (as `project_of` maps a session's cwd),
+/// largest first.
+fn by_project(
+    usage: &[(SessionSummary, u64)],
+    project_of: impl Fn(&str) -> String,
+) -> Vec<ProjectUsage> {
+    let mut projects: HashMap<String, ProjectUsage> = HashMap::new();
+    for (s, bytes) in usage {
+        let path = project_of(&s.cwd);
+        let entry = projects.entry(path.clone()).or_insert(ProjectUsage {
+            path,
+            sessions: 0,
+            bytes: 0,
+        });
+        entry.sessions += 1;
+        entry.bytes += bytes;
+    }
+    let mut projects: Vec<ProjectUsage> = projects.into_values().collect();
+    projects.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    projects
+}
+
+/// `512 B`, `3.4 KB`, `12.0 MB`, `1.2 GB`
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// The session `-c/--continue` or `--resume [id]` asks for. `--resume` with
+/// an empty ID opens a picker.
+pub(crate) async fn resume_target(
+    config: &ChetConfig,
+    continue_session: bool,
+    resume: Option<&str>,
+    cwd: &Path,
+) -> Result<Option<Session>> {
+    if !continue_session && resume.is_none() {
+        return Ok(None);
+    }
+    let store = SessionStore::new(config.config_dir.clone()).await?;
+    let id = match resume {
+        Some(prefix) if !prefix.is_empty() => prefix.to_string(),
+        Some(_) => match pick_session(&store).await? {
+            Some(id) => id,
+            None => anyhow::bail!("No session selected"),
+        },
+        None => {
+            let root = chet_core::worktree::git_repo_root(cwd).await.ok();
+            let dirs: Vec<String> = std::iter::once(cwd)
+                .chain(root.as_deref())
+                .map(|d| d.display().to_string())
+                .collect();
+            let summaries = store.list().await?;
+            match latest_in(&summaries, &dirs) {
+                Some(s) => s.id.to_string(),
+                None => anyhow::bail!("No saved session for {} to continue", cwd.display()),
+            }
+        }
+    };
+    Ok(Some(store.load_by_prefix(&id).await?))
+}
+
+/// The most recently updated of `summaries` started in one of `dirs`.
+fn latest_in<'a>(summaries: &'a [SessionSummary], dirs: &[String]) -> Option<&'a SessionSummary> {
+    summaries
+        .iter()
+        .filter(|s| dirs.contains(&s.cwd))
+        .max_by_key(|s| s.updated_at)
+}
+
+/// Choose a saved session with the fuzzy picker; its full ID.
+async fn pick_session(store: &SessionStore) -> Result<Option<String>> {
+    use std::io::IsTerminal;
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        anyhow::bail!("--resume without an ID needs a terminal; pass an ID or use --continue");
+    }
+    let summaries = store.list().await?;
+    if summaries.is_empty() {
+        anyhow::bail!("No saved sessions");
+    }
+    let lines = summaries.iter().map(picker_line).collect();
+    let picked = chet_terminal::picker::pick("Resume session: ", lines).await?;
+    Ok(picked.map(|i| summaries[i].id.to_string()))
+}
+
+/// A picker row: ID, age, label or preview, and directory — all searchable.
+fn picker_line(s: &SessionSummary) -> String {
+    let title = match s.label.as_deref() {
+        Some(label) if !label.is_empty() => label,
+        _ if s.preview.is_empty() => "(empty)",
+        _ => &s.preview,
+    };
+    format!("{} {:>8}  {title}  {}", s.short_id(), s.age(), s.cwd)
+}
+
+/// Render `session`; `redact` hides secrets and home directory paths.
+pub(crate) fn export(session: &Session, format: ExportFormat, redact: bool) -> String {
+    let redaction = if redact {
+        Redaction {
+            secrets: true,
+            home: chet_config::home_dir().map(|h| h.display().to_string()),
+        }
+    } else {
+        Redaction::default()
+    };
+    chet_session::export(session, format, &redaction)
+}
+
+/// Sessions with each fork listed under its parent (most recent first at
+/// every level), paired with their depth. Forks whose parent is gone are roots.
+pub(crate) fn fork_tree(summaries: &[SessionSummary]) -> Vec<(usize, &SessionSummary)> {
+    let ids: HashSet<_> = summaries.iter().map(|s| s.id).collect();
+    let mut children: HashMap<_, Vec<&SessionSummary>> = HashMap::new();
+    let mut roots = Vec::new();
+    for s in summaries {
+        match s.parent_id.filter(|p| ids.contains(p)) {
+            Some(parent) => children.entry(parent).or_default().push(s),
+            None => roots.push(s),
+        }
+    }
+
+    let mut out = Vec::with_capacity(summaries.len());
+    // Depth-first; pushed in reverse so the most recent pops first
+    let mut stack: Vec<(usize, &SessionSummary)> =
+        roots.into_iter().rev().map(|s| (0, s)).collect();
+    while let Some((depth, s)) = stack.pop() {
+        out.push((depth, s));
+        if let Some(kids) = children.get(&s.id) {
+            stack.extend(kids.iter().rev().map(|k| (depth + 1, *k)));
+        }
+    }
+    out
+}
+
+/// One `/sessions` row: number, ID, age, size, model, label and preview,
+/// indented under its parent when it is a fork.
+pub(crate) fn format_summary(n: usize, depth: usize, s: &SessionSummary) -> String {
+    let branch = if depth == 0 {
+        String::new()
+    } else {
+        format!("{}└─ ", "   ".repeat(depth - 1))
+    };
+    let label = match s.label.as_deref() {
+        Some(label) if !label.is_empty() => format!(" [{label}]"),
+        _ => String::new(),
+    };
+    format!(
+        "  {n:>2}. {branch}{} {:>8}  {:>3} msgs  {}{}  {}",
+        s.short_id(),
+        s.age(),
+        s.message_count,
+        chet_terminal::statusline::shorten_model_name(&s.model),
+        label,
+        if s.preview.is_empty() {
+            "(empty)"
+        } else {
+            &s.preview
+        }
+    )
+}
+
+/// A search result: number, ID, age and directory, then the snippet.
+pub(crate) fn format_hit(n: usize, hit: &SearchHit, color: bool) -> String {
+    let s = &hit.summary;
+    let label = s
+        .label
+        .as_deref()
+        .map(|l| format!("  [{l}]"))
+        .unwrap_or_default();
+    format!(
+        "  {n:>2}. {} {:>8}  {}{label}\n      {}",
+        s.short_id(),
+        s.age(),
+        s.cwd,
+        highlight(&hit.snippet, &hit.highlights, color)
+    )
+}
+
+/// Mark `ranges` of `text`: bold on a terminal, `[...]` otherwise.
+fn highlight(text: &str, ranges: &[Range<usize>], color: bool) -> String {
+    let mut out = String::new();
+    let mut pos = 0;
+    for range in ranges {
+        out.push_str(&text[pos..range.start]);
+        let word = &text[range.clone()];
+        if color {
+            out.push_str(&chet_terminal::style::bold(word));
+        } else {
+            out.push_str(&format!("[{word}]"));
+        }
+        pos = range.end;
+    }
+    out.push_str(&text[pos..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlight_marks_ranges() {
+        let text = "fix the token refresh";
+        assert_eq!(
+            highlight(text, &[8..13, 14..21], false),
+            "fix the [token] [refresh]"
+        );
+        assert!(highlight(text, &[8..13, 14..21], true).contains('\x1b'));
+        assert_eq!(highlight(text, &[], false), text);
+    }
+
+    #[test]
+    fn continue_picks_the_latest_session_in_the_directory() {
+        let mut summaries: Vec<SessionSummary> = ["/repo", "/repo/sub", "/other", "/repo"]
+            .iter()
+            .map(|cwd| Session::new("m".into(), (*cwd).into()).to_summary())
+            .collect();
+        summaries[2].updated_at += chrono::Duration::hours(2);
+        summaries[3].updated_at += chrono::Duration::hours(1);
+        let latest = |dirs: &[&str]| {
+            let dirs: Vec<String> = dirs.iter().map(|d| d.to_string()).collect();
+            latest_in(&summaries, &dirs).map(|s| s.id)
+        };
+        assert_eq!(latest(&["/repo/sub"]), Some(summaries[1].id));
+        assert_eq!(latest(&["/repo/sub", "/repo"]), Some(summaries[3].id));
+        assert_eq!(latest(&["/elsewhere"]), None);
+    }
+
+    #[test]
+    fn usage_is_summed_per_project() {
+        let summary = |cwd: &str| Session::new("m".into(), cwd.into()).to_summary();
+        let usage = vec![
+            (summary("/repo"), 100),
+            (summary("/repo/sub"), 50),
+            (summary("/other"), 500),
+        ];
+        let projects = by_project(&usage, |cwd| {
+            if cwd.starts_with("/repo") {
+                "/repo".into()
+            } else {
+                cwd.into()
+            }
+        });
+        assert_eq!(
+            projects,
+            vec![
+                ProjectUsage {
+                    path: "/other".into(),
+                    sessions: 1,
+                    bytes: 500
+                },
+                ProjectUsage {
+                    path: "/repo".into(),
+                    sessions: 2,
+                    bytes: 150
+                },
+            ]
+        );
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MB");
+    }
+
+    #[test]
+    fn forks_are_listed_under_their_parent() {
+        let root = Session::new("m".into(), "/tmp".into());
+        let fork = root.fork();
+        let nested = fork.fork();
+        let orphan = {
+            let mut s = Session::new("m".into(), "/tmp".into()).fork();
+            s.metadata.parent_id = Some(Session::new("m".into(), "/tmp".into()).id);
+            s
+        };
+        // Most recent first, as the store lists them
+        let summaries: Vec<SessionSummary> = [&orphan, &nested, &fork, &root]
+            .iter()
+            .map(|s| s.to_summary())
+            .collect();
+        let tree: Vec<(usize, _)> = fork_tree(&summaries)
+            .into_iter()
+            .map(|(depth, s)| (depth, s.id))
+            .collect();
+        assert_eq!(
+            tree,
+            vec![(0, orphan.id), (0, root.id), (1, fork.id), (2, nested.id)]
+        );
+        assert!(format_summary(4, 2, &summaries[1]).contains("   └─ "));
+    }
+}
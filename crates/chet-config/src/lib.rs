@@ -1,12 +1,19 @@
 //! Multi-tier TOML configuration for Chet.
 //!
 //! Reads configuration from multiple sources with precedence:
-//! env vars > project > global > defaults
+//! managed policy > env vars > project > global > defaults
+
+mod managed;
+
+pub use managed::{
+    AllowedMcpServer, ManagedApi, ManagedMcp, ManagedPermissions, ManagedPolicy,
+    managed_policy_path,
+};
 
 use chet_api::RetryConfig;
 use chet_types::{AuthCredential, Effort};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// The default Anthropic API base URL.
 pub const DEFAULT_API_BASE_URL: &str = "https://api.anthropic.com";
@@ -37,13 +44,28 @@ pub struct ChetConfig {
     pub effort: Option<Effort>,
     pub retry: RetryConfig,
     pub config_dir: PathBuf,
+    /// Directory whose `.chet/` project config was loaded, if any.
+    pub project_dir: Option<PathBuf>,
     /// Directory for persistent memory files. Defaults to `<config_dir>/memory/`.
     pub memory_dir: PathBuf,
     pub permission_rules: Vec<chet_permissions::PermissionRule>,
+    /// Directories outside the project root that tools may use freely.
+    /// Relative entries are relative to the project root.
+    pub additional_directories: Vec<PathBuf>,
+    /// What happens on file access outside the workspace.
+    pub outside_workspace: chet_permissions::OutsideWorkspace,
     pub hooks: Vec<chet_permissions::HookConfig>,
     pub mcp: chet_mcp::McpConfig,
+    /// MCP servers defined by the project (`.mcp.json` and `.chet/config.toml`).
+    /// These need a one-time trust approval before they are started.
+    pub project_mcp: chet_mcp::McpConfig,
     /// Per-agent configuration profiles.
     pub agents: std::collections::HashMap<String, AgentConfig>,
+    /// How long saved sessions are kept.
+    pub sessions: SessionsSettings,
+    /// Administrator policy; its rules and hooks are kept separate from
+    /// `permission_rules` and `hooks` so user config cannot drop them.
+    pub managed: ManagedPolicy,
 }
 
 /// Settings that can be read from a TOML config file.
@@ -65,6 +87,8 @@ pub struct SettingsFile {
     /// Custom model aliases (e.g. { "fast" = "claude-haiku-4-5-20251001" }).
     #[serde(default)]
     pub models: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub sessions: SessionsSettings,
 }
 
 /// Per-agent configuration profile (used by SubagentTool and named agents).
@@ -81,11 +105,26 @@ pub struct AgentConfig {
     pub system_prompt: Option<String>,
 }
 
+/// Session retention from the `[sessions]` config section. Unset means
+/// sessions are kept forever.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionsSettings {
+    /// Delete sessions not updated for this many days.
+    pub retention_days: Option<u32>,
+    /// Keep at most this many sessions.
+    pub max_count: Option<usize>,
+}
+
 /// Permission rules section of the config file.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PermissionsSettings {
     #[serde(default)]
     pub rules: Vec<chet_permissions::PermissionRule>,
+    /// Extra workspace directories (`~/` is expanded).
+    #[serde(default)]
+    pub additional_directories: Vec<String>,
+    /// `prompt` (default) or `block` for file access outside the workspace.
+    pub outside_workspace: Option<chet_permissions::OutsideWorkspace>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -135,17 +174,38 @@ impl ChetConfig {
 
     /// Load configuration, optionally merging a project-level config.
     /// The project dir (e.g. a worktree or repo root) can contain `.chet/config.toml`
-    /// with hooks and permission rules that supplement the global config.
+    /// with hooks and permission rules that supplement the global config, and MCP
+    /// servers (there or in `.mcp.json`) that are collected into `project_mcp`.
+    /// A git-ignored `.chet/config.local.toml` is merged the same way, after it.
+    ///
+    /// The managed policy ([`managed_policy_path`]) is applied on top of
+    /// everything: a disallowed model is an error and disallowed MCP servers
+    /// are dropped.
     pub fn load_with_project_dir(
         overrides: CliOverrides,
         project_dir: Option<&std::path::Path>,
+    ) -> Result<Self, chet_types::ConfigError> {
+        let managed = ManagedPolicy::load(&managed_policy_path())?;
+        Self::load_with_policy(overrides, project_dir, managed)
+    }
+
+    fn load_with_policy(
+        overrides: CliOverrides,
+        project_dir: Option<&std::path::Path>,
+        managed: ManagedPolicy,
     ) -> Result<Self, chet_types::ConfigError> {
         let config_dir = config_dir();
         let global_settings = load_settings_file(&config_dir.join("config.toml"));
 
-        // Load project-level config if available
-        let project_settings =
-            project_dir.map(|dir| load_settings_file(&dir.join(".chet").join("config.toml")));
+        // Load project-level configs if available (shared, then local)
+        let project_settings: Vec<SettingsFile> = project_dir
+            .map(|dir| {
+                [RuleFile::Project, RuleFile::Local]
+                    .iter()
+                    .map(|file| load_settings_file(&file.path(dir)))
+                    .collect()
+            })
+            .unwrap_or_default();
 
         // Resolve auth credential: auth_token takes precedence over api_key at each tier.
         let credential = overrides
@@ -192,6 +252,9 @@ impl ChetConfig {
             .get(&raw_model)
             .cloned()
             .unwrap_or(raw_model);
+        if !managed.model_allowed(&model) {
+            return Err(managed.violation("api.model", format!("model '{model}' is not allowed")));
+        }
 
         // Resolve max tokens, clamped to what the model actually supports
         let max_tokens = overrides
@@ -242,11 +305,43 @@ impl ChetConfig {
 
         // Merge project-level hooks and permission rules (project supplements global)
         let mut permission_rules = global_settings.permissions.rules;
+        let additional_directories = global_settings.permissions.additional_directories;
+        let mut outside_workspace = global_settings
+            .permissions
+            .outside_workspace
+            .unwrap_or_default();
         let mut hooks = global_settings.hooks;
-        if let Some(ref proj) = project_settings {
+        for proj in &project_settings {
             permission_rules.extend(proj.permissions.rules.clone());
+            // A project can tighten the workspace boundary but not loosen it,
+            // so its additional directories are ignored
+            if !proj.permissions.additional_directories.is_empty() {
+                tracing::warn!(
+                    "Ignoring additional_directories from project config; set them in {}",
+                    config_dir.join("config.toml").display()
+                );
+            }
+            if proj.permissions.outside_workspace == Some(chet_permissions::OutsideWorkspace::Block)
+            {
+                outside_workspace = chet_permissions::OutsideWorkspace::Block;
+            }
             hooks.extend(proj.hooks.clone());
         }
+        let additional_directories = additional_directories
+            .iter()
+            .map(|dir| expand_home(dir))
+            .collect();
+
+        // Project MCP servers: `.chet/config.toml` wins over `.mcp.json`
+        let mut project_mcp = project_dir
+            .map(|dir| load_mcp_json(&dir.join(".mcp.json")))
+            .unwrap_or_default();
+        for proj in project_settings {
+            project_mcp.servers.extend(proj.mcp.servers);
+        }
+        let mut mcp = global_settings.mcp;
+        managed.filter_mcp_servers(&mut mcp);
+        managed.filter_mcp_servers(&mut project_mcp);
 
         Ok(ChetConfig {
             credential,
@@ -257,15 +352,109 @@ impl ChetConfig {
             effort,
             retry,
             permission_rules,
+            additional_directories,
+            outside_workspace,
             hooks,
-            mcp: global_settings.mcp,
+            mcp,
+            project_mcp,
             agents: global_settings.agents,
+            sessions: global_settings.sessions,
+            managed,
             config_dir,
+            project_dir: project_dir.map(|dir| dir.to_path_buf()),
             memory_dir,
         })
     }
 }
 
+/// Which project config file saved permission rules go to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleFile {
+    /// `.chet/config.toml`, shared with the team.
+    Project,
+    /// `.chet/config.local.toml`, git-ignored.
+    Local,
+}
+
+impl RuleFile {
+    /// Path of this file under a project dir.
+    pub fn path(self, project_dir: &Path) -> PathBuf {
+        let name = match self {
+            RuleFile::Project => "config.toml",
+            RuleFile::Local => "config.local.toml",
+        };
+        project_dir.join(".chet").join(name)
+    }
+}
+
+/// The exact TOML [`save_permission_rules`] appends for `rules`.
+pub fn format_permission_rules(rules: &[chet_permissions::PermissionRule]) -> String {
+    #[derive(Serialize)]
+    struct Rules<'a> {
+        rules: &'a [chet_permissions::PermissionRule],
+    }
+    #[derive(Serialize)]
+    struct Wrapper<'a> {
+        permissions: Rules<'a>,
+    }
+    toml::to_string(&Wrapper {
+        permissions: Rules { rules },
+    })
+    .unwrap_or_default()
+}
+
+/// Append permit rules to a project config file, creating it if needed, and
+/// return its path. The file is re-parsed first, so a write that would
+/// break it is refused. A new local file is added to `.chet/.gitignore`.
+pub fn save_permission_rules(
+    project_dir: &Path,
+    file: RuleFile,
+    rules: &[chet_permissions::PermissionRule],
+) -> Result<PathBuf, chet_types::ConfigError> {
+    let path = file.path(project_dir);
+    let io_error = |e: std::io::Error| chet_types::ConfigError::Parse {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    };
+
+    let existing = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(io_error(e)),
+    };
+    let mut content = existing;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    content.push_str(&format_permission_rules(rules));
+    toml::from_str::<SettingsFile>(&content).map_err(|e| chet_types::ConfigError::Parse {
+        path: path.display().to_string(),
+        message: format!("saving the rule would make the file invalid: {e}"),
+    })?;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    std::fs::write(&path, content).map_err(io_error)?;
+
+    if file == RuleFile::Local {
+        let gitignore = project_dir.join(".chet").join(".gitignore");
+        let ignored = std::fs::read_to_string(&gitignore).unwrap_or_default();
+        if !ignored.lines().any(|l| l.trim() == "config.local.toml") {
+            let mut ignored = ignored;
+            if !ignored.is_empty() && !ignored.ends_with('\n') {
+                ignored.push('\n');
+            }
+            ignored.push_str("config.local.toml\n");
+            std::fs::write(&gitignore, ignored).map_err(io_error)?;
+        }
+    }
+    Ok(path)
+}
+
 /// Get the Chet config directory path (~/.chet/).
 pub fn config_dir() -> PathBuf {
     if let Ok(dir) = std::env::var("CHET_CONFIG_DIR") {
@@ -276,6 +465,19 @@ pub fn config_dir() -> PathBuf {
         .join(".chet")
 }
 
+/// The user's home directory.
+pub fn home_dir() -> Option<PathBuf> {
+    dirs_next::home_dir()
+}
+
+/// Expand a leading `~/` to the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs_next::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
 /// Load and parse a TOML settings file, returning defaults on any error.
 fn load_settings_file(path: &std::path::Path) -> SettingsFile {
     match std::fs::read_to_string(path) {
@@ -287,6 +489,20 @@ fn load_settings_file(path: &std::path::Path) -> SettingsFile {
     }
 }
 
+/// Load a `.mcp.json` file, returning an empty config on any error.
+fn load_mcp_json(path: &std::path::Path) -> chet_mcp::McpConfig {
+    match std::fs::read_to_string(path) {
+        Ok(content) => {
+            chet_mcp::McpConfig::from_mcp_json(&content, |name| std::env::var(name).ok())
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to parse {}: {}", path.display(), e);
+                    chet_mcp::McpConfig::default()
+                })
+        }
+        Err(_) => chet_mcp::McpConfig::default(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -383,6 +599,28 @@ model = "claude-opus-4-6"
         assert!(settings.api.retry.max_delay_ms.is_none());
     }
 
+    #[test]
+    fn test_settings_workspace_boundary() {
+        let toml_str = r#"
+[permissions]
+additional_directories = ["~/shared", "../docs"]
+outside_workspace = "block"
+"#;
+        let settings: SettingsFile = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            settings.permissions.additional_directories,
+            vec!["~/shared", "../docs"]
+        );
+        assert_eq!(
+            settings.permissions.outside_workspace,
+            Some(chet_permissions::OutsideWorkspace::Block)
+        );
+        assert_eq!(expand_home("../docs"), PathBuf::from("../docs"));
+        if let Some(home) = dirs_next::home_dir() {
+            assert_eq!(expand_home("~/shared"), home.join("shared"));
+        }
+    }
+
     #[test]
     fn test_settings_missing_permissions_defaults_to_empty() {
         let toml_str = r#"
@@ -489,6 +727,20 @@ model = "claude-opus-4-6"
         assert!(settings.memory_dir.is_none());
     }
 
+    #[test]
+    fn test_settings_with_sessions() {
+        let toml_str = r#"
+[sessions]
+retention_days = 30
+max_count = 200
+"#;
+        let settings: SettingsFile = toml::from_str(toml_str).unwrap();
+        assert_eq!(settings.sessions.retention_days, Some(30));
+        assert_eq!(settings.sessions.max_count, Some(200));
+        let empty: SettingsFile = toml::from_str("").unwrap();
+        assert_eq!(empty.sessions, SessionsSettings::default());
+    }
+
     #[test]
     fn test_settings_with_agents() {
         let toml_str = r#"
@@ -635,4 +887,133 @@ model = "claude-opus-4-6"
         .unwrap();
         assert_eq!(config.max_tokens, 4_096);
     }
+
+    #[test]
+    fn test_load_mcp_json() {
+        let dir = std::env::temp_dir().join(format!("chet-mcp-json-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".mcp.json");
+        std::fs::write(
+            &path,
+            r#"{"mcpServers": {"docs": {"command": "node", "args": ["${CHET_TEST_UNSET_VAR:-server.js}"]}}}"#,
+        )
+        .unwrap();
+        let mcp = load_mcp_json(&path);
+        assert_eq!(mcp.servers["docs"].args, ["server.js"]);
+
+        std::fs::write(&path, "{ broken").unwrap();
+        assert!(load_mcp_json(&path).servers.is_empty());
+        assert!(load_mcp_json(&dir.join("missing.json")).servers.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn permit(tool: &str, args: &str) -> chet_permissions::PermissionRule {
+        chet_permissions::PermissionRule {
+            tool: tool.into(),
+            args: Some(args.into()),
+            level: chet_permissions::PermissionLevel::Permit,
+        }
+    }
+
+    #[test]
+    fn test_format_permission_rules() {
+        let toml = format_permission_rules(&[permit("Bash", "command:cargo test *")]);
+        assert_eq!(
+            toml,
+            "[[permissions.rules]]\ntool = \"Bash\"\nargs = \"command:cargo test *\"\nlevel = \"permit\"\n"
+        );
+    }
+
+    #[test]
+    fn test_save_permission_rules_appends() {
+        let dir = std::env::temp_dir().join(format!("chet-save-rules-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(".chet")).unwrap();
+        let config = RuleFile::Project.path(&dir);
+        std::fs::write(
+            &config,
+            "# team rules\n[[hooks]]\nevent = \"stop\"\ncommand = \"true\"",
+        )
+        .unwrap();
+
+        let rules = [permit("Bash", "command:cargo test *")];
+        assert_eq!(
+            save_permission_rules(&dir, RuleFile::Project, &rules).unwrap(),
+            config
+        );
+        let content = std::fs::read_to_string(&config).unwrap();
+        assert!(content.starts_with("# team rules\n"));
+        let settings: SettingsFile = toml::from_str(&content).unwrap();
+        assert_eq!(settings.hooks.len(), 1);
+        assert_eq!(settings.permissions.rules, rules);
+
+        // Local rules go to a git-ignored file
+        let local = save_permission_rules(&dir, RuleFile::Local, &rules).unwrap();
+        assert_eq!(local, dir.join(".chet/config.local.toml"));
+        let ignore = std::fs::read_to_string(dir.join(".chet/.gitignore")).unwrap();
+        assert_eq!(ignore, "config.local.toml\n");
+        save_permission_rules(&dir, RuleFile::Local, &rules).unwrap();
+        let ignore = std::fs::read_to_string(dir.join(".chet/.gitignore")).unwrap();
+        assert_eq!(ignore, "config.local.toml\n");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_permission_rules_refuses_to_break_file() {
+        let dir = std::env::temp_dir().join(format!("chet-save-bad-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(".chet")).unwrap();
+        let config = RuleFile::Project.path(&dir);
+        // An inline `rules` array can't be extended with `[[permissions.rules]]`
+        let original = "[permissions]\nrules = []\n";
+        std::fs::write(&config, original).unwrap();
+
+        let result = save_permission_rules(&dir, RuleFile::Project, &[permit("Read", "path:**")]);
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&config).unwrap(), original);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_managed_policy_rejects_model() {
+        let managed: ManagedPolicy =
+            toml::from_str("[api]\nallowed_models = [\"claude-sonnet-*\"]").unwrap();
+        let overrides = |model: &str| CliOverrides {
+            api_key: Some("sk-test".into()),
+            model: Some(model.into()),
+            ..Default::default()
+        };
+        let err = ChetConfig::load_with_policy(overrides("claude-opus-4-6"), None, managed.clone())
+            .unwrap_err();
+        assert!(err.to_string().contains("claude-opus-4-6"), "{err}");
+        let config =
+            ChetConfig::load_with_policy(overrides("claude-sonnet-4-5"), None, managed).unwrap();
+        assert_eq!(config.model, "claude-sonnet-4-5");
+        assert!(config.managed.api.allowed_models.is_some());
+    }
+
+    #[test]
+    fn test_project_cannot_widen_workspace() {
+        let dir = std::env::temp_dir().join(format!("chet-widen-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(".chet")).unwrap();
+        std::fs::write(
+            dir.join(".chet/config.toml"),
+            "[permissions]\nadditional_directories = [\"/\", \"~\"]\noutside_workspace = \"block\"\n",
+        )
+        .unwrap();
+        let overrides = CliOverrides {
+            api_key: Some("sk-test".into()),
+            ..Default::default()
+        };
+        let config =
+            ChetConfig::load_with_policy(overrides, Some(&dir), ManagedPolicy::default()).unwrap();
+        assert!(!config.additional_directories.contains(&PathBuf::from("/")));
+        assert!(!config.additional_directories.contains(&expand_home("~")));
+        // Tightening still applies
+        assert_eq!(
+            config.outside_workspace,
+            chet_permissions::OutsideWorkspace::Block
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
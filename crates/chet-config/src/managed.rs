@@ -0,0 +1,311 @@
+//! Managed (administrator) policy.
+//!
+//! A system-wide TOML file, normally `/etc/chet/managed.toml`, that user and
+//! project config cannot override. It can add rules and hooks that always
+//! apply, restrict providers, models and MCP servers, and forbid
+//! `--ludicrous`. A policy file that exists but cannot be read or parsed is an
+//! error: starting without the policy would silently drop its restrictions.
+//!
+//! ```toml
+//! allow_ludicrous = false
+//!
+//! [[permissions.rules]]
+//! tool = "Bash"
+//! args = "command:curl *"
+//! level = "block"
+//!
+//! [[hooks]]
+//! event = "before_tool"
+//! command = "/opt/corp/audit.sh"
+//!
+//! [api]
+//! allowed_providers = ["bedrock"]
+//! allowed_models = ["claude-sonnet-*"]
+//!
+//! [[mcp.allowed_servers]]
+//! name = "github"
+//! command = "gh-mcp"
+//! ```
+
+use chet_types::ConfigError;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Where the managed policy is read from on this platform.
+pub fn managed_policy_path() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/chet/managed.toml")
+    } else if cfg!(windows) {
+        PathBuf::from(r"C:\ProgramData\chet\managed.toml")
+    } else {
+        PathBuf::from("/etc/chet/managed.toml")
+    }
+}
+
+/// Settings locked by the managed policy. The default locks nothing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManagedPolicy {
+    /// The file this policy was loaded from; `None` when there is none.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    /// `false` forbids `--ludicrous` and bypass mode.
+    pub allow_ludicrous: Option<bool>,
+    #[serde(default)]
+    pub permissions: ManagedPermissions,
+    /// Hooks that always run, even in bypass mode.
+    #[serde(default)]
+    pub hooks: Vec<chet_permissions::HookConfig>,
+    #[serde(default)]
+    pub api: ManagedApi,
+    #[serde(default)]
+    pub mcp: ManagedMcp,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManagedPermissions {
+    /// Evaluated before every other layer; whatever they decide is final.
+    #[serde(default)]
+    pub rules: Vec<chet_permissions::PermissionRule>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManagedApi {
+    /// Provider names (`anthropic`, `bedrock`, `vertex`); unset allows all.
+    pub allowed_providers: Option<Vec<String>>,
+    /// Model ID globs (e.g. `claude-sonnet-*`); unset allows all.
+    pub allowed_models: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManagedMcp {
+    /// MCP servers that may be started; unset allows all.
+    pub allowed_servers: Option<Vec<AllowedMcpServer>>,
+}
+
+/// An allowlisted MCP server. Names are chosen by whoever writes the config,
+/// so a server only matches if it also runs the allowed command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AllowedMcpServer {
+    pub name: String,
+    pub command: String,
+    /// Exact arguments; unset allows any.
+    pub args: Option<Vec<String>>,
+}
+
+impl AllowedMcpServer {
+    fn matches(&self, name: &str, server: &chet_mcp::McpServerConfig) -> bool {
+        self.name == name
+            && self.command == server.command
+            && self.args.as_ref().is_none_or(|args| *args == server.args)
+    }
+}
+
+impl ManagedPolicy {
+    /// Load the policy at `path`. A missing file is an empty policy.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(ConfigError::Parse {
+                    path: path.display().to_string(),
+                    message: e.to_string(),
+                });
+            }
+        };
+        let mut policy: Self = toml::from_str(&content).map_err(|e| ConfigError::Parse {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        policy.path = Some(path.to_path_buf());
+        Ok(policy)
+    }
+
+    /// Whether `--ludicrous` / bypass mode may be used.
+    pub fn ludicrous_allowed(&self) -> bool {
+        self.allow_ludicrous != Some(false)
+    }
+
+    pub fn provider_allowed(&self, provider: &str) -> bool {
+        self.api
+            .allowed_providers
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|p| p.eq_ignore_ascii_case(provider)))
+    }
+
+    pub fn model_allowed(&self, model: &str) -> bool {
+        self.api
+            .allowed_models
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|pattern| glob_matches(pattern, model)))
+    }
+
+    pub fn mcp_server_allowed(&self, name: &str, server: &chet_mcp::McpServerConfig) -> bool {
+        self.mcp
+            .allowed_servers
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|s| s.matches(name, server)))
+    }
+
+    /// Error for a setting the policy forbids, naming the policy file.
+    pub fn violation(&self, key: &str, message: impl std::fmt::Display) -> ConfigError {
+        let source = self
+            .path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "managed policy".to_string());
+        ConfigError::InvalidValue {
+            key: key.to_string(),
+            message: format!("{message} (locked by {source})"),
+        }
+    }
+
+    /// Drop MCP servers the policy does not allow, with a warning.
+    pub(crate) fn filter_mcp_servers(&self, mcp: &mut chet_mcp::McpConfig) {
+        mcp.servers.retain(|name, server| {
+            let allowed = self.mcp_server_allowed(name, server);
+            if !allowed {
+                tracing::warn!("MCP server '{name}' is not allowed by managed policy; skipping");
+            }
+            allowed
+        });
+    }
+}
+
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    match globset::Glob::new(pattern) {
+        Ok(glob) => glob.compile_matcher().is_match(value),
+        Err(_) => pattern == value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(toml_str: &str) -> ManagedPolicy {
+        toml::from_str(toml_str).unwrap()
+    }
+
+    #[test]
+    fn empty_policy_allows_everything() {
+        let p = ManagedPolicy::default();
+        assert!(p.ludicrous_allowed());
+        assert!(p.provider_allowed("vertex"));
+        assert!(p.model_allowed("claude-opus-4-6"));
+        let server: chet_mcp::McpServerConfig = toml::from_str("command = \"x\"").unwrap();
+        assert!(p.mcp_server_allowed("anything", &server));
+    }
+
+    #[test]
+    fn policy_locks_settings() {
+        let p = policy(
+            r#"
+allow_ludicrous = false
+
+[[permissions.rules]]
+tool = "Bash"
+args = "command:curl *"
+level = "block"
+
+[[hooks]]
+event = "before_tool"
+command = "/opt/corp/audit.sh"
+
+[api]
+allowed_providers = ["bedrock"]
+allowed_models = ["claude-sonnet-*"]
+
+[[mcp.allowed_servers]]
+name = "github"
+command = "gh-mcp"
+"#,
+        );
+        assert!(!p.ludicrous_allowed());
+        assert_eq!(p.permissions.rules.len(), 1);
+        assert_eq!(p.hooks.len(), 1);
+        assert!(p.provider_allowed("Bedrock"));
+        assert!(!p.provider_allowed("anthropic"));
+        assert!(p.model_allowed("claude-sonnet-4-5-20250929"));
+        assert!(!p.model_allowed("claude-opus-4-6"));
+
+        let mut mcp: chet_mcp::McpConfig = toml::from_str(
+            r#"
+[servers.github]
+command = "gh-mcp"
+
+[servers.random]
+command = "random-mcp"
+"#,
+        )
+        .unwrap();
+        p.filter_mcp_servers(&mut mcp);
+        assert_eq!(mcp.servers.keys().collect::<Vec<_>>(), vec!["github"]);
+    }
+
+    #[test]
+    fn mcp_allowlist_checks_what_runs() {
+        let p = policy(
+            r#"
+[[mcp.allowed_servers]]
+name = "github"
+command = "gh-mcp"
+args = ["--read-only"]
+"#,
+        );
+        let mut mcp: chet_mcp::McpConfig = toml::from_str(
+            r#"
+[servers.github]
+command = "curl"
+args = ["--read-only"]
+"#,
+        )
+        .unwrap();
+        p.filter_mcp_servers(&mut mcp);
+        assert!(mcp.servers.is_empty());
+
+        let server = |args: &str| -> chet_mcp::McpServerConfig {
+            toml::from_str(&format!("command = \"gh-mcp\"\nargs = {args}")).unwrap()
+        };
+        assert!(p.mcp_server_allowed("github", &server(r#"["--read-only"]"#)));
+        assert!(!p.mcp_server_allowed("github", &server(r#"["--write"]"#)));
+        assert!(!p.mcp_server_allowed("gh", &server(r#"["--read-only"]"#)));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        // A typo must not silently drop a restriction
+        assert!(toml::from_str::<ManagedPolicy>("allow_ludicrus = false").is_err());
+        assert!(toml::from_str::<ManagedPolicy>("[api]\nallowed_model = [\"x\"]").is_err());
+    }
+
+    #[test]
+    fn load_missing_and_invalid_files() {
+        let dir = std::env::temp_dir().join(format!("chet-managed-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let missing = ManagedPolicy::load(&dir.join("missing.toml")).unwrap();
+        assert!(missing.path.is_none());
+
+        let path = dir.join("managed.toml");
+        std::fs::write(&path, "allow_ludicrous = \"no\"").unwrap();
+        assert!(ManagedPolicy::load(&path).is_err());
+
+        std::fs::write(&path, "allow_ludicrous = false").unwrap();
+        let loaded = ManagedPolicy::load(&path).unwrap();
+        assert_eq!(loaded.path.as_deref(), Some(path.as_path()));
+        assert!(
+            loaded
+                .violation("permission_mode", "bypass is not allowed")
+                .to_string()
+                .contains(&path.display().to_string())
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
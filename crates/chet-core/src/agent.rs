@@ -1,10 +1,11 @@
 //! The core agent loop that orchestrates conversation with tool use.
 
-use crate::util::{finalize_tool_result, fire_stop_failure_hook};
-use chet_permissions::{
-    HookEvent, HookInput, PermissionDecision, PermissionEngine, PermissionLevel, PermissionRule,
-    PromptResponse,
+use crate::tool_call::authorize_tool_call;
+use crate::util::{
+    default_tool_output_dir, finalize_tool_result, fire_stop_failure_hook, truncate_for_display,
+    with_hook_context,
 };
+use chet_permissions::{HookDecision, HookEvent, HookInput, PermissionEngine};
 use chet_tools::ToolRegistry;
 use chet_types::{
     CacheControl, ContentBlock, ContentDelta, CreateMessageRequest, Effort, Message, Role,
@@ -14,12 +15,15 @@ use chet_types::{
 };
 use futures_util::StreamExt;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio_util::sync::CancellationToken;
 
 /// Maximum number of consecutive tool-use loops before stopping.
 const MAX_TOOL_LOOPS: usize = 50;
 
+/// Maximum times Stop hooks may send the agent back to work in one run.
+const MAX_STOP_HOOK_CONTINUATIONS: usize = 5;
+
 /// Events emitted by the agent during execution.
 #[derive(Debug)]
 pub enum AgentEvent {
@@ -41,23 +45,47 @@ pub enum AgentEvent {
     Done,
     /// A tool call was blocked by the permission system.
     ToolBlocked { name: String, reason: String },
+    /// A Stop hook refused to let the agent finish; it continues with the reason.
+    StopBlocked { reason: String },
     /// The operation was cancelled (e.g. Ctrl+C).
     Cancelled,
     /// An error occurred.
     Error(String),
 }
 
+/// The size of the prompt the API read for one request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptSize {
+    /// Input tokens the API reported, including cache reads and writes.
+    pub tokens: u64,
+    /// How many conversation messages the request contained.
+    pub messages: usize,
+}
+
 /// Result of collecting the assistant's streaming response.
 struct CollectResult {
     content_blocks: Vec<ContentBlock>,
     stop_reason: Option<StopReason>,
     tool_uses: Vec<(String, String, serde_json::Value)>,
+    /// Prompt tokens from `message_start`, if the provider reported any.
+    prompt_tokens: Option<u64>,
 }
 
 /// Result of checking permissions for tool uses.
 struct PermissionCheckResult {
     tool_results: Vec<Option<ContentBlock>>,
-    permitted_tools: Vec<(usize, String, String, serde_json::Value, bool)>,
+    permitted_tools: Vec<PermittedTool>,
+}
+
+/// A tool use that passed permission checks and before_tool hooks.
+struct PermittedTool {
+    idx: usize,
+    id: String,
+    name: String,
+    input: serde_json::Value,
+    is_read_only: bool,
+    /// Additional context from before_tool hooks.
+    context: Vec<String>,
 }
 
 /// The main agent that manages conversation with the LLM and tool execution.
@@ -71,7 +99,13 @@ pub struct Agent {
     thinking_budget: Option<u32>,
     effort: Option<Effort>,
     cwd: PathBuf,
+    /// Where tool results too large to inline are written.
+    tool_output_dir: PathBuf,
     read_only_mode: bool,
+    /// Hook event fired before finishing: `Stop`, or `SubagentStop` for children.
+    stop_event: HookEvent,
+    /// Prompt size of the most recent request, for context calibration.
+    last_prompt: Mutex<Option<PromptSize>>,
 }
 
 impl Agent {
@@ -83,6 +117,9 @@ impl Agent {
         max_tokens: u32,
         cwd: PathBuf,
     ) -> Self {
+        let tool_output_dir = permissions
+            .tool_output_dir()
+            .unwrap_or_else(default_tool_output_dir);
         Self {
             provider,
             registry,
@@ -93,7 +130,10 @@ impl Agent {
             thinking_budget: None,
             effort: None,
             cwd,
+            tool_output_dir,
             read_only_mode: false,
+            stop_event: HookEvent::Stop,
+            last_prompt: Mutex::new(None),
         }
     }
 
@@ -117,11 +157,46 @@ impl Agent {
         self.read_only_mode = enabled;
     }
 
+    /// Set the hook event fired before the agent finishes (`SubagentStop`
+    /// for child agents).
+    pub fn set_stop_event(&mut self, event: HookEvent) {
+        self.stop_event = event;
+    }
+
     /// Update the agent's working directory (e.g., after exiting a worktree).
     pub fn set_cwd(&mut self, cwd: PathBuf) {
         self.cwd = cwd;
     }
 
+    /// Write tool results too large to inline to `dir` (normally the session's
+    /// directory) instead of the system temp directory. The permission engine
+    /// lets read-only tools open it, and subagents sharing the engine write
+    /// there too.
+    pub fn set_tool_output_dir(&mut self, dir: PathBuf) {
+        self.permissions.set_tool_output_dir(&dir);
+        self.tool_output_dir = dir;
+    }
+
+    /// Prompt size of the last request in the most recent run, if the API
+    /// reported one.
+    pub fn last_prompt_size(&self) -> Option<PromptSize> {
+        *self.last_prompt.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Exact input token count for `messages` with this agent's system prompt
+    /// and tools, or `None` if the provider can't count.
+    pub async fn count_tokens(
+        &self,
+        messages: &[Message],
+    ) -> Result<Option<u64>, chet_types::ChetError> {
+        let mut messages = messages.to_vec();
+        let request = self.build_request(&mut messages);
+        self.provider
+            .count_tokens(&request)
+            .await
+            .map_err(chet_types::ChetError::Api)
+    }
+
     /// Run the agent loop: send messages, handle tool calls, repeat until done.
     ///
     /// The callback receives AgentEvents as they occur (for streaming UI).
@@ -136,9 +211,12 @@ impl Agent {
         F: FnMut(AgentEvent),
     {
         let mut total_usage = Usage::default();
+        let mut stop_continuations = 0;
+        *self.last_prompt.lock().unwrap_or_else(|e| e.into_inner()) = None;
 
         for _loop_iter in 0..MAX_TOOL_LOOPS {
             let mut request = self.build_request(messages);
+            let sent = request.messages.len();
             let stream_result = self.provider.create_message_stream(&request).await;
             *messages = std::mem::take(&mut request.messages);
             let stream = stream_result.map_err(chet_types::ChetError::Api)?;
@@ -146,6 +224,12 @@ impl Agent {
             let result = self
                 .collect_response(stream, &cancel, &mut on_event, &mut total_usage)
                 .await?;
+            if let Some(tokens) = result.prompt_tokens {
+                *self.last_prompt.lock().unwrap_or_else(|e| e.into_inner()) = Some(PromptSize {
+                    tokens,
+                    messages: sent,
+                });
+            }
 
             if !result.content_blocks.is_empty() {
                 messages.push(Message {
@@ -155,6 +239,25 @@ impl Agent {
             }
 
             if result.tool_uses.is_empty() || result.stop_reason == Some(StopReason::EndTurn) {
+                if result.tool_uses.is_empty() {
+                    if let Some(reason) = self.run_stop_hooks(messages).await {
+                        if stop_continuations < MAX_STOP_HOOK_CONTINUATIONS {
+                            stop_continuations += 1;
+                            on_event(AgentEvent::StopBlocked {
+                                reason: reason.clone(),
+                            });
+                            push_user_text(
+                                messages,
+                                format!("A stop hook asked you to keep working: {reason}"),
+                            );
+                            continue;
+                        }
+                        on_event(AgentEvent::Error(format!(
+                            "Stop hook still blocking after {MAX_STOP_HOOK_CONTINUATIONS} \
+                             continuations; stopping: {reason}"
+                        )));
+                    }
+                }
                 on_event(AgentEvent::Done);
                 on_event(AgentEvent::Usage(total_usage.clone()));
                 return Ok(total_usage);
@@ -172,15 +275,15 @@ impl Agent {
             let mut tool_results = check.tool_results;
 
             // Execute permitted tools: read-only in parallel, mutating sequentially
-            let (read_only, mutating): (Vec<_>, Vec<_>) =
-                check.permitted_tools.into_iter().partition(|t| t.4);
+            let (read_only, mutating): (Vec<_>, Vec<_>) = check
+                .permitted_tools
+                .into_iter()
+                .partition(|t| t.is_read_only);
 
             if !read_only.is_empty() {
                 let futures: Vec<_> = read_only
                     .iter()
-                    .map(|(_, _, name, input, _)| {
-                        self.registry.execute(name, input.clone(), ctx.clone())
-                    })
+                    .map(|t| self.registry.execute(&t.name, t.input.clone(), ctx.clone()))
                     .collect();
 
                 let cancel_ref = &cancel;
@@ -195,21 +298,19 @@ impl Agent {
                     results = futures_util::future::join_all(futures) => results
                 };
 
-                for (result, (idx, tool_id, tool_name, tool_input, _)) in
-                    results.into_iter().zip(read_only)
-                {
+                for (result, tool) in results.into_iter().zip(read_only) {
                     let output = match result {
                         Ok(output) => output,
                         Err(e) => ToolOutput::error(e.to_string()),
                     };
-                    tool_results[idx] = Some(
+                    tool_results[tool.idx] = Some(
                         finalize_tool_result(
                             &self.permissions,
-                            &self.cwd,
-                            &tool_id,
-                            &tool_name,
-                            &tool_input,
-                            output,
+                            &self.tool_output_dir,
+                            &tool.id,
+                            &tool.name,
+                            &tool.input,
+                            with_hook_context(output, tool.context),
                             &mut on_event,
                         )
                         .await,
@@ -217,7 +318,7 @@ impl Agent {
                 }
             }
 
-            for (idx, tool_id, tool_name, tool_input, _) in mutating {
+            for tool in mutating {
                 let tool_result = tokio::select! {
                     _ = cancel.cancelled() => {
                         on_event(AgentEvent::Cancelled);
@@ -226,21 +327,21 @@ impl Agent {
                         }
                         return Err(chet_types::ChetError::Cancelled);
                     }
-                    result = self.registry.execute(&tool_name, tool_input.clone(), ctx.clone()) => result
+                    result = self.registry.execute(&tool.name, tool.input.clone(), ctx.clone()) => result
                 };
 
                 let output = match tool_result {
                     Ok(output) => output,
                     Err(e) => ToolOutput::error(e.to_string()),
                 };
-                tool_results[idx] = Some(
+                tool_results[tool.idx] = Some(
                     finalize_tool_result(
                         &self.permissions,
-                        &self.cwd,
-                        &tool_id,
-                        &tool_name,
-                        &tool_input,
-                        output,
+                        &self.tool_output_dir,
+                        &tool.id,
+                        &tool.name,
+                        &tool.input,
+                        with_hook_context(output, tool.context),
                         &mut on_event,
                     )
                     .await,
@@ -332,6 +433,7 @@ impl Agent {
         let mut current_signature = String::new();
         let mut in_thinking_block = false;
         let mut stop_reason = None;
+        let mut prompt_tokens = None;
 
         loop {
             tokio::select! {
@@ -343,6 +445,11 @@ impl Agent {
                     match event {
                         Some(Ok(StreamEvent::MessageStart { message })) => {
                             total_usage.add(&message.usage);
+                            let usage = &message.usage;
+                            let tokens = usage.input_tokens
+                                + usage.cache_read_input_tokens
+                                + usage.cache_creation_input_tokens;
+                            prompt_tokens = (tokens > 0).then_some(tokens);
                         }
                         Some(Ok(StreamEvent::ContentBlockStart {
                             content_block: ContentBlock::Text { .. },
@@ -460,9 +567,47 @@ impl Agent {
             content_blocks,
             stop_reason,
             tool_uses,
+            prompt_tokens,
         })
     }
 
+    /// Run Stop (or SubagentStop) hooks. Returns the reason if one denied.
+    async fn run_stop_hooks(&self, messages: &[Message]) -> Option<String> {
+        let last_text = messages
+            .last()
+            .filter(|m| m.role == Role::Assistant)
+            .map(|m| {
+                m.content
+                    .iter()
+                    .filter_map(|b| match b {
+                        ContentBlock::Text { text } => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            });
+        let hook_input = HookInput {
+            event: self.stop_event.clone(),
+            tool_name: None,
+            tool_input: None,
+            tool_output: last_text.map(|t| truncate_for_display(&t, 1000)),
+            is_error: None,
+            worktree_path: None,
+            worktree_source: None,
+            messages_removed: None,
+            messages_remaining: None,
+            config_path: None,
+        };
+        let outcome = self
+            .permissions
+            .evaluate_hooks(&self.stop_event, &hook_input)
+            .await;
+        match outcome.decision {
+            HookDecision::Deny => outcome.reason,
+            HookDecision::Approve | HookDecision::Ask => None,
+        }
+    }
+
     /// Check permissions and run before_tool hooks for each tool use.
     async fn check_tool_permissions<F>(
         &self,
@@ -473,119 +618,45 @@ impl Agent {
         F: FnMut(AgentEvent),
     {
         let mut tool_results: Vec<Option<ContentBlock>> = vec![None; tool_uses.len()];
-        let mut permitted_tools: Vec<(usize, String, String, serde_json::Value, bool)> = Vec::new();
+        let mut permitted_tools = Vec::new();
 
         for (i, (tool_id, tool_name, tool_input)) in tool_uses.iter().enumerate() {
             let is_read_only = self.registry.is_read_only(tool_name).unwrap_or(false);
 
-            if self.read_only_mode && !is_read_only {
-                on_event(AgentEvent::ToolBlocked {
-                    name: tool_name.clone(),
-                    reason: "plan mode (read-only)".to_string(),
-                });
-                tool_results[i] = Some(ContentBlock::ToolResult {
-                    tool_use_id: tool_id.clone(),
-                    content: vec![ToolResultContent::Text {
-                        text: "Blocked: plan mode only allows read-only tools".to_string(),
-                    }],
-                    is_error: Some(true),
-                });
-                continue;
-            }
-
-            let decision = self.permissions.check(tool_name, tool_input, is_read_only);
-
-            let permitted = match decision {
-                PermissionDecision::Permit => true,
-                PermissionDecision::Block { reason } => {
+            let authorized = match authorize_tool_call(
+                &self.permissions,
+                &self.registry,
+                tool_name,
+                tool_input,
+                self.read_only_mode,
+            )
+            .await
+            {
+                Ok(authorized) => authorized,
+                Err(denial) => {
                     on_event(AgentEvent::ToolBlocked {
                         name: tool_name.clone(),
-                        reason: reason.clone(),
+                        reason: denial.reason,
                     });
                     tool_results[i] = Some(ContentBlock::ToolResult {
                         tool_use_id: tool_id.clone(),
                         content: vec![ToolResultContent::Text {
-                            text: format!("Permission denied: {reason}"),
+                            text: denial.message,
                         }],
                         is_error: Some(true),
                     });
-                    false
+                    continue;
                 }
-                PermissionDecision::Prompt { description, .. } => {
-                    let response = self
-                        .permissions
-                        .prompt(tool_name, tool_input, &description)
-                        .await;
-                    match response {
-                        PromptResponse::AllowOnce => true,
-                        PromptResponse::AlwaysAllow => {
-                            self.permissions.add_session_rule(PermissionRule {
-                                tool: tool_name.clone(),
-                                args: None,
-                                level: PermissionLevel::Permit,
-                            });
-                            true
-                        }
-                        PromptResponse::Deny => {
-                            on_event(AgentEvent::ToolBlocked {
-                                name: tool_name.clone(),
-                                reason: "Denied by user".to_string(),
-                            });
-                            tool_results[i] = Some(ContentBlock::ToolResult {
-                                tool_use_id: tool_id.clone(),
-                                content: vec![ToolResultContent::Text {
-                                    text: "Permission denied by user".to_string(),
-                                }],
-                                is_error: Some(true),
-                            });
-                            false
-                        }
-                    }
-                }
-            };
-
-            if !permitted {
-                continue;
-            }
-
-            let hook_input = HookInput {
-                event: HookEvent::BeforeTool,
-                tool_name: Some(tool_name.clone()),
-                tool_input: Some(tool_input.clone()),
-                tool_output: None,
-                is_error: None,
-                worktree_path: None,
-                worktree_source: None,
-                messages_removed: None,
-                messages_remaining: None,
-                config_path: None,
             };
-            if let Err(reason) = self
-                .permissions
-                .run_hooks(&HookEvent::BeforeTool, &hook_input)
-                .await
-            {
-                on_event(AgentEvent::ToolBlocked {
-                    name: tool_name.clone(),
-                    reason: reason.clone(),
-                });
-                tool_results[i] = Some(ContentBlock::ToolResult {
-                    tool_use_id: tool_id.clone(),
-                    content: vec![ToolResultContent::Text {
-                        text: format!("Blocked by hook: {reason}"),
-                    }],
-                    is_error: Some(true),
-                });
-                continue;
-            }
 
-            permitted_tools.push((
-                i,
-                tool_id.clone(),
-                tool_name.clone(),
-                tool_input.clone(),
+            permitted_tools.push(PermittedTool {
+                idx: i,
+                id: tool_id.clone(),
+                name: tool_name.clone(),
+                input: authorized.input,
                 is_read_only,
-            ));
+                context: authorized.context,
+            });
         }
 
         PermissionCheckResult {
@@ -595,6 +666,18 @@ impl Agent {
     }
 }
 
+/// Append a user text message, merging into a trailing user message so
+/// roles keep alternating.
+fn push_user_text(messages: &mut Vec<Message>, text: String) {
+    match messages.last_mut() {
+        Some(last) if last.role == Role::User => last.content.push(ContentBlock::Text { text }),
+        _ => messages.push(Message {
+            role: Role::User,
+            content: vec![ContentBlock::Text { text }],
+        }),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -653,6 +736,28 @@ mod tests {
         assert!(!agent.read_only_mode);
     }
 
+    #[test]
+    fn agents_sharing_an_engine_share_the_tool_output_dir() {
+        let permissions = Arc::new(PermissionEngine::ludicrous());
+        let new_agent = || {
+            Agent::new(
+                make_provider(),
+                ToolRegistry::new(),
+                Arc::clone(&permissions),
+                "test".into(),
+                1024,
+                PathBuf::from("/tmp"),
+            )
+        };
+        let mut parent = new_agent();
+        parent.set_tool_output_dir(PathBuf::from("/sessions/abc/tool-output"));
+        let child = new_agent();
+        assert_eq!(
+            child.tool_output_dir,
+            PathBuf::from("/sessions/abc/tool-output")
+        );
+    }
+
     #[test]
     fn set_read_only_mode_toggles() {
         let provider = make_provider();
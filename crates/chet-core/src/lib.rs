@@ -2,9 +2,11 @@
 
 mod agent;
 mod subagent;
+mod tool_call;
 mod util;
 pub mod worktree;
 
-pub use agent::{Agent, AgentEvent};
+pub use agent::{Agent, AgentEvent, PromptSize};
 pub use subagent::SubagentTool;
+pub use tool_call::execute_tool_call;
 pub use worktree::{ManagedWorktree, WorktreeError, create_worktree, is_git_repo};
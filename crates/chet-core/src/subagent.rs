@@ -2,7 +2,7 @@
 
 use crate::Agent;
 use crate::worktree;
-use chet_permissions::PermissionEngine;
+use chet_permissions::{HookEvent, PermissionEngine};
 use chet_tools::ToolRegistry;
 use chet_types::{
     ContentBlock, Message, Role, ToolContext, ToolDefinition, ToolError, ToolOutput,
@@ -165,7 +165,9 @@ impl chet_types::Tool for SubagentTool {
                 self.cwd.clone()
             };
 
-            // Create child agent with builtins only (no SubagentTool → no recursion)
+            // Create child agent with builtins only (no SubagentTool → no recursion).
+            // It shares the parent's engine, so large results land in the
+            // parent session's tool output directory.
             let registry = ToolRegistry::with_builtins();
             let mut child = Agent::new(
                 Arc::clone(&self.provider),
@@ -176,6 +178,7 @@ impl chet_types::Tool for SubagentTool {
                 effective_cwd.clone(),
             );
             child.set_system_prompt(subagent_system_prompt(&effective_cwd));
+            child.set_stop_event(HookEvent::SubagentStop);
 
             let mut messages = vec![Message {
                 role: Role::User,
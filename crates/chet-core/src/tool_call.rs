@@ -0,0 +1,535 @@
+//! Single tool-call pipeline: permission check, prompt, before_tool hooks,
+//! execution and after_tool hooks.
+//!
+//! Shared by the agent loop and by callers that run tools on behalf of
+//! someone other than the model (e.g. `chet mcp serve`).
+
+use crate::util::{default_tool_output_dir, finalize_tool_result, with_hook_context};
+use chet_permissions::{
+    HookDecision, HookEvent, HookInput, PermissionDecision, PermissionEngine, PromptResponse,
+};
+use chet_tools::ToolRegistry;
+use chet_types::{ContentBlock, ToolContext, ToolOutput, ToolOutputContent, ToolResultContent};
+use std::sync::Arc;
+
+/// Why a tool call was refused before execution.
+pub(crate) struct ToolDenial {
+    /// Short reason for the `ToolBlocked` event.
+    pub reason: String,
+    /// Text returned to the caller as the (error) tool result.
+    pub message: String,
+}
+
+/// A tool call cleared to run.
+pub(crate) struct AuthorizedCall {
+    /// The input to execute with — rewritten if a before_tool hook asked.
+    pub input: serde_json::Value,
+    /// Context from before_tool hooks, appended to the tool result.
+    pub context: Vec<String>,
+}
+
+/// Decide whether a tool call may run: plan-mode guard, permission rules,
+/// interactive prompt, then before_tool hooks. Prompts include the tool's
+/// preview of the change, if it has one.
+///
+/// A hook may deny the call, ask the user, or rewrite its input; a rewritten
+/// input goes back through the permission rules so a hook can't widen access.
+pub(crate) async fn authorize_tool_call(
+    permissions: &PermissionEngine,
+    registry: &ToolRegistry,
+    tool_name: &str,
+    tool_input: &serde_json::Value,
+    read_only_mode: bool,
+) -> Result<AuthorizedCall, ToolDenial> {
+    let is_read_only = registry.is_read_only(tool_name).unwrap_or(false);
+    if read_only_mode && !is_read_only {
+        return Err(ToolDenial {
+            reason: "plan mode (read-only)".to_string(),
+            message: "Blocked: plan mode only allows read-only tools".to_string(),
+        });
+    }
+
+    check_permission(permissions, registry, tool_name, tool_input, is_read_only).await?;
+
+    let hook_input = HookInput {
+        event: HookEvent::BeforeTool,
+        tool_name: Some(tool_name.to_string()),
+        tool_input: Some(tool_input.clone()),
+        tool_output: None,
+        is_error: None,
+        worktree_path: None,
+        worktree_source: None,
+        messages_removed: None,
+        messages_remaining: None,
+        config_path: None,
+    };
+    let outcome = permissions
+        .evaluate_hooks(&HookEvent::BeforeTool, &hook_input)
+        .await;
+
+    match outcome.decision {
+        HookDecision::Approve => {}
+        HookDecision::Deny => {
+            let reason = outcome.reason.unwrap_or_default();
+            return Err(ToolDenial {
+                message: format!("Blocked by hook: {reason}"),
+                reason,
+            });
+        }
+        HookDecision::Ask => {
+            let description = outcome.reason.unwrap_or_default();
+            let input = outcome.updated_input.as_ref().unwrap_or(tool_input);
+            ask_user(permissions, registry, tool_name, input, &description).await?;
+        }
+    }
+
+    let input = match outcome.updated_input {
+        Some(updated) if &updated != tool_input => {
+            tracing::debug!(tool = tool_name, "before_tool hook rewrote input");
+            check_permission(permissions, registry, tool_name, &updated, is_read_only).await?;
+            updated
+        }
+        _ => tool_input.clone(),
+    };
+
+    Ok(AuthorizedCall {
+        input,
+        context: outcome.additional_context,
+    })
+}
+
+/// Apply the permission rules to one input, prompting when they say so.
+async fn check_permission(
+    permissions: &PermissionEngine,
+    registry: &ToolRegistry,
+    tool_name: &str,
+    tool_input: &serde_json::Value,
+    is_read_only: bool,
+) -> Result<(), ToolDenial> {
+    let check = permissions.evaluate(tool_name, tool_input, is_read_only);
+    tracing::debug!(tool = tool_name, layer = %check.layer, "permission decided");
+    match check.decision {
+        PermissionDecision::Permit => Ok(()),
+        PermissionDecision::Block { reason } => Err(ToolDenial {
+            message: format!("Permission denied: {reason}"),
+            reason,
+        }),
+        PermissionDecision::Prompt { description, .. } => {
+            ask_user(permissions, registry, tool_name, tool_input, &description).await
+        }
+    }
+}
+
+async fn ask_user(
+    permissions: &PermissionEngine,
+    registry: &ToolRegistry,
+    tool_name: &str,
+    tool_input: &serde_json::Value,
+    description: &str,
+) -> Result<(), ToolDenial> {
+    let preview = registry.preview(tool_name, tool_input).await;
+    match permissions
+        .prompt(tool_name, tool_input, description, preview.as_deref())
+        .await
+    {
+        PromptResponse::AllowOnce => Ok(()),
+        PromptResponse::AlwaysAllow => {
+            permissions.grant_session(tool_name, tool_input);
+            Ok(())
+        }
+        PromptResponse::AlwaysAllowSaved(rules) => {
+            for rule in rules {
+                permissions.add_session_rule(rule);
+            }
+            Ok(())
+        }
+        PromptResponse::Deny => Err(ToolDenial {
+            reason: "Denied by user".to_string(),
+            message: "Permission denied by user".to_string(),
+        }),
+    }
+}
+
+/// Run one tool call through the same permission and hook pipeline the
+/// agent uses for model-issued calls, returning the final tool output.
+///
+/// `read_only_mode` applies the plan-mode guard. Calls that need approval are
+/// sent to the engine's prompt handler; an engine built without one denies
+/// them. Denials are returned as error outputs rather than `Err`, matching
+/// what the model would see.
+pub async fn execute_tool_call(
+    registry: &ToolRegistry,
+    permissions: &Arc<PermissionEngine>,
+    ctx: ToolContext,
+    tool_name: &str,
+    tool_input: serde_json::Value,
+    read_only_mode: bool,
+) -> ToolOutput {
+    let authorized = match authorize_tool_call(
+        permissions,
+        registry,
+        tool_name,
+        &tool_input,
+        read_only_mode,
+    )
+    .await
+    {
+        Ok(authorized) => authorized,
+        Err(denial) => return ToolOutput::error(denial.message),
+    };
+    let tool_input = authorized.input;
+
+    let output = match registry.execute(tool_name, tool_input.clone(), ctx).await {
+        Ok(output) => output,
+        Err(e) => ToolOutput::error(e.to_string()),
+    };
+
+    let tool_id = uuid::Uuid::new_v4().simple().to_string();
+    let block = finalize_tool_result(
+        permissions,
+        &permissions
+            .tool_output_dir()
+            .unwrap_or_else(default_tool_output_dir),
+        &tool_id,
+        tool_name,
+        &tool_input,
+        with_hook_context(output, authorized.context),
+        &mut |_| {},
+    )
+    .await;
+
+    match block {
+        ContentBlock::ToolResult {
+            content, is_error, ..
+        } => ToolOutput {
+            content: content
+                .into_iter()
+                .map(|c| match c {
+                    ToolResultContent::Text { text } => ToolOutputContent::Text { text },
+                    ToolResultContent::Image { source } => ToolOutputContent::Image { source },
+                })
+                .collect(),
+            is_error: is_error.unwrap_or(false),
+        },
+        _ => ToolOutput::error("Unexpected tool result"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chet_permissions::{HookConfig, PermissionLevel, PermissionRule, PromptHandler};
+    use chet_types::{Tool, ToolDefinition, ToolError};
+    use std::future::Future;
+    use std::path::PathBuf;
+    use std::pin::Pin;
+    use std::sync::Mutex;
+
+    struct UpperTool {
+        read_only: bool,
+    }
+
+    impl Tool for UpperTool {
+        fn name(&self) -> &str {
+            "Upper"
+        }
+
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "Upper".into(),
+                description: "Uppercase text".into(),
+                input_schema: serde_json::json!({"type": "object"}),
+                cache_control: None,
+            }
+        }
+
+        fn is_read_only(&self) -> bool {
+            self.read_only
+        }
+
+        fn preview<'a>(
+            &'a self,
+            input: &'a serde_json::Value,
+        ) -> Pin<Box<dyn Future<Output = Option<String>> + Send + 'a>> {
+            Box::pin(async move {
+                let text = input["text"].as_str()?;
+                Some(format!("-{text}\n+{}", text.to_uppercase()))
+            })
+        }
+
+        fn execute(
+            &self,
+            input: serde_json::Value,
+            _ctx: ToolContext,
+        ) -> Pin<Box<dyn Future<Output = Result<ToolOutput, ToolError>> + Send + '_>> {
+            Box::pin(async move {
+                Ok(ToolOutput::text(
+                    input["text"].as_str().unwrap_or_default().to_uppercase(),
+                ))
+            })
+        }
+    }
+
+    fn registry(read_only: bool) -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(UpperTool { read_only }));
+        registry
+    }
+
+    fn ctx() -> ToolContext {
+        ToolContext {
+            cwd: PathBuf::from("/tmp"),
+            env: Default::default(),
+            sandboxed: false,
+        }
+    }
+
+    fn text_of(output: &ToolOutput) -> &str {
+        match &output.content[0] {
+            ToolOutputContent::Text { text } => text,
+            _ => panic!("expected text"),
+        }
+    }
+
+    #[tokio::test]
+    async fn read_only_tool_runs_by_default() {
+        let permissions = Arc::new(PermissionEngine::new(vec![], vec![], None));
+        let output = execute_tool_call(
+            &registry(true),
+            &permissions,
+            ctx(),
+            "Upper",
+            serde_json::json!({"text": "hi"}),
+            false,
+        )
+        .await;
+        assert!(!output.is_error);
+        assert_eq!(text_of(&output), "HI");
+    }
+
+    #[tokio::test]
+    async fn mutating_tool_denied_without_prompt_handler() {
+        let permissions = Arc::new(PermissionEngine::new(vec![], vec![], None));
+        let output = execute_tool_call(
+            &registry(false),
+            &permissions,
+            ctx(),
+            "Upper",
+            serde_json::json!({"text": "hi"}),
+            false,
+        )
+        .await;
+        assert!(output.is_error);
+        assert_eq!(text_of(&output), "Permission denied by user");
+    }
+
+    /// Allows every call, recording the descriptions and previews it was shown.
+    #[derive(Default)]
+    struct RecordingPrompt {
+        shown: Mutex<Vec<(String, Option<String>)>>,
+    }
+
+    impl PromptHandler for RecordingPrompt {
+        fn prompt_permission(
+            &self,
+            _tool_name: &str,
+            _tool_input: &serde_json::Value,
+            description: &str,
+            preview: Option<&str>,
+            _suggested_rules: &[PermissionRule],
+        ) -> Pin<Box<dyn Future<Output = PromptResponse> + Send + '_>> {
+            self.shown
+                .lock()
+                .unwrap()
+                .push((description.to_string(), preview.map(str::to_string)));
+            Box::pin(async { PromptResponse::AllowOnce })
+        }
+    }
+
+    #[tokio::test]
+    async fn prompt_shows_tool_preview() {
+        let prompt = Arc::new(RecordingPrompt::default());
+        let permissions = Arc::new(PermissionEngine::new(
+            vec![],
+            vec![],
+            Some(Arc::clone(&prompt) as Arc<dyn PromptHandler>),
+        ));
+        let output = execute_tool_call(
+            &registry(false),
+            &permissions,
+            ctx(),
+            "Upper",
+            serde_json::json!({"text": "hi"}),
+            false,
+        )
+        .await;
+        assert_eq!(text_of(&output), "HI");
+        let shown = prompt.shown.lock().unwrap();
+        assert_eq!(
+            shown.as_slice(),
+            [(
+                "Tool 'Upper' requires permission".to_string(),
+                Some("-hi\n+HI".to_string())
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn block_rule_denies_call() {
+        let permissions = Arc::new(PermissionEngine::new(
+            vec![PermissionRule {
+                tool: "Upper".into(),
+                args: None,
+                level: PermissionLevel::Block,
+            }],
+            vec![],
+            None,
+        ));
+        let output = execute_tool_call(
+            &registry(true),
+            &permissions,
+            ctx(),
+            "Upper",
+            serde_json::json!({"text": "hi"}),
+            false,
+        )
+        .await;
+        assert!(output.is_error);
+        assert!(text_of(&output).starts_with("Permission denied:"));
+    }
+
+    #[tokio::test]
+    async fn read_only_mode_blocks_mutating_tool() {
+        let permissions = Arc::new(PermissionEngine::ludicrous());
+        let output = execute_tool_call(
+            &registry(false),
+            &permissions,
+            ctx(),
+            "Upper",
+            serde_json::json!({"text": "hi"}),
+            true,
+        )
+        .await;
+        assert!(output.is_error);
+        assert_eq!(
+            text_of(&output),
+            "Blocked: plan mode only allows read-only tools"
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_tool_is_error_output() {
+        let permissions = Arc::new(PermissionEngine::ludicrous());
+        let output = execute_tool_call(
+            &registry(true),
+            &permissions,
+            ctx(),
+            "Nope",
+            serde_json::json!({}),
+            false,
+        )
+        .await;
+        assert!(output.is_error);
+    }
+
+    fn before_tool_hook(command: &str) -> Vec<HookConfig> {
+        vec![HookConfig {
+            event: HookEvent::BeforeTool,
+            command: command.to_string(),
+            timeout_ms: 5000,
+            tool: None,
+            args: None,
+        }]
+    }
+
+    #[tokio::test]
+    async fn hook_rewrites_input_and_adds_context() {
+        let permissions = Arc::new(PermissionEngine::new(
+            vec![],
+            before_tool_hook(
+                r#"echo '{"updated_input":{"text":"bye"},"additional_context":"text was rewritten"}'"#,
+            ),
+            None,
+        ));
+        let output = execute_tool_call(
+            &registry(true),
+            &permissions,
+            ctx(),
+            "Upper",
+            serde_json::json!({"text": "hi"}),
+            false,
+        )
+        .await;
+        assert!(!output.is_error);
+        assert_eq!(text_of(&output), "BYE");
+        match &output.content[1] {
+            ToolOutputContent::Text { text } => assert_eq!(text, "text was rewritten"),
+            _ => panic!("expected text"),
+        }
+    }
+
+    #[tokio::test]
+    async fn hook_deny_reason_reaches_caller() {
+        let permissions = Arc::new(PermissionEngine::new(
+            vec![],
+            before_tool_hook(r#"echo '{"decision":"deny","reason":"no shouting"}'"#),
+            None,
+        ));
+        let output = execute_tool_call(
+            &registry(true),
+            &permissions,
+            ctx(),
+            "Upper",
+            serde_json::json!({"text": "hi"}),
+            false,
+        )
+        .await;
+        assert!(output.is_error);
+        assert_eq!(text_of(&output), "Blocked by hook: no shouting");
+    }
+
+    #[tokio::test]
+    async fn hook_ask_prompts_user() {
+        // No prompt handler, so asking denies
+        let permissions = Arc::new(PermissionEngine::new(
+            vec![],
+            before_tool_hook(r#"echo '{"decision":"ask","reason":"are you sure?"}'"#),
+            None,
+        ));
+        let output = execute_tool_call(
+            &registry(true),
+            &permissions,
+            ctx(),
+            "Upper",
+            serde_json::json!({"text": "hi"}),
+            false,
+        )
+        .await;
+        assert!(output.is_error);
+        assert_eq!(text_of(&output), "Permission denied by user");
+    }
+
+    #[tokio::test]
+    async fn rewritten_input_is_rechecked() {
+        let permissions = Arc::new(PermissionEngine::new(
+            vec![PermissionRule {
+                tool: "Upper".into(),
+                args: Some("text:secret".into()),
+                level: PermissionLevel::Block,
+            }],
+            before_tool_hook(r#"echo '{"updated_input":{"text":"secret"}}'"#),
+            None,
+        ));
+        let output = execute_tool_call(
+            &registry(true),
+            &permissions,
+            ctx(),
+            "Upper",
+            serde_json::json!({"text": "hi"}),
+            false,
+        )
+        .await;
+        assert!(output.is_error);
+        assert!(text_of(&output).starts_with("Permission denied:"));
+    }
+}
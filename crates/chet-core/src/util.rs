@@ -1,11 +1,21 @@
 //! Internal utility functions for the agent loop.
 
 use crate::AgentEvent;
-use chet_permissions::{HookEvent, HookInput, PermissionEngine};
+use chet_permissions::{HookDecision, HookEvent, HookInput, PermissionEngine};
 use chet_types::{ContentBlock, ToolOutput, ToolResultContent};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+/// Append before_tool hook context to a tool's output.
+pub(crate) fn with_hook_context(mut output: ToolOutput, context: Vec<String>) -> ToolOutput {
+    output.content.extend(
+        context
+            .into_iter()
+            .map(|text| chet_types::ToolOutputContent::Text { text }),
+    );
+    output
+}
+
 /// Truncate a string for display, adding "..." if truncated.
 pub(crate) fn truncate_for_display(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
@@ -15,16 +25,20 @@ pub(crate) fn truncate_for_display(s: &str, max_len: usize) -> String {
     }
 }
 
-/// Persist a large tool result to a temp file under the CWD.
+/// Where large tool results go when the agent has no session directory.
+pub(crate) fn default_tool_output_dir() -> PathBuf {
+    std::env::temp_dir().join("chet-tool-output")
+}
+
+/// Persist a large tool result to a file in `dir`.
 /// Returns the path on success, None on failure.
 pub(crate) fn persist_tool_result(
-    cwd: &Path,
+    dir: &Path,
     tool_name: &str,
     tool_id: &str,
     text: &str,
 ) -> Option<PathBuf> {
-    let dir = cwd.join(".chet-tool-output");
-    if let Err(e) = std::fs::create_dir_all(&dir) {
+    if let Err(e) = std::fs::create_dir_all(dir) {
         tracing::warn!("Failed to create tool output dir: {e}");
         return None;
     }
@@ -68,9 +82,10 @@ pub(crate) async fn fire_stop_failure_hook(permissions: &Arc<PermissionEngine>,
 }
 
 /// Emit ToolEnd event, run after_tool hooks, and build the ToolResult ContentBlock.
+/// Text too large to inline is persisted to `output_dir`.
 pub(crate) async fn finalize_tool_result(
     permissions: &Arc<PermissionEngine>,
-    cwd: &Path,
+    output_dir: &Path,
     tool_id: &str,
     tool_name: &str,
     tool_input: &serde_json::Value,
@@ -105,11 +120,14 @@ pub(crate) async fn finalize_tool_result(
         messages_remaining: None,
         config_path: None,
     };
-    if let Err(msg) = permissions
-        .run_hooks(&HookEvent::AfterTool, &after_hook_input)
-        .await
-    {
-        tracing::warn!("after_tool hook error: {msg}");
+    let outcome = permissions
+        .evaluate_hooks(&HookEvent::AfterTool, &after_hook_input)
+        .await;
+    if outcome.decision == HookDecision::Deny {
+        tracing::warn!(
+            "after_tool hook error: {}",
+            outcome.reason.unwrap_or_default()
+        );
     }
 
     const MAX_INLINE_RESULT_CHARS: usize = 50_000;
@@ -121,7 +139,7 @@ pub(crate) async fn finalize_tool_result(
             chet_types::ToolOutputContent::Text { text }
                 if text.len() > MAX_INLINE_RESULT_CHARS =>
             {
-                let path = persist_tool_result(cwd, tool_name, tool_id, &text);
+                let path = persist_tool_result(output_dir, tool_name, tool_id, &text);
                 let truncated = chet_types::truncate_str(&text, MAX_INLINE_RESULT_CHARS);
                 let note = match path {
                     Some(p) => format!(
@@ -139,6 +157,12 @@ pub(crate) async fn finalize_tool_result(
             chet_types::ToolOutputContent::Text { text } => ToolResultContent::Text { text },
             chet_types::ToolOutputContent::Image { source } => ToolResultContent::Image { source },
         })
+        .chain(
+            outcome
+                .additional_context
+                .into_iter()
+                .map(|text| ToolResultContent::Text { text }),
+        )
         .collect();
 
     ContentBlock::ToolResult {
@@ -5,6 +5,8 @@
 //! 2. Multi-tool-use turns — multiple tool_use blocks in a single response
 //! 3. Plan mode tool blocking — read-only safety net
 //! 4. Subagent end-to-end — parent spawns child via SubagentTool
+//! 5. Permission denials — `ToolBlocked` reasons for each denial path
+//! 6. Stop hooks — a denying hook keeps the agent working, up to a limit
 //!
 //! Run with: `cargo test -p chet-core --test cancellation_integration -- --ignored`
 
@@ -14,8 +16,8 @@ use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use chet_core::{Agent, SubagentTool};
-use chet_permissions::PermissionEngine;
+use chet_core::{Agent, PromptSize, SubagentTool};
+use chet_permissions::{HookConfig, HookEvent, PermissionEngine, PermissionLevel, PermissionRule};
 use chet_session::compact;
 use chet_tools::ToolRegistry;
 use chet_types::{ChetError, ContentBlock, Message, Role, StreamEvent, provider::Provider};
@@ -287,6 +289,15 @@ async fn test_multi_tool_use_turn() {
         })
         .collect();
     assert_eq!(final_text, "Both tools done");
+
+    // The last request carried the first three messages
+    assert_eq!(
+        agent.last_prompt_size(),
+        Some(PromptSize {
+            tokens: 10,
+            messages: 3
+        })
+    );
 }
 
 /// Agent in read-only mode blocks non-read-only tools.
@@ -369,6 +380,103 @@ async fn test_plan_mode_tool_blocking() {
     assert_eq!(final_text, "Understood, tool was blocked");
 }
 
+/// Run one `WriteTool` call that gets denied; return the `ToolBlocked`
+/// reason and the error text sent back to the model.
+async fn run_denied_tool_call(permissions: PermissionEngine, read_only: bool) -> (String, String) {
+    let call1_events = vec![
+        (message_start_event(), None),
+        (tool_use_block_start(0, "t1", "WriteTool"), None),
+        (input_json_delta(0, r#"{"msg":"denied"}"#), None),
+        (content_block_stop(0), None),
+        (message_delta_tool_use(), None),
+        (message_stop(), None),
+    ];
+    let call2_events = vec![
+        (message_start_event(), None),
+        (text_block_start(0), None),
+        (text_delta(0, "ok"), None),
+        (content_block_stop(0), None),
+        (message_delta_end_turn(), None),
+        (message_stop(), None),
+    ];
+    let provider: Arc<dyn Provider> =
+        Arc::new(SequencedMockProvider::new(vec![call1_events, call2_events]));
+
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(EchoTool::new_writable("WriteTool")));
+    let mut agent = Agent::new(
+        provider,
+        registry,
+        Arc::new(permissions),
+        "test-model".to_string(),
+        1024,
+        PathBuf::from("/tmp"),
+    );
+    agent.set_read_only_mode(read_only);
+
+    let capture = Arc::new(Mutex::new(EventCapture::default()));
+    let mut messages = vec![Message {
+        role: Role::User,
+        content: vec![ContentBlock::Text {
+            text: "Write something".to_string(),
+        }],
+    }];
+    agent
+        .run(
+            &mut messages,
+            CancellationToken::new(),
+            EventCapture::callback(capture.clone()),
+        )
+        .await
+        .expect("run should complete");
+
+    let c = capture.lock().unwrap();
+    assert_eq!(c.tool_blocked.len(), 1);
+    assert_eq!(c.tool_blocked[0].0, "WriteTool");
+    assert!(c.tool_ends.is_empty(), "tool should NOT have executed");
+
+    let result_text = messages[2]
+        .content
+        .iter()
+        .find_map(|b| match b {
+            ContentBlock::ToolResult {
+                content, is_error, ..
+            } => {
+                assert_eq!(*is_error, Some(true));
+                content.iter().find_map(|c| match c {
+                    chet_types::ToolResultContent::Text { text } => Some(text.clone()),
+                    _ => None,
+                })
+            }
+            _ => None,
+        })
+        .expect("tool result");
+    (c.tool_blocked[0].1.clone(), result_text)
+}
+
+/// The agent reports the same `ToolBlocked` reasons for every denial path.
+#[tokio::test]
+async fn test_tool_blocked_reasons() {
+    let (reason, text) = run_denied_tool_call(PermissionEngine::ludicrous(), true).await;
+    assert_eq!(reason, "plan mode (read-only)");
+    assert_eq!(text, "Blocked: plan mode only allows read-only tools");
+
+    // No prompt handler: a call that would prompt is denied
+    let (reason, text) =
+        run_denied_tool_call(PermissionEngine::new(vec![], vec![], None), false).await;
+    assert_eq!(reason, "Denied by user");
+    assert_eq!(text, "Permission denied by user");
+
+    let block = PermissionRule {
+        tool: "WriteTool".to_string(),
+        args: None,
+        level: PermissionLevel::Block,
+    };
+    let (reason, text) =
+        run_denied_tool_call(PermissionEngine::new(vec![block], vec![], None), false).await;
+    assert!(text == format!("Permission denied: {reason}"), "{text}");
+}
+
 /// Parent agent calls SubagentTool, child returns text, parent produces final response.
 #[tokio::test]
 #[ignore]
@@ -753,3 +861,92 @@ async fn test_mixed_parallel_and_sequential_tools() {
         other => panic!("expected ToolResult, got {other:?}"),
     }
 }
+
+fn end_turn_text(text: &str) -> Vec<(StreamEvent, Option<u64>)> {
+    vec![
+        (message_start_event(), None),
+        (text_block_start(0), None),
+        (text_delta(0, text), None),
+        (content_block_stop(0), None),
+        (message_delta_end_turn(), None),
+        (message_stop(), None),
+    ]
+}
+
+/// Run an agent whose Stop hook is `command`, answering with `responses`.
+async fn run_with_stop_hook(
+    command: String,
+    responses: Vec<Vec<(StreamEvent, Option<u64>)>>,
+) -> (Vec<Message>, EventCapture) {
+    let provider: Arc<dyn Provider> = Arc::new(SequencedMockProvider::new(responses));
+    let hooks = vec![HookConfig {
+        event: HookEvent::Stop,
+        command,
+        timeout_ms: 5000,
+        tool: None,
+        args: None,
+    }];
+    let agent = Agent::new(
+        provider,
+        ToolRegistry::new(),
+        Arc::new(PermissionEngine::new(vec![], hooks, None)),
+        "test-model".to_string(),
+        1024,
+        PathBuf::from("/tmp"),
+    );
+
+    let capture = Arc::new(Mutex::new(EventCapture::default()));
+    let mut messages = vec![Message {
+        role: Role::User,
+        content: vec![ContentBlock::Text {
+            text: "Fix the tests".to_string(),
+        }],
+    }];
+    agent
+        .run(
+            &mut messages,
+            CancellationToken::new(),
+            EventCapture::callback(capture.clone()),
+        )
+        .await
+        .expect("run should complete");
+    let capture = std::mem::take(&mut *capture.lock().unwrap());
+    (messages, capture)
+}
+
+/// A Stop hook that denies once sends its reason back as a user message and
+/// the agent keeps going.
+#[tokio::test]
+async fn test_stop_hook_continues_loop() {
+    let marker = std::env::temp_dir().join(format!("chet-stop-hook-{}", std::process::id()));
+    let _ = std::fs::remove_file(&marker);
+    let command = format!(
+        "test -e {m} && exit 0; touch {m}; echo 'tests still failing' >&2; exit 2",
+        m = marker.display()
+    );
+    let (messages, c) =
+        run_with_stop_hook(command, vec![end_turn_text("Done"), end_turn_text("Fixed")]).await;
+    let _ = std::fs::remove_file(&marker);
+
+    assert_eq!(c.stop_blocked, vec!["tests still failing"]);
+    assert!(c.saw_done);
+    assert_eq!(messages.len(), 4);
+    assert_eq!(messages[2].role, Role::User);
+    match &messages[2].content[0] {
+        ContentBlock::Text { text } => assert!(text.ends_with("tests still failing"), "{text}"),
+        other => panic!("expected text, got {other:?}"),
+    }
+}
+
+/// A Stop hook that always denies can't keep the agent running forever.
+#[tokio::test]
+async fn test_stop_hook_loop_guard() {
+    let responses = (0..6).map(|_| end_turn_text("Done")).collect();
+    let (messages, c) = run_with_stop_hook("exit 2".to_string(), responses).await;
+
+    assert_eq!(c.stop_blocked.len(), 5);
+    assert_eq!(c.errors.len(), 1);
+    assert!(c.errors[0].contains("Stop hook still blocking"));
+    assert!(c.saw_done);
+    assert_eq!(messages.len(), 12);
+}
@@ -273,6 +273,8 @@ pub struct EventCapture {
     pub tool_starts: Vec<String>,
     pub tool_ends: Vec<(String, String, bool)>,
     pub tool_blocked: Vec<(String, String)>,
+    pub stop_blocked: Vec<String>,
+    pub errors: Vec<String>,
 }
 
 impl EventCapture {
@@ -290,6 +292,8 @@ impl EventCapture {
                     is_error,
                 } => c.tool_ends.push((name, output, is_error)),
                 AgentEvent::ToolBlocked { name, reason } => c.tool_blocked.push((name, reason)),
+                AgentEvent::StopBlocked { reason } => c.stop_blocked.push(reason),
+                AgentEvent::Error(e) => c.errors.push(e),
                 _ => {}
             }
         }
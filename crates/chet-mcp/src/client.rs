@@ -5,18 +5,47 @@
 
 use crate::config::McpServerConfig;
 use crate::error::McpError;
-use crate::transport::StdioTransport;
-use serde::Deserialize;
+use crate::handler::{McpRequestHandler, RequestRouter};
+use crate::transport::{StderrLog, StdioTransport};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 /// MCP protocol version we support.
-const PROTOCOL_VERSION: &str = "2024-11-05";
+pub(crate) const PROTOCOL_VERSION: &str = "2024-11-05";
 
 /// Information about a tool exposed by an MCP server.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpToolInfo {
     pub name: String,
     pub description: String,
     pub input_schema: serde_json::Value,
+    #[serde(default)]
+    pub annotations: McpToolAnnotations,
+}
+
+/// Behavioral hints a server attaches to a tool (`annotations` in `tools/list`).
+///
+/// These are claims made by the server, not guarantees.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolAnnotations {
+    /// Human-readable display name.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// The tool does not modify its environment.
+    #[serde(default)]
+    pub read_only_hint: bool,
+    /// The tool may perform destructive updates. Only meaningful when not
+    /// read-only; the MCP default is `true`.
+    #[serde(default)]
+    pub destructive_hint: Option<bool>,
+}
+
+impl McpToolAnnotations {
+    /// Whether the tool may make destructive changes.
+    pub fn is_destructive(&self) -> bool {
+        !self.read_only_hint && self.destructive_hint.unwrap_or(true)
+    }
 }
 
 /// Result of calling a tool on an MCP server.
@@ -60,6 +89,10 @@ struct ToolEntry {
     description: Option<String>,
     #[serde(default = "default_schema", rename = "inputSchema")]
     input_schema: serde_json::Value,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    annotations: McpToolAnnotations,
 }
 
 fn default_schema() -> serde_json::Value {
@@ -76,17 +109,47 @@ struct ToolCallResult {
 impl McpClient {
     /// Connect to an MCP server: spawn, handshake, discover tools.
     pub async fn connect(name: String, config: &McpServerConfig) -> Result<Self, McpError> {
+        Self::connect_with_handler(name, config, None).await
+    }
+
+    /// Connect to an MCP server, routing server-initiated sampling and
+    /// elicitation requests to `handler`. Those capabilities are only
+    /// advertised when a handler is given.
+    pub async fn connect_with_handler(
+        name: String,
+        config: &McpServerConfig,
+        handler: Option<Arc<dyn McpRequestHandler>>,
+    ) -> Result<Self, McpError> {
+        Self::connect_with_log(name, config, handler, StderrLog::default()).await
+    }
+
+    /// Connect, appending the server's stderr to `stderr`.
+    pub(crate) async fn connect_with_log(
+        name: String,
+        config: &McpServerConfig,
+        handler: Option<Arc<dyn McpRequestHandler>>,
+        stderr: StderrLog,
+    ) -> Result<Self, McpError> {
+        let router = RequestRouter::new(name.clone(), handler);
+        let capabilities = if router.has_handler() {
+            serde_json::json!({"sampling": {}, "elicitation": {}})
+        } else {
+            serde_json::json!({})
+        };
+
         let transport = StdioTransport::spawn(
             &config.command,
             &config.args,
             &config.env,
             config.timeout_ms,
+            router,
+            stderr,
         )?;
 
         // Send `initialize` request
         let init_params = serde_json::json!({
             "protocolVersion": PROTOCOL_VERSION,
-            "capabilities": {},
+            "capabilities": capabilities,
             "clientInfo": {
                 "name": "chet",
                 "version": env!("CARGO_PKG_VERSION")
@@ -119,10 +182,17 @@ impl McpClient {
             })?;
             list.tools
                 .into_iter()
-                .map(|t| McpToolInfo {
-                    name: t.name,
-                    description: t.description.unwrap_or_default(),
-                    input_schema: t.input_schema,
+                .filter(|t| config.tool_enabled(&t.name))
+                .map(|t| {
+                    let mut annotations = t.annotations;
+                    // Newer servers put the title at the top level
+                    annotations.title = annotations.title.or(t.title);
+                    McpToolInfo {
+                        name: t.name,
+                        description: t.description.unwrap_or_default(),
+                        input_schema: t.input_schema,
+                        annotations,
+                    }
                 })
                 .collect()
         } else if let Some(err) = tools_resp.error {
@@ -158,7 +228,8 @@ impl McpClient {
         let resp = self
             .transport
             .send_request("tools/call", Some(params))
-            .await?;
+            .await
+            .map_err(|e| self.named(e))?;
 
         if let Some(err) = resp.error {
             return Err(McpError::JsonRpc {
@@ -181,6 +252,48 @@ impl McpClient {
         })
     }
 
+    /// Send a `ping` and wait for the reply.
+    pub async fn ping(&self) -> Result<(), McpError> {
+        let resp = self
+            .transport
+            .send_request("ping", None)
+            .await
+            .map_err(|e| self.named(e))?;
+        match resp.error {
+            Some(err) => Err(McpError::JsonRpc {
+                server: self.name.clone(),
+                code: err.code,
+                message: err.message,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Report transport errors under the server's name rather than its command.
+    fn named(&self, error: McpError) -> McpError {
+        match error {
+            McpError::ServerNotRunning { .. } => McpError::ServerNotRunning {
+                name: self.name.clone(),
+            },
+            other => other,
+        }
+    }
+
+    /// Whether the server process has gone away.
+    pub fn is_closed(&self) -> bool {
+        self.transport.is_closed()
+    }
+
+    /// Wait until the server process goes away.
+    pub async fn closed(&self) {
+        self.transport.closed().await
+    }
+
+    /// Kill the server process immediately.
+    pub async fn kill(&self) {
+        self.transport.kill().await
+    }
+
     /// Get the tools exposed by this server.
     pub fn tools(&self) -> &[McpToolInfo] {
         &self.tools
@@ -230,6 +343,32 @@ mod tests {
         assert!(entry.description.is_none());
     }
 
+    #[test]
+    fn deserialize_tool_entry_annotations() {
+        let json = r#"{
+            "name": "search",
+            "inputSchema": {"type": "object"},
+            "annotations": {"title": "Search issues", "readOnlyHint": true}
+        }"#;
+        let entry: ToolEntry = serde_json::from_str(json).unwrap();
+        assert_eq!(entry.annotations.title.as_deref(), Some("Search issues"));
+        assert!(entry.annotations.read_only_hint);
+        assert!(!entry.annotations.is_destructive());
+    }
+
+    #[test]
+    fn missing_annotations_are_conservative() {
+        let json = r#"{"name": "write", "title": "Write", "inputSchema": {"type": "object"}}"#;
+        let entry: ToolEntry = serde_json::from_str(json).unwrap();
+        assert_eq!(entry.title.as_deref(), Some("Write"));
+        assert!(!entry.annotations.read_only_hint);
+        assert!(entry.annotations.is_destructive());
+
+        let json = r#"{"name": "append", "annotations": {"destructiveHint": false}}"#;
+        let entry: ToolEntry = serde_json::from_str(json).unwrap();
+        assert!(!entry.annotations.is_destructive());
+    }
+
     #[test]
     fn deserialize_tool_call_result_text() {
         let json = r#"{
@@ -1,5 +1,6 @@
 //! Configuration types for MCP servers.
 
+use crate::error::McpError;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -7,11 +8,131 @@ fn default_timeout() -> u64 {
     30000
 }
 
+fn default_ping_interval() -> u64 {
+    30
+}
+
+fn default_max_restarts() -> u32 {
+    5
+}
+
 /// Top-level MCP configuration.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpConfig {
     #[serde(default)]
     pub servers: HashMap<String, McpServerConfig>,
+    /// Seconds between health-check pings to running servers (0 disables).
+    #[serde(default = "default_ping_interval")]
+    pub ping_interval_secs: u64,
+    /// Restart attempts after a server exits before it is marked failed.
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+}
+
+impl Default for McpConfig {
+    fn default() -> Self {
+        Self {
+            servers: HashMap::new(),
+            ping_interval_secs: default_ping_interval(),
+            max_restarts: default_max_restarts(),
+        }
+    }
+}
+
+impl McpConfig {
+    /// Parse the `.mcp.json` format used across the MCP ecosystem:
+    /// `{"mcpServers": {"name": {"command", "args", "env"}}}`.
+    ///
+    /// `${VAR}` and `${VAR:-default}` in `command`, `args` and `env` values are
+    /// expanded with `lookup`. Servers with a non-stdio `type` are skipped.
+    pub fn from_mcp_json(
+        json: &str,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, McpError> {
+        let file: McpJsonFile = serde_json::from_str(json)?;
+        let mut servers = HashMap::new();
+        for (name, entry) in file.mcp_servers {
+            if entry.transport.as_deref().is_some_and(|t| t != "stdio") {
+                tracing::warn!(
+                    "Skipping MCP server '{name}' in .mcp.json: only stdio is supported"
+                );
+                continue;
+            }
+            let Some(command) = entry.command else {
+                tracing::warn!("Skipping MCP server '{name}' in .mcp.json: no command");
+                continue;
+            };
+            servers.insert(
+                name,
+                McpServerConfig {
+                    command: expand_vars(&command, &lookup),
+                    args: entry.args.iter().map(|a| expand_vars(a, &lookup)).collect(),
+                    env: entry
+                        .env
+                        .into_iter()
+                        .map(|(k, v)| (k, expand_vars(&v, &lookup)))
+                        .collect(),
+                    timeout_ms: entry.timeout.unwrap_or_else(default_timeout),
+                    lazy: false,
+                    allowed_tools: Vec::new(),
+                    disabled_tools: Vec::new(),
+                },
+            );
+        }
+        Ok(Self {
+            servers,
+            ..Self::default()
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct McpJsonFile {
+    #[serde(default)]
+    mcp_servers: HashMap<String, McpJsonServer>,
+}
+
+#[derive(Deserialize)]
+struct McpJsonServer {
+    #[serde(default, rename = "type")]
+    transport: Option<String>,
+    command: Option<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    timeout: Option<u64>,
+}
+
+/// Expand `${VAR}` and `${VAR:-default}`. Unset variables without a default
+/// expand to an empty string; unterminated references are left as-is.
+fn expand_vars(input: &str, lookup: &impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let reference = &after[..end];
+        let (name, default) = match reference.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (reference, None),
+        };
+        match lookup(name).filter(|v| !v.is_empty()) {
+            Some(value) => out.push_str(&value),
+            None => match default {
+                Some(default) => out.push_str(default),
+                None => tracing::warn!("MCP config references unset variable ${{{name}}}"),
+            },
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
 }
 
 /// Configuration for a single MCP server.
@@ -28,6 +149,42 @@ pub struct McpServerConfig {
     /// Timeout for requests in milliseconds (default: 30000).
     #[serde(default = "default_timeout")]
     pub timeout_ms: u64,
+    /// Start the server on first tool use instead of at session start. Needs
+    /// a cached tool list from an earlier run; otherwise it starts eagerly once.
+    #[serde(default)]
+    pub lazy: bool,
+    /// Glob patterns of tool names to expose. Empty exposes every tool.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_tools: Vec<String>,
+    /// Glob patterns of tool names to hide. Takes precedence over `allowed_tools`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_tools: Vec<String>,
+}
+
+impl McpServerConfig {
+    /// Whether a tool (by its server-side name) passes the
+    /// `allowed_tools`/`disabled_tools` filters.
+    pub fn tool_enabled(&self, tool_name: &str) -> bool {
+        if self
+            .disabled_tools
+            .iter()
+            .any(|p| glob_matches(p, tool_name))
+        {
+            return false;
+        }
+        self.allowed_tools.is_empty()
+            || self
+                .allowed_tools
+                .iter()
+                .any(|p| glob_matches(p, tool_name))
+    }
+}
+
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    match globset::Glob::new(pattern) {
+        Ok(glob) => glob.compile_matcher().is_match(value),
+        Err(_) => pattern == value,
+    }
 }
 
 #[cfg(test)]
@@ -79,9 +236,103 @@ env = { GITHUB_TOKEN = "ghp_xxxx" }
         assert_eq!(gh.env["GITHUB_TOKEN"], "ghp_xxxx");
     }
 
+    #[test]
+    fn parse_tool_filters() {
+        let toml_str = r#"
+[servers.github]
+command = "npx"
+allowed_tools = ["list_*", "get_issue"]
+disabled_tools = ["list_secrets"]
+"#;
+        let config: McpConfig = toml::from_str(toml_str).unwrap();
+        let gh = &config.servers["github"];
+        assert!(gh.tool_enabled("list_repos"));
+        assert!(gh.tool_enabled("get_issue"));
+        assert!(!gh.tool_enabled("create_issue"));
+        assert!(!gh.tool_enabled("list_secrets"));
+    }
+
+    #[test]
+    fn no_filters_enable_every_tool() {
+        let toml_str = r#"
+[servers.fs]
+command = "npx"
+disabled_tools = ["write_*"]
+"#;
+        let config: McpConfig = toml::from_str(toml_str).unwrap();
+        let fs = &config.servers["fs"];
+        assert!(fs.allowed_tools.is_empty());
+        assert!(fs.tool_enabled("read_file"));
+        assert!(!fs.tool_enabled("write_file"));
+    }
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/me".to_string()),
+            "TOKEN" => Some("t0k".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expand_variables() {
+        assert_eq!(expand_vars("${HOME}/data", &lookup), "/home/me/data");
+        assert_eq!(expand_vars("${MISSING:-fallback}", &lookup), "fallback");
+        assert_eq!(expand_vars("${TOKEN:-x}", &lookup), "t0k");
+        assert_eq!(expand_vars("a${MISSING}b", &lookup), "ab");
+        assert_eq!(expand_vars("no vars", &lookup), "no vars");
+        assert_eq!(expand_vars("${UNTERMINATED", &lookup), "${UNTERMINATED");
+    }
+
+    #[test]
+    fn parse_mcp_json() {
+        let json = r#"{
+            "mcpServers": {
+                "files": {
+                    "command": "npx",
+                    "args": ["-y", "server-filesystem", "${HOME}/src"],
+                    "env": {"API_TOKEN": "${TOKEN}"}
+                },
+                "remote": {"type": "http", "url": "https://example.com/mcp"}
+            }
+        }"#;
+        let config = McpConfig::from_mcp_json(json, lookup).unwrap();
+        assert_eq!(config.servers.len(), 1);
+        let files = &config.servers["files"];
+        assert_eq!(files.command, "npx");
+        assert_eq!(files.args[2], "/home/me/src");
+        assert_eq!(files.env["API_TOKEN"], "t0k");
+        assert_eq!(files.timeout_ms, 30000);
+    }
+
+    #[test]
+    fn parse_mcp_json_invalid() {
+        assert!(McpConfig::from_mcp_json("not json", lookup).is_err());
+        let empty = McpConfig::from_mcp_json("{}", lookup).unwrap();
+        assert!(empty.servers.is_empty());
+    }
+
     #[test]
     fn default_config_is_empty() {
         let config = McpConfig::default();
         assert!(config.servers.is_empty());
+        assert_eq!(config.ping_interval_secs, 30);
+        assert_eq!(config.max_restarts, 5);
+    }
+
+    #[test]
+    fn parse_supervision_settings() {
+        let toml_str = r#"
+ping_interval_secs = 0
+max_restarts = 2
+
+[servers.docs]
+command = "docs-server"
+lazy = true
+"#;
+        let config: McpConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.ping_interval_secs, 0);
+        assert_eq!(config.max_restarts, 2);
+        assert!(config.servers["docs"].lazy);
     }
 }
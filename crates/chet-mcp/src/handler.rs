@@ -0,0 +1,408 @@
+//! Server-initiated requests — sampling and elicitation.
+//!
+//! MCP servers may call back into the client while a connection is open:
+//! `sampling/createMessage` asks the client's LLM for a completion, and
+//! `elicitation/create` asks the user for structured input. Both are routed
+//! to an application-provided [`McpRequestHandler`].
+
+use crate::jsonrpc::{
+    INTERNAL_ERROR, INVALID_PARAMS, JsonRpcIncomingRequest, JsonRpcReply, METHOD_NOT_FOUND,
+};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Error code returned when the user rejects a sampling request.
+pub const USER_REJECTED: i64 = -1;
+
+/// A `sampling/createMessage` request from a server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplingRequest {
+    pub messages: Vec<SamplingMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    pub max_tokens: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+}
+
+/// A single message in a sampling request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingMessage {
+    pub role: String,
+    pub content: SamplingContent,
+}
+
+/// Content of a sampling message or result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SamplingContent {
+    Text {
+        text: String,
+    },
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+}
+
+/// The client's answer to a sampling request.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplingResult {
+    pub role: String,
+    pub content: SamplingContent,
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+}
+
+/// Why a sampling request was not served.
+#[derive(Debug, Clone, Error)]
+pub enum SamplingError {
+    /// The user (or policy) declined the request — reported as [`USER_REJECTED`].
+    #[error("{0}")]
+    Rejected(String),
+    /// The request was approved but the provider call failed — reported as
+    /// an internal error.
+    #[error("{0}")]
+    Failed(String),
+}
+
+/// An `elicitation/create` request from a server.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElicitationRequest {
+    pub message: String,
+    #[serde(default = "default_requested_schema")]
+    pub requested_schema: serde_json::Value,
+}
+
+fn default_requested_schema() -> serde_json::Value {
+    serde_json::json!({"type": "object", "properties": {}})
+}
+
+/// How the user responded to an elicitation request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElicitationAction {
+    Accept,
+    Decline,
+    Cancel,
+}
+
+/// The client's answer to an elicitation request.
+#[derive(Debug, Clone, Serialize)]
+pub struct ElicitationResult {
+    pub action: ElicitationAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl ElicitationResult {
+    /// A decline with no content (used in non-interactive mode).
+    pub fn decline() -> Self {
+        Self {
+            action: ElicitationAction::Decline,
+            content: None,
+        }
+    }
+}
+
+/// Application hook for server-initiated requests.
+///
+/// Uses `Pin<Box<dyn Future>>` for dyn-compatibility, like `Tool` and `Provider`.
+pub trait McpRequestHandler: Send + Sync {
+    /// Serve a sampling request. `Err` distinguishes a rejection from a
+    /// provider failure so the server sees the right error code.
+    fn create_message<'a>(
+        &'a self,
+        server: &'a str,
+        request: SamplingRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<SamplingResult, SamplingError>> + Send + 'a>>;
+
+    /// Serve an elicitation request.
+    fn elicit<'a>(
+        &'a self,
+        server: &'a str,
+        request: ElicitationRequest,
+    ) -> Pin<Box<dyn Future<Output = ElicitationResult> + Send + 'a>>;
+}
+
+/// Routes server-initiated requests for one connection to its handler.
+#[derive(Clone)]
+pub(crate) struct RequestRouter {
+    server: String,
+    handler: Option<Arc<dyn McpRequestHandler>>,
+}
+
+impl RequestRouter {
+    pub(crate) fn new(
+        server: impl Into<String>,
+        handler: Option<Arc<dyn McpRequestHandler>>,
+    ) -> Self {
+        Self {
+            server: server.into(),
+            handler,
+        }
+    }
+
+    /// Whether a handler is installed (and capabilities should be advertised).
+    pub(crate) fn has_handler(&self) -> bool {
+        self.handler.is_some()
+    }
+
+    /// Handle one incoming request. Returns `None` for notifications.
+    pub(crate) async fn dispatch(&self, request: JsonRpcIncomingRequest) -> Option<JsonRpcReply> {
+        let id = request.id?;
+        let params = request.params.unwrap_or(serde_json::Value::Null);
+
+        let reply = match (request.method.as_str(), &self.handler) {
+            ("ping", _) => JsonRpcReply::success(id, serde_json::json!({})),
+            ("sampling/createMessage", Some(handler)) => {
+                match serde_json::from_value::<SamplingRequest>(params) {
+                    Ok(req) => match handler.create_message(&self.server, req).await {
+                        Ok(result) => match serde_json::to_value(&result) {
+                            Ok(value) => JsonRpcReply::success(id, value),
+                            Err(e) => JsonRpcReply::error(id, INTERNAL_ERROR, e.to_string()),
+                        },
+                        Err(SamplingError::Rejected(message)) => {
+                            JsonRpcReply::error(id, USER_REJECTED, message)
+                        }
+                        Err(SamplingError::Failed(message)) => {
+                            JsonRpcReply::error(id, INTERNAL_ERROR, message)
+                        }
+                    },
+                    Err(e) => JsonRpcReply::error(id, INVALID_PARAMS, e.to_string()),
+                }
+            }
+            ("elicitation/create", Some(handler)) => {
+                match serde_json::from_value::<ElicitationRequest>(params) {
+                    Ok(req) => {
+                        let result = handler.elicit(&self.server, req).await;
+                        match serde_json::to_value(&result) {
+                            Ok(value) => JsonRpcReply::success(id, value),
+                            Err(e) => JsonRpcReply::error(id, INTERNAL_ERROR, e.to_string()),
+                        }
+                    }
+                    Err(e) => JsonRpcReply::error(id, INVALID_PARAMS, e.to_string()),
+                }
+            }
+            (method, _) => {
+                JsonRpcReply::error(id, METHOD_NOT_FOUND, format!("Method not found: {method}"))
+            }
+        };
+        Some(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedHandler;
+
+    impl McpRequestHandler for FixedHandler {
+        fn create_message<'a>(
+            &'a self,
+            server: &'a str,
+            request: SamplingRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<SamplingResult, SamplingError>> + Send + 'a>>
+        {
+            Box::pin(async move {
+                if request.max_tokens == 0 {
+                    return Err(SamplingError::Rejected(
+                        "User rejected sampling request".to_string(),
+                    ));
+                }
+                if request.max_tokens == 1 {
+                    return Err(SamplingError::Failed("provider unavailable".to_string()));
+                }
+                Ok(SamplingResult {
+                    role: "assistant".to_string(),
+                    content: SamplingContent::Text {
+                        text: format!("hello from {server}"),
+                    },
+                    model: "test-model".to_string(),
+                    stop_reason: Some("endTurn".to_string()),
+                })
+            })
+        }
+
+        fn elicit<'a>(
+            &'a self,
+            _server: &'a str,
+            _request: ElicitationRequest,
+        ) -> Pin<Box<dyn Future<Output = ElicitationResult> + Send + 'a>> {
+            Box::pin(async { ElicitationResult::decline() })
+        }
+    }
+
+    fn incoming(
+        id: Option<serde_json::Value>,
+        method: &str,
+        params: serde_json::Value,
+    ) -> JsonRpcIncomingRequest {
+        JsonRpcIncomingRequest {
+            id,
+            method: method.to_string(),
+            params: Some(params),
+        }
+    }
+
+    fn router() -> RequestRouter {
+        RequestRouter::new("srv", Some(Arc::new(FixedHandler)))
+    }
+
+    #[test]
+    fn parse_sampling_request() {
+        let json = serde_json::json!({
+            "messages": [{"role": "user", "content": {"type": "text", "text": "hi"}}],
+            "systemPrompt": "be brief",
+            "maxTokens": 100
+        });
+        let req: SamplingRequest = serde_json::from_value(json).unwrap();
+        assert_eq!(req.messages.len(), 1);
+        assert_eq!(req.system_prompt.as_deref(), Some("be brief"));
+        assert_eq!(req.max_tokens, 100);
+        assert!(matches!(&req.messages[0].content, SamplingContent::Text { text } if text == "hi"));
+    }
+
+    #[test]
+    fn serialize_elicitation_result() {
+        let mut content = serde_json::Map::new();
+        content.insert("name".to_string(), serde_json::json!("octocat"));
+        let result = ElicitationResult {
+            action: ElicitationAction::Accept,
+            content: Some(content),
+        };
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["action"], "accept");
+        assert_eq!(json["content"]["name"], "octocat");
+
+        let json = serde_json::to_value(ElicitationResult::decline()).unwrap();
+        assert_eq!(json["action"], "decline");
+        assert!(json.get("content").is_none());
+    }
+
+    #[tokio::test]
+    async fn dispatch_sampling_success() {
+        let params = serde_json::json!({
+            "messages": [{"role": "user", "content": {"type": "text", "text": "hi"}}],
+            "maxTokens": 10
+        });
+        let reply = router()
+            .dispatch(incoming(
+                Some(serde_json::json!(1)),
+                "sampling/createMessage",
+                params,
+            ))
+            .await
+            .unwrap();
+        let result = reply.result.unwrap();
+        assert_eq!(result["role"], "assistant");
+        assert_eq!(result["content"]["text"], "hello from srv");
+        assert_eq!(result["stopReason"], "endTurn");
+    }
+
+    #[tokio::test]
+    async fn dispatch_sampling_rejected() {
+        let params = serde_json::json!({"messages": [], "maxTokens": 0});
+        let reply = router()
+            .dispatch(incoming(
+                Some(serde_json::json!(2)),
+                "sampling/createMessage",
+                params,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(reply.error.unwrap().code, USER_REJECTED);
+    }
+
+    #[tokio::test]
+    async fn dispatch_sampling_provider_failure() {
+        let params = serde_json::json!({"messages": [], "maxTokens": 1});
+        let reply = router()
+            .dispatch(incoming(
+                Some(serde_json::json!(6)),
+                "sampling/createMessage",
+                params,
+            ))
+            .await
+            .unwrap();
+        let error = reply.error.unwrap();
+        assert_eq!(error.code, INTERNAL_ERROR);
+        assert_eq!(error.message, "provider unavailable");
+    }
+
+    #[tokio::test]
+    async fn dispatch_invalid_params() {
+        let reply = router()
+            .dispatch(incoming(
+                Some(serde_json::json!(3)),
+                "sampling/createMessage",
+                serde_json::json!({"bogus": true}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(reply.error.unwrap().code, INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn dispatch_elicitation() {
+        let params = serde_json::json!({"message": "Name?", "requestedSchema": {"type": "object"}});
+        let reply = router()
+            .dispatch(incoming(
+                Some(serde_json::json!("e1")),
+                "elicitation/create",
+                params,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(reply.id, serde_json::json!("e1"));
+        assert_eq!(reply.result.unwrap()["action"], "decline");
+    }
+
+    #[tokio::test]
+    async fn dispatch_without_handler_is_method_not_found() {
+        let router = RequestRouter::new("srv", None);
+        let reply = router
+            .dispatch(incoming(
+                Some(serde_json::json!(4)),
+                "sampling/createMessage",
+                serde_json::json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(reply.error.unwrap().code, METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn dispatch_ping_and_notification() {
+        let router = RequestRouter::new("srv", None);
+        let reply = router
+            .dispatch(incoming(
+                Some(serde_json::json!(5)),
+                "ping",
+                serde_json::json!({}),
+            ))
+            .await
+            .unwrap();
+        assert!(reply.result.is_some());
+
+        let none = router
+            .dispatch(incoming(
+                None,
+                "notifications/progress",
+                serde_json::json!({}),
+            ))
+            .await;
+        assert!(none.is_none());
+    }
+}
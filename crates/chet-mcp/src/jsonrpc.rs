@@ -34,13 +34,73 @@ pub struct JsonRpcResponse {
 }
 
 /// A JSON-RPC 2.0 error object.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcError {
     pub code: i64,
     pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub data: Option<serde_json::Value>,
 }
 
+/// Standard JSON-RPC error code: the message is not valid JSON.
+pub const PARSE_ERROR: i64 = -32700;
+/// Standard JSON-RPC error code: the method does not exist.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// Standard JSON-RPC error code: invalid method parameters.
+pub const INVALID_PARAMS: i64 = -32602;
+/// Standard JSON-RPC error code: internal error.
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// A JSON-RPC 2.0 request or notification received from the peer.
+///
+/// Ids from the peer may be numbers or strings, so they are kept as raw
+/// JSON and echoed back unchanged. A missing id means a notification.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JsonRpcIncomingRequest {
+    #[serde(default)]
+    pub id: Option<serde_json::Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Option<serde_json::Value>,
+}
+
+/// A JSON-RPC 2.0 response sent back to the peer.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonRpcReply {
+    pub jsonrpc: &'static str,
+    pub id: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+}
+
+impl JsonRpcReply {
+    /// Create a successful reply.
+    pub fn success(id: serde_json::Value, result: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    /// Create an error reply.
+    pub fn error(id: serde_json::Value, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(JsonRpcError {
+                code,
+                message: message.into(),
+                data: None,
+            }),
+        }
+    }
+}
+
 /// A JSON-RPC 2.0 notification (no id, no response expected).
 #[derive(Debug, Clone, Serialize)]
 pub struct JsonRpcNotification {
@@ -135,6 +195,44 @@ mod tests {
         assert!(json.get("params").is_none());
     }
 
+    #[test]
+    fn deserialize_incoming_request_with_string_id() {
+        let json = r#"{"jsonrpc":"2.0","id":"abc","method":"sampling/createMessage","params":{}}"#;
+        let req: JsonRpcIncomingRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(req.id, Some(serde_json::json!("abc")));
+        assert_eq!(req.method, "sampling/createMessage");
+        assert!(req.params.is_some());
+    }
+
+    #[test]
+    fn deserialize_incoming_notification() {
+        let json = r#"{"jsonrpc":"2.0","method":"notifications/progress"}"#;
+        let req: JsonRpcIncomingRequest = serde_json::from_str(json).unwrap();
+        assert!(req.id.is_none());
+        assert!(req.params.is_none());
+    }
+
+    #[test]
+    fn serialize_reply_success() {
+        let reply = JsonRpcReply::success(serde_json::json!(7), serde_json::json!({"ok": true}));
+        let json = serde_json::to_value(&reply).unwrap();
+        assert_eq!(json["jsonrpc"], "2.0");
+        assert_eq!(json["id"], 7);
+        assert_eq!(json["result"]["ok"], true);
+        assert!(json.get("error").is_none());
+    }
+
+    #[test]
+    fn serialize_reply_error() {
+        let reply = JsonRpcReply::error(serde_json::json!("x"), METHOD_NOT_FOUND, "nope");
+        let json = serde_json::to_value(&reply).unwrap();
+        assert_eq!(json["id"], "x");
+        assert_eq!(json["error"]["code"], -32601);
+        assert_eq!(json["error"]["message"], "nope");
+        assert!(json.get("result").is_none());
+        assert!(json["error"].get("data").is_none());
+    }
+
     #[test]
     fn request_roundtrip() {
         let req = JsonRpcRequest::new(42, "test/method", Some(serde_json::json!({"key": "val"})));
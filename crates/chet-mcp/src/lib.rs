@@ -1,19 +1,32 @@
-//! MCP (Model Context Protocol) client implementation for Chet.
+//! MCP (Model Context Protocol) client and server implementation for Chet.
 //!
 //! Supports stdio-based MCP servers that communicate via newline-delimited
 //! JSON-RPC 2.0 messages. Each configured server is spawned as a child process,
 //! initialized with a handshake, and its tools are discovered and registered.
+//! Servers may also request LLM sampling and user elicitation from the client.
+//! The [`server`] module exposes chet's own tools to other MCP clients.
 
 pub mod client;
 pub mod config;
 pub mod error;
+pub mod handler;
 pub mod jsonrpc;
 pub mod manager;
+pub mod server;
+pub mod supervisor;
 pub mod tool;
 mod transport;
+pub mod trust;
 
-pub use client::{McpClient, McpToolInfo, McpToolResult};
+pub use client::{McpClient, McpToolAnnotations, McpToolInfo, McpToolResult};
 pub use config::{McpConfig, McpServerConfig};
 pub use error::McpError;
-pub use manager::McpManager;
+pub use handler::{
+    ElicitationAction, ElicitationRequest, ElicitationResult, McpRequestHandler, SamplingContent,
+    SamplingError, SamplingMessage, SamplingRequest, SamplingResult,
+};
+pub use manager::{McpManager, McpServerStatus, McpStartOptions};
+pub use server::{HttpAccess, McpServer, ToolBackend};
+pub use supervisor::{ServerState, SupervisedServer, SupervisorSettings};
 pub use tool::McpTool;
+pub use trust::McpTrustStore;
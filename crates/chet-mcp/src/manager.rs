@@ -1,145 +1,203 @@
-//! MCP manager — orchestrates multiple MCP server connections.
+//! MCP manager — orchestrates multiple supervised MCP server connections.
 
-use crate::client::{McpClient, McpToolInfo};
+use crate::client::McpToolInfo;
 use crate::config::McpConfig;
+use crate::handler::McpRequestHandler;
+use crate::supervisor::{ServerState, SupervisedServer, SupervisorSettings};
+use std::path::PathBuf;
 use std::sync::Arc;
 
+/// Options for [`McpManager::start_with_options`].
+#[derive(Default)]
+pub struct McpStartOptions {
+    /// Serves the servers' sampling and elicitation requests.
+    pub handler: Option<Arc<dyn McpRequestHandler>>,
+    /// Cache of tool lists; lets `lazy` servers register tools without starting.
+    pub tool_cache_dir: Option<PathBuf>,
+}
+
+/// Status of one server, for display.
+#[derive(Debug, Clone)]
+pub struct McpServerStatus {
+    pub name: String,
+    pub state: ServerState,
+    pub tool_count: usize,
+    pub restarts: u32,
+    /// Last lines the server wrote to stderr.
+    pub stderr_tail: Vec<String>,
+}
+
+/// Number of stderr lines included in [`McpServerStatus`].
+const STATUS_STDERR_LINES: usize = 5;
+
 /// Manages connections to multiple MCP servers.
 pub struct McpManager {
-    clients: Vec<Arc<McpClient>>,
-    config: McpConfig,
+    servers: Vec<Arc<SupervisedServer>>,
 }
 
 impl McpManager {
     /// Start all configured MCP servers.
     ///
-    /// Servers that fail to start are logged and skipped — the session continues
-    /// with whatever servers are available.
+    /// Servers that fail to start are logged and left in the failed state —
+    /// the session continues with whatever servers are available.
     pub async fn start(config: &McpConfig) -> Self {
-        let mut clients = Vec::new();
-
-        for (name, server_config) in &config.servers {
-            match McpClient::connect(name.clone(), server_config).await {
-                Ok(client) => {
-                    tracing::info!(
-                        "MCP server '{}' started ({} tools)",
-                        name,
-                        client.tools().len()
-                    );
-                    clients.push(Arc::new(client));
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to start MCP server '{}': {}", name, e);
-                    eprintln!("Warning: MCP server '{name}' failed to start: {e}");
+        Self::start_with_options(config, McpStartOptions::default()).await
+    }
+
+    /// Start all configured MCP servers, serving their sampling and
+    /// elicitation requests with `handler`.
+    pub async fn start_with_handler(
+        config: &McpConfig,
+        handler: Option<Arc<dyn McpRequestHandler>>,
+    ) -> Self {
+        Self::start_with_options(
+            config,
+            McpStartOptions {
+                handler,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Start all configured MCP servers under supervision.
+    ///
+    /// `lazy` servers with a cached tool list are left idle until their first
+    /// tool call; all others are connected now.
+    pub async fn start_with_options(config: &McpConfig, options: McpStartOptions) -> Self {
+        let mut settings = SupervisorSettings::from_config(config);
+        settings.tool_cache_dir = options.tool_cache_dir;
+
+        let mut names: Vec<&String> = config.servers.keys().collect();
+        names.sort();
+
+        let mut servers = Vec::new();
+        for name in names {
+            let server_config = &config.servers[name];
+            let server = SupervisedServer::new(
+                name.clone(),
+                server_config.clone(),
+                options.handler.clone(),
+                settings.clone(),
+            );
+            if server_config.lazy && server.load_cached_tools() {
+                tracing::info!(
+                    "MCP server '{}' deferred until first use ({} cached tools)",
+                    name,
+                    server.tools().len()
+                );
+            } else {
+                match server.start().await {
+                    Ok(client) => {
+                        tracing::info!(
+                            "MCP server '{}' started ({} tools)",
+                            name,
+                            client.tools().len()
+                        );
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to start MCP server '{}': {}", name, e);
+                        eprintln!("Warning: MCP server '{name}' failed to start: {e}");
+                    }
                 }
             }
+            servers.push(server);
         }
 
-        Self {
-            clients,
-            config: config.clone(),
-        }
+        Self { servers }
     }
 
     /// Reconnect a specific server by name, or all servers if name is None.
     /// Returns the number of servers successfully (re)connected.
     pub async fn reconnect(&mut self, server_name: Option<&str>) -> usize {
-        let servers_to_reconnect: Vec<(String, crate::config::McpServerConfig)> = match server_name
-        {
-            Some(name) => {
-                if let Some(cfg) = self.config.servers.get(name) {
-                    vec![(name.to_string(), cfg.clone())]
-                } else {
+        let targets: Vec<&Arc<SupervisedServer>> = match server_name {
+            Some(name) => match self.servers.iter().find(|s| s.name() == name) {
+                Some(server) => vec![server],
+                None => {
                     eprintln!("Unknown MCP server: {name}");
                     eprintln!(
                         "Configured servers: {}",
-                        self.config
-                            .servers
-                            .keys()
-                            .cloned()
+                        self.servers
+                            .iter()
+                            .map(|s| s.name())
                             .collect::<Vec<_>>()
                             .join(", ")
                     );
                     return 0;
                 }
-            }
-            None => self
-                .config
-                .servers
-                .iter()
-                .map(|(k, v)| (k.clone(), v.clone()))
-                .collect(),
+            },
+            None => self.servers.iter().collect(),
         };
 
-        // Shut down existing clients for the servers we're reconnecting
-        let reconnect_names: Vec<&str> = servers_to_reconnect
-            .iter()
-            .map(|(n, _)| n.as_str())
-            .collect();
-        let mut kept_clients = Vec::new();
-        for client in self.clients.drain(..) {
-            if reconnect_names.contains(&client.server_name()) {
-                if let Ok(c) = Arc::try_unwrap(client) {
-                    c.shutdown().await;
-                }
-            } else {
-                kept_clients.push(client);
-            }
-        }
-        self.clients = kept_clients;
-
-        // Reconnect
         let mut connected = 0;
-        for (name, server_config) in &servers_to_reconnect {
-            match McpClient::connect(name.clone(), server_config).await {
-                Ok(client) => {
+        for server in targets {
+            match server.restart().await {
+                Ok(()) => {
                     eprintln!(
                         "MCP server '{}' connected ({} tools)",
-                        name,
-                        client.tools().len()
+                        server.name(),
+                        server.tools().len()
                     );
-                    self.clients.push(Arc::new(client));
                     connected += 1;
                 }
                 Err(e) => {
-                    eprintln!("Failed to connect MCP server '{name}': {e}");
+                    eprintln!("Failed to connect MCP server '{}': {e}", server.name());
                 }
             }
         }
         connected
     }
 
-    /// Get all tools from all connected servers, paired with their client.
-    pub fn tools(&self) -> Vec<(Arc<McpClient>, McpToolInfo)> {
+    /// Get all tools from all servers, paired with their supervisor.
+    pub fn tools(&self) -> Vec<(Arc<SupervisedServer>, McpToolInfo)> {
         let mut all_tools = Vec::new();
-        for client in &self.clients {
-            for tool in client.tools() {
-                all_tools.push((Arc::clone(client), tool.clone()));
+        for server in &self.servers {
+            for tool in server.tools() {
+                all_tools.push((Arc::clone(server), tool));
             }
         }
         all_tools
     }
 
-    /// Number of connected servers.
+    /// Number of servers currently connected.
     pub fn client_count(&self) -> usize {
-        self.clients.len()
+        self.servers
+            .iter()
+            .filter(|s| s.state() == ServerState::Ready)
+            .count()
     }
 
-    /// Get a summary of connected servers and their tool counts.
+    /// Number of configured servers, whatever their state.
+    pub fn server_count(&self) -> usize {
+        self.servers.len()
+    }
+
+    /// Get a summary of servers and their tool counts.
     pub fn server_summary(&self) -> Vec<(&str, usize)> {
-        self.clients
+        self.servers
+            .iter()
+            .map(|s| (s.name(), s.tools().len()))
+            .collect()
+    }
+
+    /// Per-server state, tool count and recent stderr.
+    pub fn status(&self) -> Vec<McpServerStatus> {
+        self.servers
             .iter()
-            .map(|c| (c.server_name(), c.tools().len()))
+            .map(|s| McpServerStatus {
+                name: s.name().to_string(),
+                state: s.state(),
+                tool_count: s.tools().len(),
+                restarts: s.restart_count(),
+                stderr_tail: s.stderr_tail(STATUS_STDERR_LINES),
+            })
             .collect()
     }
 
-    /// Shut down all connected servers.
+    /// Shut down all servers.
     pub async fn shutdown(self) {
-        for client in self.clients {
-            // Try to unwrap the Arc; if other references exist, skip
-            if let Ok(client) = Arc::try_unwrap(client) {
-                client.shutdown().await;
-            }
+        for server in self.servers {
+            server.shutdown().await;
         }
     }
 }
@@ -158,7 +216,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn failed_server_is_skipped() {
+    async fn failed_server_is_reported() {
         let mut config = McpConfig::default();
         config.servers.insert(
             "bad".to_string(),
@@ -167,10 +225,18 @@ mod tests {
                 args: vec![],
                 env: std::collections::HashMap::new(),
                 timeout_ms: 1000,
+                lazy: false,
+                allowed_tools: vec![],
+                disabled_tools: vec![],
             },
         );
         let manager = McpManager::start(&config).await;
         assert_eq!(manager.client_count(), 0);
+        assert_eq!(manager.server_count(), 1);
+        let status = manager.status();
+        assert_eq!(status[0].name, "bad");
+        assert!(matches!(status[0].state, ServerState::Failed { .. }));
+        assert!(manager.tools().is_empty());
         manager.shutdown().await;
     }
 
@@ -0,0 +1,645 @@
+//! MCP server — exposes a set of tools to MCP clients.
+//!
+//! Serves `initialize`, `ping`, `tools/list` and `tools/call` over stdio
+//! (newline-delimited JSON-RPC) or a minimal HTTP endpoint that accepts one
+//! JSON-RPC message per POST, guarded by a bearer token and an `Origin` check.
+//! Tool execution is delegated to a [`ToolBackend`].
+
+use crate::client::PROTOCOL_VERSION;
+use crate::jsonrpc::{
+    INVALID_PARAMS, JsonRpcIncomingRequest, JsonRpcReply, METHOD_NOT_FOUND, PARSE_ERROR,
+};
+use chet_types::{ImageSourceType, ToolDefinition, ToolOutput, ToolOutputContent};
+use serde::Deserialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+/// Largest HTTP request body the server accepts.
+const MAX_HTTP_BODY: usize = 10 * 1024 * 1024;
+
+/// The tools an [`McpServer`] exposes and how to run them.
+///
+/// Uses `Pin<Box<dyn Future>>` for dyn-compatibility, like `Tool`.
+pub trait ToolBackend: Send + Sync {
+    /// Definitions for `tools/list`.
+    fn definitions(&self) -> Vec<ToolDefinition>;
+
+    /// Whether the named tool only reads state (advertised as `readOnlyHint`).
+    fn is_read_only(&self, name: &str) -> bool;
+
+    /// Run a tool for `tools/call`. Failures are reported as error outputs.
+    fn call_tool<'a>(
+        &'a self,
+        name: &'a str,
+        arguments: serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = ToolOutput> + Send + 'a>>;
+}
+
+#[derive(Deserialize)]
+struct ToolCallParams {
+    name: String,
+    #[serde(default)]
+    arguments: Option<serde_json::Value>,
+}
+
+/// An MCP server backed by a [`ToolBackend`].
+pub struct McpServer {
+    backend: Arc<dyn ToolBackend>,
+}
+
+impl McpServer {
+    pub fn new(backend: Arc<dyn ToolBackend>) -> Self {
+        Self { backend }
+    }
+
+    /// Handle one JSON-RPC message. Returns `None` for notifications.
+    pub async fn handle(&self, request: JsonRpcIncomingRequest) -> Option<JsonRpcReply> {
+        let id = request.id?;
+        let reply = match request.method.as_str() {
+            "initialize" => {
+                let version = request
+                    .params
+                    .as_ref()
+                    .and_then(|p| p["protocolVersion"].as_str())
+                    .unwrap_or(PROTOCOL_VERSION)
+                    .to_string();
+                JsonRpcReply::success(
+                    id,
+                    serde_json::json!({
+                        "protocolVersion": version,
+                        "capabilities": {"tools": {}},
+                        "serverInfo": {
+                            "name": "chet",
+                            "version": env!("CARGO_PKG_VERSION")
+                        }
+                    }),
+                )
+            }
+            "ping" => JsonRpcReply::success(id, serde_json::json!({})),
+            "tools/list" => JsonRpcReply::success(id, self.list_tools()),
+            "tools/call" => {
+                let params = request.params.unwrap_or(serde_json::Value::Null);
+                match serde_json::from_value::<ToolCallParams>(params) {
+                    Ok(call) => {
+                        let arguments = call.arguments.unwrap_or_else(|| serde_json::json!({}));
+                        let output = self.backend.call_tool(&call.name, arguments).await;
+                        JsonRpcReply::success(id, tool_output_to_result(output))
+                    }
+                    Err(e) => JsonRpcReply::error(id, INVALID_PARAMS, e.to_string()),
+                }
+            }
+            method => {
+                JsonRpcReply::error(id, METHOD_NOT_FOUND, format!("Method not found: {method}"))
+            }
+        };
+        Some(reply)
+    }
+
+    /// Handle one raw JSON-RPC line or body, including parse failures.
+    async fn handle_raw(&self, raw: &str) -> Option<JsonRpcReply> {
+        match serde_json::from_str::<JsonRpcIncomingRequest>(raw) {
+            Ok(request) => self.handle(request).await,
+            Err(e) => Some(JsonRpcReply::error(
+                serde_json::Value::Null,
+                PARSE_ERROR,
+                e.to_string(),
+            )),
+        }
+    }
+
+    fn list_tools(&self) -> serde_json::Value {
+        let mut defs = self.backend.definitions();
+        defs.sort_by(|a, b| a.name.cmp(&b.name));
+        let tools: Vec<serde_json::Value> = defs
+            .into_iter()
+            .map(|d| {
+                let read_only = self.backend.is_read_only(&d.name);
+                serde_json::json!({
+                    "name": d.name,
+                    "description": d.description,
+                    "inputSchema": d.input_schema,
+                    "annotations": {
+                        "readOnlyHint": read_only,
+                        "destructiveHint": !read_only
+                    }
+                })
+            })
+            .collect();
+        serde_json::json!({ "tools": tools })
+    }
+
+    /// Serve over stdin/stdout until stdin closes. Requests are handled
+    /// concurrently; replies are written as they complete.
+    pub async fn serve_stdio(self: Arc<Self>) -> std::io::Result<()> {
+        let (reply_tx, mut reply_rx) = mpsc::channel::<String>(64);
+        let writer = tokio::spawn(async move {
+            let mut stdout = tokio::io::stdout();
+            while let Some(msg) = reply_rx.recv().await {
+                if stdout.write_all(msg.as_bytes()).await.is_err()
+                    || stdout.write_all(b"\n").await.is_err()
+                    || stdout.flush().await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut tasks = tokio::task::JoinSet::new();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let server = Arc::clone(&self);
+            let reply_tx = reply_tx.clone();
+            tasks.spawn(async move {
+                if let Some(reply) = server.handle_raw(&line).await {
+                    if let Ok(serialized) = serde_json::to_string(&reply) {
+                        let _ = reply_tx.send(serialized).await;
+                    }
+                }
+            });
+            // Reap finished calls so the set only holds in-flight ones
+            while tasks.try_join_next().is_some() {}
+        }
+
+        // Let in-flight calls finish before closing stdout
+        while tasks.join_next().await.is_some() {}
+        drop(reply_tx);
+        let _ = writer.await;
+        Ok(())
+    }
+
+    /// Serve over HTTP on `addr`. Each POST body is one JSON-RPC message;
+    /// the reply is returned as `application/json` (202 for notifications).
+    ///
+    /// Every request must carry `Authorization: Bearer <token>`, and browser
+    /// requests (those with an `Origin` header) must come from a loopback
+    /// origin to defeat DNS rebinding. Non-loopback addresses are refused
+    /// unless `access.allow_remote` is set.
+    pub async fn serve_http(
+        self: Arc<Self>,
+        addr: &str,
+        access: HttpAccess,
+    ) -> std::io::Result<()> {
+        let addrs: Vec<std::net::SocketAddr> = tokio::net::lookup_host(addr).await?.collect();
+        if addrs.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{addr} did not resolve to any address"),
+            ));
+        }
+        if !access.allow_remote && addrs.iter().any(|a| !a.ip().is_loopback()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!(
+                    "refusing to serve on non-loopback address {addr} without opting in to remote access"
+                ),
+            ));
+        }
+
+        let listener = tokio::net::TcpListener::bind(&addrs[..]).await?;
+        tracing::info!("MCP server listening on http://{}", listener.local_addr()?);
+        let access = Arc::new(access);
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let server = Arc::clone(&self);
+            let access = Arc::clone(&access);
+            tokio::spawn(async move {
+                if let Err(e) = server.serve_http_connection(stream, &access).await {
+                    tracing::debug!("MCP HTTP connection error: {e}");
+                }
+            });
+        }
+    }
+
+    async fn serve_http_connection(
+        &self,
+        stream: tokio::net::TcpStream,
+        access: &HttpAccess,
+    ) -> std::io::Result<()> {
+        let mut reader = BufReader::new(stream);
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).await?;
+        let method = request_line.split_whitespace().next().unwrap_or_default();
+        let is_post = method.eq_ignore_ascii_case("POST");
+
+        let mut content_length = 0usize;
+        let mut authorization = None;
+        let mut origin = None;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).await? == 0 {
+                break;
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                let name = name.trim();
+                let value = value.trim();
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.parse().unwrap_or(0);
+                } else if name.eq_ignore_ascii_case("authorization") {
+                    authorization = Some(value.to_string());
+                } else if name.eq_ignore_ascii_case("origin") {
+                    origin = Some(value.to_string());
+                }
+            }
+        }
+
+        if !is_post {
+            return write_http(reader.get_mut(), "405 Method Not Allowed", None).await;
+        }
+        if origin.as_deref().is_some_and(|o| !is_loopback_origin(o)) {
+            return write_http(reader.get_mut(), "403 Forbidden", None).await;
+        }
+        let token = authorization
+            .as_deref()
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        if !token_matches(token.trim(), &access.token) {
+            return write_http(reader.get_mut(), "401 Unauthorized", None).await;
+        }
+        if content_length > MAX_HTTP_BODY {
+            return write_http(reader.get_mut(), "413 Payload Too Large", None).await;
+        }
+
+        // Read through the BufReader — it has usually buffered the body already
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).await?;
+        let body = String::from_utf8_lossy(&body);
+
+        let stream = reader.get_mut();
+        match self.handle_raw(&body).await {
+            Some(reply) => {
+                let json = serde_json::to_string(&reply).map_err(std::io::Error::other)?;
+                write_http(stream, "200 OK", Some(&json)).await
+            }
+            None => write_http(stream, "202 Accepted", None).await,
+        }
+    }
+}
+
+/// Access control for [`McpServer::serve_http`].
+#[derive(Debug, Clone)]
+pub struct HttpAccess {
+    /// Bearer token clients must send in the `Authorization` header.
+    pub token: String,
+    /// Allow binding a non-loopback address, exposing tools to the network.
+    pub allow_remote: bool,
+}
+
+impl HttpAccess {
+    /// Loopback-only access with a freshly generated random token.
+    pub fn with_random_token() -> Self {
+        Self {
+            token: format!(
+                "{}{}",
+                uuid::Uuid::new_v4().simple(),
+                uuid::Uuid::new_v4().simple()
+            ),
+            allow_remote: false,
+        }
+    }
+}
+
+/// Whether an `Origin` header names a loopback host (any scheme or port).
+fn is_loopback_origin(origin: &str) -> bool {
+    let Some((_, rest)) = origin.split_once("://") else {
+        return false;
+    };
+    let authority = rest.split('/').next().unwrap_or_default();
+    let host = if let Some(bracketed) = authority.strip_prefix('[') {
+        bracketed.split(']').next().unwrap_or_default()
+    } else {
+        authority.split(':').next().unwrap_or_default()
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Compare tokens without short-circuiting on the first mismatch.
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+async fn write_http(
+    stream: &mut tokio::net::TcpStream,
+    status: &str,
+    json: Option<&str>,
+) -> std::io::Result<()> {
+    let body = json.unwrap_or("");
+    let content_type = if json.is_some() {
+        "Content-Type: application/json\r\n"
+    } else {
+        ""
+    };
+    let challenge = if status.starts_with("401") {
+        "WWW-Authenticate: Bearer\r\n"
+    } else {
+        ""
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\n{content_type}{challenge}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.flush().await
+}
+
+/// Map a tool output to an MCP `tools/call` result.
+pub fn tool_output_to_result(output: ToolOutput) -> serde_json::Value {
+    let content: Vec<serde_json::Value> = output
+        .content
+        .into_iter()
+        .map(|c| match c {
+            ToolOutputContent::Text { text } => serde_json::json!({"type": "text", "text": text}),
+            ToolOutputContent::Image { source } => match source.source_type {
+                ImageSourceType::Base64 => serde_json::json!({
+                    "type": "image",
+                    "data": source.data,
+                    "mimeType": source.media_type
+                }),
+                ImageSourceType::Url => {
+                    serde_json::json!({"type": "text", "text": format!("[image: {}]", source.data)})
+                }
+            },
+        })
+        .collect();
+    serde_json::json!({ "content": content, "isError": output.is_error })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chet_types::ImageSource;
+
+    struct EchoBackend;
+
+    impl ToolBackend for EchoBackend {
+        fn definitions(&self) -> Vec<ToolDefinition> {
+            vec![
+                ToolDefinition {
+                    name: "Write".into(),
+                    description: "Write a file".into(),
+                    input_schema: serde_json::json!({"type": "object"}),
+                    cache_control: None,
+                },
+                ToolDefinition {
+                    name: "Echo".into(),
+                    description: "Echo input".into(),
+                    input_schema: serde_json::json!({"type": "object"}),
+                    cache_control: None,
+                },
+            ]
+        }
+
+        fn is_read_only(&self, name: &str) -> bool {
+            name == "Echo"
+        }
+
+        fn call_tool<'a>(
+            &'a self,
+            name: &'a str,
+            arguments: serde_json::Value,
+        ) -> Pin<Box<dyn Future<Output = ToolOutput> + Send + 'a>> {
+            Box::pin(async move {
+                match name {
+                    "Echo" => ToolOutput::text(arguments["text"].as_str().unwrap_or_default()),
+                    other => ToolOutput::error(format!("Unknown tool: {other}")),
+                }
+            })
+        }
+    }
+
+    fn server() -> McpServer {
+        McpServer::new(Arc::new(EchoBackend))
+    }
+
+    fn request(id: u64, method: &str, params: Option<serde_json::Value>) -> JsonRpcIncomingRequest {
+        JsonRpcIncomingRequest {
+            id: Some(serde_json::json!(id)),
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    #[tokio::test]
+    async fn initialize_reports_tools_capability() {
+        let reply = server()
+            .handle(request(
+                1,
+                "initialize",
+                Some(serde_json::json!({"protocolVersion": "2025-03-26"})),
+            ))
+            .await
+            .unwrap();
+        let result = reply.result.unwrap();
+        assert_eq!(result["protocolVersion"], "2025-03-26");
+        assert!(result["capabilities"]["tools"].is_object());
+        assert_eq!(result["serverInfo"]["name"], "chet");
+    }
+
+    #[tokio::test]
+    async fn tools_list_is_sorted_with_annotations() {
+        let reply = server()
+            .handle(request(2, "tools/list", None))
+            .await
+            .unwrap();
+        let tools = reply.result.unwrap()["tools"].as_array().unwrap().clone();
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[0]["name"], "Echo");
+        assert_eq!(tools[0]["annotations"]["readOnlyHint"], true);
+        assert_eq!(tools[1]["name"], "Write");
+        assert_eq!(tools[1]["annotations"]["destructiveHint"], true);
+        assert!(tools[1]["inputSchema"].is_object());
+    }
+
+    #[tokio::test]
+    async fn tools_call_maps_output() {
+        let reply = server()
+            .handle(request(
+                3,
+                "tools/call",
+                Some(serde_json::json!({"name": "Echo", "arguments": {"text": "hi"}})),
+            ))
+            .await
+            .unwrap();
+        let result = reply.result.unwrap();
+        assert_eq!(result["content"][0]["type"], "text");
+        assert_eq!(result["content"][0]["text"], "hi");
+        assert_eq!(result["isError"], false);
+    }
+
+    #[tokio::test]
+    async fn tools_call_error_sets_is_error() {
+        let reply = server()
+            .handle(request(
+                4,
+                "tools/call",
+                Some(serde_json::json!({"name": "Write"})),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(reply.result.unwrap()["isError"], true);
+    }
+
+    #[tokio::test]
+    async fn tools_call_without_name_is_invalid_params() {
+        let reply = server()
+            .handle(request(5, "tools/call", Some(serde_json::json!({}))))
+            .await
+            .unwrap();
+        assert_eq!(reply.error.unwrap().code, INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn notifications_get_no_reply() {
+        let reply = server()
+            .handle(JsonRpcIncomingRequest {
+                id: None,
+                method: "notifications/initialized".into(),
+                params: None,
+            })
+            .await;
+        assert!(reply.is_none());
+    }
+
+    #[tokio::test]
+    async fn unknown_method_and_parse_error() {
+        let reply = server()
+            .handle(request(6, "resources/list", None))
+            .await
+            .unwrap();
+        assert_eq!(reply.error.unwrap().code, METHOD_NOT_FOUND);
+
+        let reply = server().handle_raw("not json").await.unwrap();
+        assert_eq!(reply.error.unwrap().code, PARSE_ERROR);
+        assert!(reply.id.is_null());
+    }
+
+    #[test]
+    fn image_output_maps_to_image_block() {
+        let output = ToolOutput {
+            content: vec![ToolOutputContent::Image {
+                source: ImageSource {
+                    source_type: ImageSourceType::Base64,
+                    media_type: "image/png".into(),
+                    data: "abcd".into(),
+                },
+            }],
+            is_error: false,
+        };
+        let result = tool_output_to_result(output);
+        assert_eq!(result["content"][0]["type"], "image");
+        assert_eq!(result["content"][0]["mimeType"], "image/png");
+        assert_eq!(result["content"][0]["data"], "abcd");
+    }
+
+    fn access() -> HttpAccess {
+        HttpAccess {
+            token: "secret-token".into(),
+            allow_remote: false,
+        }
+    }
+
+    /// Send one raw HTTP request to a single-connection server and return the response.
+    async fn http_exchange(extra_headers: &str, body: &str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = Arc::new(server());
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            server
+                .serve_http_connection(stream, &access())
+                .await
+                .unwrap();
+        });
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "POST /mcp HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n{extra_headers}Content-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        handle.await.unwrap();
+        response
+    }
+
+    const PING: &str = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+
+    #[tokio::test]
+    async fn http_roundtrip() {
+        let response = http_exchange("Authorization: Bearer secret-token\r\n", PING).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.contains(r#""id":1"#));
+        assert!(response.contains(r#""result":{}"#));
+    }
+
+    #[tokio::test]
+    async fn http_requires_bearer_token() {
+        let response = http_exchange("", PING).await;
+        assert!(response.starts_with("HTTP/1.1 401"), "{response}");
+        assert!(response.contains("WWW-Authenticate: Bearer"));
+
+        let response = http_exchange("Authorization: Bearer wrong\r\n", PING).await;
+        assert!(response.starts_with("HTTP/1.1 401"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn http_rejects_foreign_origin() {
+        let response = http_exchange(
+            "Authorization: Bearer secret-token\r\nOrigin: https://evil.example\r\n",
+            PING,
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 403"), "{response}");
+
+        let response = http_exchange(
+            "Authorization: Bearer secret-token\r\nOrigin: http://localhost:3000\r\n",
+            PING,
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn http_refuses_non_loopback_bind() {
+        let server = Arc::new(server());
+        let err = server.serve_http("0.0.0.0:0", access()).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn loopback_origins() {
+        assert!(is_loopback_origin("http://localhost"));
+        assert!(is_loopback_origin("http://127.0.0.1:8080"));
+        assert!(is_loopback_origin("https://[::1]:443"));
+        assert!(!is_loopback_origin("http://localhost.evil.example"));
+        assert!(!is_loopback_origin("http://192.168.1.5"));
+        assert!(!is_loopback_origin("null"));
+    }
+
+    #[test]
+    fn token_comparison() {
+        assert!(token_matches("abc", "abc"));
+        assert!(!token_matches("abd", "abc"));
+        assert!(!token_matches("ab", "abc"));
+        assert_eq!(HttpAccess::with_random_token().token.len(), 64);
+    }
+}
@@ -0,0 +1,570 @@
+//! Supervised MCP server connections.
+//!
+//! A [`SupervisedServer`] owns one server's connection for the whole session:
+//! it connects at startup or on first use, pings the server periodically, and
+//! restarts it with exponential backoff when the process exits or stops
+//! answering. Tools hold the supervisor rather than a client, so restarts are
+//! transparent to them.
+
+use crate::client::{McpClient, McpToolInfo, McpToolResult};
+use crate::config::{McpConfig, McpServerConfig};
+use crate::error::McpError;
+use crate::handler::McpRequestHandler;
+use crate::transport::StderrLog;
+use crate::trust::fingerprint;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Lifecycle state of a supervised server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerState {
+    /// Lazy server that has not been needed yet.
+    Idle,
+    Starting,
+    Ready,
+    /// Waiting to reconnect after the server exited or failed a health check.
+    Restarting {
+        attempt: u32,
+    },
+    Failed {
+        error: String,
+    },
+    Stopped,
+}
+
+impl std::fmt::Display for ServerState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Idle => write!(f, "idle"),
+            Self::Starting => write!(f, "starting"),
+            Self::Ready => write!(f, "ready"),
+            Self::Restarting { attempt } => write!(f, "restarting (attempt {attempt})"),
+            Self::Failed { error } => write!(f, "failed: {error}"),
+            Self::Stopped => write!(f, "stopped"),
+        }
+    }
+}
+
+/// Health-check and restart policy.
+#[derive(Debug, Clone)]
+pub struct SupervisorSettings {
+    /// Interval between pings to a ready server (`None` disables pings).
+    pub ping_interval: Option<Duration>,
+    /// Restart attempts before the server is marked failed.
+    pub max_restarts: u32,
+    /// Delay before the first restart attempt; doubles on each failure.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Where to cache tool lists so lazy servers can register tools without starting.
+    pub tool_cache_dir: Option<PathBuf>,
+}
+
+impl SupervisorSettings {
+    /// Settings from the `[mcp]` config section.
+    pub fn from_config(config: &McpConfig) -> Self {
+        Self {
+            ping_interval: (config.ping_interval_secs > 0)
+                .then(|| Duration::from_secs(config.ping_interval_secs)),
+            max_restarts: config.max_restarts,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            tool_cache_dir: None,
+        }
+    }
+}
+
+/// Cached `tools/list` result for one server definition.
+#[derive(Serialize, Deserialize)]
+struct ToolCache {
+    fingerprint: String,
+    tools: Vec<McpToolInfo>,
+}
+
+/// One MCP server under supervision.
+pub struct SupervisedServer {
+    name: String,
+    config: McpServerConfig,
+    handler: Option<Arc<dyn McpRequestHandler>>,
+    settings: SupervisorSettings,
+    /// Current connection. The lock also serializes (re)connects.
+    client: tokio::sync::Mutex<Option<Arc<McpClient>>>,
+    state: std::sync::Mutex<ServerState>,
+    tools: std::sync::Mutex<Vec<McpToolInfo>>,
+    stderr: StderrLog,
+    /// Bumped on every connect and on shutdown so stale monitors stand down.
+    generation: AtomicU64,
+    restarts: AtomicU32,
+    /// Dropped or signalled on shutdown to stop monitor tasks.
+    stop_tx: watch::Sender<bool>,
+}
+
+impl SupervisedServer {
+    pub fn new(
+        name: String,
+        config: McpServerConfig,
+        handler: Option<Arc<dyn McpRequestHandler>>,
+        settings: SupervisorSettings,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            name,
+            config,
+            handler,
+            settings,
+            client: tokio::sync::Mutex::new(None),
+            state: std::sync::Mutex::new(ServerState::Idle),
+            tools: std::sync::Mutex::new(Vec::new()),
+            stderr: StderrLog::default(),
+            generation: AtomicU64::new(0),
+            restarts: AtomicU32::new(0),
+            stop_tx: watch::channel(false).0,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn state(&self) -> ServerState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Tools from the last successful connection (or the cache for idle lazy servers).
+    pub fn tools(&self) -> Vec<McpToolInfo> {
+        self.tools.lock().unwrap().clone()
+    }
+
+    /// The server's last `n` stderr lines, across restarts.
+    pub fn stderr_tail(&self, n: usize) -> Vec<String> {
+        self.stderr.tail(n)
+    }
+
+    /// Number of automatic restarts so far.
+    pub fn restart_count(&self) -> u32 {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    /// Prepare a lazy server from its cached tool list without starting it.
+    /// Returns false when there is no usable cache.
+    pub fn load_cached_tools(&self) -> bool {
+        let Some(path) = self.cache_path() else {
+            return false;
+        };
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return false;
+        };
+        match serde_json::from_str::<ToolCache>(&content) {
+            Ok(cache) if cache.fingerprint == fingerprint(&self.config) => {
+                *self.tools.lock().unwrap() = cache.tools;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Connect now unless already connected.
+    pub async fn start(self: &Arc<Self>) -> Result<Arc<McpClient>, McpError> {
+        let mut slot = self.client.lock().await;
+        if let Some(client) = slot.as_ref().filter(|c| !c.is_closed()) {
+            return Ok(Arc::clone(client));
+        }
+        self.connect_locked(&mut slot, ServerState::Starting).await
+    }
+
+    /// Replace the current connection with a fresh one (e.g. `/mcp reconnect`).
+    pub async fn restart(self: &Arc<Self>) -> Result<(), McpError> {
+        let mut slot = self.client.lock().await;
+        self.connect_locked(&mut slot, ServerState::Starting)
+            .await
+            .map(|_| ())
+    }
+
+    /// Call a tool, starting the server first if it is idle or has exited.
+    ///
+    /// A server that dies during the call is not retried — the call may have
+    /// had side effects — but the supervisor restarts it in the background.
+    pub async fn call_tool(
+        self: &Arc<Self>,
+        tool_name: &str,
+        arguments: serde_json::Value,
+    ) -> Result<McpToolResult, McpError> {
+        let client = {
+            let mut slot = self.client.lock().await;
+            match slot.as_ref().filter(|c| !c.is_closed()) {
+                Some(client) => Arc::clone(client),
+                None => match self.state() {
+                    ServerState::Failed { error } => {
+                        return Err(McpError::Protocol(format!(
+                            "MCP server '{}' failed ({error}); use /mcp reconnect {}",
+                            self.name, self.name
+                        )));
+                    }
+                    ServerState::Stopped => {
+                        return Err(McpError::ServerNotRunning {
+                            name: self.name.clone(),
+                        });
+                    }
+                    _ => {
+                        self.connect_locked(&mut slot, ServerState::Starting)
+                            .await?
+                    }
+                },
+            }
+        };
+        client.call_tool(tool_name, arguments).await
+    }
+
+    /// Stop supervising and shut the server down.
+    pub async fn shutdown(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        let _ = self.stop_tx.send(true);
+        let client = self.client.lock().await.take();
+        self.set_state(ServerState::Stopped);
+        if let Some(client) = client {
+            match Arc::try_unwrap(client) {
+                Ok(client) => client.shutdown().await,
+                Err(shared) => shared.kill().await,
+            }
+        }
+    }
+
+    fn set_state(&self, state: ServerState) {
+        *self.state.lock().unwrap() = state;
+    }
+
+    fn cache_path(&self) -> Option<PathBuf> {
+        self.settings
+            .tool_cache_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", self.name)))
+    }
+
+    fn save_tool_cache(&self, tools: &[McpToolInfo]) {
+        let Some(path) = self.cache_path() else {
+            return;
+        };
+        let cache = ToolCache {
+            fingerprint: fingerprint(&self.config),
+            tools: tools.to_vec(),
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| {
+                let json = serde_json::to_string(&cache).map_err(std::io::Error::other)?;
+                std::fs::write(&path, json)
+            });
+        if let Err(e) = result {
+            tracing::debug!("Failed to cache tools for MCP server '{}': {e}", self.name);
+        }
+    }
+
+    /// Connect with the client lock held, replacing any previous connection.
+    async fn connect_locked(
+        self: &Arc<Self>,
+        slot: &mut Option<Arc<McpClient>>,
+        state: ServerState,
+    ) -> Result<Arc<McpClient>, McpError> {
+        // Retire the old connection's monitor before killing its process
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        if let Some(old) = slot.take() {
+            old.kill().await;
+        }
+        self.set_state(state);
+
+        let client = match McpClient::connect_with_log(
+            self.name.clone(),
+            &self.config,
+            self.handler.clone(),
+            self.stderr.clone(),
+        )
+        .await
+        {
+            Ok(client) => Arc::new(client),
+            Err(e) => {
+                self.set_state(ServerState::Failed {
+                    error: e.to_string(),
+                });
+                return Err(e);
+            }
+        };
+
+        let tools = client.tools().to_vec();
+        self.save_tool_cache(&tools);
+        *self.tools.lock().unwrap() = tools;
+        *slot = Some(Arc::clone(&client));
+        self.set_state(ServerState::Ready);
+
+        let generation = self.generation.load(Ordering::SeqCst);
+        self.spawn_monitor(Arc::clone(&client), generation);
+        Ok(client)
+    }
+
+    /// Watch a connection until it exits or fails a ping, then restart.
+    fn spawn_monitor(self: &Arc<Self>, client: Arc<McpClient>, generation: u64) {
+        let server = Arc::downgrade(self);
+        let ping_interval = self.settings.ping_interval;
+        let mut stop_rx = self.stop_tx.subscribe();
+        tokio::spawn(async move {
+            let reason = tokio::select! {
+                reason = watch_client(&client, ping_interval) => reason,
+                // Shutdown signalled, or the supervisor was dropped
+                _ = stop_rx.changed() => return,
+            };
+            drop(client);
+            restart_with_backoff(server, generation, reason).await;
+        });
+    }
+}
+
+/// Resolve when the client exits or fails a health check, with the reason.
+async fn watch_client(client: &McpClient, ping_interval: Option<Duration>) -> String {
+    loop {
+        let tick = async {
+            match ping_interval {
+                Some(interval) => tokio::time::sleep(interval).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = client.closed() => return "exited".to_string(),
+            _ = tick => {
+                if let Err(e) = client.ping().await {
+                    client.kill().await;
+                    return format!("failed health check ({e})");
+                }
+            }
+        }
+    }
+}
+
+/// Reconnect with exponential backoff until it works or attempts run out.
+/// Stands down as soon as anything else reconnects or stops the server.
+async fn restart_with_backoff(server: Weak<SupervisedServer>, mut generation: u64, reason: String) {
+    let settings = match server.upgrade() {
+        Some(s) if s.generation.load(Ordering::SeqCst) == generation => {
+            tracing::warn!("MCP server '{}' {reason}", s.name);
+            if s.settings.max_restarts == 0 {
+                s.set_state(ServerState::Failed { error: reason });
+                return;
+            }
+            s.settings.clone()
+        }
+        _ => return,
+    };
+
+    let mut delay = settings.initial_backoff;
+    for attempt in 1..=settings.max_restarts {
+        match server.upgrade() {
+            Some(s) if s.generation.load(Ordering::SeqCst) == generation => {
+                s.set_state(ServerState::Restarting { attempt });
+            }
+            _ => return,
+        }
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(settings.max_backoff);
+
+        let Some(s) = server.upgrade() else {
+            return;
+        };
+        let mut slot = s.client.lock().await;
+        if s.generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        match s
+            .connect_locked(&mut slot, ServerState::Restarting { attempt })
+            .await
+        {
+            Ok(_) => {
+                s.restarts.fetch_add(1, Ordering::Relaxed);
+                tracing::info!("MCP server '{}' restarted", s.name);
+                return;
+            }
+            Err(e) => {
+                tracing::warn!("MCP server '{}' restart {attempt} failed: {e}", s.name);
+                // Our own connect attempt bumped the generation; keep ownership
+                generation = s.generation.load(Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Minimal MCP server in bash. With `$CRASH_MARKER` set, the first run
+    /// exits right after listing tools; later runs stay up.
+    const FAKE_SERVER: &str = r#"
+while read -r line; do
+  [[ $line =~ \"id\":([0-9]+) ]] || continue
+  id=${BASH_REMATCH[1]}
+  case $line in
+    *'"initialize"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"protocolVersion\":\"2024-11-05\",\"capabilities\":{\"tools\":{}},\"serverInfo\":{\"name\":\"fake\",\"version\":\"1\"}}}" ;;
+    *'"tools/list"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"tools\":[{\"name\":\"echo\",\"inputSchema\":{\"type\":\"object\"}}]}}"
+      if [[ -n $CRASH_MARKER && ! -e $CRASH_MARKER ]]; then
+        touch "$CRASH_MARKER"; echo "crashing on purpose" >&2; exit 1
+      fi ;;
+    *'"tools/call"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"content\":[{\"type\":\"text\",\"text\":\"ok\"}]}}" ;;
+    *)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{}}" ;;
+  esac
+done
+"#;
+
+    fn fake_server(env: HashMap<String, String>, lazy: bool) -> McpServerConfig {
+        McpServerConfig {
+            command: "bash".to_string(),
+            args: vec!["-c".to_string(), FAKE_SERVER.to_string()],
+            env,
+            timeout_ms: 5000,
+            lazy,
+            allowed_tools: vec![],
+            disabled_tools: vec![],
+        }
+    }
+
+    fn fast_settings(tool_cache_dir: Option<PathBuf>) -> SupervisorSettings {
+        SupervisorSettings {
+            ping_interval: None,
+            max_restarts: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(50),
+            tool_cache_dir,
+        }
+    }
+
+    async fn wait_for(server: &SupervisedServer, pred: impl Fn(&SupervisedServer) -> bool) {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while !pred(server) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("timed out; state is {}", server.state()));
+    }
+
+    #[test]
+    fn state_display() {
+        assert_eq!(ServerState::Ready.to_string(), "ready");
+        assert_eq!(
+            ServerState::Restarting { attempt: 2 }.to_string(),
+            "restarting (attempt 2)"
+        );
+        assert_eq!(
+            ServerState::Failed {
+                error: "boom".to_string()
+            }
+            .to_string(),
+            "failed: boom"
+        );
+    }
+
+    #[test]
+    fn settings_from_config() {
+        let mut config = McpConfig::default();
+        assert_eq!(
+            SupervisorSettings::from_config(&config).ping_interval,
+            Some(Duration::from_secs(30))
+        );
+        config.ping_interval_secs = 0;
+        config.max_restarts = 1;
+        let settings = SupervisorSettings::from_config(&config);
+        assert_eq!(settings.ping_interval, None);
+        assert_eq!(settings.max_restarts, 1);
+    }
+
+    #[tokio::test]
+    async fn exited_server_is_restarted() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("crashed");
+        let env = HashMap::from([(
+            "CRASH_MARKER".to_string(),
+            marker.to_string_lossy().into_owned(),
+        )]);
+        let server = SupervisedServer::new(
+            "fake".to_string(),
+            fake_server(env, false),
+            None,
+            fast_settings(None),
+        );
+        server.start().await.expect("first start");
+
+        wait_for(&server, |s| s.restart_count() == 1).await;
+        assert_eq!(server.state(), ServerState::Ready);
+        assert!(marker.exists());
+        assert_eq!(server.stderr_tail(1), vec!["crashing on purpose"]);
+
+        let result = server
+            .call_tool("echo", serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(!result.is_error);
+        server.shutdown().await;
+        assert_eq!(server.state(), ServerState::Stopped);
+    }
+
+    #[tokio::test]
+    async fn failed_start_is_reported() {
+        let mut config = fake_server(HashMap::new(), false);
+        config.command = "nonexistent_mcp_server_binary_xyz".to_string();
+        let server = SupervisedServer::new("bad".to_string(), config, None, fast_settings(None));
+        assert!(server.start().await.is_err());
+        assert!(matches!(server.state(), ServerState::Failed { .. }));
+
+        let err = server
+            .call_tool("echo", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("/mcp reconnect bad"));
+    }
+
+    #[tokio::test]
+    async fn lazy_server_starts_on_first_call() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = fast_settings(Some(dir.path().to_path_buf()));
+
+        // No cache yet: nothing to load
+        let config = fake_server(HashMap::new(), true);
+        let server =
+            SupervisedServer::new("lazy".to_string(), config.clone(), None, settings.clone());
+        assert!(!server.load_cached_tools());
+        server.start().await.unwrap();
+        server.shutdown().await;
+
+        // The first connection cached the tool list
+        let server = SupervisedServer::new("lazy".to_string(), config, None, settings);
+        assert!(server.load_cached_tools());
+        assert_eq!(server.state(), ServerState::Idle);
+        assert_eq!(server.tools()[0].name, "echo");
+
+        server
+            .call_tool("echo", serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(server.state(), ServerState::Ready);
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn stopped_server_rejects_calls() {
+        let server = SupervisedServer::new(
+            "fake".to_string(),
+            fake_server(HashMap::new(), false),
+            None,
+            fast_settings(None),
+        );
+        server.shutdown().await;
+        assert!(matches!(
+            server.call_tool("echo", serde_json::json!({})).await,
+            Err(McpError::ServerNotRunning { .. })
+        ));
+    }
+}
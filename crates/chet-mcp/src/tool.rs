@@ -1,6 +1,7 @@
 //! McpTool — wraps an MCP server tool as a chet_types::Tool.
 
-use crate::client::{McpClient, McpToolContent, McpToolInfo};
+use crate::client::{McpToolContent, McpToolInfo};
+use crate::supervisor::SupervisedServer;
 use chet_types::{
     ImageSource, ImageSourceType, ToolContext, ToolDefinition, ToolError, ToolOutput,
     ToolOutputContent,
@@ -18,18 +19,19 @@ pub struct McpTool {
     namespaced_name: String,
     server_name: String,
     tool_info: McpToolInfo,
-    client: Arc<McpClient>,
+    server: Arc<SupervisedServer>,
 }
 
 impl McpTool {
-    /// Create a new MCP tool wrapper.
-    pub fn new(server_name: &str, tool_info: McpToolInfo, client: Arc<McpClient>) -> Self {
-        let namespaced_name = format!("mcp__{}__{}", server_name, tool_info.name);
+    /// Create a new MCP tool wrapper. Calls go through the server's
+    /// supervisor, which starts or restarts the server as needed.
+    pub fn new(tool_info: McpToolInfo, server: Arc<SupervisedServer>) -> Self {
+        let namespaced_name = format!("mcp__{}__{}", server.name(), tool_info.name);
         Self {
             namespaced_name,
-            server_name: server_name.to_string(),
+            server_name: server.name().to_string(),
             tool_info,
-            client,
+            server,
         }
     }
 }
@@ -42,14 +44,15 @@ impl chet_types::Tool for McpTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: self.namespaced_name.clone(),
-            description: format!("[MCP: {}] {}", self.server_name, self.tool_info.description),
+            description: describe(&self.server_name, &self.tool_info),
             input_schema: self.tool_info.input_schema.clone(),
             cache_control: None,
         }
     }
 
+    /// Trusts the server's `readOnlyHint`; tools without it are treated as mutating.
     fn is_read_only(&self) -> bool {
-        false // conservative — we can't know
+        self.tool_info.annotations.read_only_hint
     }
 
     fn execute(
@@ -59,7 +62,7 @@ impl chet_types::Tool for McpTool {
     ) -> Pin<Box<dyn Future<Output = Result<ToolOutput, ToolError>> + Send + '_>> {
         Box::pin(async move {
             let result = self
-                .client
+                .server
                 .call_tool(&self.tool_info.name, input)
                 .await
                 .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
@@ -100,6 +103,16 @@ impl chet_types::Tool for McpTool {
     }
 }
 
+/// Model-facing description: server prefix, optional title, then the server's description.
+fn describe(server_name: &str, info: &McpToolInfo) -> String {
+    match &info.annotations.title {
+        Some(title) if !title.is_empty() => {
+            format!("[MCP: {server_name}] {title}: {}", info.description)
+        }
+        _ => format!("[MCP: {server_name}] {}", info.description),
+    }
+}
+
 /// Decode base64 data and save to a file with the correct extension.
 /// Returns the file path on success, None on failure.
 fn save_binary_content(
@@ -167,7 +180,6 @@ fn base64_decode(input: &str) -> Option<Vec<u8>> {
 mod tests {
     use super::*;
 
-    #[allow(dead_code)]
     fn sample_tool_info() -> McpToolInfo {
         McpToolInfo {
             name: "read_file".to_string(),
@@ -179,6 +191,7 @@ mod tests {
                 },
                 "required": ["path"]
             }),
+            annotations: Default::default(),
         }
     }
 
@@ -198,6 +211,17 @@ mod tests {
         assert!(desc.contains("List repositories"));
     }
 
+    #[test]
+    fn description_includes_title() {
+        let mut info = sample_tool_info();
+        assert_eq!(describe("fs", &info), "[MCP: fs] Read a file from disk");
+        info.annotations.title = Some("Read File".to_string());
+        assert_eq!(
+            describe("fs", &info),
+            "[MCP: fs] Read File: Read a file from disk"
+        );
+    }
+
     #[test]
     fn mcp_tool_is_send_sync() {
         fn assert_send_sync<T: Send + Sync>() {}
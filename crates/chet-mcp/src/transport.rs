@@ -1,19 +1,51 @@
 //! Stdio transport for MCP server communication.
 //!
 //! Spawns a child process and manages async communication over stdin/stdout
-//! using newline-delimited JSON-RPC messages.
+//! using newline-delimited JSON-RPC messages. Requests initiated by the
+//! server (sampling, elicitation, ping) are answered via a [`RequestRouter`].
 
 use crate::error::McpError;
-use crate::jsonrpc::{JsonRpcNotification, JsonRpcRequest, JsonRpcResponse};
-use std::collections::HashMap;
+use crate::handler::RequestRouter;
+use crate::jsonrpc::{
+    JsonRpcIncomingRequest, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse,
+};
+use std::collections::{HashMap, VecDeque};
 use std::process::Stdio;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::{Mutex, mpsc, oneshot};
+use tokio::sync::{Mutex, mpsc, oneshot, watch};
 use tokio::task::JoinHandle;
 
+/// Number of stderr lines kept per server.
+const STDERR_TAIL_LINES: usize = 20;
+
+/// The most recent stderr lines of a server process. Shared so the lines
+/// outlive the process (and survive restarts) for diagnostics.
+#[derive(Debug, Clone, Default)]
+pub struct StderrLog(Arc<std::sync::Mutex<VecDeque<String>>>);
+
+impl StderrLog {
+    fn push(&self, line: String) {
+        let mut lines = self.0.lock().unwrap();
+        if lines.len() == STDERR_TAIL_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// The last `n` lines, oldest first.
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        let lines = self.0.lock().unwrap();
+        lines
+            .iter()
+            .skip(lines.len().saturating_sub(n))
+            .cloned()
+            .collect()
+    }
+}
+
 /// Async stdio transport for communicating with an MCP server process.
 pub struct StdioTransport {
     next_id: AtomicU64,
@@ -22,23 +54,28 @@ pub struct StdioTransport {
     reader_handle: JoinHandle<()>,
     writer_handle: JoinHandle<()>,
     child: Arc<Mutex<Child>>,
+    closed: watch::Receiver<bool>,
+    command: String,
     timeout_ms: u64,
 }
 
 impl StdioTransport {
     /// Spawn a child process and start background reader/writer tasks.
+    /// The child's stderr is appended to `stderr`.
     pub fn spawn(
         command: &str,
         args: &[String],
         env: &HashMap<String, String>,
         timeout_ms: u64,
+        router: RequestRouter,
+        stderr: StderrLog,
     ) -> Result<Self, McpError> {
         let mut cmd = Command::new(command);
         cmd.args(args)
             .envs(env)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::null())
+            .stderr(Stdio::piped())
             .kill_on_drop(true);
 
         let mut child = cmd.spawn().map_err(|e| McpError::SpawnFailed {
@@ -54,6 +91,14 @@ impl StdioTransport {
             .stdout
             .take()
             .ok_or_else(|| McpError::Protocol("Failed to obtain piped stdout".into()))?;
+        if let Some(child_stderr) = child.stderr.take() {
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(child_stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    stderr.push(line);
+                }
+            });
+        }
 
         let pending: Arc<Mutex<HashMap<u64, oneshot::Sender<JsonRpcResponse>>>> =
             Arc::new(Mutex::new(HashMap::new()));
@@ -75,8 +120,11 @@ impl StdioTransport {
             }
         });
 
-        // Reader task: reads lines from stdout, parses JSON-RPC, dispatches
+        // Reader task: reads lines from stdout, parses JSON-RPC, dispatches.
+        // Holds only a weak sender so dropping the transport still closes stdin.
         let pending_for_reader = Arc::clone(&pending);
+        let reply_tx = write_tx.downgrade();
+        let (closed_tx, closed) = watch::channel(false);
         let reader_handle = tokio::spawn(async move {
             let reader = BufReader::new(stdout);
             let mut lines = reader.lines();
@@ -84,7 +132,41 @@ impl StdioTransport {
                 if line.trim().is_empty() {
                     continue;
                 }
-                let resp: JsonRpcResponse = match serde_json::from_str(&line) {
+                let value: serde_json::Value = match serde_json::from_str(&line) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        tracing::warn!("Failed to parse MCP message: {e}: {line}");
+                        continue;
+                    }
+                };
+
+                // Server-initiated request or notification
+                if value.get("method").is_some() {
+                    let request: JsonRpcIncomingRequest = match serde_json::from_value(value) {
+                        Ok(r) => r,
+                        Err(e) => {
+                            tracing::warn!("Failed to parse MCP server request: {e}: {line}");
+                            continue;
+                        }
+                    };
+                    let router = router.clone();
+                    let reply_tx = reply_tx.clone();
+                    // Answer in a separate task — handlers may wait on the user
+                    tokio::spawn(async move {
+                        let Some(reply) = router.dispatch(request).await else {
+                            return;
+                        };
+                        let (Some(tx), Ok(serialized)) =
+                            (reply_tx.upgrade(), serde_json::to_string(&reply))
+                        else {
+                            return;
+                        };
+                        let _ = tx.send(serialized).await;
+                    });
+                    continue;
+                }
+
+                let resp: JsonRpcResponse = match serde_json::from_value(value) {
                     Ok(r) => r,
                     Err(e) => {
                         tracing::warn!("Failed to parse MCP response: {e}: {line}");
@@ -97,8 +179,12 @@ impl StdioTransport {
                         let _ = tx.send(resp);
                     }
                 }
-                // Notifications from server (no id) are currently ignored
             }
+
+            // stdout closed: the server exited. Fail outstanding requests now
+            // rather than letting them run into the timeout.
+            pending_for_reader.lock().await.clear();
+            let _ = closed_tx.send(true);
         });
 
         Ok(Self {
@@ -108,10 +194,23 @@ impl StdioTransport {
             reader_handle,
             writer_handle,
             child: Arc::new(Mutex::new(child)),
+            closed,
+            command: command.to_string(),
             timeout_ms,
         })
     }
 
+    /// Whether the server has closed its stdout (usually because it exited).
+    pub fn is_closed(&self) -> bool {
+        *self.closed.borrow()
+    }
+
+    /// Wait until the server closes its stdout.
+    pub async fn closed(&self) {
+        let mut closed = self.closed.clone();
+        let _ = closed.wait_for(|c| *c).await;
+    }
+
     /// Send a JSON-RPC request and wait for the response.
     pub async fn send_request(
         &self,
@@ -122,6 +221,10 @@ impl StdioTransport {
         let request = JsonRpcRequest::new(id, method, params);
         let serialized = serde_json::to_string(&request)?;
 
+        if self.is_closed() {
+            return Err(self.not_running());
+        }
+
         let (tx, rx) = oneshot::channel();
         {
             let mut pending = self.pending.lock().await;
@@ -135,6 +238,7 @@ impl StdioTransport {
 
         match tokio::time::timeout(std::time::Duration::from_millis(self.timeout_ms), rx).await {
             Ok(Ok(resp)) => Ok(resp),
+            Ok(Err(_)) if self.is_closed() => Err(self.not_running()),
             Ok(Err(_)) => Err(McpError::Protocol("Response channel dropped".to_string())),
             Err(_) => {
                 // Clean up pending entry on timeout
@@ -165,6 +269,17 @@ impl StdioTransport {
         Ok(())
     }
 
+    fn not_running(&self) -> McpError {
+        McpError::ServerNotRunning {
+            name: self.command.clone(),
+        }
+    }
+
+    /// Kill the server process without waiting for a graceful exit.
+    pub async fn kill(&self) {
+        let _ = self.child.lock().await.kill().await;
+    }
+
     /// Shut down the transport: drop the write channel, wait briefly, then kill.
     pub async fn shutdown(self) {
         // Drop write channel to send EOF to child stdin
@@ -194,11 +309,22 @@ impl StdioTransport {
 mod tests {
     use super::*;
 
+    fn router() -> RequestRouter {
+        RequestRouter::new("test", None)
+    }
+
     #[cfg(unix)]
     #[tokio::test]
     async fn spawn_echo_process() {
         // Use `cat` as a simple echo process
-        let transport = StdioTransport::spawn("cat", &[], &HashMap::new(), 5000);
+        let transport = StdioTransport::spawn(
+            "cat",
+            &[],
+            &HashMap::new(),
+            5000,
+            router(),
+            StderrLog::default(),
+        );
         assert!(transport.is_ok());
         let transport = transport.unwrap();
         transport.shutdown().await;
@@ -211,6 +337,8 @@ mod tests {
             &[],
             &HashMap::new(),
             5000,
+            router(),
+            StderrLog::default(),
         );
         match result {
             Err(McpError::SpawnFailed { name, .. }) => {
@@ -231,6 +359,8 @@ mod tests {
             &["-c".to_string(), script.to_string()],
             &HashMap::new(),
             5000,
+            router(),
+            StderrLog::default(),
         );
 
         if transport.is_err() {
@@ -252,7 +382,15 @@ mod tests {
     #[cfg(unix)]
     #[tokio::test]
     async fn notification_does_not_block() {
-        let transport = StdioTransport::spawn("cat", &[], &HashMap::new(), 5000).unwrap();
+        let transport = StdioTransport::spawn(
+            "cat",
+            &[],
+            &HashMap::new(),
+            5000,
+            router(),
+            StderrLog::default(),
+        )
+        .unwrap();
 
         let result = transport
             .send_notification("notifications/initialized", None)
@@ -266,8 +404,15 @@ mod tests {
     #[tokio::test]
     async fn timeout_fires_on_unresponsive_server() {
         // `sleep` never writes to stdout, so requests will time out
-        let transport =
-            StdioTransport::spawn("sleep", &["10".to_string()], &HashMap::new(), 100).unwrap();
+        let transport = StdioTransport::spawn(
+            "sleep",
+            &["10".to_string()],
+            &HashMap::new(),
+            100,
+            router(),
+            StderrLog::default(),
+        )
+        .unwrap();
 
         let result = transport
             .send_request("test/method", Some(serde_json::json!({})))
@@ -282,4 +427,87 @@ mod tests {
 
         transport.shutdown().await;
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn server_ping_is_answered() {
+        // Server pings the client, then echoes the client's reply back as a
+        // response to our own request so the test can observe it.
+        let script = r#"echo '{"jsonrpc":"2.0","id":"p1","method":"ping"}'
+read -r reply
+read -r req
+[[ $req =~ \"id\":([0-9]+) ]] || exit 1
+echo "{\"jsonrpc\":\"2.0\",\"id\":${BASH_REMATCH[1]},\"result\":$reply}""#;
+        let transport = StdioTransport::spawn(
+            "bash",
+            &["-c".to_string(), script.to_string()],
+            &HashMap::new(),
+            5000,
+            router(),
+            StderrLog::default(),
+        )
+        .expect("spawn bash");
+
+        // Give the reader time to answer the ping before we send our request
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let resp = transport
+            .send_request("test/method", None)
+            .await
+            .expect("server should echo the ping reply");
+        let echoed = resp.result.unwrap();
+        assert_eq!(echoed["id"], "p1");
+        assert!(echoed["result"].is_object());
+
+        transport.shutdown().await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn exit_is_detected_and_stderr_kept() {
+        let stderr = StderrLog::default();
+        let transport = StdioTransport::spawn(
+            "bash",
+            &["-c".to_string(), "echo boom >&2; exit 1".to_string()],
+            &HashMap::new(),
+            5000,
+            router(),
+            stderr.clone(),
+        )
+        .expect("spawn bash");
+
+        tokio::time::timeout(std::time::Duration::from_secs(5), transport.closed())
+            .await
+            .expect("exit should be detected");
+        assert!(transport.is_closed());
+        let result = transport.send_request("test/method", None).await;
+        assert!(matches!(result, Err(McpError::ServerNotRunning { .. })));
+
+        // The stderr reader may finish just after stdout closes
+        for _ in 0..50 {
+            if !stderr.tail(5).is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(stderr.tail(5), ["boom"]);
+
+        transport.shutdown().await;
+    }
+
+    #[test]
+    fn stderr_log_keeps_last_lines() {
+        let log = StderrLog::default();
+        for i in 0..STDERR_TAIL_LINES + 5 {
+            log.push(format!("line {i}"));
+        }
+        let tail = log.tail(2);
+        assert_eq!(
+            tail,
+            [
+                format!("line {}", STDERR_TAIL_LINES + 3),
+                format!("line {}", STDERR_TAIL_LINES + 4)
+            ]
+        );
+        assert_eq!(log.tail(100).len(), STDERR_TAIL_LINES);
+    }
 }
@@ -0,0 +1,152 @@
+//! Per-project trust decisions for project-defined MCP servers.
+//!
+//! Servers from a repository's `.chet/config.toml` or `.mcp.json` run
+//! arbitrary commands, so they are only started after the user approves
+//! them once. Approvals are keyed by a fingerprint of the command line and
+//! environment; editing the server definition requires approving it again.
+
+use crate::config::McpServerConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Trusted project MCP servers, persisted as JSON.
+#[derive(Debug)]
+pub struct McpTrustStore {
+    path: PathBuf,
+    trusted: HashMap<String, String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct TrustFile {
+    #[serde(default)]
+    servers: HashMap<String, String>,
+}
+
+impl McpTrustStore {
+    /// Trust file for a project: `<config_dir>/trust/<project_id>.json`.
+    pub fn path_for(config_dir: &Path, project_id: &str) -> PathBuf {
+        config_dir.join("trust").join(format!("{project_id}.json"))
+    }
+
+    /// Load the store at `path`. A missing or unreadable file trusts nothing.
+    pub fn load(path: PathBuf) -> Self {
+        let trusted = match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                serde_json::from_str::<TrustFile>(&content)
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("Failed to parse {}: {}", path.display(), e);
+                        TrustFile::default()
+                    })
+                    .servers
+            }
+            Err(_) => HashMap::new(),
+        };
+        Self { path, trusted }
+    }
+
+    /// Whether `name` was approved with exactly this definition.
+    pub fn is_trusted(&self, name: &str, config: &McpServerConfig) -> bool {
+        self.trusted.get(name) == Some(&fingerprint(config))
+    }
+
+    /// Record approval of `name` and write the store (tmp file + rename).
+    pub fn trust(&mut self, name: &str, config: &McpServerConfig) -> std::io::Result<()> {
+        self.trusted.insert(name.to_string(), fingerprint(config));
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = TrustFile {
+            servers: self.trusted.clone(),
+        };
+        let json = serde_json::to_string_pretty(&file).map_err(std::io::Error::other)?;
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, &self.path)
+    }
+}
+
+/// What a server definition would execute, in a canonical form for hashing.
+#[derive(Serialize)]
+struct Executed<'a> {
+    command: &'a str,
+    args: &'a [String],
+    env: BTreeMap<&'a String, &'a String>,
+}
+
+/// SHA-256 (hex) of what a server definition would execute: its command,
+/// args and environment, serialized as JSON with sorted env keys. The
+/// algorithm is fixed, so stored approvals and tool caches keyed by it stay
+/// valid across toolchain upgrades.
+pub(crate) fn fingerprint(config: &McpServerConfig) -> String {
+    use sha2::{Digest, Sha256};
+    let executed = Executed {
+        command: &config.command,
+        args: &config.args,
+        env: config.env.iter().collect(),
+    };
+    let json = serde_json::to_vec(&executed).expect("server definition serializes");
+    Sha256::digest(json)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(command: &str) -> McpServerConfig {
+        McpServerConfig {
+            command: command.to_string(),
+            args: vec!["--stdio".to_string()],
+            env: HashMap::new(),
+            timeout_ms: 30000,
+            lazy: false,
+            allowed_tools: vec![],
+            disabled_tools: vec![],
+        }
+    }
+
+    #[test]
+    fn untrusted_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = McpTrustStore::load(McpTrustStore::path_for(dir.path(), "abc"));
+        assert!(!store.is_trusted("srv", &server("node")));
+    }
+
+    #[test]
+    fn trust_persists_and_tracks_definition() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = McpTrustStore::path_for(dir.path(), "abc");
+        let mut store = McpTrustStore::load(path.clone());
+        store.trust("srv", &server("node")).unwrap();
+
+        let reloaded = McpTrustStore::load(path);
+        assert!(reloaded.is_trusted("srv", &server("node")));
+        assert!(!reloaded.is_trusted("srv", &server("bash")));
+        assert!(!reloaded.is_trusted("other", &server("node")));
+    }
+
+    #[test]
+    fn env_changes_require_new_approval() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = McpTrustStore::load(McpTrustStore::path_for(dir.path(), "abc"));
+        let mut config = server("node");
+        store.trust("srv", &config).unwrap();
+        config
+            .env
+            .insert("NODE_OPTIONS".to_string(), "--require evil.js".to_string());
+        assert!(!store.is_trusted("srv", &config));
+    }
+
+    #[test]
+    fn fingerprint_is_a_fixed_sha256() {
+        // sha256 of {"command":"node","args":["--stdio"],"env":{}}. Pinned:
+        // changing it re-prompts every stored approval.
+        assert_eq!(
+            fingerprint(&server("node")),
+            "1674233fdf449ec20b2964b82ef4c1159194f41a46a7ce4db90a1fa7cb976741"
+        );
+    }
+}
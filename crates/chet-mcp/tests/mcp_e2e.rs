@@ -16,7 +16,7 @@ use std::collections::HashMap;
 ///
 /// Handles three JSON-RPC methods:
 /// - `initialize` → returns server info + capabilities
-/// - `tools/list` → returns one tool: "echo" that echoes its input
+/// - `tools/list` → returns "echo" (read-only, echoes its input) and "wipe"
 /// - `tools/call` → for "echo", returns the arguments as text; otherwise error
 const MCP_SERVER_SCRIPT: &str = r#"
 import sys, json
@@ -62,7 +62,12 @@ for line in sys.stdin:
                         "message": {"type": "string", "description": "Message to echo"}
                     },
                     "required": ["message"]
-                }
+                },
+                "annotations": {"title": "Echo", "readOnlyHint": True}
+            }, {
+                "name": "wipe",
+                "description": "Deletes everything",
+                "inputSchema": {"type": "object"}
             }]
         })
     elif method == "tools/call":
@@ -89,6 +94,9 @@ fn server_config() -> McpServerConfig {
         args: vec!["-c".to_string(), MCP_SERVER_SCRIPT.to_string()],
         env: HashMap::new(),
         timeout_ms: 5000,
+        lazy: false,
+        allowed_tools: vec![],
+        disabled_tools: vec![],
     }
 }
 
@@ -102,10 +110,29 @@ async fn test_mcp_connect_and_discover_tools() {
         .expect("connect should succeed");
 
     let tools = client.tools();
-    assert_eq!(tools.len(), 1, "server exposes one tool");
+    assert_eq!(tools.len(), 2, "server exposes two tools");
     assert_eq!(tools[0].name, "echo");
     assert_eq!(tools[0].description, "Echoes the input message back");
     assert!(tools[0].input_schema["properties"]["message"].is_object());
+    assert_eq!(tools[0].annotations.title.as_deref(), Some("Echo"));
+    assert!(tools[0].annotations.read_only_hint);
+    assert!(tools[1].annotations.is_destructive());
+
+    client.shutdown().await;
+}
+
+/// `disabled_tools` hides matching tools at discovery time.
+#[tokio::test]
+#[ignore]
+async fn test_mcp_disabled_tools_are_filtered() {
+    let mut config = server_config();
+    config.disabled_tools = vec!["w*".to_string()];
+    let client = McpClient::connect("test-server".to_string(), &config)
+        .await
+        .expect("connect should succeed");
+
+    let names: Vec<&str> = client.tools().iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["echo"]);
 
     client.shutdown().await;
 }
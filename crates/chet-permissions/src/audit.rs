@@ -0,0 +1,286 @@
+//! Append-only audit log of permission decisions, hook outcomes and prompt
+//! answers.
+//!
+//! One JSONL file per project under the audit directory (normally
+//! `~/.chet/audit/<project-id>.jsonl`); every entry carries the session it
+//! came from. Writes are best-effort — a failing disk never blocks a tool call.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// What an audit entry records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    /// A permission rule evaluation.
+    Permission,
+    /// A hook's result.
+    Hook,
+    /// The user's answer to a permission prompt.
+    Prompt,
+}
+
+impl AuditKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditKind::Permission => "permission",
+            AuditKind::Hook => "hook",
+            AuditKind::Prompt => "prompt",
+        }
+    }
+}
+
+/// One line of the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub project: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    pub kind: AuditKind,
+    /// Hook event, for hook entries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// Summarized tool input (key field, truncated).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+    /// permit/block/prompt, approved/asked/denied/error, or the prompt answer.
+    pub decision: String,
+    /// Deciding layer, or the hook command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The matching rule, when a rule decided.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Writer for one project's audit log.
+pub struct AuditLog {
+    path: PathBuf,
+    project: String,
+    session: Mutex<Option<String>>,
+}
+
+impl AuditLog {
+    /// Log for `project` in `dir`. Nothing is created until the first entry.
+    pub fn new(dir: &Path, project: &str) -> Self {
+        Self {
+            path: dir.join(format!("{project}.jsonl")),
+            project: project.to_string(),
+            session: Mutex::new(None),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Tag subsequent entries with this session ID.
+    pub fn set_session(&self, session_id: &str) {
+        *self.session.lock().unwrap() = Some(session_id.to_string());
+    }
+
+    /// A new entry stamped with the current time, project and session.
+    pub fn entry(&self, kind: AuditKind, decision: impl Into<String>) -> AuditEntry {
+        AuditEntry {
+            timestamp: Utc::now(),
+            project: self.project.clone(),
+            session: self.session.lock().unwrap().clone(),
+            kind,
+            event: None,
+            tool: None,
+            input: None,
+            decision: decision.into(),
+            source: None,
+            rule: None,
+            reason: None,
+        }
+    }
+
+    /// Append an entry. Errors are logged, not returned.
+    pub fn append(&self, entry: &AuditEntry) {
+        // Holding the session lock serializes writers within this process
+        let _guard = self.session.lock().unwrap();
+        if let Err(e) = self.write_line(entry) {
+            tracing::warn!("Failed to write audit log {}: {e}", self.path.display());
+        }
+    }
+
+    fn write_line(&self, entry: &AuditEntry) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        // One write per line so concurrent processes don't interleave entries
+        file.write_all(line.as_bytes())
+    }
+}
+
+/// Filters for [`query`]. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    /// Only this project's log; `None` reads every project.
+    pub project: Option<String>,
+    /// Session ID prefix.
+    pub session: Option<String>,
+    /// Tool name (case-insensitive).
+    pub tool: Option<String>,
+    /// Decision or prompt answer (case-insensitive).
+    pub decision: Option<String>,
+}
+
+impl AuditQuery {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        let session_ok = match &self.session {
+            Some(prefix) => entry
+                .session
+                .as_deref()
+                .is_some_and(|s| s.starts_with(prefix.as_str())),
+            None => true,
+        };
+        let tool_ok = match &self.tool {
+            Some(tool) => entry
+                .tool
+                .as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case(tool)),
+            None => true,
+        };
+        let decision_ok = match &self.decision {
+            Some(decision) => entry.decision.eq_ignore_ascii_case(decision),
+            None => true,
+        };
+        session_ok && tool_ok && decision_ok
+    }
+}
+
+/// Read matching entries from the logs in `dir`, oldest first.
+/// Malformed lines (e.g. a write cut short) are skipped.
+pub fn query(dir: &Path, query: &AuditQuery) -> std::io::Result<Vec<AuditEntry>> {
+    let files = match &query.project {
+        Some(project) => vec![dir.join(format!("{project}.jsonl"))],
+        None => match std::fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        },
+    };
+
+    let mut entries = Vec::new();
+    for file in files {
+        let content = match std::fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        entries.extend(
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+                .filter(|entry| query.matches(entry)),
+        );
+    }
+    entries.sort_by_key(|e| e.timestamp);
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_entry(log: &AuditLog, tool: &str, decision: &str) {
+        let mut entry = log.entry(AuditKind::Permission, decision);
+        entry.tool = Some(tool.to_string());
+        log.append(&entry);
+    }
+
+    #[test]
+    fn entries_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::new(dir.path(), "proj");
+        log.set_session("abc123");
+        let mut entry = log.entry(AuditKind::Prompt, "always_allow");
+        entry.tool = Some("Bash".into());
+        entry.input = Some("command: git push".into());
+        log.append(&entry);
+
+        let entries = query(dir.path(), &AuditQuery::default()).unwrap();
+        assert_eq!(entries, vec![entry]);
+        assert_eq!(entries[0].session.as_deref(), Some("abc123"));
+        assert_eq!(entries[0].project, "proj");
+    }
+
+    #[test]
+    fn query_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::new(dir.path(), "proj");
+        log.set_session("aaaa1111");
+        log_entry(&log, "Bash", "block");
+        log_entry(&log, "Read", "permit");
+        log.set_session("bbbb2222");
+        log_entry(&log, "Bash", "permit");
+        AuditLog::new(dir.path(), "other").append(&log.entry(AuditKind::Permission, "permit"));
+
+        let count = |q: AuditQuery| query(dir.path(), &q).unwrap().len();
+        assert_eq!(count(AuditQuery::default()), 4);
+        let project = || Some("proj".to_string());
+        assert_eq!(
+            count(AuditQuery {
+                project: project(),
+                ..Default::default()
+            }),
+            3
+        );
+        assert_eq!(
+            count(AuditQuery {
+                session: Some("aaaa".into()),
+                ..Default::default()
+            }),
+            2
+        );
+        assert_eq!(
+            count(AuditQuery {
+                tool: Some("bash".into()),
+                decision: Some("permit".into()),
+                ..Default::default()
+            }),
+            1
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::new(dir.path(), "proj");
+        log_entry(&log, "Bash", "block");
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(log.path())
+            .unwrap();
+        file.write_all(b"{\"timestamp\":\"2026-").unwrap();
+
+        let entries = query(dir.path(), &AuditQuery::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn missing_dir_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries = query(&dir.path().join("audit"), &AuditQuery::default()).unwrap();
+        assert!(entries.is_empty());
+    }
+}
@@ -1,23 +1,43 @@
 //! Permission engine — the main entry point for permission checks.
 
-use crate::hooks::run_hooks;
+use crate::audit::{AuditKind, AuditLog};
+use crate::hooks::evaluate_hooks_in;
 use crate::matcher::RuleMatcher;
 use crate::prompt::PromptHandler;
 use crate::types::*;
+use crate::workspace::{OutsideWorkspace, PATH_FIELDS, Workspace, canonicalize};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
+/// File-editing tools that acceptEdits mode permits inside the workspace.
+const EDIT_TOOLS: &[&str] = &["Write", "Edit"];
+
 /// The central permission engine that evaluates rules, runs hooks, and prompts users.
 pub struct PermissionEngine {
+    /// Managed (administrator) rules; they override everything else.
+    managed_rules: Vec<PermissionRule>,
     /// Static rules loaded from config.
     rules: Vec<PermissionRule>,
     /// Session-scoped rules added via "always allow" responses.
     session_rules: Mutex<Vec<PermissionRule>>,
-    /// Hook configurations.
+    /// Hook configurations; the first `managed_hooks` come from managed policy.
     hooks: Vec<HookConfig>,
+    /// Number of managed hooks at the front of `hooks`. They run in every
+    /// mode, including bypass.
+    managed_hooks: usize,
+    /// Per-hook match count and last result, parallel to `hooks`.
+    hook_stats: Mutex<Vec<(u64, Option<HookRunResult>)>>,
     /// Optional prompt handler for interactive permission prompts.
     prompt_handler: Option<Arc<dyn PromptHandler>>,
-    /// When true, all tool calls are auto-permitted (--ludicrous mode).
-    ludicrous: bool,
+    /// Current permission mode; `Bypass` is `--ludicrous`.
+    mode: Mutex<PermissionMode>,
+    /// Trust boundary for file paths; `None` disables path checks.
+    workspace: Option<Workspace>,
+    /// Where decisions, hook results and prompt answers are recorded.
+    audit: Option<AuditLog>,
+    /// The current session's directory for large tool results, which
+    /// read-only tools may use without asking.
+    tool_output_dir: Mutex<Option<PathBuf>>,
 }
 
 impl PermissionEngine {
@@ -28,98 +48,400 @@ impl PermissionEngine {
         prompt_handler: Option<Arc<dyn PromptHandler>>,
     ) -> Self {
         Self {
+            managed_rules: Vec::new(),
             rules,
             session_rules: Mutex::new(Vec::new()),
+            hook_stats: Mutex::new(vec![(0, None); hooks.len()]),
             hooks,
+            managed_hooks: 0,
             prompt_handler,
-            ludicrous: false,
+            mode: Mutex::new(PermissionMode::Default),
+            workspace: None,
+            audit: None,
+            tool_output_dir: Mutex::new(None),
+        }
+    }
+
+    /// Enforce a workspace boundary: file paths in tool inputs are
+    /// canonicalized before rules run, and access outside the workspace
+    /// prompts or blocks unless a rule decides.
+    pub fn with_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// Add managed policy rules. They are evaluated before everything else,
+    /// including `--ludicrous` mode, and whatever they decide is final.
+    pub fn with_managed_rules(mut self, rules: Vec<PermissionRule>) -> Self {
+        self.managed_rules = rules;
+        self
+    }
+
+    /// Add managed policy hooks. They run before the configured hooks and,
+    /// unlike them, also in bypass mode.
+    pub fn with_managed_hooks(mut self, hooks: Vec<HookConfig>) -> Self {
+        self.managed_hooks += hooks.len();
+        let stats = self.hook_stats.get_mut().unwrap();
+        stats.splice(0..0, vec![(0, None); hooks.len()]);
+        self.hooks.splice(0..0, hooks);
+        self
+    }
+
+    /// Record permission decisions, hook results and prompt answers.
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Tag audit entries with the current session.
+    pub fn set_audit_session(&self, session_id: &str) {
+        if let Some(audit) = &self.audit {
+            audit.set_session(session_id);
         }
     }
 
+    /// Start in the given permission mode.
+    pub fn with_mode(self, mode: PermissionMode) -> Self {
+        self.set_mode(mode);
+        self
+    }
+
+    pub fn mode(&self) -> PermissionMode {
+        *self.mode.lock().unwrap()
+    }
+
+    /// Switch permission mode; takes effect for the next tool call.
+    pub fn set_mode(&self, mode: PermissionMode) {
+        *self.mode.lock().unwrap() = mode;
+    }
+
+    pub fn workspace(&self) -> Option<&Workspace> {
+        self.workspace.as_ref()
+    }
+
+    /// Set where the current session's large tool results are written. Only
+    /// that directory is readable without asking, not other sessions'.
+    pub fn set_tool_output_dir(&self, dir: &Path) {
+        *self.tool_output_dir.lock().unwrap() = Some(canonicalize(dir, Path::new("/")));
+    }
+
+    pub fn tool_output_dir(&self) -> Option<PathBuf> {
+        self.tool_output_dir.lock().unwrap().clone()
+    }
+
     /// Create an engine that auto-permits everything (--ludicrous mode).
     pub fn ludicrous() -> Self {
         Self {
+            managed_rules: Vec::new(),
             rules: Vec::new(),
             session_rules: Mutex::new(Vec::new()),
             hooks: Vec::new(),
+            managed_hooks: 0,
+            hook_stats: Mutex::new(Vec::new()),
             prompt_handler: None,
-            ludicrous: true,
+            mode: Mutex::new(PermissionMode::Bypass),
+            workspace: None,
+            audit: None,
+            tool_output_dir: Mutex::new(None),
         }
     }
 
     /// Check whether a tool call is permitted, blocked, or needs a prompt.
-    ///
-    /// Evaluation order:
-    /// 1. If ludicrous mode, always Permit.
-    /// 2. Check session rules (added via "always allow").
-    /// 3. Check static rules from config.
-    /// 4. Priority: block > permit > prompt.
-    /// 5. Default: read-only tools = Permit, mutating tools = Prompt.
+    /// See [`PermissionEngine::evaluate`] for the evaluation order.
     pub fn check(
         &self,
         tool_name: &str,
         tool_input: &serde_json::Value,
         is_read_only: bool,
     ) -> PermissionDecision {
-        if self.ludicrous {
-            return PermissionDecision::Permit;
-        }
+        self.evaluate(tool_name, tool_input, is_read_only).decision
+    }
 
-        // Check session rules first (they can only add permits)
-        let session_rules = self.session_rules.lock().unwrap();
-        if let Some(result) = RuleMatcher::evaluate(&session_rules, tool_name, tool_input) {
-            if result.level == PermissionLevel::Permit {
-                return PermissionDecision::Permit;
-            }
+    /// Decide a tool call and report which layer decided it.
+    ///
+    /// File paths in the input are canonicalized first (with a workspace).
+    /// Layers, in order:
+    /// 1. Managed policy — any matching rule is final.
+    /// 2. Mode — bypass Permits everything; plan Blocks mutating tools.
+    /// 3. Config blocks — a config rule evaluating to Block.
+    /// 4. Session grants — "always allow" answers from this session.
+    /// 5. Config permits and prompts.
+    /// 6. Mode — acceptEdits Permits Write/Edit inside the workspace.
+    /// 7. Workspace — paths outside it Prompt or Block.
+    /// 8. Default — read-only tools Permit, mutating tools Prompt.
+    ///
+    /// Within a layer, specific rules beat general ones, then
+    /// block > permit > prompt (see [`RuleMatcher::evaluate`]).
+    ///
+    /// Every decision is recorded in the audit log, if one is set.
+    pub fn evaluate(
+        &self,
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+        is_read_only: bool,
+    ) -> PermissionCheck {
+        let check = self.evaluate_layers(tool_name, tool_input, is_read_only);
+        if let Some(audit) = &self.audit {
+            let (decision, reason) = match &check.decision {
+                PermissionDecision::Permit => ("permit", None),
+                PermissionDecision::Block { reason } => ("block", Some(reason.clone())),
+                PermissionDecision::Prompt { description, .. } => {
+                    ("prompt", Some(description.clone()))
+                }
+            };
+            let mut entry = audit.entry(AuditKind::Permission, decision);
+            entry.tool = Some(tool_name.to_string());
+            entry.input = audit_input(tool_input);
+            entry.source = Some(check.layer.to_string());
+            entry.rule = check.rule.clone();
+            entry.reason = reason;
+            audit.append(&entry);
         }
-        drop(session_rules);
+        check
+    }
 
-        // Check static rules
-        if let Some(result) = RuleMatcher::evaluate(&self.rules, tool_name, tool_input) {
+    fn evaluate_layers(
+        &self,
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+        is_read_only: bool,
+    ) -> PermissionCheck {
+        let (resolved_input, mut outside) = self.resolve_paths(tool_name, tool_input);
+        let tool_input = resolved_input.as_ref().unwrap_or(tool_input);
+        let workspace = self.workspace.as_ref();
+        if let (Some(ws), true) = (workspace, is_read_only) {
+            let output_dir = self.tool_output_dir();
+            outside.retain(|path| {
+                !ws.is_readable(path) && !output_dir.as_ref().is_some_and(|d| path.starts_with(d))
+            });
+        }
+        let decide = |layer: DecisionLayer, level: PermissionLevel, description: &str| {
             let input_summary = summarize_tool_input(tool_input);
-            return match result.level {
+            let decision = match level {
                 PermissionLevel::Permit => PermissionDecision::Permit,
                 PermissionLevel::Block => PermissionDecision::Block {
                     reason: format!(
-                        "Tool '{tool_name}'{input_summary} blocked by permission ({})",
-                        result.description
+                        "Tool '{tool_name}'{input_summary} blocked by {layer} ({description})"
                     ),
                 },
                 PermissionLevel::Prompt => PermissionDecision::Prompt {
                     tool: tool_name.to_string(),
                     description: format!(
-                        "Tool '{tool_name}'{input_summary} requires permission ({})",
-                        result.description
+                        "Tool '{tool_name}'{input_summary} requires permission under {layer} ({description})"
                     ),
                 },
             };
+            PermissionCheck {
+                decision,
+                layer,
+                rule: Some(description.to_string()),
+            }
+        };
+
+        if let Some(result) =
+            RuleMatcher::evaluate_in(&self.managed_rules, tool_name, tool_input, workspace)
+        {
+            return decide(DecisionLayer::Managed, result.level, &result.description);
+        }
+
+        let mode = self.mode();
+        match mode {
+            PermissionMode::Bypass => {
+                return PermissionCheck {
+                    decision: PermissionDecision::Permit,
+                    layer: DecisionLayer::Mode(mode),
+                    rule: None,
+                };
+            }
+            PermissionMode::Plan if !is_read_only => {
+                return decide(
+                    DecisionLayer::Mode(mode),
+                    PermissionLevel::Block,
+                    "read-only",
+                );
+            }
+            _ => {}
+        }
+
+        // Config blocks come before session grants, so "always allow" can
+        // never override a configured block
+        let config = RuleMatcher::evaluate_in(&self.rules, tool_name, tool_input, workspace);
+        if let Some(result) = config
+            .as_ref()
+            .filter(|r| r.level == PermissionLevel::Block)
+        {
+            return decide(
+                DecisionLayer::Config,
+                result.level.clone(),
+                &result.description,
+            );
+        }
+
+        let session_rules = self.session_rules.lock().unwrap();
+        if let Some(result) =
+            RuleMatcher::evaluate_in(&session_rules, tool_name, tool_input, workspace)
+        {
+            if result.level == PermissionLevel::Permit {
+                return decide(
+                    DecisionLayer::SessionGrant,
+                    result.level,
+                    &result.description,
+                );
+            }
+        }
+        drop(session_rules);
+
+        if let Some(result) = config {
+            return decide(DecisionLayer::Config, result.level, &result.description);
+        }
+
+        if mode == PermissionMode::AcceptEdits
+            && EDIT_TOOLS.contains(&tool_name)
+            && outside.is_empty()
+        {
+            return decide(
+                DecisionLayer::Mode(mode),
+                PermissionLevel::Permit,
+                "workspace edit",
+            );
+        }
+
+        // Outside the workspace: the workspace policy applies even to reads
+        if let (Some(ws), Some(path)) = (workspace, outside.first()) {
+            let path = path.display();
+            return PermissionCheck {
+                decision: match ws.outside_access() {
+                    OutsideWorkspace::Block => PermissionDecision::Block {
+                        reason: format!(
+                            "Tool '{tool_name}' accesses {path}, outside the workspace"
+                        ),
+                    },
+                    OutsideWorkspace::Prompt => PermissionDecision::Prompt {
+                        tool: tool_name.to_string(),
+                        description: format!(
+                            "Tool '{tool_name}' wants to access {path}, outside the workspace"
+                        ),
+                    },
+                },
+                layer: DecisionLayer::Workspace,
+                rule: None,
+            };
         }
 
         // Default behavior: read-only = permit, mutating = prompt
-        if is_read_only {
+        let decision = if is_read_only {
             PermissionDecision::Permit
         } else {
             PermissionDecision::Prompt {
                 tool: tool_name.to_string(),
                 description: format!("Tool '{tool_name}' requires permission"),
             }
+        };
+        PermissionCheck {
+            decision,
+            layer: DecisionLayer::Default,
+            rule: None,
         }
     }
 
     /// Check if a tool is statically blocked by rules (no args considered).
     /// Used to filter tool definitions before sending to the API.
     pub fn is_tool_blocked(&self, tool_name: &str) -> bool {
-        if self.ludicrous {
+        // Check static rules with empty input (no args to match)
+        let empty = serde_json::json!({});
+        if let Some(result) = RuleMatcher::evaluate(&self.managed_rules, tool_name, &empty) {
+            return result.level == PermissionLevel::Block;
+        }
+        if self.mode() == PermissionMode::Bypass {
             return false;
         }
-        // Check static rules with empty input (no args to match)
-        if let Some(result) = RuleMatcher::evaluate(&self.rules, tool_name, &serde_json::json!({}))
-        {
+        if let Some(result) = RuleMatcher::evaluate(&self.rules, tool_name, &empty) {
             return result.level == PermissionLevel::Block;
         }
         false
     }
 
+    /// Canonicalize the file path fields of a built-in tool's input.
+    /// Returns the input with those fields rewritten, and the resolved
+    /// paths that fall outside the workspace. MCP tools are left alone: their
+    /// `path` arguments need not name local files.
+    fn resolve_paths(
+        &self,
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+    ) -> (Option<serde_json::Value>, Vec<PathBuf>) {
+        let Some(ws) = &self.workspace else {
+            return (None, Vec::new());
+        };
+        let Some(obj) = tool_input.as_object() else {
+            return (None, Vec::new());
+        };
+        if tool_name.starts_with("mcp__") {
+            return (None, Vec::new());
+        }
+
+        let mut resolved = obj.clone();
+        let mut outside = Vec::new();
+        for field in PATH_FIELDS {
+            if let Some(serde_json::Value::String(raw)) = obj.get(*field) {
+                let path = ws.resolve(raw);
+                if !ws.contains(&path) {
+                    outside.push(path.clone());
+                }
+                resolved.insert(
+                    field.to_string(),
+                    serde_json::Value::String(path.to_string_lossy().into_owned()),
+                );
+            }
+        }
+        // A Glob pattern can itself climb out of its search directory
+        // (`/home/u/.ssh/*`, `../other/**`), so check where it starts.
+        if tool_name == "Glob"
+            && let Some(serde_json::Value::String(pattern)) = obj.get("pattern")
+        {
+            let prefix = glob_literal_prefix(pattern);
+            if !prefix.is_empty() {
+                let base = match resolved.get("path") {
+                    Some(serde_json::Value::String(dir)) => PathBuf::from(dir),
+                    _ => ws.resolve("."),
+                };
+                let path = canonicalize(Path::new(&prefix), &base);
+                if !ws.contains(&path) {
+                    outside.push(path);
+                }
+            }
+        }
+        (Some(serde_json::Value::Object(resolved)), outside)
+    }
+
+    /// Grant "always allow" for calls like this one for the rest of the
+    /// session, and return the rules added. Grants are scoped: Bash to the
+    /// prefix of each command it runs (`git push`, `cargo`), file tools to
+    /// the directory of each path, memory writes to their `scope`. Other
+    /// tools are granted as a whole.
+    pub fn grant_session(
+        &self,
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+    ) -> Vec<PermissionRule> {
+        let rules = self.suggested_rules(tool_name, tool_input);
+        for rule in &rules {
+            self.add_session_rule(rule.clone());
+        }
+        rules
+    }
+
+    /// The scoped permit rules "always allow" would add for this call: a
+    /// command prefix for Bash, a directory for file tools, a memory scope
+    /// for MemoryWrite, else the tool.
+    pub fn suggested_rules(
+        &self,
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+    ) -> Vec<PermissionRule> {
+        session_grants(tool_name, tool_input, self.workspace.as_ref())
+    }
+
     /// Add a session-scoped permit rule (from "always allow" responses).
     /// Dies with the process — not persisted to config.
     /// Deduplicates by tool name + args to prevent unbounded growth.
@@ -136,10 +458,68 @@ impl PermissionEngine {
 
     /// Run hooks for the given event.
     pub async fn run_hooks(&self, event: &HookEvent, hook_input: &HookInput) -> Result<(), String> {
-        if self.ludicrous {
-            return Ok(());
+        let outcome = self.evaluate_hooks(event, hook_input).await;
+        match outcome.decision {
+            HookDecision::Deny => Err(outcome.reason.unwrap_or_default()),
+            HookDecision::Approve | HookDecision::Ask => Ok(()),
+        }
+    }
+
+    /// Run hooks for the given event and return their combined decision,
+    /// rewritten input and additional context.
+    pub async fn evaluate_hooks(&self, event: &HookEvent, hook_input: &HookInput) -> HookOutcome {
+        // Bypass skips every hook but the managed ones
+        let hooks = if self.mode() == PermissionMode::Bypass {
+            &self.hooks[..self.managed_hooks]
+        } else {
+            &self.hooks[..]
+        };
+        if hooks.is_empty() {
+            return HookOutcome::default();
+        }
+        let (outcome, results) =
+            evaluate_hooks_in(hooks, event, hook_input, self.workspace.as_ref()).await;
+        if let Some(audit) = &self.audit {
+            for (index, result) in &results {
+                let (decision, reason) = match result {
+                    HookRunResult::Approved => ("approved", None),
+                    HookRunResult::Asked => ("asked", outcome.reason.clone()),
+                    HookRunResult::Denied(reason) => ("denied", Some(reason.clone())),
+                    HookRunResult::Error(msg) => ("error", Some(msg.clone())),
+                };
+                let mut entry = audit.entry(AuditKind::Hook, decision);
+                entry.event = Some(event.as_str().to_string());
+                entry.tool = hook_input.tool_name.clone();
+                entry.input = hook_input.tool_input.as_ref().and_then(audit_input);
+                entry.source = Some(self.hooks[*index].command.clone());
+                entry.reason = reason;
+                audit.append(&entry);
+            }
         }
-        run_hooks(&self.hooks, event, hook_input).await
+        let mut stats = self.hook_stats.lock().unwrap();
+        for (index, result) in results {
+            if let Some((matched, last)) = stats.get_mut(index) {
+                *matched += 1;
+                *last = Some(result);
+            }
+        }
+        outcome
+    }
+
+    /// Configured hooks with how often each matched and its last result.
+    pub fn hook_status(&self) -> Vec<HookStatus> {
+        let stats = self.hook_stats.lock().unwrap();
+        self.hooks
+            .iter()
+            .zip(stats.iter())
+            .enumerate()
+            .map(|(index, (hook, (matched, last)))| HookStatus {
+                hook: hook.clone(),
+                managed: index < self.managed_hooks,
+                matched: *matched,
+                last_result: last.clone(),
+            })
+            .collect()
     }
 
     /// Prompt the user for permission. Returns Block if no handler is set.
@@ -148,43 +528,204 @@ impl PermissionEngine {
         tool_name: &str,
         tool_input: &serde_json::Value,
         description: &str,
+        preview: Option<&str>,
     ) -> PromptResponse {
-        match &self.prompt_handler {
+        let response = match &self.prompt_handler {
             Some(handler) => {
+                let suggested = self.suggested_rules(tool_name, tool_input);
                 handler
-                    .prompt_permission(tool_name, tool_input, description)
+                    .prompt_permission(tool_name, tool_input, description, preview, &suggested)
                     .await
             }
             None => {
                 // No prompt handler = non-interactive mode, safe default is deny
                 PromptResponse::Deny
             }
+        };
+        if let Some(audit) = &self.audit {
+            let answer = match response {
+                PromptResponse::AllowOnce => "allow_once",
+                PromptResponse::AlwaysAllow => "always_allow",
+                PromptResponse::AlwaysAllowSaved(_) => "always_allow_saved",
+                PromptResponse::Deny => "deny",
+            };
+            let mut entry = audit.entry(AuditKind::Prompt, answer);
+            if let PromptResponse::AlwaysAllowSaved(rules) = &response {
+                entry.rule = Some(
+                    rules
+                        .iter()
+                        .map(|r| format!("{}({})", r.tool, r.args.as_deref().unwrap_or("*")))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                );
+            }
+            entry.tool = Some(tool_name.to_string());
+            entry.input = audit_input(tool_input);
+            entry.source = Some(if self.prompt_handler.is_some() {
+                "user".to_string()
+            } else {
+                "no prompt handler".to_string()
+            });
+            entry.reason = Some(description.to_string());
+            audit.append(&entry);
         }
+        response
+    }
+}
+
+/// Scoped permit rules covering calls like `tool_input`.
+fn session_grants(
+    tool_name: &str,
+    tool_input: &serde_json::Value,
+    workspace: Option<&Workspace>,
+) -> Vec<PermissionRule> {
+    let grant = |args: Option<String>| PermissionRule {
+        tool: tool_name.to_string(),
+        args,
+        level: PermissionLevel::Permit,
+    };
+
+    // Allowing global memory writes shouldn't allow writes to the repo's
+    // team memory
+    if tool_name == "MemoryWrite"
+        && let Some(serde_json::Value::String(scope)) = tool_input.get("scope")
+    {
+        return vec![grant(Some(format!("scope:{}", globset::escape(scope))))];
+    }
+
+    if let Some(serde_json::Value::String(command)) = tool_input.get("command") {
+        let mut rules = Vec::new();
+        for sub in crate::shell::parse(command).commands {
+            let prefix = command_prefix(&sub);
+            if prefix.is_empty() {
+                continue;
+            }
+            let escaped = globset::escape(&prefix);
+            for args in [format!("command:{escaped}"), format!("command:{escaped} *")] {
+                let rule = grant(Some(args));
+                if !rules.iter().any(|r: &PermissionRule| r.args == rule.args) {
+                    rules.push(rule);
+                }
+            }
+        }
+        if !rules.is_empty() {
+            return rules;
+        }
+    }
+
+    if !tool_name.starts_with("mcp__") {
+        let mut rules = Vec::new();
+        for field in PATH_FIELDS {
+            let Some(serde_json::Value::String(raw)) = tool_input.get(*field) else {
+                continue;
+            };
+            let args = match workspace {
+                Some(ws) => {
+                    let path = ws.resolve(raw);
+                    match path.parent() {
+                        // Outside the workspace, only the file itself
+                        _ if !ws.contains(&path) => {
+                            format!("path:{}", globset::escape(&ws.display_path(&path)))
+                        }
+                        Some(parent) if parent != ws.root() => {
+                            format!("path:{}/**", globset::escape(&ws.display_path(parent)))
+                        }
+                        _ => format!("path:{}", globset::escape(&ws.display_path(&path))),
+                    }
+                }
+                None => match std::path::Path::new(raw).parent() {
+                    Some(parent) if !parent.as_os_str().is_empty() => {
+                        format!("{field}:{}/**", globset::escape(&parent.to_string_lossy()))
+                    }
+                    _ => format!("{field}:{}", globset::escape(raw)),
+                },
+            };
+            rules.push(grant(Some(args)));
+        }
+        if !rules.is_empty() {
+            return rules;
+        }
+    }
+
+    vec![grant(None)]
+}
+
+/// The leading directory components of a glob pattern that contain no
+/// wildcards: `/etc/*.conf` → `/etc`, `../lib/**/*.rs` → `../lib`.
+fn glob_literal_prefix(pattern: &str) -> String {
+    let mut parts: Vec<&str> = pattern.split('/').collect();
+    // The last component names files, not a directory to search
+    parts.pop();
+    let literal = parts
+        .iter()
+        .take_while(|part| !part.contains(['*', '?', '[', '{']))
+        .count();
+    let prefix = parts[..literal].join("/");
+    if prefix.is_empty() && pattern.starts_with('/') {
+        "/".to_string()
+    } else {
+        prefix
+    }
+}
+
+/// The part of a command that a grant covers: the program, plus its
+/// subcommand when it has one (`git push`, `cargo test`, but `ls`).
+fn command_prefix(command: &str) -> String {
+    let mut words = command.split_whitespace();
+    let Some(program) = words.next() else {
+        return String::new();
+    };
+    match words.next() {
+        Some(sub)
+            if sub
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ':')
+                && !sub.starts_with('-')
+                && sub.chars().any(|c| c.is_ascii_alphabetic()) =>
+        {
+            format!("{program} {sub}")
+        }
+        _ => program.to_string(),
     }
 }
 
 /// Summarize a tool input as a short human-readable string for error messages.
 /// Extracts key string fields (command, file_path, path, url) and truncates them.
 fn summarize_tool_input(input: &serde_json::Value) -> String {
+    match key_field(input) {
+        Some((field, value)) => format!(" ({field}: {value})"),
+        None => String::new(),
+    }
+}
+
+/// The first key string field of a tool input, truncated.
+fn key_field(input: &serde_json::Value) -> Option<(&'static str, String)> {
     const KEY_FIELDS: &[&str] = &["command", "file_path", "path", "url", "pattern"];
-    const MAX_LEN: usize = 80;
 
-    let obj = match input.as_object() {
-        Some(o) => o,
-        None => return String::new(),
-    };
+    let obj = input.as_object()?;
+    KEY_FIELDS.iter().find_map(|field| match obj.get(*field) {
+        Some(serde_json::Value::String(val)) => Some((*field, truncate_summary(val))),
+        _ => None,
+    })
+}
 
-    for field in KEY_FIELDS {
-        if let Some(serde_json::Value::String(val)) = obj.get(*field) {
-            let truncated = if val.len() > MAX_LEN {
-                format!("{}...", chet_types::truncate_str(val, MAX_LEN))
-            } else {
-                val.clone()
-            };
-            return format!(" ({field}: {truncated})");
-        }
+fn truncate_summary(val: &str) -> String {
+    const MAX_LEN: usize = 80;
+    if val.len() > MAX_LEN {
+        format!("{}...", chet_types::truncate_str(val, MAX_LEN))
+    } else {
+        val.to_string()
+    }
+}
+
+/// Tool input as recorded in the audit log: the key field, or else the
+/// truncated JSON.
+fn audit_input(input: &serde_json::Value) -> Option<String> {
+    match key_field(input) {
+        Some((field, value)) => Some(format!("{field}: {value}")),
+        None if input.as_object().is_some_and(|o| o.is_empty()) || input.is_null() => None,
+        None => Some(truncate_summary(&input.to_string())),
     }
-    String::new()
 }
 
 #[cfg(test)]
@@ -269,7 +810,7 @@ mod tests {
     #[tokio::test]
     async fn test_no_prompt_handler_returns_deny() {
         let e = engine(vec![]);
-        let response = e.prompt("Bash", &json!({}), "test").await;
+        let response = e.prompt("Bash", &json!({}), "test", None).await;
         assert_eq!(response, PromptResponse::Deny);
     }
 
@@ -331,4 +872,550 @@ mod tests {
         assert!(summary.len() < 120, "summary too long: {summary}");
         assert!(summary.contains("..."));
     }
+
+    fn workspace_engine(rules: Vec<PermissionRule>) -> (tempfile::TempDir, PermissionEngine) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("repo");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let e = engine(rules).with_workspace(Workspace::new(&root, &[]));
+        (dir, e)
+    }
+
+    #[test]
+    fn test_workspace_read_inside_permits() {
+        let (_dir, e) = workspace_engine(vec![]);
+        let root = e.workspace().unwrap().root().to_path_buf();
+        let inside = root.join("src/main.rs");
+        assert_eq!(
+            e.check("Read", &json!({"file_path": inside}), true),
+            PermissionDecision::Permit
+        );
+        assert_eq!(
+            e.check("Read", &json!({"file_path": "src/lib.rs"}), true),
+            PermissionDecision::Permit
+        );
+    }
+
+    #[test]
+    fn test_workspace_read_outside_prompts() {
+        let (_dir, e) = workspace_engine(vec![]);
+        match e.check("Read", &json!({"file_path": "/etc/shadow"}), true) {
+            PermissionDecision::Prompt { description, .. } => {
+                assert!(
+                    description.contains("outside the workspace"),
+                    "{description}"
+                );
+            }
+            other => panic!("expected Prompt, got {other:?}"),
+        }
+        // `..` can't climb out unnoticed
+        assert!(matches!(
+            e.check("Read", &json!({"file_path": "src/../../secret"}), true),
+            PermissionDecision::Prompt { .. }
+        ));
+    }
+
+    #[test]
+    fn test_workspace_readable_dir_permits_reads_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        let ws = Workspace::new(&base.join("repo"), &[])
+            .with_readable(&base.join("sessions"))
+            .with_outside_access(OutsideWorkspace::Block);
+        let e = engine(vec![]).with_workspace(ws);
+        let output = base.join("sessions/abc/tool-output/Bash-1.txt");
+        assert_eq!(
+            e.check("Read", &json!({"file_path": output}), true),
+            PermissionDecision::Permit
+        );
+        assert!(matches!(
+            e.check("Write", &json!({"file_path": output}), false),
+            PermissionDecision::Block { .. }
+        ));
+    }
+
+    #[test]
+    fn test_session_tool_output_dir_permits_reads_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        let ws =
+            Workspace::new(&base.join("repo"), &[]).with_outside_access(OutsideWorkspace::Block);
+        let e = engine(vec![]).with_workspace(ws);
+        e.set_tool_output_dir(&base.join("sessions/abc/tool-output"));
+        let output = base.join("sessions/abc/tool-output/Bash-1.txt");
+        assert_eq!(
+            e.check("Read", &json!({"file_path": output}), true),
+            PermissionDecision::Permit
+        );
+        // Other sessions' transcripts are not readable
+        assert!(matches!(
+            e.check(
+                "Read",
+                &json!({"file_path": base.join("sessions/def/events.jsonl")}),
+                true
+            ),
+            PermissionDecision::Block { .. }
+        ));
+    }
+
+    #[test]
+    fn test_workspace_outside_block_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let ws = Workspace::new(dir.path(), &[]).with_outside_access(OutsideWorkspace::Block);
+        let e = engine(vec![]).with_workspace(ws);
+        assert!(matches!(
+            e.check("Write", &json!({"file_path": "/etc/passwd"}), false),
+            PermissionDecision::Block { .. }
+        ));
+    }
+
+    #[test]
+    fn test_workspace_rules_see_canonical_paths() {
+        let (_dir, e) = workspace_engine(vec![rule(
+            "Read",
+            Some("file_path:/etc/*"),
+            PermissionLevel::Block,
+        )]);
+        let sneaky = format!("src/{}etc/passwd", "../".repeat(40));
+        assert!(matches!(
+            e.check("Read", &json!({"file_path": sneaky}), true),
+            PermissionDecision::Block { .. }
+        ));
+    }
+
+    #[test]
+    fn test_workspace_glob_pattern_outside_prompts() {
+        let (_dir, e) = workspace_engine(vec![]);
+        assert_eq!(
+            e.check("Glob", &json!({"pattern": "src/**/*.rs"}), true),
+            PermissionDecision::Permit
+        );
+        for pattern in ["/home/u/.ssh/*", "../**/*", "/*"] {
+            assert!(
+                matches!(
+                    e.check("Glob", &json!({"pattern": pattern}), true),
+                    PermissionDecision::Prompt { .. }
+                ),
+                "{pattern}"
+            );
+        }
+        assert!(matches!(
+            e.check("Glob", &json!({"pattern": "../../*", "path": "src"}), true),
+            PermissionDecision::Prompt { .. }
+        ));
+    }
+
+    #[test]
+    fn test_workspace_path_rules_are_relative() {
+        let (_dir, e) = workspace_engine(vec![rule(
+            "Write",
+            Some("path:src/**"),
+            PermissionLevel::Permit,
+        )]);
+        assert_eq!(
+            e.check("Write", &json!({"file_path": "src/new.rs"}), false),
+            PermissionDecision::Permit
+        );
+        assert!(matches!(
+            e.check("Write", &json!({"file_path": "Cargo.toml"}), false),
+            PermissionDecision::Prompt { .. }
+        ));
+    }
+
+    #[test]
+    fn test_workspace_rule_can_permit_outside() {
+        let (_dir, e) = workspace_engine(vec![rule(
+            "Read",
+            Some("path:/usr/share/**"),
+            PermissionLevel::Permit,
+        )]);
+        assert_eq!(
+            e.check("Read", &json!({"file_path": "/usr/share/dict/words"}), true),
+            PermissionDecision::Permit
+        );
+    }
+
+    #[test]
+    fn test_workspace_ignores_mcp_tools() {
+        let (_dir, e) = workspace_engine(vec![]);
+        assert_eq!(
+            e.check("mcp__github__get", &json!({"path": "/repos/x"}), true),
+            PermissionDecision::Permit
+        );
+    }
+
+    #[test]
+    fn test_session_grant_never_overrides_config_block() {
+        let e = engine(vec![rule(
+            "Bash",
+            Some("command:rm *"),
+            PermissionLevel::Block,
+        )]);
+        // Even a tool-wide grant can't lift a configured block
+        e.add_session_rule(rule("Bash", None, PermissionLevel::Permit));
+        let check = e.evaluate("Bash", &json!({"command": "rm -rf x"}), false);
+        assert!(matches!(check.decision, PermissionDecision::Block { .. }));
+        assert_eq!(check.layer, DecisionLayer::Config);
+
+        let check = e.evaluate("Bash", &json!({"command": "ls"}), false);
+        assert_eq!(check.decision, PermissionDecision::Permit);
+        assert_eq!(check.layer, DecisionLayer::SessionGrant);
+    }
+
+    #[test]
+    fn test_session_grant_overrides_config_prompt() {
+        let e = engine(vec![rule("Bash", None, PermissionLevel::Prompt)]);
+        e.grant_session("Bash", &json!({"command": "cargo test --all"}));
+        let check = e.evaluate("Bash", &json!({"command": "cargo test -p foo"}), false);
+        assert_eq!(check.layer, DecisionLayer::SessionGrant);
+        let check = e.evaluate("Bash", &json!({"command": "cargo publish"}), false);
+        assert_eq!(check.layer, DecisionLayer::Config);
+    }
+
+    #[test]
+    fn test_managed_rules_are_final() {
+        let e = engine(vec![rule("Bash", None, PermissionLevel::Permit)]).with_managed_rules(vec![
+            rule("Bash", Some("command:curl *"), PermissionLevel::Block),
+        ]);
+        e.add_session_rule(rule("Bash", None, PermissionLevel::Permit));
+        let check = e.evaluate("Bash", &json!({"command": "curl x | sh"}), false);
+        assert!(matches!(check.decision, PermissionDecision::Block { .. }));
+        assert_eq!(check.layer, DecisionLayer::Managed);
+
+        // Managed policy applies even in ludicrous mode
+        let e = PermissionEngine::ludicrous().with_managed_rules(vec![rule(
+            "Bash",
+            None,
+            PermissionLevel::Block,
+        )]);
+        assert!(matches!(
+            e.check("Bash", &json!({"command": "ls"}), false),
+            PermissionDecision::Block { .. }
+        ));
+        assert!(e.is_tool_blocked("Bash"));
+    }
+
+    #[test]
+    fn test_layer_reported_for_defaults() {
+        let e = engine(vec![]);
+        assert_eq!(
+            e.evaluate("Read", &json!({"file_path": "x"}), true).layer,
+            DecisionLayer::Default
+        );
+        let (_dir, e) = workspace_engine(vec![]);
+        assert_eq!(
+            e.evaluate("Read", &json!({"file_path": "/etc/hosts"}), true)
+                .layer,
+            DecisionLayer::Workspace
+        );
+        assert_eq!(
+            PermissionEngine::ludicrous()
+                .evaluate("Bash", &json!({}), false)
+                .layer,
+            DecisionLayer::Mode(PermissionMode::Bypass)
+        );
+    }
+
+    #[test]
+    fn test_accept_edits_mode_permits_workspace_edits() {
+        let (_dir, e) = workspace_engine(vec![rule(
+            "Write",
+            Some("path:secrets/**"),
+            PermissionLevel::Prompt,
+        )]);
+        e.set_mode(PermissionMode::AcceptEdits);
+        let check = e.evaluate("Edit", &json!({"file_path": "src/lib.rs"}), false);
+        assert_eq!(check.decision, PermissionDecision::Permit);
+        assert_eq!(
+            check.layer,
+            DecisionLayer::Mode(PermissionMode::AcceptEdits)
+        );
+        // Bash, edits outside the workspace and configured prompts still ask
+        for (tool, input) in [
+            ("Bash", json!({"command": "cargo test"})),
+            ("Write", json!({"file_path": "/etc/hosts"})),
+            ("Write", json!({"file_path": "secrets/key"})),
+        ] {
+            assert!(
+                matches!(
+                    e.check(tool, &input, false),
+                    PermissionDecision::Prompt { .. }
+                ),
+                "{tool} {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_plan_mode_blocks_mutating_tools() {
+        let e = engine(vec![rule("Bash", None, PermissionLevel::Permit)]);
+        e.set_mode(PermissionMode::Plan);
+        assert!(matches!(
+            e.check("Bash", &json!({"command": "ls"}), false),
+            PermissionDecision::Block { reason } if reason.contains("plan mode")
+        ));
+        assert_eq!(
+            e.check("Read", &json!({"file_path": "x"}), true),
+            PermissionDecision::Permit
+        );
+        e.set_mode(PermissionMode::Default);
+        assert_eq!(
+            e.check("Bash", &json!({"command": "ls"}), false),
+            PermissionDecision::Permit
+        );
+    }
+
+    #[test]
+    fn test_permission_mode_parse_and_cycle() {
+        assert_eq!(
+            "accept-edits".parse::<PermissionMode>(),
+            Ok(PermissionMode::AcceptEdits)
+        );
+        assert_eq!("acceptEdits".parse(), Ok(PermissionMode::AcceptEdits));
+        assert_eq!("bypass".parse(), Ok(PermissionMode::Bypass));
+        assert!("yolo".parse::<PermissionMode>().is_err());
+        for mode in PermissionMode::ALL {
+            assert_eq!(mode.as_str().parse(), Ok(mode));
+        }
+        assert_eq!(PermissionMode::Plan.next(false), PermissionMode::Default);
+        assert_eq!(PermissionMode::Plan.next(true), PermissionMode::Bypass);
+        assert_eq!(PermissionMode::Bypass.next(true), PermissionMode::Default);
+    }
+
+    #[test]
+    fn test_bash_grants_are_scoped_to_command_prefix() {
+        let e = engine(vec![]);
+        let rules = e.grant_session("Bash", &json!({"command": "git push origin && ls -la"}));
+        let args: Vec<_> = rules.iter().filter_map(|r| r.args.as_deref()).collect();
+        assert_eq!(
+            args,
+            vec![
+                "command:git push",
+                "command:git push *",
+                "command:ls",
+                "command:ls *"
+            ]
+        );
+        assert_eq!(
+            e.check("Bash", &json!({"command": "git push"}), false),
+            PermissionDecision::Permit
+        );
+        assert!(matches!(
+            e.check("Bash", &json!({"command": "git reset --hard"}), false),
+            PermissionDecision::Prompt { .. }
+        ));
+        // Every part of a compound command needs a grant
+        assert!(matches!(
+            e.check("Bash", &json!({"command": "ls && rm -rf x"}), false),
+            PermissionDecision::Prompt { .. }
+        ));
+    }
+
+    #[test]
+    fn test_file_grants_are_scoped_to_directory() {
+        let (_dir, e) = workspace_engine(vec![]);
+        let rules = e.grant_session("Write", &json!({"file_path": "src/main.rs"}));
+        assert_eq!(rules[0].args.as_deref(), Some("path:src/**"));
+        assert_eq!(
+            e.check("Write", &json!({"file_path": "src/lib.rs"}), false),
+            PermissionDecision::Permit
+        );
+        assert!(matches!(
+            e.check("Write", &json!({"file_path": "Cargo.toml"}), false),
+            PermissionDecision::Prompt { .. }
+        ));
+    }
+
+    #[test]
+    fn test_file_grants_outside_workspace_are_exact() {
+        let (dir, e) = workspace_engine(vec![]);
+        let secrets = dir.path().join("home/.ssh");
+        std::fs::create_dir_all(&secrets).unwrap();
+        let key = secrets.join("id_rsa.pub");
+        let rules = e.grant_session("Read", &json!({"file_path": key}));
+        assert_eq!(
+            rules[0].args,
+            Some(format!("path:{}", globset::escape(&key.to_string_lossy())))
+        );
+        assert_eq!(
+            e.check("Read", &json!({"file_path": key}), true),
+            PermissionDecision::Permit
+        );
+        assert!(matches!(
+            e.check("Read", &json!({"file_path": secrets.join("id_rsa")}), true),
+            PermissionDecision::Prompt { .. }
+        ));
+    }
+
+    #[test]
+    fn test_memory_grants_are_scoped_to_memory_scope() {
+        let e = engine(vec![]);
+        let rules = e.grant_session("MemoryWrite", &json!({"scope": "global", "title": "a"}));
+        assert_eq!(rules[0].args.as_deref(), Some("scope:global"));
+        assert_eq!(
+            e.check("MemoryWrite", &json!({"scope": "global", "id": "b"}), false),
+            PermissionDecision::Permit
+        );
+        assert!(matches!(
+            e.check(
+                "MemoryWrite",
+                &json!({"scope": "team", "title": "a"}),
+                false
+            ),
+            PermissionDecision::Prompt { .. }
+        ));
+    }
+
+    #[test]
+    fn test_scope_grants_are_only_for_memory_writes() {
+        let (_dir, e) = workspace_engine(vec![]);
+        let rules = e.grant_session(
+            "Write",
+            &json!({"file_path": "src/main.rs", "scope": "global"}),
+        );
+        assert_eq!(rules[0].args.as_deref(), Some("path:src/**"));
+    }
+
+    #[test]
+    fn test_other_tools_grant_whole_tool() {
+        let e = engine(vec![]);
+        let rules = e.grant_session("mcp__db__query", &json!({"sql": "select 1"}));
+        assert_eq!(rules.len(), 1);
+        assert!(rules[0].args.is_none());
+    }
+
+    #[test]
+    fn test_command_prefix() {
+        assert_eq!(command_prefix("git push origin"), "git push");
+        assert_eq!(command_prefix("ls -la"), "ls");
+        assert_eq!(command_prefix("cat ./file"), "cat");
+        assert_eq!(command_prefix("npm run test:unit"), "npm run");
+        assert_eq!(command_prefix("sleep 10"), "sleep");
+    }
+
+    #[tokio::test]
+    async fn hook_status_counts_matches() {
+        let hook = |tool: &str, command: &str| HookConfig {
+            event: HookEvent::BeforeTool,
+            command: command.to_string(),
+            timeout_ms: 5000,
+            tool: Some(tool.to_string()),
+            args: None,
+        };
+        let engine = PermissionEngine::new(
+            vec![],
+            vec![hook("Bash", "exit 0"), hook("Read", "exit 2")],
+            None,
+        );
+        let input = HookInput {
+            event: HookEvent::BeforeTool,
+            tool_name: Some("Bash".into()),
+            tool_input: Some(json!({"command": "ls"})),
+            tool_output: None,
+            is_error: None,
+            worktree_path: None,
+            worktree_source: None,
+            messages_removed: None,
+            messages_remaining: None,
+            config_path: None,
+        };
+        assert!(
+            engine
+                .run_hooks(&HookEvent::BeforeTool, &input)
+                .await
+                .is_ok()
+        );
+        assert!(
+            engine
+                .run_hooks(&HookEvent::BeforeTool, &input)
+                .await
+                .is_ok()
+        );
+
+        let status = engine.hook_status();
+        assert_eq!(status[0].matched, 2);
+        assert_eq!(status[0].last_result, Some(HookRunResult::Approved));
+        assert_eq!(status[1].matched, 0);
+        assert_eq!(status[1].last_result, None);
+    }
+
+    #[tokio::test]
+    async fn managed_hooks_run_in_bypass_mode() {
+        let hook = |command: &str| HookConfig {
+            event: HookEvent::BeforeTool,
+            command: command.to_string(),
+            timeout_ms: 5000,
+            tool: None,
+            args: None,
+        };
+        let engine = PermissionEngine::new(vec![], vec![hook("exit 0")], None)
+            .with_managed_hooks(vec![hook("echo blocked by policy >&2; exit 2")])
+            .with_mode(PermissionMode::Bypass);
+        let input = HookInput {
+            event: HookEvent::BeforeTool,
+            tool_name: Some("Bash".into()),
+            tool_input: Some(json!({"command": "ls"})),
+            tool_output: None,
+            is_error: None,
+            worktree_path: None,
+            worktree_source: None,
+            messages_removed: None,
+            messages_remaining: None,
+            config_path: None,
+        };
+        let err = engine
+            .run_hooks(&HookEvent::BeforeTool, &input)
+            .await
+            .unwrap_err();
+        assert!(err.contains("blocked by policy"), "{err}");
+
+        let status = engine.hook_status();
+        assert!(status[0].managed);
+        assert_eq!(status[0].matched, 1);
+        // The user's hook is skipped in bypass mode
+        assert!(!status[1].managed);
+        assert_eq!(status[1].matched, 0);
+    }
+
+    #[tokio::test]
+    async fn audit_log_records_decisions_and_answers() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = PermissionEngine::new(
+            vec![rule("Bash", Some("command:rm *"), PermissionLevel::Block)],
+            vec![],
+            None,
+        )
+        .with_audit_log(AuditLog::new(dir.path(), "proj"));
+        engine.set_audit_session("sess1");
+
+        engine.evaluate("Bash", &json!({"command": "rm -rf /"}), false);
+        engine.evaluate("Read", &json!({"file_path": "a.txt"}), true);
+        engine
+            .prompt(
+                "Write",
+                &json!({"file_path": "b.txt"}),
+                "needs approval",
+                Some("+new contents"),
+            )
+            .await;
+
+        let entries =
+            crate::audit::query(dir.path(), &crate::audit::AuditQuery::default()).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].decision, "block");
+        assert_eq!(entries[0].input.as_deref(), Some("command: rm -rf /"));
+        assert_eq!(entries[0].source.as_deref(), Some("config"));
+        assert!(entries[0].rule.as_deref().unwrap().contains("rm *"));
+        assert_eq!(entries[1].decision, "permit");
+        assert_eq!(entries[1].source.as_deref(), Some("default policy"));
+        assert_eq!(entries[2].kind, AuditKind::Prompt);
+        assert_eq!(entries[2].decision, "deny");
+        // The preview is shown, not logged
+        assert_eq!(entries[2].reason.as_deref(), Some("needs approval"));
+        assert!(
+            entries
+                .iter()
+                .all(|e| e.session.as_deref() == Some("sess1"))
+        );
+    }
 }
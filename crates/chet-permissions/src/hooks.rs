@@ -1,67 +1,183 @@
 //! Hook runner — executes external scripts on permission events.
 
-use crate::types::{HookConfig, HookEvent, HookInput};
+use crate::matcher::RuleMatcher;
+use crate::types::{
+    HookConfig, HookDecision, HookEvent, HookInput, HookOutcome, HookResponse, HookRunResult,
+};
+use crate::workspace::Workspace;
 use std::time::Duration;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-/// Run all hooks matching the given event, sequentially.
+/// Run all hooks matching the given event.
 ///
 /// Exit code protocol:
 /// - 0 = approve (continue)
 /// - 1 = error (warn and continue)
 /// - 2 = deny (stop and return error)
 ///
-/// First deny wins — subsequent hooks are not executed.
-/// Timeout kills the process and is treated as an error (warn + continue).
+/// Any deny wins. Timeout kills the process and is treated as an error
+/// (warn + continue). See [`evaluate_hooks`] for matching and the JSON
+/// protocol; this returns only the deny reason.
 pub async fn run_hooks(
     hooks: &[HookConfig],
     event: &HookEvent,
     hook_input: &HookInput,
 ) -> Result<(), String> {
-    let matching: Vec<&HookConfig> = hooks.iter().filter(|h| &h.event == event).collect();
+    let outcome = evaluate_hooks(hooks, event, hook_input).await;
+    match outcome.decision {
+        HookDecision::Deny => Err(outcome.reason.unwrap_or_default()),
+        HookDecision::Approve | HookDecision::Ask => Ok(()),
+    }
+}
+
+/// Run all hooks matching the given event in parallel and combine their
+/// responses.
+///
+/// A hook matches when its event does and, if set, its `tool` and `args`
+/// patterns match the tool call. On top of the exit codes, a hook may print
+/// a JSON object (see [`HookResponse`]) on stdout — or return one as a 2xx
+/// HTTP body — with a `decision` (approve/deny/ask), a `reason` for the
+/// model, an `updated_input` that replaces the tool input, and
+/// `additional_context` for the conversation. Exit code 2 denies, with the
+/// JSON `reason` or else stderr.
+///
+/// Responses combine in config order: the first deny wins, then the first
+/// ask; the first rewrite of the input wins.
+pub async fn evaluate_hooks(
+    hooks: &[HookConfig],
+    event: &HookEvent,
+    hook_input: &HookInput,
+) -> HookOutcome {
+    evaluate_hooks_in(hooks, event, hook_input, None).await.0
+}
+
+/// Like [`evaluate_hooks`], resolving `path:` patterns against a workspace
+/// and also returning each matching hook's index and result.
+pub(crate) async fn evaluate_hooks_in(
+    hooks: &[HookConfig],
+    event: &HookEvent,
+    hook_input: &HookInput,
+    workspace: Option<&Workspace>,
+) -> (HookOutcome, Vec<(usize, HookRunResult)>) {
+    let matching: Vec<(usize, &HookConfig)> = hooks
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| hook_matches(h, event, hook_input, workspace))
+        .collect();
+    let responses = futures_util::future::join_all(
+        matching
+            .iter()
+            .map(|(_, hook)| run_single_hook(hook, hook_input)),
+    )
+    .await;
 
-    for hook in matching {
-        match run_single_hook(hook, hook_input).await {
-            HookResult::Approve => continue,
-            HookResult::Deny(reason) => return Err(reason),
-            HookResult::Error(msg) => {
+    let mut outcome = HookOutcome::default();
+    let mut results = Vec::with_capacity(matching.len());
+    for ((index, hook), response) in matching.into_iter().zip(responses) {
+        let response = match response {
+            Ok(response) => response,
+            Err(msg) => {
                 tracing::warn!("Hook '{}' error: {}", hook.command, msg);
+                results.push((index, HookRunResult::Error(msg)));
                 continue;
             }
+        };
+        if let Some(context) = response.additional_context.filter(|c| !c.is_empty()) {
+            outcome.additional_context.push(context);
+        }
+        match response.decision.unwrap_or_default() {
+            HookDecision::Deny => {
+                let reason = response
+                    .reason
+                    .unwrap_or_else(|| format!("Hook '{}' denied the operation", hook.command));
+                if outcome.decision != HookDecision::Deny {
+                    outcome.decision = HookDecision::Deny;
+                    outcome.reason = Some(reason.clone());
+                }
+                results.push((index, HookRunResult::Denied(reason)));
+                continue;
+            }
+            HookDecision::Ask => {
+                if outcome.decision == HookDecision::Approve {
+                    outcome.decision = HookDecision::Ask;
+                    outcome.reason = Some(response.reason.unwrap_or_else(|| {
+                        format!("Hook '{}' asked for confirmation", hook.command)
+                    }));
+                }
+                results.push((index, HookRunResult::Asked));
+            }
+            HookDecision::Approve => results.push((index, HookRunResult::Approved)),
+        }
+        if let Some(updated) = response.updated_input {
+            if outcome.updated_input.is_none() {
+                outcome.updated_input = Some(updated);
+            } else {
+                tracing::warn!(
+                    "Hook '{}' input rewrite ignored: an earlier hook already rewrote it",
+                    hook.command
+                );
+            }
         }
     }
 
-    Ok(())
+    (outcome, results)
 }
 
-enum HookResult {
-    Approve,
-    Deny(String),
-    Error(String),
+/// Whether a hook applies to an event.
+fn hook_matches(
+    hook: &HookConfig,
+    event: &HookEvent,
+    hook_input: &HookInput,
+    workspace: Option<&Workspace>,
+) -> bool {
+    if &hook.event != event {
+        return false;
+    }
+    if hook.tool.is_none() && hook.args.is_none() {
+        return true;
+    }
+    let Some(tool_name) = hook_input.tool_name.as_deref() else {
+        return false;
+    };
+    let null = serde_json::Value::Null;
+    RuleMatcher::matches_call(
+        hook.tool.as_deref().unwrap_or("*"),
+        hook.args.as_deref(),
+        tool_name,
+        hook_input.tool_input.as_ref().unwrap_or(&null),
+        workspace,
+    )
 }
 
-async fn run_single_hook(hook: &HookConfig, hook_input: &HookInput) -> HookResult {
-    let input_json = match serde_json::to_string(hook_input) {
-        Ok(json) => json,
-        Err(e) => return HookResult::Error(format!("Failed to serialize hook input: {e}")),
-    };
+/// Parse a hook's stdout or HTTP body. Empty or non-JSON output approves.
+fn parse_response(output: &str) -> Result<HookResponse, String> {
+    let output = output.trim();
+    if !output.starts_with('{') {
+        return Ok(HookResponse::default());
+    }
+    serde_json::from_str(output).map_err(|e| format!("Invalid hook JSON: {e}"))
+}
+
+async fn run_single_hook(
+    hook: &HookConfig,
+    hook_input: &HookInput,
+) -> Result<HookResponse, String> {
+    let input_json = serde_json::to_string(hook_input)
+        .map_err(|e| format!("Failed to serialize hook input: {e}"))?;
 
     // HTTP hooks: POST JSON to a URL
     if hook.command.starts_with("http://") || hook.command.starts_with("https://") {
         return run_http_hook(hook, &input_json).await;
     }
 
-    let mut child = match tokio::process::Command::new("sh")
+    let mut child = tokio::process::Command::new("sh")
         .arg("-c")
         .arg(&hook.command)
         .stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()
-    {
-        Ok(child) => child,
-        Err(e) => return HookResult::Error(format!("Failed to spawn hook: {e}")),
-    };
+        .map_err(|e| format!("Failed to spawn hook: {e}"))?;
 
     // Write JSON to stdin
     if let Some(mut stdin) = child.stdin.take() {
@@ -69,60 +185,93 @@ async fn run_single_hook(hook: &HookConfig, hook_input: &HookInput) -> HookResul
         let _ = stdin.shutdown().await;
     }
 
-    // Wait with timeout
+    // Wait with timeout, collecting stdout and stderr
+    let mut stdout = child.stdout.take();
+    let mut stderr = child.stderr.take();
     let timeout = Duration::from_millis(hook.timeout_ms);
-    match tokio::time::timeout(timeout, child.wait()).await {
-        Ok(Ok(status)) => match status.code() {
-            Some(0) => HookResult::Approve,
-            Some(2) => HookResult::Deny(format!("Hook '{}' denied the operation", hook.command)),
-            Some(code) => HookResult::Error(format!("Hook exited with code {code}")),
-            None => HookResult::Error("Hook terminated by signal".to_string()),
-        },
-        Ok(Err(e)) => HookResult::Error(format!("Failed to wait for hook: {e}")),
+    let run = async {
+        let mut out = String::new();
+        let mut err = String::new();
+        let read_out = async {
+            if let Some(pipe) = stdout.as_mut() {
+                let _ = pipe.read_to_string(&mut out).await;
+            }
+        };
+        let read_err = async {
+            if let Some(pipe) = stderr.as_mut() {
+                let _ = pipe.read_to_string(&mut err).await;
+            }
+        };
+        let (status, (), ()) = tokio::join!(child.wait(), read_out, read_err);
+        (status, out, err)
+    };
+    let (status, out, err) = match tokio::time::timeout(timeout, run).await {
+        Ok(result) => result,
         Err(_) => {
             // Timeout — kill the process
             let _ = child.kill().await;
-            HookResult::Error(format!(
+            return Err(format!(
                 "Hook '{}' timed out after {}ms",
                 hook.command, hook.timeout_ms
-            ))
+            ));
+        }
+    };
+
+    let status = status.map_err(|e| format!("Failed to wait for hook: {e}"))?;
+    match status.code() {
+        Some(0) => parse_response(&out),
+        Some(2) => {
+            let mut response = parse_response(&out).unwrap_or_default();
+            response.decision = Some(HookDecision::Deny);
+            let stderr = err.trim();
+            if response.reason.is_none() && !stderr.is_empty() {
+                response.reason = Some(stderr.to_string());
+            }
+            Ok(response)
         }
+        Some(code) => Err(format!("Hook exited with code {code}")),
+        None => Err("Hook terminated by signal".to_string()),
     }
 }
 
 /// Run an HTTP hook by POSTing JSON to the URL.
-/// Response protocol: 2xx = approve, 403 = deny, other = error.
-async fn run_http_hook(hook: &HookConfig, input_json: &str) -> HookResult {
+/// Response protocol: 2xx = approve (or the JSON body's decision),
+/// 403 = deny, other = error.
+async fn run_http_hook(hook: &HookConfig, input_json: &str) -> Result<HookResponse, String> {
     let client = reqwest::Client::new();
     let timeout = Duration::from_millis(hook.timeout_ms);
 
-    let result = tokio::time::timeout(
-        timeout,
-        client
+    let result = tokio::time::timeout(timeout, async {
+        let response = client
             .post(&hook.command)
             .header("Content-Type", "application/json")
             .body(input_json.to_string())
-            .send(),
-    )
+            .send()
+            .await?;
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap_or_default();
+        Ok::<_, reqwest::Error>((status, body))
+    })
     .await;
 
     match result {
-        Ok(Ok(response)) => {
-            let status = response.status().as_u16();
-            match status {
-                200..=299 => HookResult::Approve,
-                403 => HookResult::Deny(format!(
-                    "HTTP hook '{}' denied the operation (403)",
-                    hook.command
-                )),
-                _ => HookResult::Error(format!(
-                    "HTTP hook '{}' returned status {status}",
-                    hook.command
-                )),
+        Ok(Ok((status, body))) => match status {
+            200..=299 => parse_response(&body),
+            403 => {
+                let mut response = parse_response(&body).unwrap_or_default();
+                response.decision = Some(HookDecision::Deny);
+                response.reason.get_or_insert_with(|| {
+                    format!("HTTP hook '{}' denied the operation (403)", hook.command)
+                });
+                Ok(response)
             }
-        }
-        Ok(Err(e)) => HookResult::Error(format!("HTTP hook '{}' failed: {e}", hook.command)),
-        Err(_) => HookResult::Error(format!(
+            _ => Err(format!(
+                "HTTP hook '{}' returned status {status}",
+                hook.command
+            )),
+        },
+        Ok(Err(e)) => Err(format!("HTTP hook '{}' failed: {e}", hook.command)),
+        Err(_) => Err(format!(
             "HTTP hook '{}' timed out after {}ms",
             hook.command, hook.timeout_ms
         )),
@@ -140,6 +289,8 @@ mod tests {
             event: HookEvent::BeforeTool,
             command: command.to_string(),
             timeout_ms: 5000,
+            tool: None,
+            args: None,
         }
     }
 
@@ -187,6 +338,8 @@ mod tests {
             event: HookEvent::BeforeTool,
             command: "sleep 10".to_string(),
             timeout_ms: 100,
+            tool: None,
+            args: None,
         }];
         let result = run_hooks(&hooks, &HookEvent::BeforeTool, &test_input()).await;
         // Timeout is treated as error (warn + continue)
@@ -215,11 +368,125 @@ mod tests {
     #[tokio::test]
     async fn test_hook_chain_first_deny_wins() {
         let hooks = vec![
-            test_hook("exit 0"), // approve
-            test_hook("exit 2"), // deny
-            test_hook("exit 0"), // would approve but never reached
+            test_hook("exit 0"),                               // approve
+            test_hook("echo 'first' >&2; exit 2"),             // deny
+            test_hook("sleep 0.1; echo 'second' >&2; exit 2"), // also deny, later in config
         ];
         let result = run_hooks(&hooks, &HookEvent::BeforeTool, &test_input()).await;
-        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "first");
+    }
+
+    #[tokio::test]
+    async fn test_json_deny_reason() {
+        let hooks = vec![test_hook(
+            r#"echo '{"decision":"deny","reason":"use trash instead of rm"}'"#,
+        )];
+        let outcome = evaluate_hooks(&hooks, &HookEvent::BeforeTool, &test_input()).await;
+        assert_eq!(outcome.decision, HookDecision::Deny);
+        assert_eq!(outcome.reason.as_deref(), Some("use trash instead of rm"));
+    }
+
+    #[tokio::test]
+    async fn test_exit_2_uses_stderr_as_reason() {
+        let hooks = vec![test_hook("echo 'not on main' >&2; exit 2")];
+        let result = run_hooks(&hooks, &HookEvent::BeforeTool, &test_input()).await;
+        assert_eq!(result.unwrap_err(), "not on main");
+    }
+
+    #[tokio::test]
+    async fn test_first_rewrite_wins_and_context_combines() {
+        let hooks = vec![
+            test_hook(r#"echo '{"updated_input":{"command":"ls -la"}}'"#),
+            test_hook(
+                r#"echo '{"updated_input":{"command":"ls -R"},"additional_context":"listing is safe"}'"#,
+            ),
+        ];
+        let outcome = evaluate_hooks(&hooks, &HookEvent::BeforeTool, &test_input()).await;
+        assert_eq!(outcome.decision, HookDecision::Approve);
+        assert_eq!(
+            outcome.updated_input,
+            Some(serde_json::json!({"command": "ls -la"}))
+        );
+        assert_eq!(outcome.additional_context, vec!["listing is safe"]);
+    }
+
+    #[tokio::test]
+    async fn test_matching_hooks_run_in_parallel() {
+        let hooks = vec![test_hook("sleep 0.5"), test_hook("sleep 0.5")];
+        let start = std::time::Instant::now();
+        let (_, results) =
+            evaluate_hooks_in(&hooks, &HookEvent::BeforeTool, &test_input(), None).await;
+        assert!(start.elapsed() < Duration::from_millis(900));
+        assert_eq!(
+            results,
+            vec![(0, HookRunResult::Approved), (1, HookRunResult::Approved)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_and_args_matchers() {
+        let matched = |tool: Option<&str>, args: Option<&str>| HookConfig {
+            tool: tool.map(String::from),
+            args: args.map(String::from),
+            ..test_hook("exit 2")
+        };
+        let input = test_input(); // Bash: ls
+        let run = |hook: HookConfig| {
+            let input = input.clone();
+            async move { run_hooks(&[hook], &HookEvent::BeforeTool, &input).await }
+        };
+        assert!(run(matched(Some("Bash"), None)).await.is_err());
+        assert!(
+            run(matched(Some("Bash"), Some("command:ls*")))
+                .await
+                .is_err()
+        );
+        assert!(run(matched(None, Some("command:l?"))).await.is_err());
+        assert!(run(matched(Some("Read"), None)).await.is_ok());
+        assert!(
+            run(matched(Some("Bash"), Some("command:git *")))
+                .await
+                .is_ok()
+        );
+
+        // A hook with a matcher never runs for events without a tool
+        let mut no_tool = test_input();
+        no_tool.tool_name = None;
+        no_tool.tool_input = None;
+        let hooks = [matched(Some("*"), None)];
+        assert!(
+            run_hooks(&hooks, &HookEvent::BeforeTool, &no_tool)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ask_decision() {
+        let hooks = vec![
+            test_hook(r#"echo '{"decision":"ask","reason":"touches prod"}'"#),
+            test_hook("exit 0"),
+        ];
+        let outcome = evaluate_hooks(&hooks, &HookEvent::BeforeTool, &test_input()).await;
+        assert_eq!(outcome.decision, HookDecision::Ask);
+        assert_eq!(outcome.reason.as_deref(), Some("touches prod"));
+        // run_hooks only reports denials
+        assert!(
+            run_hooks(&hooks, &HookEvent::BeforeTool, &test_input())
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plain_stdout_approves_and_bad_json_is_error() {
+        let hooks = vec![test_hook("echo checked")];
+        let outcome = evaluate_hooks(&hooks, &HookEvent::BeforeTool, &test_input()).await;
+        assert_eq!(outcome, HookOutcome::default());
+
+        // Malformed JSON is a hook error: warn and continue
+        let hooks = vec![test_hook(r#"echo '{"decision":'"#)];
+        let outcome = evaluate_hooks(&hooks, &HookEvent::BeforeTool, &test_input()).await;
+        assert_eq!(outcome.decision, HookDecision::Approve);
     }
 }
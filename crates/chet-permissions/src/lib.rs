@@ -3,14 +3,19 @@
 //! Permission levels: permit / block / prompt
 //! Hook events: before_tool / after_tool / before_input / on_exit / on_session_start / on_session_end
 
+pub mod audit;
 pub mod engine;
 pub mod hooks;
 pub mod matcher;
 pub mod prompt;
+pub mod shell;
 pub mod types;
+pub mod workspace;
 
+pub use audit::{AuditEntry, AuditKind, AuditLog, AuditQuery};
 pub use engine::PermissionEngine;
-pub use hooks::run_hooks;
+pub use hooks::{evaluate_hooks, run_hooks};
 pub use matcher::{EvaluateResult, RuleMatcher};
 pub use prompt::PromptHandler;
 pub use types::*;
+pub use workspace::{OutsideWorkspace, Workspace};
@@ -1,6 +1,8 @@
 //! Rule matcher — evaluates permission rules against tool calls.
 
+use crate::shell;
 use crate::types::{PermissionLevel, PermissionRule};
+use crate::workspace::{OutsideWorkspace, PATH_FIELDS, Workspace};
 
 /// Result of evaluating rules: the winning level + a human-readable description.
 #[derive(Debug, Clone)]
@@ -19,14 +21,45 @@ impl RuleMatcher {
     /// Argument matching: `field_name:glob_pattern` format
     /// (e.g., `command:git *`, `file_path:/etc/*`).
     pub fn matches(rule: &PermissionRule, tool_name: &str, tool_input: &serde_json::Value) -> bool {
+        Self::matches_in(rule, tool_name, tool_input, None)
+    }
+
+    /// Like [`RuleMatcher::matches`], resolving `path:` patterns against a
+    /// workspace: `path:src/**` matches any file path field (`file_path`,
+    /// `path`, `notebook_path`) that resolves under `<root>/src/`. Paths
+    /// outside the project root are matched in their absolute form.
+    pub fn matches_in(
+        rule: &PermissionRule,
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+        workspace: Option<&Workspace>,
+    ) -> bool {
+        Self::matches_call(
+            &rule.tool,
+            rule.args.as_deref(),
+            tool_name,
+            tool_input,
+            workspace,
+        )
+    }
+
+    /// Match a tool pattern and optional args pattern, as in a rule, against
+    /// a tool call.
+    pub fn matches_call(
+        tool_pattern: &str,
+        args_pattern: Option<&str>,
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+        workspace: Option<&Workspace>,
+    ) -> bool {
         // Match tool name
-        if !Self::matches_tool_name(&rule.tool, tool_name) {
+        if !Self::matches_tool_name(tool_pattern, tool_name) {
             return false;
         }
 
         // Match args pattern (if specified)
-        if let Some(ref args_pattern) = rule.args {
-            if !Self::matches_args(args_pattern, tool_input) {
+        if let Some(args_pattern) = args_pattern {
+            if !Self::matches_args(args_pattern, tool_input, workspace) {
                 return false;
             }
         }
@@ -44,10 +77,112 @@ impl RuleMatcher {
         rules: &[PermissionRule],
         tool_name: &str,
         tool_input: &serde_json::Value,
+    ) -> Option<EvaluateResult> {
+        Self::evaluate_in(rules, tool_name, tool_input, None)
+    }
+
+    /// Like [`RuleMatcher::evaluate`], with `path:` patterns resolved against
+    /// a workspace (see [`RuleMatcher::matches_in`]).
+    ///
+    /// A `command` input is parsed as shell and every command it runs, plus
+    /// every redirection target, is evaluated on its own; the strictest
+    /// outcome wins (block > prompt > permit). A command no rule matches
+    /// yields `None`, so a compound command is only permitted when each part
+    /// is. Redirection targets are checked against path rules (`path:`,
+    /// `file_path:`) and, with a workspace, the outside-workspace policy.
+    pub fn evaluate_in(
+        rules: &[PermissionRule],
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+        workspace: Option<&Workspace>,
+    ) -> Option<EvaluateResult> {
+        match tool_input.get("command") {
+            Some(serde_json::Value::String(command)) => {
+                Self::evaluate_shell(rules, tool_name, tool_input, command, workspace)
+            }
+            _ => Self::evaluate_unit(rules, tool_name, tool_input, workspace),
+        }
+    }
+
+    fn evaluate_shell(
+        rules: &[PermissionRule],
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+        command: &str,
+        workspace: Option<&Workspace>,
+    ) -> Option<EvaluateResult> {
+        let parsed = shell::parse(command);
+        if parsed.commands.is_empty() && parsed.redirects.is_empty() {
+            return Self::evaluate_unit(rules, tool_name, tool_input, workspace);
+        }
+
+        let mut results = Vec::new();
+        let mut unmatched = false;
+        for sub in &parsed.commands {
+            let mut unit = tool_input.clone();
+            unit["command"] = serde_json::Value::String(sub.clone());
+            match Self::evaluate_unit(rules, tool_name, &unit, workspace) {
+                Some(result) => results.push(result),
+                None => unmatched = true,
+            }
+        }
+
+        // Tool-wide rules already applied to each command; redirections only
+        // answer to path rules, the workspace, or a tool-wide permit.
+        let path_rules: Vec<PermissionRule> = rules
+            .iter()
+            .filter(|r| r.args.as_deref().is_some_and(is_path_pattern))
+            .cloned()
+            .collect();
+        let tool_wide_permit = Self::evaluate_unit(rules, tool_name, &serde_json::json!({}), None)
+            .is_some_and(|r| r.level == PermissionLevel::Permit);
+        for target in &parsed.redirects {
+            let path = workspace.map(|ws| ws.resolve(target));
+            let file_path = path
+                .as_ref()
+                .map_or_else(|| target.clone(), |p| p.to_string_lossy().into_owned());
+            let unit = serde_json::json!({ "file_path": file_path });
+            if let Some(result) = Self::evaluate_unit(&path_rules, tool_name, &unit, workspace) {
+                results.push(result);
+                continue;
+            }
+            if let (Some(ws), Some(path)) = (workspace, &path) {
+                if !tool_wide_permit && !ws.contains(path) && !is_harmless_device(path) {
+                    results.push(EvaluateResult {
+                        level: match ws.outside_access() {
+                            OutsideWorkspace::Block => PermissionLevel::Block,
+                            OutsideWorkspace::Prompt => PermissionLevel::Prompt,
+                        },
+                        description: format!(
+                            "redirection to {} outside the workspace",
+                            path.display()
+                        ),
+                    });
+                }
+            }
+        }
+
+        for level in [PermissionLevel::Block, PermissionLevel::Prompt] {
+            if let Some(pos) = results.iter().position(|r| r.level == level) {
+                return Some(results.swap_remove(pos));
+            }
+        }
+        if unmatched {
+            return None;
+        }
+        results.into_iter().next()
+    }
+
+    /// Evaluate rules against a single tool input (or a single shell command).
+    fn evaluate_unit(
+        rules: &[PermissionRule],
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+        workspace: Option<&Workspace>,
     ) -> Option<EvaluateResult> {
         let matching: Vec<&PermissionRule> = rules
             .iter()
-            .filter(|r| Self::matches(r, tool_name, tool_input))
+            .filter(|r| Self::matches_in(r, tool_name, tool_input, workspace))
             .collect();
 
         if matching.is_empty() {
@@ -110,21 +245,38 @@ impl RuleMatcher {
         }
     }
 
-    fn matches_args(args_pattern: &str, tool_input: &serde_json::Value) -> bool {
+    fn matches_args(
+        args_pattern: &str,
+        tool_input: &serde_json::Value,
+        workspace: Option<&Workspace>,
+    ) -> bool {
         // Format: "field_name:glob_pattern"
         let Some((field_name, glob_pattern)) = args_pattern.split_once(':') else {
             return false;
         };
 
+        // `path:` matches every file path field, relative to the workspace
+        if field_name == "path" {
+            if let Some(ws) = workspace {
+                return PATH_FIELDS
+                    .iter()
+                    .filter_map(|field| tool_input.get(*field).and_then(|v| v.as_str()))
+                    .any(|value| {
+                        Self::glob_matches(glob_pattern, &ws.display_path(&ws.resolve(value)))
+                    });
+            }
+        }
+
         let field_value = match tool_input.get(field_name) {
             Some(serde_json::Value::String(s)) => s.as_str(),
             _ => return false,
         };
 
-        // For Bash command field, split compound commands and match each subcommand.
-        // A rule matches if ANY subcommand matches the glob pattern.
+        // For Bash command field, match each command the shell would run.
+        // A rule matches if ANY of them matches the glob pattern.
         if field_name == "command" {
-            return split_subcommands(field_value)
+            return shell::parse(field_value)
+                .commands
                 .iter()
                 .any(|sub| Self::glob_matches(glob_pattern, sub));
         }
@@ -143,66 +295,20 @@ impl RuleMatcher {
     }
 }
 
-/// Split a shell command into subcommands on `&&`, `||`, `;`, and `|`.
-/// Each subcommand is trimmed. Preserves quoted strings (single/double).
-#[allow(clippy::collapsible_match)]
-fn split_subcommands(command: &str) -> Vec<&str> {
-    let mut results = Vec::new();
-    let mut start = 0;
-    let mut chars = command.char_indices().peekable();
-    let mut in_single_quote = false;
-    let mut in_double_quote = false;
-
-    while let Some((i, c)) = chars.next() {
-        match c {
-            '\'' if !in_double_quote => in_single_quote = !in_single_quote,
-            '"' if !in_single_quote => in_double_quote = !in_double_quote,
-            '&' if !in_single_quote && !in_double_quote => {
-                if chars.peek().map(|(_, c)| *c) == Some('&') {
-                    let sub = command[start..i].trim();
-                    if !sub.is_empty() {
-                        results.push(sub);
-                    }
-                    chars.next(); // consume second '&'
-                    start = chars.peek().map(|(i, _)| *i).unwrap_or(command.len());
-                }
-            }
-            '|' if !in_single_quote && !in_double_quote => {
-                if chars.peek().map(|(_, c)| *c) == Some('|') {
-                    // ||
-                    let sub = command[start..i].trim();
-                    if !sub.is_empty() {
-                        results.push(sub);
-                    }
-                    chars.next(); // consume second '|'
-                    start = chars.peek().map(|(i, _)| *i).unwrap_or(command.len());
-                } else {
-                    // pipe |
-                    let sub = command[start..i].trim();
-                    if !sub.is_empty() {
-                        results.push(sub);
-                    }
-                    start = chars.peek().map(|(i, _)| *i).unwrap_or(command.len());
-                }
-            }
-            ';' if !in_single_quote && !in_double_quote => {
-                let sub = command[start..i].trim();
-                if !sub.is_empty() {
-                    results.push(sub);
-                }
-                start = chars.peek().map(|(i, _)| *i).unwrap_or(command.len());
-            }
-            _ => {}
-        }
-    }
-
-    // Remaining tail
-    let tail = command[start..].trim();
-    if !tail.is_empty() {
-        results.push(tail);
-    }
+/// Whether an args pattern matches on file paths.
+fn is_path_pattern(args_pattern: &str) -> bool {
+    args_pattern
+        .split_once(':')
+        .is_some_and(|(field, _)| PATH_FIELDS.contains(&field))
+}
 
-    results
+/// Redirection targets that never need a workspace check.
+fn is_harmless_device(path: &std::path::Path) -> bool {
+    ["/dev/null", "/dev/stdout", "/dev/stderr", "/dev/tty"]
+        .iter()
+        .any(|dev| path == std::path::Path::new(dev))
+        || path.starts_with("/dev/fd")
+        || path.starts_with("/proc/self/fd")
 }
 
 #[cfg(test)]
@@ -393,32 +499,88 @@ mod tests {
     }
 
     #[test]
-    fn test_split_subcommands_simple() {
-        assert_eq!(split_subcommands("git status"), vec!["git status"]);
+    fn test_substitution_matches_block() {
+        let r = rule("Bash", Some("command:rm *"), PermissionLevel::Block);
+        for command in [
+            "echo $(rm -rf x)",
+            "echo `rm -rf x`",
+            "(cd /tmp; rm -rf x)",
+            "bash -c 'rm -rf x'",
+            "sudo rm -rf x",
+            "env FOO=1 rm -rf x",
+            "find . | xargs rm -f",
+            "timeout 5 nice -n 10 rm -rf x",
+        ] {
+            assert!(
+                RuleMatcher::matches(&r, "Bash", &json!({ "command": command })),
+                "{command}"
+            );
+        }
     }
 
     #[test]
-    fn test_split_subcommands_and() {
-        assert_eq!(
-            split_subcommands("cd /tmp && git fetch && git push"),
-            vec!["cd /tmp", "git fetch", "git push"]
+    fn test_compound_permit_requires_every_command() {
+        let rules = vec![rule("Bash", Some("command:git *"), PermissionLevel::Permit)];
+        let result =
+            RuleMatcher::evaluate(&rules, "Bash", &json!({"command": "git fetch && git push"}));
+        assert_eq!(result.unwrap().level, PermissionLevel::Permit);
+        // `rm` has no rule, so the compound command falls to the default
+        let result = RuleMatcher::evaluate(
+            &rules,
+            "Bash",
+            &json!({"command": "git status && rm -rf x"}),
         );
+        assert!(result.is_none());
     }
 
     #[test]
-    fn test_split_subcommands_mixed() {
-        assert_eq!(
-            split_subcommands("echo a; echo b | cat && echo c || echo d"),
-            vec!["echo a", "echo b", "cat", "echo c", "echo d"]
-        );
+    fn test_strictest_outcome_wins() {
+        let rules = vec![
+            rule("Bash", Some("command:git *"), PermissionLevel::Permit),
+            rule("Bash", Some("command:curl *"), PermissionLevel::Prompt),
+            rule("Bash", Some("command:rm *"), PermissionLevel::Block),
+        ];
+        let eval = |command: &str| {
+            RuleMatcher::evaluate(&rules, "Bash", &json!({ "command": command }))
+                .unwrap()
+                .level
+        };
+        assert_eq!(eval("git log | curl -d @- x"), PermissionLevel::Prompt);
+        assert_eq!(eval("git log $(rm x) | curl x"), PermissionLevel::Block);
+    }
+
+    #[test]
+    fn test_redirect_target_matches_path_rule() {
+        let rules = vec![
+            rule("Bash", Some("command:echo *"), PermissionLevel::Permit),
+            rule("Bash", Some("file_path:/etc/*"), PermissionLevel::Block),
+        ];
+        let result =
+            RuleMatcher::evaluate(&rules, "Bash", &json!({"command": "echo x > /etc/hosts"}));
+        assert_eq!(result.unwrap().level, PermissionLevel::Block);
+        let result = RuleMatcher::evaluate(&rules, "Bash", &json!({"command": "echo x > out"}));
+        assert_eq!(result.unwrap().level, PermissionLevel::Permit);
     }
 
     #[test]
-    fn test_split_subcommands_preserves_quotes() {
+    fn test_redirect_outside_workspace_prompts() {
+        let dir = tempfile::tempdir().unwrap();
+        let ws = Workspace::new(dir.path(), &[]);
+        let rules = vec![rule(
+            "Bash",
+            Some("command:echo *"),
+            PermissionLevel::Permit,
+        )];
+        let eval = |command: &str| {
+            RuleMatcher::evaluate_in(&rules, "Bash", &json!({ "command": command }), Some(&ws))
+                .map(|r| r.level)
+        };
+        assert_eq!(eval("echo x > out.txt"), Some(PermissionLevel::Permit));
         assert_eq!(
-            split_subcommands("echo 'a && b' && echo c"),
-            vec!["echo 'a && b'", "echo c"]
+            eval("echo x > /dev/null 2>&1"),
+            Some(PermissionLevel::Permit)
         );
+        assert_eq!(eval("echo x > ../escape"), Some(PermissionLevel::Prompt));
     }
 
     #[test]
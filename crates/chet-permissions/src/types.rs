@@ -22,7 +22,7 @@ impl PermissionLevel {
 }
 
 /// A single permission rule matching a tool + optional argument pattern.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PermissionRule {
     /// Tool name or glob pattern (e.g., "Bash", "*").
     pub tool: String,
@@ -45,13 +45,123 @@ pub enum PermissionDecision {
     Prompt { tool: String, description: String },
 }
 
+/// How much the engine asks before running tools. Switchable at runtime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionMode {
+    /// Read-only tools run, mutating tools prompt.
+    #[default]
+    Default,
+    /// Like default, but Write/Edit inside the workspace are permitted.
+    AcceptEdits,
+    /// Read-only: every mutating tool is blocked.
+    Plan,
+    /// Everything is permitted and hooks are skipped (`--ludicrous`).
+    Bypass,
+}
+
+impl PermissionMode {
+    /// All modes, in the order [`PermissionMode::next`] cycles through them.
+    pub const ALL: [PermissionMode; 4] = [
+        PermissionMode::Default,
+        PermissionMode::AcceptEdits,
+        PermissionMode::Plan,
+        PermissionMode::Bypass,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PermissionMode::Default => "default",
+            PermissionMode::AcceptEdits => "acceptEdits",
+            PermissionMode::Plan => "plan",
+            PermissionMode::Bypass => "bypass",
+        }
+    }
+
+    /// The mode after this one, skipping `Bypass` unless it is allowed.
+    pub fn next(self, allow_bypass: bool) -> PermissionMode {
+        match self {
+            PermissionMode::Default => PermissionMode::AcceptEdits,
+            PermissionMode::AcceptEdits => PermissionMode::Plan,
+            PermissionMode::Plan if allow_bypass => PermissionMode::Bypass,
+            PermissionMode::Plan | PermissionMode::Bypass => PermissionMode::Default,
+        }
+    }
+}
+
+impl std::fmt::Display for PermissionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for PermissionMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['-', '_'], "").as_str() {
+            "default" => Ok(PermissionMode::Default),
+            "acceptedits" | "edits" => Ok(PermissionMode::AcceptEdits),
+            "plan" => Ok(PermissionMode::Plan),
+            "bypass" | "bypasspermissions" => Ok(PermissionMode::Bypass),
+            _ => Err(format!(
+                "unknown permission mode: {s} (use default, acceptEdits, plan, or bypass)"
+            )),
+        }
+    }
+}
+
+/// The evaluation layer that produced a [`PermissionDecision`], in the order
+/// layers are consulted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecisionLayer {
+    /// Managed (administrator) policy; nothing overrides it.
+    Managed,
+    /// The permission mode: bypass permits, plan blocks mutating tools,
+    /// acceptEdits permits workspace edits.
+    Mode(PermissionMode),
+    /// A configured rule (blocks are checked before session grants).
+    Config,
+    /// An "always allow" granted earlier in this session.
+    SessionGrant,
+    /// The outside-workspace policy.
+    Workspace,
+    /// Built-in default: read-only permits, mutating prompts.
+    Default,
+}
+
+impl std::fmt::Display for DecisionLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Managed => f.write_str("managed policy"),
+            Self::Mode(mode) => write!(f, "{mode} mode"),
+            Self::Config => f.write_str("config"),
+            Self::SessionGrant => f.write_str("session grant"),
+            Self::Workspace => f.write_str("workspace boundary"),
+            Self::Default => f.write_str("default policy"),
+        }
+    }
+}
+
+/// A decision together with the layer that made it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionCheck {
+    pub decision: PermissionDecision,
+    pub layer: DecisionLayer,
+    /// The rule that decided, if one did.
+    pub rule: Option<String>,
+}
+
 /// User's response to a permission prompt.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PromptResponse {
     /// Allow this specific invocation only.
     AllowOnce,
-    /// Allow this tool for the rest of the session.
+    /// Allow calls like this one (same command prefix or directory) for the
+    /// rest of the session.
     AlwaysAllow,
+    /// The handler saved these permit rules to project config; they also
+    /// apply for the rest of the session.
+    AlwaysAllowSaved(Vec<PermissionRule>),
     /// Deny this tool call.
     Deny,
 }
@@ -72,6 +182,33 @@ pub enum HookEvent {
     InstructionsLoaded,
     StopFailure,
     ConfigChange,
+    /// The agent is about to finish its turn. A deny sends it back to work
+    /// with the reason as a new user message.
+    Stop,
+    /// Like `Stop`, for a Subagent tool's child agent.
+    SubagentStop,
+}
+
+impl HookEvent {
+    /// The event name as written in config.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::BeforeTool => "before_tool",
+            HookEvent::AfterTool => "after_tool",
+            HookEvent::BeforeInput => "before_input",
+            HookEvent::OnExit => "on_exit",
+            HookEvent::OnSessionStart => "on_session_start",
+            HookEvent::OnSessionEnd => "on_session_end",
+            HookEvent::WorktreeCreate => "worktree_create",
+            HookEvent::WorktreeRemove => "worktree_remove",
+            HookEvent::PostCompact => "post_compact",
+            HookEvent::InstructionsLoaded => "instructions_loaded",
+            HookEvent::StopFailure => "stop_failure",
+            HookEvent::ConfigChange => "config_change",
+            HookEvent::Stop => "stop",
+            HookEvent::SubagentStop => "subagent_stop",
+        }
+    }
 }
 
 /// Configuration for a single hook script.
@@ -84,12 +221,94 @@ pub struct HookConfig {
     /// Timeout in milliseconds (default: 10000).
     #[serde(default = "default_hook_timeout")]
     pub timeout_ms: u64,
+    /// Only run for tools matching this pattern (same syntax as
+    /// `PermissionRule::tool`). Hooks with a matcher never run for events
+    /// without a tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// Only run when the tool input matches this pattern (same syntax as
+    /// `PermissionRule::args`, e.g. `command:git *`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<String>,
 }
 
 fn default_hook_timeout() -> u64 {
     10_000
 }
 
+/// A hook's verdict in the JSON protocol.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookDecision {
+    #[default]
+    Approve,
+    Deny,
+    /// Ask the user, as if a rule said `prompt`.
+    Ask,
+}
+
+/// JSON object a hook may print on stdout (or return as a 2xx HTTP body).
+/// Every field is optional; a hook that prints nothing just approves.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HookResponse {
+    #[serde(default)]
+    pub decision: Option<HookDecision>,
+    /// Explanation shown to the model (and the user, for `ask`).
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Replacement tool input (before_tool only).
+    #[serde(default)]
+    pub updated_input: Option<serde_json::Value>,
+    /// Text added to the conversation alongside the tool result.
+    #[serde(default)]
+    pub additional_context: Option<String>,
+}
+
+/// Combined result of running every hook for an event.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HookOutcome {
+    /// Deny if any hook denied, else Ask if any asked, else Approve.
+    pub decision: HookDecision,
+    /// Reason from the deciding hook.
+    pub reason: Option<String>,
+    /// Tool input after every hook's rewrite, if any hook rewrote it.
+    pub updated_input: Option<serde_json::Value>,
+    /// Context from each hook that provided some, in order.
+    pub additional_context: Vec<String>,
+}
+
+/// How a hook's most recent run ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookRunResult {
+    Approved,
+    Asked,
+    Denied(String),
+    /// The hook failed or timed out; the operation went ahead.
+    Error(String),
+}
+
+impl std::fmt::Display for HookRunResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookRunResult::Approved => write!(f, "approved"),
+            HookRunResult::Asked => write!(f, "asked"),
+            HookRunResult::Denied(reason) => write!(f, "denied: {reason}"),
+            HookRunResult::Error(msg) => write!(f, "error: {msg}"),
+        }
+    }
+}
+
+/// A configured hook with its run statistics for this session.
+#[derive(Debug, Clone)]
+pub struct HookStatus {
+    pub hook: HookConfig,
+    /// Set by managed policy; cannot be removed by user config.
+    pub managed: bool,
+    /// How many times the hook matched an event.
+    pub matched: u64,
+    pub last_result: Option<HookRunResult>,
+}
+
 /// JSON payload sent to hooks on stdin.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HookInput {
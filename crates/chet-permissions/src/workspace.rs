@@ -0,0 +1,235 @@
+//! Workspace trust boundary — the directories file tools may use freely.
+//!
+//! The workspace is the project root plus any configured
+//! `additional_directories`. File paths in tool inputs are resolved to
+//! canonical absolute paths (symlinks and `..` included) before rules are
+//! evaluated, so `path:` rules and the outside-workspace default can't be
+//! sidestepped with `../../etc/shadow` or a symlink out of the repo.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// Tool input fields that hold file system paths.
+pub const PATH_FIELDS: &[&str] = &["file_path", "path", "notebook_path"];
+
+/// What happens when a tool touches a path outside the workspace and no rule
+/// decides otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutsideWorkspace {
+    /// Ask the user, even for read-only tools.
+    #[default]
+    Prompt,
+    /// Refuse without asking.
+    Block,
+}
+
+/// The project root plus additional trusted directories.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    /// Canonical roots; the first is the project root.
+    roots: Vec<PathBuf>,
+    /// Canonical directories outside the roots that read-only tools may use.
+    readable: Vec<PathBuf>,
+    /// Directory that relative tool paths are resolved against.
+    cwd: PathBuf,
+    outside: OutsideWorkspace,
+}
+
+impl Workspace {
+    /// Create a workspace rooted at `root`. Relative `additional_directories`
+    /// are taken relative to the root. Relative tool paths resolve against the
+    /// root until [`Workspace::with_cwd`] says otherwise.
+    pub fn new(root: &Path, additional_directories: &[PathBuf]) -> Self {
+        let root = canonicalize(root, Path::new("/"));
+        let mut roots = vec![root.clone()];
+        for dir in additional_directories {
+            let dir = canonicalize(dir, &root);
+            if !roots.contains(&dir) {
+                roots.push(dir);
+            }
+        }
+        Self {
+            roots,
+            readable: Vec::new(),
+            cwd: root,
+            outside: OutsideWorkspace::default(),
+        }
+    }
+
+    /// Resolve relative tool paths against `cwd` (e.g. a subdirectory of the root).
+    pub fn with_cwd(mut self, cwd: &Path) -> Self {
+        self.cwd = canonicalize(cwd, &self.roots[0]);
+        self
+    }
+
+    /// Let read-only tools use `dir` without asking, e.g. chet's own files
+    /// that tool results point the model to.
+    pub fn with_readable(mut self, dir: &Path) -> Self {
+        self.readable.push(canonicalize(dir, &self.roots[0]));
+        self
+    }
+
+    /// Set the default for access outside the workspace.
+    pub fn with_outside_access(mut self, outside: OutsideWorkspace) -> Self {
+        self.outside = outside;
+        self
+    }
+
+    /// The project root.
+    pub fn root(&self) -> &Path {
+        &self.roots[0]
+    }
+
+    /// All workspace directories, project root first.
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    pub fn outside_access(&self) -> OutsideWorkspace {
+        self.outside
+    }
+
+    /// Canonical absolute form of a path as a tool would see it.
+    pub fn resolve(&self, path: &str) -> PathBuf {
+        canonicalize(Path::new(path), &self.cwd)
+    }
+
+    /// Whether a resolved path lies inside the workspace.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| path.starts_with(root))
+    }
+
+    /// Whether read-only tools may use a resolved path without asking.
+    pub fn is_readable(&self, path: &Path) -> bool {
+        self.contains(path) || self.readable.iter().any(|dir| path.starts_with(dir))
+    }
+
+    /// How `path:` rules see a resolved path: relative to the project root
+    /// when inside it, absolute otherwise.
+    pub fn display_path(&self, path: &Path) -> String {
+        match path.strip_prefix(self.root()) {
+            Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+            Ok(rel) => rel.to_string_lossy().into_owned(),
+            Err(_) => path.to_string_lossy().into_owned(),
+        }
+    }
+}
+
+/// Resolve `path` against `base` to a canonical absolute path.
+///
+/// Unlike [`std::fs::canonicalize`], the path need not exist: each existing
+/// prefix is resolved through symlinks, and the missing remainder is
+/// appended with `.` and `..` applied lexically (a missing directory can't be
+/// a symlink, so that is exact).
+pub fn canonicalize(path: &Path, base: &Path) -> PathBuf {
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => {
+                resolved.push(component.as_os_str());
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => {
+                resolved.push(name);
+                if let Ok(real) = std::fs::canonicalize(&resolved) {
+                    resolved = real;
+                }
+            }
+        }
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_dot_dot_and_relative_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        let ws = Workspace::new(&root, &[]);
+
+        assert_eq!(ws.resolve("src/main.rs"), root.join("src/main.rs"));
+        assert_eq!(ws.resolve("./src/../Cargo.toml"), root.join("Cargo.toml"));
+        // Missing directories are resolved lexically
+        assert_eq!(ws.resolve("new/dir/../file.rs"), root.join("new/file.rs"));
+        assert!(!ws.contains(&ws.resolve("../outside.txt")));
+        assert!(!ws.contains(&ws.resolve("src/../../outside.txt")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_symlinks_out_of_the_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        let root = base.join("repo");
+        let secret = base.join("secret");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(&secret).unwrap();
+        std::os::unix::fs::symlink(&secret, root.join("link")).unwrap();
+
+        let ws = Workspace::new(&root, &[]);
+        let resolved = ws.resolve("link/key.pem");
+        assert_eq!(resolved, secret.join("key.pem"));
+        assert!(!ws.contains(&resolved));
+    }
+
+    #[test]
+    fn additional_directories_are_inside() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        let root = base.join("repo");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(base.join("shared")).unwrap();
+
+        let ws = Workspace::new(&root, &[PathBuf::from("../shared")]);
+        assert_eq!(ws.roots().len(), 2);
+        assert!(ws.contains(&ws.resolve("../shared/lib.rs")));
+        assert!(!ws.contains(&ws.resolve("../other/lib.rs")));
+    }
+
+    #[test]
+    fn readable_directories_are_not_inside() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        let root = base.join("repo");
+        std::fs::create_dir_all(&root).unwrap();
+
+        let ws = Workspace::new(&root, &[]).with_readable(&base.join("sessions"));
+        let output = ws.resolve(&base.join("sessions/a/out.txt").to_string_lossy());
+        assert!(!ws.contains(&output));
+        assert!(ws.is_readable(&output));
+        assert!(!ws.is_readable(&ws.resolve("../other.txt")));
+    }
+
+    #[test]
+    fn display_path_is_root_relative() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        let ws = Workspace::new(&root, &[]);
+        assert_eq!(ws.display_path(&root.join("src/lib.rs")), "src/lib.rs");
+        assert_eq!(ws.display_path(&root), ".");
+        assert_eq!(ws.display_path(Path::new("/etc/passwd")), "/etc/passwd");
+    }
+
+    #[test]
+    fn with_cwd_changes_relative_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(root.join("sub")).unwrap();
+        let ws = Workspace::new(&root, &[]).with_cwd(&root.join("sub"));
+        assert_eq!(ws.resolve("a.txt"), root.join("sub/a.txt"));
+    }
+}
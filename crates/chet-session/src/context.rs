@@ -12,6 +12,13 @@ pub struct ContextInfo {
     pub system_tokens: u64,
     pub last_turn_input_tokens: u64,
     pub last_turn_output_tokens: u64,
+    /// Tool definitions and request framing. Only known once calibrated.
+    pub overhead_tokens: u64,
+    /// Whether the estimate is anchored to a token count from the API.
+    pub calibrated: bool,
+    /// Tokens micro-compaction has freed in this session. Not known to the
+    /// tracker; callers fill it in from the session.
+    pub reclaimed_tokens: u64,
 }
 
 impl ContextInfo {
@@ -24,9 +31,34 @@ impl ContextInfo {
     }
 }
 
+/// Weight of a new sample in the learned ratio (exponential moving average).
+const RATIO_WEIGHT: f64 = 0.3;
+
+/// Heuristic tokens that must be added between two counts before their
+/// difference is trusted as a ratio sample.
+const MIN_RATIO_SAMPLE: u64 = 500;
+
+/// Bounds on the learned ratio, so one odd sample can't wreck the estimate.
+const RATIO_RANGE: (f64, f64) = (0.5, 2.0);
+
+/// Correction learned from the API's token counts. The real count is modelled
+/// as `overhead + ratio * heuristic`: `ratio` corrects the chars-per-token
+/// guess, `overhead` covers what the heuristic doesn't see (tool definitions,
+/// request framing). The model passes exactly through the latest count, so
+/// only messages added since then are estimated.
+#[derive(Debug, Clone, Copy)]
+struct Calibration {
+    ratio: f64,
+    overhead: f64,
+    /// Heuristic and real tokens at the latest count.
+    anchor_heuristic: u64,
+    anchor_tokens: u64,
+}
+
 /// Tracks context window usage for a conversation.
 pub struct ContextTracker {
     context_window: u64,
+    calibration: Option<Calibration>,
 }
 
 impl ContextTracker {
@@ -34,9 +66,46 @@ impl ContextTracker {
     pub fn new(model: &str) -> Self {
         Self {
             context_window: model_context_window(model),
+            calibration: None,
         }
     }
 
+    /// Whether the tracker has learned from a real token count yet.
+    pub fn is_calibrated(&self) -> bool {
+        self.calibration.is_some()
+    }
+
+    /// Learn from a real token count: `actual_tokens` is what the API read for
+    /// a request carrying `messages` and `system_prompt` (input tokens plus
+    /// cache reads and writes, or a `count_tokens` result).
+    pub fn calibrate(
+        &mut self,
+        messages: &[Message],
+        system_prompt: Option<&str>,
+        actual_tokens: u64,
+    ) {
+        let heuristic = heuristic_tokens(messages, system_prompt);
+        let ratio = match self.calibration {
+            Some(prev)
+                if heuristic >= prev.anchor_heuristic + MIN_RATIO_SAMPLE
+                    && actual_tokens > prev.anchor_tokens =>
+            {
+                let sample = (actual_tokens - prev.anchor_tokens) as f64
+                    / (heuristic - prev.anchor_heuristic) as f64;
+                let sample = sample.clamp(RATIO_RANGE.0, RATIO_RANGE.1);
+                prev.ratio * (1.0 - RATIO_WEIGHT) + sample * RATIO_WEIGHT
+            }
+            Some(prev) => prev.ratio,
+            None => 1.0,
+        };
+        self.calibration = Some(Calibration {
+            ratio,
+            overhead: actual_tokens as f64 - ratio * heuristic as f64,
+            anchor_heuristic: heuristic,
+            anchor_tokens: actual_tokens,
+        });
+    }
+
     /// Estimate token usage for the current conversation state.
     pub fn estimate(&self, messages: &[Message], system_prompt: Option<&str>) -> ContextInfo {
         let system_tokens = system_prompt.map(estimate_text_tokens).unwrap_or(0);
@@ -66,16 +135,43 @@ impl ContextTracker {
             }
         }
 
-        let estimated_tokens = system_tokens + user_tokens + assistant_tokens;
+        let heuristic = system_tokens + user_tokens + assistant_tokens;
+        let Some(cal) = self.calibration else {
+            return ContextInfo {
+                estimated_tokens: heuristic,
+                context_window: self.context_window,
+                user_tokens,
+                assistant_tokens,
+                system_tokens,
+                last_turn_input_tokens,
+                last_turn_output_tokens,
+                overhead_tokens: 0,
+                calibrated: false,
+                reclaimed_tokens: 0,
+            };
+        };
 
+        let scale = |tokens: u64| (tokens as f64 * cal.ratio).round() as u64;
+        let estimated_tokens = (cal.overhead + cal.ratio * heuristic as f64)
+            .round()
+            .max(0.0) as u64;
+        let (system_tokens, user_tokens, assistant_tokens) = (
+            scale(system_tokens),
+            scale(user_tokens),
+            scale(assistant_tokens),
+        );
         ContextInfo {
             estimated_tokens,
             context_window: self.context_window,
             user_tokens,
             assistant_tokens,
             system_tokens,
-            last_turn_input_tokens,
-            last_turn_output_tokens,
+            last_turn_input_tokens: scale(last_turn_input_tokens),
+            last_turn_output_tokens: scale(last_turn_output_tokens),
+            overhead_tokens: estimated_tokens
+                .saturating_sub(system_tokens + user_tokens + assistant_tokens),
+            calibrated: true,
+            reclaimed_tokens: 0,
         }
     }
 
@@ -95,12 +191,26 @@ impl ContextTracker {
         let est_k = info.estimated_tokens as f64 / 1000.0;
         let win_k = info.context_window as f64 / 1000.0;
         let pct = info.usage_percent();
+        let source = if info.calibrated {
+            "calibrated from API usage"
+        } else {
+            "estimated"
+        };
         lines.push(format!(
-            "Context window: {est_k:.1}k / {win_k:.0}k tokens ({pct:.1}%)",
+            "Context window: {est_k:.1}k / {win_k:.0}k tokens ({pct:.1}%, {source})",
         ));
         lines.push(format!("  System:    ~{} tokens", info.system_tokens));
         lines.push(format!("  User:      ~{} tokens", info.user_tokens));
         lines.push(format!("  Assistant: ~{} tokens", info.assistant_tokens));
+        if info.overhead_tokens > 0 {
+            lines.push(format!("  Tools:     ~{} tokens", info.overhead_tokens));
+        }
+        if info.reclaimed_tokens > 0 {
+            lines.push(format!(
+                "  Reclaimed: ~{} tokens of stale tool results",
+                info.reclaimed_tokens
+            ));
+        }
         if info.last_turn_input_tokens > 0 || info.last_turn_output_tokens > 0 {
             lines.push(format!(
                 "  Last turn: ~{} in / ~{} out",
@@ -136,6 +246,12 @@ impl ContextTracker {
     }
 }
 
+/// Uncalibrated estimate for a request carrying `messages` and `system_prompt`.
+fn heuristic_tokens(messages: &[Message], system_prompt: Option<&str>) -> u64 {
+    system_prompt.map(estimate_text_tokens).unwrap_or(0)
+        + messages.iter().map(estimate_message_tokens).sum::<u64>()
+}
+
 /// Estimate tokens for a text string (~3.5 chars/token for prose/code).
 pub fn estimate_text_tokens(text: &str) -> u64 {
     // 3.5 chars/token is more accurate than 4 for mixed code/prose.
@@ -151,7 +267,7 @@ fn estimate_json_tokens(text: &str) -> u64 {
 }
 
 /// Estimate tokens for a single content block.
-fn estimate_block_tokens(block: &ContentBlock) -> u64 {
+pub(crate) fn estimate_block_tokens(block: &ContentBlock) -> u64 {
     match block {
         ContentBlock::Text { text } => estimate_text_tokens(text),
         ContentBlock::ToolUse { name, input, .. } => {
@@ -189,10 +305,9 @@ fn estimate_message_tokens(msg: &Message) -> u64 {
 
 /// Look up the context window size for a model.
 fn model_context_window(model: &str) -> u64 {
+    // Opus has a 1M window; Sonnet, Haiku and unknown models get 200k
     if model.contains("opus") {
         1_000_000
-    } else if model.contains("sonnet") || model.contains("haiku") {
-        200_000
     } else {
         200_000
     }
@@ -349,6 +464,85 @@ mod tests {
         assert!(json_est < text_est, "json={json_est} text={text_est}");
     }
 
+    #[test]
+    fn calibration_matches_the_latest_count() {
+        let mut tracker = ContextTracker::new("claude-sonnet-4-5-20250929");
+        let mut msgs = vec![text_msg(Role::User, "Hello world")];
+        assert!(!tracker.estimate(&msgs, Some("sys")).calibrated);
+
+        // First count: the gap is attributed to overhead (tools etc.)
+        tracker.calibrate(&msgs, Some("sys"), 3_000);
+        let info = tracker.estimate(&msgs, Some("sys"));
+        assert!(info.calibrated);
+        assert_eq!(info.estimated_tokens, 3_000);
+        assert!(info.overhead_tokens > 2_900);
+
+        // New messages are estimated on top of the exact count
+        msgs.push(text_msg(Role::Assistant, "Hi there"));
+        let added = estimate_message_tokens(&msgs[1]);
+        assert_eq!(
+            tracker.estimate(&msgs, Some("sys")).estimated_tokens,
+            3_000 + added
+        );
+    }
+
+    #[test]
+    fn calibration_learns_the_ratio_from_growth() {
+        let mut tracker = ContextTracker::new("claude-sonnet-4-5-20250929");
+        let mut msgs = vec![text_msg(Role::User, "start")];
+        tracker.calibrate(&msgs, None, 5_000);
+        let before = heuristic_tokens(&msgs, None);
+
+        // The heuristic undercounts the new text by half
+        msgs.push(text_msg(Role::Assistant, &"x".repeat(7_000)));
+        let grown = heuristic_tokens(&msgs, None) - before;
+        tracker.calibrate(&msgs, None, 5_000 + grown * 2);
+        let ratio = tracker.calibration.unwrap().ratio;
+        assert!((ratio - 1.3).abs() < 1e-9, "ratio={ratio}");
+
+        // The latest count is still reproduced exactly
+        assert_eq!(
+            tracker.estimate(&msgs, None).estimated_tokens,
+            5_000 + grown * 2
+        );
+
+        // Dropping messages (e.g. after /compact) lowers the estimate
+        let compacted = tracker.estimate(&msgs[..1], None);
+        assert!(compacted.estimated_tokens < 5_000 + grown);
+    }
+
+    #[test]
+    fn implausible_samples_are_clamped() {
+        let mut tracker = ContextTracker::new("claude-sonnet-4-5-20250929");
+        let mut msgs = vec![text_msg(Role::User, "start")];
+        tracker.calibrate(&msgs, None, 1_000);
+        msgs.push(text_msg(Role::User, &"y".repeat(7_000)));
+        tracker.calibrate(&msgs, None, 1_000_000);
+        // The sample is clamped to 2.0 before blending
+        let ratio = tracker.calibration.unwrap().ratio;
+        assert!((ratio - 1.3).abs() < 1e-9, "ratio={ratio}");
+    }
+
+    #[test]
+    fn format_detailed_shows_calibration() {
+        let mut tracker = ContextTracker::new("claude-sonnet-4-5-20250929");
+        let msgs = vec![text_msg(Role::User, "Hello")];
+        let output = tracker.format_detailed(&tracker.estimate(&msgs, None));
+        assert!(output.contains("estimated"));
+        assert!(!output.contains("Tools:"));
+
+        tracker.calibrate(&msgs, None, 4_000);
+        let output = tracker.format_detailed(&tracker.estimate(&msgs, None));
+        assert!(output.contains("calibrated from API usage"));
+        assert!(output.contains("Tools:"));
+        assert!(!output.contains("Reclaimed:"));
+
+        let mut info = tracker.estimate(&msgs, None);
+        info.reclaimed_tokens = 12_000;
+        let output = tracker.format_detailed(&info);
+        assert!(output.contains("Reclaimed: ~12000 tokens"));
+    }
+
     #[test]
     fn format_detailed_suggests_compact_at_high_usage() {
         let tracker = ContextTracker::new("claude-sonnet-4-5-20250929");
@@ -361,6 +555,9 @@ mod tests {
             system_tokens: 10_000,
             last_turn_input_tokens: 0,
             last_turn_output_tokens: 0,
+            overhead_tokens: 0,
+            calibrated: false,
+            reclaimed_tokens: 0,
         };
         let output = tracker.format_detailed(&info);
         assert!(output.contains("/compact"));
@@ -378,6 +575,9 @@ mod tests {
             system_tokens: 1_000,
             last_turn_input_tokens: 0,
             last_turn_output_tokens: 0,
+            overhead_tokens: 0,
+            calibrated: false,
+            reclaimed_tokens: 0,
         };
         let output = tracker.format_detailed(&info);
         assert!(!output.contains("Suggestions:"));
@@ -395,6 +595,9 @@ mod tests {
             system_tokens: 41_000,
             last_turn_input_tokens: 0,
             last_turn_output_tokens: 0,
+            overhead_tokens: 0,
+            calibrated: false,
+            reclaimed_tokens: 0,
         };
         let output = tracker.format_detailed(&info);
         assert!(output.contains("/memory reset"));
@@ -15,6 +15,9 @@ pub enum SessionError {
     #[error("No sessions match prefix '{prefix}'")]
     PrefixNotFound { prefix: String },
 
+    #[error("Session {id} is corrupt: {message}")]
+    Corrupt { id: Uuid, message: String },
+
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -24,3 +27,19 @@ pub enum SessionError {
     #[error("Nothing to compact: conversation is too short")]
     NothingToCompact,
 }
+
+/// Errors from memory entry operations.
+#[derive(Debug, Error)]
+pub enum MemoryError {
+    #[error("No memory entry with ID or title '{key}'")]
+    NotFound { key: String },
+
+    #[error("'{key}' matches {count} memory entries; use the entry ID")]
+    Ambiguous { key: String, count: usize },
+
+    #[error("A memory entry titled '{title}' already exists (id {id}); update it instead")]
+    Duplicate { title: String, id: String },
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
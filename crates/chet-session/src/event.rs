@@ -0,0 +1,218 @@
+//! Session event log records.
+//!
+//! Each session is stored as `sessions/<id>/events.jsonl`, one [`EventRecord`]
+//! per line, appended as the conversation grows. Replaying the records in
+//! order rebuilds the [`Session`]. A snapshot carries the full state and
+//! replaces everything before it, so a long log can be rewritten as a single
+//! snapshot line.
+
+use crate::types::Session;
+use chet_types::{Message, Usage};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// One line of a session's event log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: SessionEvent,
+}
+
+/// A change to a session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionEvent {
+    /// The full session state.
+    Snapshot { session: Box<Session> },
+    /// A message appended to the conversation.
+    Message { message: Message },
+    /// Tokens used since the previous usage event — normally one turn.
+    Usage { delta: Usage },
+    /// The conversation was compacted; `messages` replaces the history.
+    Compaction {
+        compaction_count: u32,
+        messages: Vec<Message>,
+    },
+    /// The session label changed.
+    Label { label: Option<String> },
+}
+
+impl SessionEvent {
+    pub fn apply(self, session: &mut Session, at: DateTime<Utc>) {
+        match self {
+            SessionEvent::Snapshot { session: snapshot } => *session = *snapshot,
+            SessionEvent::Message { message } => session.messages.push(message),
+            SessionEvent::Usage { delta } => session.record_turn(&delta, at),
+            SessionEvent::Compaction {
+                compaction_count,
+                messages,
+            } => {
+                session.compaction_count = compaction_count;
+                session.messages = messages;
+            }
+            SessionEvent::Label { label } => session.metadata.label = label,
+        }
+    }
+}
+
+/// The state rebuilt from an event log.
+#[derive(Debug, Default)]
+pub struct Replay {
+    /// `None` when the log holds no snapshot to start from.
+    pub session: Option<Session>,
+    /// Events after the last snapshot.
+    pub events_since_snapshot: usize,
+    /// A line could not be parsed or the log does not end in a newline —
+    /// usually a write cut short by a crash. The unreadable lines are skipped.
+    pub damaged: bool,
+}
+
+/// Replay an event log.
+pub fn replay(content: &str) -> Replay {
+    let mut replay = Replay::default();
+    for line in content.split_inclusive('\n') {
+        if !line.ends_with('\n') {
+            replay.damaged = true;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let record = match serde_json::from_str::<EventRecord>(line) {
+            Ok(record) => record,
+            Err(e) => {
+                tracing::warn!("Skipping unreadable session event: {e}");
+                replay.damaged = true;
+                continue;
+            }
+        };
+        match (&mut replay.session, record.event) {
+            (_, SessionEvent::Snapshot { session }) => {
+                replay.session = Some(*session);
+                replay.events_since_snapshot = 0;
+            }
+            (Some(session), event) => {
+                event.apply(session, record.at);
+                session.updated_at = record.at;
+                replay.events_since_snapshot += 1;
+            }
+            (None, _) => {
+                tracing::warn!("Skipping session event before the first snapshot");
+                replay.damaged = true;
+            }
+        }
+    }
+    replay
+}
+
+/// Serialize records as JSONL, one line each.
+pub(crate) fn to_jsonl(records: &[EventRecord]) -> Result<String, serde_json::Error> {
+    let mut out = String::new();
+    for record in records {
+        out.push_str(&serde_json::to_string(record)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chet_types::{ContentBlock, Role};
+
+    fn record(event: SessionEvent) -> EventRecord {
+        EventRecord {
+            at: Utc::now(),
+            event,
+        }
+    }
+
+    fn text(role: Role, text: &str) -> Message {
+        Message {
+            role,
+            content: vec![ContentBlock::Text { text: text.into() }],
+        }
+    }
+
+    #[test]
+    fn replay_applies_events_in_order() {
+        let session = Session::new("model".into(), "/tmp".into());
+        let records = vec![
+            record(SessionEvent::Snapshot {
+                session: Box::new(session.clone()),
+            }),
+            record(SessionEvent::Message {
+                message: text(Role::User, "hi"),
+            }),
+            record(SessionEvent::Usage {
+                delta: Usage {
+                    input_tokens: 10,
+                    ..Default::default()
+                },
+            }),
+            record(SessionEvent::Label {
+                label: Some("greeting".into()),
+            }),
+            record(SessionEvent::Message {
+                message: text(Role::Assistant, "hello"),
+            }),
+        ];
+        let jsonl = to_jsonl(&records).unwrap();
+        assert!(
+            jsonl
+                .lines()
+                .nth(1)
+                .unwrap()
+                .contains("\"type\":\"message\"")
+        );
+
+        let replay = replay(&jsonl);
+        let loaded = replay.session.unwrap();
+        assert_eq!(loaded.id, session.id);
+        assert_eq!(loaded.messages.len(), 2);
+        assert_eq!(loaded.total_usage.input_tokens, 10);
+        assert_eq!(loaded.turns.len(), 1);
+        assert_eq!(loaded.turns[0].messages, 1);
+        assert_eq!(loaded.metadata.label.as_deref(), Some("greeting"));
+        assert_eq!(loaded.updated_at, records[4].at);
+        assert_eq!(replay.events_since_snapshot, 4);
+        assert!(!replay.damaged);
+    }
+
+    #[test]
+    fn compaction_replaces_history() {
+        let mut session = Session::new("model".into(), "/tmp".into());
+        session.messages.push(text(Role::User, "old"));
+        SessionEvent::Compaction {
+            compaction_count: 1,
+            messages: vec![text(Role::User, "summary")],
+        }
+        .apply(&mut session, Utc::now());
+        assert_eq!(session.compaction_count, 1);
+        assert_eq!(session.messages.len(), 1);
+    }
+
+    #[test]
+    fn truncated_final_line_is_skipped() {
+        let session = Session::new("model".into(), "/tmp".into());
+        let mut jsonl = to_jsonl(&[
+            record(SessionEvent::Snapshot {
+                session: Box::new(session),
+            }),
+            record(SessionEvent::Message {
+                message: text(Role::User, "kept"),
+            }),
+        ])
+        .unwrap();
+        let partial = to_jsonl(&[record(SessionEvent::Message {
+            message: text(Role::Assistant, "lost"),
+        })])
+        .unwrap();
+        jsonl.push_str(&partial[..partial.len() / 2]);
+
+        let replay = replay(&jsonl);
+        assert!(replay.damaged);
+        assert_eq!(replay.session.unwrap().messages.len(), 1);
+    }
+}
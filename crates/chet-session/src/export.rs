@@ -0,0 +1,495 @@
+//! Session export to Markdown, HTML or JSON.
+
+use crate::redact::Redaction;
+use crate::types::Session;
+use chet_types::{ContentBlock, Message, Role, ToolResultContent, Usage};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Output format for [`export`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "md" | "markdown" => Ok(ExportFormat::Markdown),
+            "html" => Ok(ExportFormat::Html),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(format!("unknown export format: {s} (use md, html or json)")),
+        }
+    }
+}
+
+/// Render `session` in `format`, applying `redaction` to every piece of text.
+pub fn export(session: &Session, format: ExportFormat, redaction: &Redaction) -> String {
+    match format {
+        ExportFormat::Json => export_json(session, redaction),
+        ExportFormat::Markdown => render(session, redaction, &mut Markdown::default()),
+        ExportFormat::Html => render(session, redaction, &mut Html::default()),
+    }
+}
+
+fn export_json(session: &Session, redaction: &Redaction) -> String {
+    let mut value = serde_json::to_value(session).unwrap_or_default();
+    if !redaction.is_none() {
+        redact_json(&mut value, redaction);
+    }
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+fn redact_json(value: &mut serde_json::Value, redaction: &Redaction) {
+    match value {
+        serde_json::Value::String(s) => *s = redaction.apply(s),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|v| redact_json(v, redaction)),
+        serde_json::Value::Object(map) => map.values_mut().for_each(|v| redact_json(v, redaction)),
+        _ => {}
+    }
+}
+
+/// The parts of a transcript, written by [`render`] in order.
+trait Writer {
+    fn header(&mut self, title: &str, fields: &[(&str, String)]);
+    fn role(&mut self, role: Role);
+    fn text(&mut self, text: &str);
+    fn thinking(&mut self, text: &str);
+    fn tool(&mut self, name: &str, input: &str, output: Option<(&str, bool)>);
+    fn note(&mut self, text: &str);
+    fn finish(&mut self) -> String;
+}
+
+fn render(session: &Session, redaction: &Redaction, w: &mut dyn Writer) -> String {
+    let clean = |text: &str| redaction.apply(text);
+    let title = session
+        .metadata
+        .label
+        .as_deref()
+        .map(clean)
+        .unwrap_or_else(|| format!("Session {}", session.short_id()));
+    let mut fields = vec![
+        ("Session", session.id.to_string()),
+        ("Model", session.metadata.model.clone()),
+        ("Directory", clean(&session.metadata.cwd)),
+        (
+            "Created",
+            session
+                .created_at
+                .format("%Y-%m-%d %H:%M:%S UTC")
+                .to_string(),
+        ),
+        (
+            "Updated",
+            session
+                .updated_at
+                .format("%Y-%m-%d %H:%M:%S UTC")
+                .to_string(),
+        ),
+        ("Messages", session.messages.len().to_string()),
+        ("Usage", format_usage(&session.total_usage)),
+    ];
+    if session.compaction_count > 0 {
+        fields.push(("Compactions", session.compaction_count.to_string()));
+    }
+    w.header(&title, &fields);
+
+    // Results are shown with the call that produced them
+    let mut results: HashMap<&str, (String, bool)> = HashMap::new();
+    for message in &session.messages {
+        for block in &message.content {
+            if let ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } = block
+            {
+                results.insert(
+                    tool_use_id,
+                    (clean(&result_text(content)), is_error.unwrap_or(false)),
+                );
+            }
+        }
+    }
+    let turn_ends: HashMap<usize, &Usage> = session
+        .current_turns()
+        .map(|t| (t.messages, &t.usage))
+        .collect();
+
+    for (i, message) in session.messages.iter().enumerate() {
+        if has_visible_content(message) {
+            w.role(message.role);
+        }
+        for block in &message.content {
+            match block {
+                ContentBlock::Text { text } => w.text(&clean(text)),
+                ContentBlock::Thinking { thinking, .. } => w.thinking(&clean(thinking)),
+                ContentBlock::ToolUse { id, name, input } => {
+                    let input = serde_json::to_string_pretty(input).unwrap_or_default();
+                    let output = results.get(id.as_str()).map(|(o, e)| (o.as_str(), *e));
+                    w.tool(name, &clean(&input), output);
+                }
+                ContentBlock::Image { .. } => w.note("[image]"),
+                ContentBlock::ToolResult { .. } => {}
+            }
+        }
+        if let Some(usage) = turn_ends.get(&(i + 1)) {
+            w.note(&format!("Turn usage: {}", format_usage(usage)));
+        }
+    }
+    w.finish()
+}
+
+/// Messages holding only tool results are shown with their calls.
+fn has_visible_content(message: &Message) -> bool {
+    message
+        .content
+        .iter()
+        .any(|b| !matches!(b, ContentBlock::ToolResult { .. }))
+}
+
+fn result_text(content: &[ToolResultContent]) -> String {
+    content
+        .iter()
+        .map(|c| match c {
+            ToolResultContent::Text { text } => text.as_str(),
+            ToolResultContent::Image { .. } => "[image]",
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_usage(usage: &Usage) -> String {
+    let mut s = format!("{} in / {} out", usage.input_tokens, usage.output_tokens);
+    if usage.cache_read_input_tokens > 0 || usage.cache_creation_input_tokens > 0 {
+        let _ = write!(
+            s,
+            " (cache: {} read, {} written)",
+            usage.cache_read_input_tokens, usage.cache_creation_input_tokens
+        );
+    }
+    s
+}
+
+fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::User => "User",
+        Role::Assistant => "Assistant",
+    }
+}
+
+// --- Markdown ---
+
+#[derive(Default)]
+struct Markdown {
+    out: String,
+}
+
+/// A code fence longer than any backtick run in `text`.
+fn fence(text: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    "`".repeat(longest.max(2) + 1)
+}
+
+impl Markdown {
+    fn code(&mut self, lang: &str, text: &str) {
+        let fence = fence(text);
+        let _ = writeln!(self.out, "{fence}{lang}\n{}\n{fence}\n", text.trim_end());
+    }
+}
+
+impl Writer for Markdown {
+    fn header(&mut self, title: &str, fields: &[(&str, String)]) {
+        let _ = writeln!(self.out, "# {title}\n");
+        for (name, value) in fields {
+            let _ = writeln!(self.out, "- **{name}:** {value}");
+        }
+        self.out.push('\n');
+    }
+
+    fn role(&mut self, role: Role) {
+        let _ = writeln!(self.out, "## {}\n", role_name(role));
+    }
+
+    fn text(&mut self, text: &str) {
+        let _ = writeln!(self.out, "{}\n", text.trim());
+    }
+
+    fn thinking(&mut self, text: &str) {
+        let _ = writeln!(self.out, "<details>\n<summary>Thinking</summary>\n");
+        let _ = writeln!(self.out, "{}\n\n</details>\n", text.trim());
+    }
+
+    fn tool(&mut self, name: &str, input: &str, output: Option<(&str, bool)>) {
+        let _ = writeln!(self.out, "**Tool call: {name}**\n");
+        self.code("json", input);
+        match output {
+            Some((output, is_error)) => {
+                let _ = writeln!(
+                    self.out,
+                    "{}\n",
+                    if is_error { "Error:" } else { "Output:" }
+                );
+                self.code("", output);
+            }
+            None => {
+                let _ = writeln!(self.out, "_No result._\n");
+            }
+        }
+    }
+
+    fn note(&mut self, text: &str) {
+        let _ = writeln!(self.out, "_{text}_\n");
+    }
+
+    fn finish(&mut self) -> String {
+        std::mem::take(&mut self.out)
+    }
+}
+
+// --- HTML ---
+
+#[derive(Default)]
+struct Html {
+    out: String,
+    in_message: bool,
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:960px;margin:2em auto;padding:0 1em;line-height:1.5;color:#222}\
+.meta{border-collapse:collapse;margin-bottom:2em}.meta td{padding:2px 12px 2px 0;vertical-align:top}\
+.message{border-left:4px solid #ccc;padding:0 1em;margin:1.5em 0}.user{border-color:#4a7bd0}.assistant{border-color:#3a9a5b}\
+.role{font-weight:bold;margin:.5em 0}.text{white-space:pre-wrap}\
+pre{background:#f5f5f5;padding:.75em;overflow-x:auto;white-space:pre-wrap}\
+details{margin:.5em 0}summary{cursor:pointer;color:#555}.error pre{background:#fdecea}.note{color:#777;font-size:.9em}";
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+impl Html {
+    fn close_message(&mut self) {
+        if self.in_message {
+            self.out.push_str("</div>\n");
+            self.in_message = false;
+        }
+    }
+}
+
+impl Writer for Html {
+    fn header(&mut self, title: &str, fields: &[(&str, String)]) {
+        let title = escape(title);
+        let _ = writeln!(
+            self.out,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<table class=\"meta\">"
+        );
+        for (name, value) in fields {
+            let _ = writeln!(
+                self.out,
+                "<tr><td>{name}</td><td>{}</td></tr>",
+                escape(value)
+            );
+        }
+        self.out.push_str("</table>\n");
+    }
+
+    fn role(&mut self, role: Role) {
+        self.close_message();
+        let class = match role {
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        };
+        let _ = writeln!(
+            self.out,
+            "<div class=\"message {class}\">\n<div class=\"role\">{}</div>",
+            role_name(role)
+        );
+        self.in_message = true;
+    }
+
+    fn text(&mut self, text: &str) {
+        let _ = writeln!(
+            self.out,
+            "<div class=\"text\">{}</div>",
+            escape(text.trim())
+        );
+    }
+
+    fn thinking(&mut self, text: &str) {
+        let _ = writeln!(
+            self.out,
+            "<details class=\"thinking\"><summary>Thinking</summary><div class=\"text\">{}</div></details>",
+            escape(text.trim())
+        );
+    }
+
+    fn tool(&mut self, name: &str, input: &str, output: Option<(&str, bool)>) {
+        let class = if output.is_some_and(|(_, e)| e) {
+            "tool error"
+        } else {
+            "tool"
+        };
+        let _ = write!(
+            self.out,
+            "<details class=\"{class}\" open><summary>Tool call: {}</summary><pre>{}</pre>",
+            escape(name),
+            escape(input.trim_end())
+        );
+        match output {
+            Some((output, is_error)) => {
+                let _ = write!(
+                    self.out,
+                    "<div class=\"note\">{}</div><pre>{}</pre>",
+                    if is_error { "Error" } else { "Output" },
+                    escape(output.trim_end())
+                );
+            }
+            None => self.out.push_str("<div class=\"note\">No result.</div>"),
+        }
+        self.out.push_str("</details>\n");
+    }
+
+    fn note(&mut self, text: &str) {
+        let _ = writeln!(self.out, "<div class=\"note\">{}</div>", escape(text));
+    }
+
+    fn finish(&mut self) -> String {
+        self.close_message();
+        self.out.push_str("</body>\n</html>\n");
+        std::mem::take(&mut self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn session() -> Session {
+        let mut s = Session::new("claude-test".into(), "/home/alice/app".into());
+        s.metadata.label = Some("Fix <auth>".into());
+        s.messages.push(Message {
+            role: Role::User,
+            content: vec![ContentBlock::Text {
+                text: "Use key sk-ant-REDACTED".into(),
+            }],
+        });
+        s.messages.push(Message {
+            role: Role::Assistant,
+            content: vec![
+                ContentBlock::Thinking {
+                    thinking: "Let me look at the file".into(),
+                    signature: None,
+                },
+                ContentBlock::ToolUse {
+                    id: "t1".into(),
+                    name: "Read".into(),
+                    input: serde_json::json!({"file_path": "/home/alice/app/src/auth.rs"}),
+                },
+            ],
+        });
+        s.messages.push(Message {
+            role: Role::User,
+            content: vec![ContentBlock::ToolResult {
+                tool_use_id: "t1".into(),
+                content: vec![ToolResultContent::Text {
+                    text: "fn login() {}\n```".into(),
+                }],
+                is_error: None,
+            }],
+        });
+        s.messages.push(Message {
+            role: Role::Assistant,
+            content: vec![ContentBlock::Text {
+                text: "Done.".into(),
+            }],
+        });
+        let usage = Usage {
+            input_tokens: 120,
+            output_tokens: 30,
+            ..Default::default()
+        };
+        s.record_turn(&usage, Utc::now());
+        s
+    }
+
+    #[test]
+    fn markdown_includes_everything() {
+        let md = export(&session(), ExportFormat::Markdown, &Redaction::default());
+        assert!(md.starts_with("# Fix <auth>\n"));
+        assert!(md.contains("- **Model:** claude-test"));
+        assert!(md.contains("<summary>Thinking</summary>"));
+        assert!(md.contains("**Tool call: Read**"));
+        assert!(md.contains("/home/alice/app/src/auth.rs"));
+        // The output contains ``` so the fence is longer
+        assert!(md.contains("````\nfn login() {}\n```\n````"));
+        assert!(md.contains("_Turn usage: 120 in / 30 out_"));
+        // The tool-result-only message gets no heading of its own
+        assert_eq!(md.matches("## User").count(), 1);
+    }
+
+    #[test]
+    fn html_escapes_and_collapses_thinking() {
+        let html = export(&session(), ExportFormat::Html, &Redaction::default());
+        assert!(html.contains("<title>Fix &lt;auth&gt;</title>"));
+        assert!(html.contains("<details class=\"thinking\"><summary>Thinking</summary>"));
+        assert!(html.contains("Tool call: Read"));
+        assert!(html.trim_end().ends_with("</html>"));
+    }
+
+    #[test]
+    fn redaction_applies_to_every_format() {
+        let redaction = Redaction {
+            secrets: true,
+            home: Some("/home/alice".into()),
+        };
+        for format in [
+            ExportFormat::Markdown,
+            ExportFormat::Html,
+            ExportFormat::Json,
+        ] {
+            let out = export(&session(), format, &redaction);
+            assert!(!out.contains("sk-ant-api03"), "{format:?}");
+            assert!(!out.contains("/home/alice"), "{format:?}");
+            assert!(out.contains("~/app"), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let s = session();
+        let json = export(&s, ExportFormat::Json, &Redaction::default());
+        let parsed: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.id, s.id);
+        assert_eq!(parsed.turns.len(), 1);
+        assert_eq!("HTML".parse::<ExportFormat>(), Ok(ExportFormat::Html));
+    }
+}
@@ -3,13 +3,27 @@
 pub mod compact;
 pub mod context;
 pub mod error;
+pub mod event;
+pub mod export;
 pub mod memory;
+pub mod memory_entry;
+pub mod microcompact;
+pub mod redact;
+pub mod retention;
+pub mod search;
 pub mod store;
 pub mod types;
 
 pub use compact::{CompactionResult, compact};
 pub use context::{ContextInfo, ContextTracker};
-pub use error::SessionError;
-pub use memory::MemoryManager;
+pub use error::{MemoryError, SessionError};
+pub use event::{EventRecord, SessionEvent};
+pub use export::{ExportFormat, export};
+pub use memory::{MemoryManager, MemoryScope};
+pub use memory_entry::{MemoryDoc, MemoryEntry, MemoryPatch};
+pub use microcompact::{MicroCompaction, micro_compact};
+pub use redact::Redaction;
+pub use retention::Retention;
+pub use search::SearchHit;
 pub use store::SessionStore;
-pub use types::{Session, SessionMetadata, SessionSummary};
+pub use types::{Session, SessionMetadata, SessionSummary, TurnUsage};
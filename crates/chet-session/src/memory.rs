@@ -1,13 +1,52 @@
-//! Persistent memory storage for Chet — global and per-project markdown files.
-
+//! Persistent memory storage for Chet — global and per-project markdown files
+//! under the memory directory, plus a team file committed in the repo.
+//!
+//! Each file holds free-form notes and structured entries (see
+//! `memory_entry`). Changes take a lock file next to the memory file, so
+//! concurrent sessions don't overwrite each other's edits.
+
+use crate::error::MemoryError;
+use crate::memory_entry::{MemoryDoc, MemoryEntry, MemoryPatch};
+use chrono::Utc;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use std::io;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Above this many bytes, the system prompt gets an index of memory entries
+/// instead of their full contents.
+pub const MEMORY_PROMPT_BUDGET: usize = 8_000;
+
+/// How long to wait for another session's lock before giving up.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A lock file older than this was left by a crashed process.
+const LOCK_STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// Which memory file an operation applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryScope<'a> {
+    Global,
+    Project(&'a str),
+    /// `.chet/memory.md` under this repo root, shared through version control.
+    Team(&'a Path),
+}
+
+impl MemoryScope<'_> {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MemoryScope::Global => "global",
+            MemoryScope::Project(_) => "project",
+            MemoryScope::Team(_) => "team",
+        }
+    }
+}
 
-/// Manages persistent memory files (global + per-project).
+/// Manages persistent memory files (global, per-project and team).
 pub struct MemoryManager {
     memory_dir: PathBuf,
+    team_root: Option<PathBuf>,
 }
 
 impl MemoryManager {
@@ -15,7 +54,21 @@ impl MemoryManager {
     /// The directory should be the resolved memory path (e.g. `~/.chet/memory/`
     /// or a custom path from the `memory_dir` config setting).
     pub fn new(memory_dir: PathBuf) -> Self {
-        Self { memory_dir }
+        Self {
+            memory_dir,
+            team_root: None,
+        }
+    }
+
+    /// Include the team memory of the repo at `root` in `load_combined`.
+    pub fn with_team_root(mut self, root: PathBuf) -> Self {
+        self.team_root = Some(root);
+        self
+    }
+
+    /// The repo root whose team memory is included, if any.
+    pub fn team_root(&self) -> Option<&Path> {
+        self.team_root.as_deref()
     }
 
     /// Compute a deterministic 16-char hex project ID from a path.
@@ -37,6 +90,11 @@ impl MemoryManager {
             .join(format!("{project_id}.md"))
     }
 
+    /// Path to the team memory file of the repo at `root`.
+    pub fn team_memory_path(root: &Path) -> PathBuf {
+        root.join(".chet").join("memory.md")
+    }
+
     /// Load global memory, returning empty string if file is missing.
     pub async fn load_global(&self) -> String {
         let path = self.global_memory_path();
@@ -49,39 +107,151 @@ impl MemoryManager {
         tokio::fs::read_to_string(&path).await.unwrap_or_default()
     }
 
-    /// Load and format both global and project memory into a single section.
-    /// Includes last-modified timestamps when files exist.
+    /// Path to the memory file for `scope`.
+    pub fn path(&self, scope: MemoryScope<'_>) -> PathBuf {
+        match scope {
+            MemoryScope::Global => self.global_memory_path(),
+            MemoryScope::Project(id) => self.project_memory_path(id),
+            MemoryScope::Team(root) => Self::team_memory_path(root),
+        }
+    }
+
+    /// Load the memory file for `scope`, returning empty string if missing.
+    pub async fn load(&self, scope: MemoryScope<'_>) -> String {
+        tokio::fs::read_to_string(self.path(scope))
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Load and parse the memory file for `scope`.
+    pub async fn load_doc(&self, scope: MemoryScope<'_>) -> MemoryDoc {
+        MemoryDoc::parse(&self.load(scope).await)
+    }
+
+    /// Load and format global, project and team memory into a single section.
+    /// Includes last-modified timestamps when files exist. Past
+    /// `MEMORY_PROMPT_BUDGET`, entries are listed by title only.
     pub async fn load_combined(&self, project_id: Option<&str>) -> String {
-        let global = self.load_global().await;
-        let global_modified = file_modified_label(&self.global_memory_path()).await;
-        let project = match project_id {
-            Some(id) => self.load_project(id).await,
-            None => String::new(),
-        };
-        let project_modified = match project_id {
-            Some(id) => file_modified_label(&self.project_memory_path(id)).await,
-            None => None,
+        self.combined(project_id, Some(MEMORY_PROMPT_BUDGET)).await
+    }
+
+    /// Like `load_combined`, but always with the full contents.
+    pub async fn load_all(&self, project_id: Option<&str>) -> String {
+        self.combined(project_id, None).await
+    }
+
+    async fn combined(&self, project_id: Option<&str>, budget: Option<usize>) -> String {
+        let mut scopes = vec![("Global Memory", MemoryScope::Global)];
+        if let Some(id) = project_id {
+            scopes.push(("Project Memory", MemoryScope::Project(id)));
+        }
+        if let Some(root) = self.team_root() {
+            scopes.push(("Team Memory", MemoryScope::Team(root)));
+        }
+        let mut layers = Vec::new();
+        for (heading, scope) in scopes {
+            let doc = self.load_doc(scope).await;
+            let modified = file_modified_label(&self.path(scope)).await;
+            layers.push((heading, doc, modified));
+        }
+        let section = |render: fn(&MemoryDoc) -> String| {
+            let rendered: Vec<String> = layers.iter().map(|(_, doc, _)| render(doc)).collect();
+            let layers: Vec<_> = layers
+                .iter()
+                .zip(&rendered)
+                .map(|((heading, _, modified), content)| {
+                    (*heading, content.as_str(), modified.as_deref())
+                })
+                .collect();
+            format_memory_layers(&layers)
         };
-        format_memory_section(
-            &global,
-            global_modified.as_deref(),
-            &project,
-            project_modified.as_deref(),
+        let full = section(MemoryDoc::to_prompt);
+        if budget.is_none_or(|budget| full.len() <= budget) {
+            return full;
+        }
+        section(MemoryDoc::to_index).replacen(
+            "# Memory\n\n",
+            &format!(
+                "# Memory\n\nMemory is over its {MEMORY_PROMPT_BUDGET}-byte prompt budget, \
+                 so only an index is shown. Use MemoryRead with an id or a query to read entries.\n\n"
+            ),
+            1,
         )
     }
 
     /// Write global memory atomically (tmp file + rename).
     pub async fn write_global(&self, content: &str) -> io::Result<()> {
-        let path = self.global_memory_path();
-        atomic_write(&path, content).await
+        self.write(MemoryScope::Global, content).await
     }
 
     /// Write project memory atomically.
     pub async fn write_project(&self, project_id: &str, content: &str) -> io::Result<()> {
-        let path = self.project_memory_path(project_id);
+        self.write(MemoryScope::Project(project_id), content).await
+    }
+
+    /// Replace the memory file for `scope` atomically.
+    pub async fn write(&self, scope: MemoryScope<'_>, content: &str) -> io::Result<()> {
+        let path = self.path(scope);
+        let _lock = FileLock::acquire(&path).await?;
         atomic_write(&path, content).await
     }
 
+    /// Add an entry. Fails if an entry with the same title exists.
+    pub async fn add_entry(
+        &self,
+        scope: MemoryScope<'_>,
+        title: &str,
+        tags: Vec<String>,
+        body: &str,
+    ) -> Result<MemoryEntry, MemoryError> {
+        self.modify(scope, |doc| {
+            let entry = MemoryEntry::new(title, tags, body, Utc::now());
+            doc.add(entry.clone())?;
+            Ok(entry)
+        })
+        .await
+    }
+
+    /// Change the entry `key` (an ID or title) names.
+    pub async fn update_entry(
+        &self,
+        scope: MemoryScope<'_>,
+        key: &str,
+        patch: MemoryPatch,
+    ) -> Result<MemoryEntry, MemoryError> {
+        self.modify(scope, |doc| doc.update(key, patch, Utc::now()).cloned())
+            .await
+    }
+
+    /// Remove the entry `key` (an ID or title) names.
+    pub async fn delete_entry(
+        &self,
+        scope: MemoryScope<'_>,
+        key: &str,
+    ) -> Result<MemoryEntry, MemoryError> {
+        self.modify(scope, |doc| doc.remove(key)).await
+    }
+
+    /// Read, change and write back a memory file under its lock. Nothing is
+    /// written if `change` fails.
+    async fn modify<T>(
+        &self,
+        scope: MemoryScope<'_>,
+        change: impl FnOnce(&mut MemoryDoc) -> Result<T, MemoryError>,
+    ) -> Result<T, MemoryError> {
+        let path = self.path(scope);
+        let _lock = FileLock::acquire(&path).await?;
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut doc = MemoryDoc::parse(&content);
+        let out = change(&mut doc)?;
+        atomic_write(&path, &doc.render()).await?;
+        Ok(out)
+    }
+
     /// Delete the global memory file.
     pub async fn reset_global(&self) -> io::Result<()> {
         let path = self.global_memory_path();
@@ -112,33 +282,33 @@ pub fn format_memory_section(
     project: &str,
     project_modified: Option<&str>,
 ) -> String {
-    let global = global.trim();
-    let project = project.trim();
+    format_memory_layers(&[
+        ("Global Memory", global, global_modified),
+        ("Project Memory", project, project_modified),
+    ])
+}
 
-    if global.is_empty() && project.is_empty() {
+/// Format `(heading, content, last modified)` layers into a combined
+/// section, skipping empty ones. Returns empty string if all are empty.
+pub fn format_memory_layers(layers: &[(&str, &str, Option<&str>)]) -> String {
+    let layers: Vec<_> = layers
+        .iter()
+        .map(|(heading, content, modified)| (heading, content.trim(), modified))
+        .filter(|(_, content, _)| !content.is_empty())
+        .collect();
+    if layers.is_empty() {
         return String::new();
     }
 
-    let mut out = String::from("# Memory\n\n");
-
-    if !global.is_empty() {
-        match global_modified {
-            Some(ts) => out.push_str(&format!("## Global Memory (last updated: {ts})\n\n")),
-            None => out.push_str("## Global Memory\n\n"),
-        }
-        out.push_str(global);
-        out.push_str("\n\n");
-    }
-
-    if !project.is_empty() {
-        match project_modified {
-            Some(ts) => out.push_str(&format!("## Project Memory (last updated: {ts})\n\n")),
-            None => out.push_str("## Project Memory\n\n"),
+    let mut out = String::from("# Memory\n");
+    for (heading, content, modified) in layers {
+        match modified {
+            Some(ts) => out.push_str(&format!("\n## {heading} (last updated: {ts})\n\n")),
+            None => out.push_str(&format!("\n## {heading}\n\n")),
         }
-        out.push_str(project);
+        out.push_str(content);
         out.push('\n');
     }
-
     out
 }
 
@@ -150,6 +320,81 @@ async fn file_modified_label(path: &Path) -> Option<String> {
     Some(dt.format("%Y-%m-%d %H:%M UTC").to_string())
 }
 
+/// Exclusive lock on a memory file, held while it is read, changed and
+/// written back: a `.lock` file created next to it, removed on drop.
+struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    async fn acquire(target: &Path) -> io::Result<Self> {
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let path = target.with_extension("lock");
+        let deadline = Instant::now() + LOCK_TIMEOUT;
+        loop {
+            let created = tokio::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .await;
+            match created {
+                Ok(_) => return Ok(Self { path }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    if is_stale(&path).await && break_stale_lock(&path).await {
+                        continue;
+                    }
+                    if Instant::now() >= deadline {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!("{} is locked by another session", target.display()),
+                        ));
+                    }
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Move a stale lock out of the way; true if the caller should retry at
+/// once. Checking and then deleting could delete a fresh lock another
+/// session created in between, so the lock is first renamed to a name only
+/// this caller knows. Whatever file that claimed is checked again: a stale
+/// one is deleted, a fresh one is handed back.
+async fn break_stale_lock(lock: &Path) -> bool {
+    let claimed = lock.with_extension(format!("lock.{}", uuid::Uuid::new_v4().simple()));
+    if tokio::fs::rename(lock, &claimed).await.is_err() {
+        // Already broken by someone else
+        return true;
+    }
+    if is_stale(&claimed).await {
+        let _ = tokio::fs::remove_file(&claimed).await;
+        return true;
+    }
+    // `hard_link` never replaces a lock taken since the rename
+    let _ = tokio::fs::hard_link(&claimed, lock).await;
+    let _ = tokio::fs::remove_file(&claimed).await;
+    false
+}
+
+async fn is_stale(lock: &Path) -> bool {
+    let Ok(modified) = tokio::fs::metadata(lock).await.and_then(|m| m.modified()) else {
+        return false;
+    };
+    SystemTime::now()
+        .duration_since(modified)
+        .is_ok_and(|age| age > LOCK_STALE_AFTER)
+}
+
 /// Write content to a file atomically via a temporary file + rename.
 async fn atomic_write(path: &Path, content: &str) -> io::Result<()> {
     if let Some(parent) = path.parent() {
@@ -295,6 +540,138 @@ mod tests {
         assert!(combined.contains("project content"));
     }
 
+    #[tokio::test]
+    async fn team_memory_lives_in_the_repo() {
+        let dir = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        let mgr = MemoryManager::new(dir.path().to_path_buf()).with_team_root(repo.path().into());
+        mgr.write_global("global content").await.unwrap();
+        mgr.add_entry(
+            MemoryScope::Team(repo.path()),
+            "Release process",
+            vec![],
+            "Tag from main only",
+        )
+        .await
+        .unwrap();
+
+        let path = repo.path().join(".chet/memory.md");
+        assert_eq!(mgr.path(MemoryScope::Team(repo.path())), path);
+        assert!(path.exists());
+        assert!(!repo.path().join(".chet/memory.lock").exists());
+
+        let combined = mgr.load_combined(Some("proj1")).await;
+        let global = combined.find("## Global Memory").unwrap();
+        let team = combined.find("## Team Memory").unwrap();
+        assert!(global < team);
+        assert!(!combined.contains("## Project Memory"));
+        assert!(combined[team..].contains("### Release process"));
+    }
+
+    #[tokio::test]
+    async fn entries_can_be_added_updated_and_deleted() {
+        let dir = TempDir::new().unwrap();
+        let mgr = MemoryManager::new(dir.path().to_path_buf());
+        mgr.write_global("legacy note").await.unwrap();
+        let scope = MemoryScope::Global;
+
+        let entry = mgr
+            .add_entry(scope, "Style", vec!["fmt".into()], "Run cargo fmt")
+            .await
+            .unwrap();
+        assert!(matches!(
+            mgr.add_entry(scope, "style", vec![], "again").await,
+            Err(MemoryError::Duplicate { .. })
+        ));
+
+        let patch = MemoryPatch {
+            body: Some("Run cargo fmt --all".into()),
+            ..MemoryPatch::default()
+        };
+        let updated = mgr.update_entry(scope, "STYLE", patch).await.unwrap();
+        assert_eq!(updated.id, entry.id);
+        assert_eq!(updated.body, "Run cargo fmt --all");
+
+        let doc = mgr.load_doc(scope).await;
+        assert_eq!(doc.notes, "legacy note");
+        assert_eq!(doc.entries, vec![updated]);
+
+        mgr.delete_entry(scope, &entry.id).await.unwrap();
+        assert!(matches!(
+            mgr.delete_entry(scope, &entry.id).await,
+            Err(MemoryError::NotFound { .. })
+        ));
+        assert!(mgr.load_doc(scope).await.entries.is_empty());
+        assert!(!dir.path().join("MEMORY.lock").exists());
+    }
+
+    #[tokio::test]
+    async fn concurrent_adds_are_all_kept() {
+        let dir = TempDir::new().unwrap();
+        let mgr = std::sync::Arc::new(MemoryManager::new(dir.path().to_path_buf()));
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let mgr = mgr.clone();
+                tokio::spawn(async move {
+                    mgr.add_entry(MemoryScope::Project("p"), &format!("Fact {i}"), vec![], "x")
+                        .await
+                        .unwrap();
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(
+            mgr.load_doc(MemoryScope::Project("p")).await.entries.len(),
+            8
+        );
+    }
+
+    #[tokio::test]
+    async fn stale_locks_are_broken() {
+        let dir = TempDir::new().unwrap();
+        let mgr = MemoryManager::new(dir.path().to_path_buf());
+        let lock = dir.path().join("MEMORY.lock");
+        let file = std::fs::File::create(&lock).unwrap();
+        file.set_modified(SystemTime::now() - LOCK_STALE_AFTER * 2)
+            .unwrap();
+        mgr.add_entry(MemoryScope::Global, "Fact", vec![], "x")
+            .await
+            .unwrap();
+        assert!(!lock.exists());
+    }
+
+    #[tokio::test]
+    async fn breaking_a_lock_keeps_a_fresh_one() {
+        // Another session replaced the stale lock after it was seen as stale
+        let dir = TempDir::new().unwrap();
+        let lock = dir.path().join("MEMORY.lock");
+        std::fs::File::create(&lock).unwrap();
+        assert!(!break_stale_lock(&lock).await);
+        assert!(lock.exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn large_memory_is_indexed_in_the_prompt() {
+        let dir = TempDir::new().unwrap();
+        let mgr = MemoryManager::new(dir.path().to_path_buf());
+        let body = "detail ".repeat(MEMORY_PROMPT_BUDGET / 7);
+        mgr.add_entry(MemoryScope::Global, "Big fact", vec![], &body)
+            .await
+            .unwrap();
+        mgr.add_entry(MemoryScope::Global, "Small fact", vec![], "short")
+            .await
+            .unwrap();
+        let combined = mgr.load_combined(None).await;
+        assert!(combined.contains("only an index is shown"));
+        assert!(combined.contains("- Big fact (id: "));
+        assert!(combined.contains("- Small fact (id: "));
+        assert!(!combined.contains("detail detail"));
+        assert!(combined.len() < 1_000);
+    }
+
     #[tokio::test]
     async fn load_combined_no_project_id() {
         let dir = TempDir::new().unwrap();
@@ -0,0 +1,447 @@
+//! Structured memory entries within a memory file.
+//!
+//! A memory file stays plain markdown, editable by hand. Each entry is a
+//! `## Title` heading followed by a metadata comment, then its body:
+//!
+//! ```text
+//! ## Use tabs in Makefiles
+//! <!-- memory: id=3f2a9c1b; tags=build,style; created=2026-10-18T12:00:00Z; updated=2026-10-18T12:00:00Z -->
+//!
+//! Recipes must be indented with tabs.
+//! ```
+//!
+//! Anything before the first entry — including files written before entries
+//! existed — is kept as free-form notes.
+
+use crate::error::MemoryError;
+use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
+
+const META_PREFIX: &str = "<!-- memory:";
+const META_SUFFIX: &str = "-->";
+
+/// One remembered fact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryEntry {
+    /// Short, stable identifier for updates and deletes.
+    pub id: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub created: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+    pub body: String,
+}
+
+/// Changes to an entry; `None` leaves a field as it is.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryPatch {
+    pub title: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub body: Option<String>,
+}
+
+/// A parsed memory file: free-form notes followed by entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryDoc {
+    pub notes: String,
+    pub entries: Vec<MemoryEntry>,
+}
+
+impl MemoryEntry {
+    pub fn new(title: &str, tags: Vec<String>, body: &str, now: DateTime<Utc>) -> Self {
+        let id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+        // Stored to the second
+        let now = now.trunc_subsecs(0);
+        Self {
+            id,
+            title: clean_title(title),
+            tags: clean_tags(tags),
+            created: now,
+            updated: now,
+            body: body.trim().to_string(),
+        }
+    }
+
+    pub fn apply(&mut self, patch: MemoryPatch, now: DateTime<Utc>) {
+        if let Some(title) = patch.title {
+            self.title = clean_title(&title);
+        }
+        if let Some(tags) = patch.tags {
+            self.tags = clean_tags(tags);
+        }
+        if let Some(body) = patch.body {
+            self.body = body.trim().to_string();
+        }
+        self.updated = now.trunc_subsecs(0);
+    }
+
+    /// `Title (id: 3f2a9c1b; tags: build, style; updated: 2026-10-18)`
+    pub fn headline(&self) -> String {
+        let mut meta = format!("id: {}", self.id);
+        if !self.tags.is_empty() {
+            meta.push_str(&format!("; tags: {}", self.tags.join(", ")));
+        }
+        meta.push_str(&format!("; updated: {}", self.updated.format("%Y-%m-%d")));
+        format!("{} ({meta})", self.title)
+    }
+
+    /// Relevance to `terms` (lowercase): title matches count most, then tags,
+    /// then the body. `0` when no term matches.
+    fn score(&self, terms: &[String]) -> u32 {
+        let title = self.title.to_lowercase();
+        let body = self.body.to_lowercase();
+        terms
+            .iter()
+            .map(|term| {
+                let mut score = 0;
+                if title.contains(term.as_str()) {
+                    score += 3;
+                }
+                if self.tags.iter().any(|t| t.to_lowercase() == *term) {
+                    score += 2;
+                }
+                if body.contains(term.as_str()) {
+                    score += 1;
+                }
+                score
+            })
+            .sum()
+    }
+}
+
+impl MemoryDoc {
+    pub fn parse(content: &str) -> Self {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut doc = MemoryDoc::default();
+        let mut notes = Vec::new();
+        let mut body: Vec<&str> = Vec::new();
+        let mut current: Option<MemoryEntry> = None;
+
+        let mut i = 0;
+        while i < lines.len() {
+            let entry = lines[i]
+                .strip_prefix("## ")
+                .zip(lines.get(i + 1))
+                .and_then(|(title, meta)| parse_meta(title, meta));
+            if let Some(entry) = entry {
+                if let Some(mut prev) = current.replace(entry) {
+                    prev.body = join_trimmed(&body);
+                    doc.entries.push(prev);
+                }
+                body.clear();
+                i += 2;
+                continue;
+            }
+            if current.is_some() {
+                body.push(lines[i]);
+            } else {
+                notes.push(lines[i]);
+            }
+            i += 1;
+        }
+        if let Some(mut last) = current {
+            last.body = join_trimmed(&body);
+            doc.entries.push(last);
+        }
+        doc.notes = join_trimmed(&notes);
+        doc
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        if !self.notes.is_empty() {
+            out.push_str(&self.notes);
+            out.push('\n');
+        }
+        for entry in &self.entries {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!(
+                "## {}\n{META_PREFIX} id={}; tags={}; created={}; updated={} {META_SUFFIX}\n",
+                entry.title,
+                entry.id,
+                entry.tags.join(","),
+                entry.created.to_rfc3339_opts(SecondsFormat::Secs, true),
+                entry.updated.to_rfc3339_opts(SecondsFormat::Secs, true),
+            ));
+            if !entry.body.is_empty() {
+                out.push('\n');
+                out.push_str(&entry.body);
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// The entry `key` names: an ID, or else a title (case-insensitive).
+    /// `Err(n)` when no entry or several entries match.
+    pub fn find(&self, key: &str) -> Result<usize, usize> {
+        let key = key.trim();
+        if let Some(i) = self.entries.iter().position(|e| e.id == key) {
+            return Ok(i);
+        }
+        let matches: Vec<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.title.eq_ignore_ascii_case(key))
+            .map(|(i, _)| i)
+            .collect();
+        match matches.as_slice() {
+            [i] => Ok(*i),
+            _ => Err(matches.len()),
+        }
+    }
+
+    /// Add `entry`. Fails if an entry with the same title exists.
+    pub fn add(&mut self, entry: MemoryEntry) -> Result<(), MemoryError> {
+        if let Some(existing) = self
+            .entries
+            .iter()
+            .find(|e| e.title.eq_ignore_ascii_case(&entry.title))
+        {
+            return Err(MemoryError::Duplicate {
+                title: existing.title.clone(),
+                id: existing.id.clone(),
+            });
+        }
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Change the entry `key` names.
+    pub fn update(
+        &mut self,
+        key: &str,
+        patch: MemoryPatch,
+        now: DateTime<Utc>,
+    ) -> Result<&MemoryEntry, MemoryError> {
+        let i = self.index(key)?;
+        self.entries[i].apply(patch, now);
+        Ok(&self.entries[i])
+    }
+
+    /// Remove the entry `key` names.
+    pub fn remove(&mut self, key: &str) -> Result<MemoryEntry, MemoryError> {
+        let i = self.index(key)?;
+        Ok(self.entries.remove(i))
+    }
+
+    fn index(&self, key: &str) -> Result<usize, MemoryError> {
+        self.find(key).map_err(|count| match count {
+            0 => MemoryError::NotFound {
+                key: key.to_string(),
+            },
+            count => MemoryError::Ambiguous {
+                key: key.to_string(),
+                count,
+            },
+        })
+    }
+
+    /// Entries matching any word of `query`, best first.
+    pub fn search(&self, query: &str) -> Vec<&MemoryEntry> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        let mut hits: Vec<(u32, &MemoryEntry)> = self
+            .entries
+            .iter()
+            .map(|e| (e.score(&terms), e))
+            .filter(|(score, _)| *score > 0)
+            .collect();
+        hits.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.updated.cmp(&a.1.updated)));
+        hits.into_iter().map(|(_, e)| e).collect()
+    }
+
+    /// Full contents for the system prompt or MemoryRead, without the
+    /// metadata comments.
+    pub fn to_prompt(&self) -> String {
+        let mut out = self.notes.clone();
+        for entry in &self.entries {
+            if !out.is_empty() {
+                out.push_str("\n\n");
+            }
+            out.push_str(&format!("### {}", entry.headline()));
+            if !entry.body.is_empty() {
+                out.push('\n');
+                out.push_str(&entry.body);
+            }
+        }
+        out
+    }
+
+    /// One line per entry, for when the full contents are over budget.
+    pub fn to_index(&self) -> String {
+        let mut lines = Vec::new();
+        if !self.notes.is_empty() {
+            lines.push(format!(
+                "- (untitled notes, {} lines)",
+                self.notes.lines().count()
+            ));
+        }
+        lines.extend(self.entries.iter().map(|e| format!("- {}", e.headline())));
+        lines.join("\n")
+    }
+}
+
+/// An entry from its heading and the metadata line after it, if that line
+/// is a memory comment.
+fn parse_meta(title: &str, meta: &str) -> Option<MemoryEntry> {
+    let meta = meta
+        .trim()
+        .strip_prefix(META_PREFIX)?
+        .strip_suffix(META_SUFFIX)?;
+    let mut entry = MemoryEntry {
+        id: String::new(),
+        title: title.trim().to_string(),
+        tags: Vec::new(),
+        created: DateTime::<Utc>::UNIX_EPOCH,
+        updated: DateTime::<Utc>::UNIX_EPOCH,
+        body: String::new(),
+    };
+    for field in meta.split(';') {
+        let Some((key, value)) = field.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "id" => entry.id = value.to_string(),
+            "tags" => entry.tags = clean_tags(value.split(',').map(String::from).collect()),
+            "created" => entry.created = parse_time(value).unwrap_or(entry.created),
+            "updated" => entry.updated = parse_time(value).unwrap_or(entry.updated),
+            _ => {}
+        }
+    }
+    (!entry.id.is_empty()).then_some(entry)
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Titles are single-line headings.
+fn clean_title(title: &str) -> String {
+    title.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Tags can't contain the metadata separators, and blanks and repeats are dropped.
+fn clean_tags(tags: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag: String = tag
+            .trim()
+            .chars()
+            .filter(|c| !matches!(c, ',' | ';' | '>'))
+            .collect();
+        if !tag.is_empty() && !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    out
+}
+
+fn join_trimmed(lines: &[&str]) -> String {
+    lines.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(&format!("2026-10-{day:02}T12:00:00Z"))
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn render_and_parse_round_trip() {
+        let mut doc = MemoryDoc {
+            notes: "Old free-form notes.\n\n## A plain heading".into(),
+            entries: vec![MemoryEntry::new(
+                "Use tabs  in Makefiles",
+                vec!["build".into(), " style ".into(), "build".into()],
+                "Recipes need tabs.\n\n## Not an entry",
+                at(1),
+            )],
+        };
+        doc.entries
+            .push(MemoryEntry::new("Empty", vec![], "", at(2)));
+        let parsed = MemoryDoc::parse(&doc.render());
+        assert_eq!(parsed, doc);
+        assert_eq!(parsed.entries[0].title, "Use tabs in Makefiles");
+        assert_eq!(parsed.entries[0].tags, vec!["build", "style"]);
+    }
+
+    #[test]
+    fn legacy_content_is_kept_as_notes() {
+        let doc = MemoryDoc::parse("- prefers tabs\n## Section\nmore\n");
+        assert!(doc.entries.is_empty());
+        assert_eq!(doc.notes, "- prefers tabs\n## Section\nmore");
+        assert_eq!(doc.to_prompt(), doc.notes);
+    }
+
+    #[test]
+    fn find_by_id_or_unique_title() {
+        let mut doc = MemoryDoc::default();
+        doc.entries
+            .push(MemoryEntry::new("Style", vec![], "a", at(1)));
+        doc.entries
+            .push(MemoryEntry::new("style", vec![], "b", at(1)));
+        doc.entries
+            .push(MemoryEntry::new("Build", vec![], "c", at(1)));
+        let id = doc.entries[1].id.clone();
+        assert_eq!(doc.find(&id), Ok(1));
+        assert_eq!(doc.find("BUILD"), Ok(2));
+        assert_eq!(doc.find("style"), Err(2));
+        assert_eq!(doc.find("nothing"), Err(0));
+    }
+
+    #[test]
+    fn search_ranks_titles_over_tags_over_bodies() {
+        let mut doc = MemoryDoc::default();
+        doc.entries
+            .push(MemoryEntry::new("Deploy", vec![], "uses cargo dist", at(1)));
+        doc.entries
+            .push(MemoryEntry::new("Lints", vec!["cargo".into()], "", at(2)));
+        doc.entries
+            .push(MemoryEntry::new("Cargo workspace", vec![], "", at(3)));
+        doc.entries
+            .push(MemoryEntry::new("Unrelated", vec![], "", at(4)));
+        let titles: Vec<&str> = doc
+            .search("CARGO")
+            .iter()
+            .map(|e| e.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Cargo workspace", "Lints", "Deploy"]);
+        assert!(doc.search("").is_empty());
+    }
+
+    #[test]
+    fn patch_updates_only_given_fields() {
+        let mut entry = MemoryEntry::new("Title", vec!["a".into()], "body", at(1));
+        entry.apply(
+            MemoryPatch {
+                body: Some("new body".into()),
+                ..MemoryPatch::default()
+            },
+            at(5),
+        );
+        assert_eq!(entry.title, "Title");
+        assert_eq!(entry.tags, vec!["a"]);
+        assert_eq!(entry.body, "new body");
+        assert_eq!((entry.created, entry.updated), (at(1), at(5)));
+    }
+
+    #[test]
+    fn index_lists_headlines() {
+        let doc = MemoryDoc {
+            notes: "one\ntwo".into(),
+            entries: vec![MemoryEntry::new("Style", vec!["fmt".into()], "x", at(3))],
+        };
+        let index = doc.to_index();
+        assert!(index.starts_with("- (untitled notes, 2 lines)\n"));
+        assert!(index.ends_with("; tags: fmt; updated: 2026-10-03)"));
+    }
+}
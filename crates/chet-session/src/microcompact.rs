@@ -0,0 +1,349 @@
+//! Micro-compaction — elide stale tool results in place.
+//!
+//! Most context bloat is old Read/Grep/Bash output the model has already
+//! acted on. Unlike [`compact`](crate::compact), this keeps every message:
+//! only the content of stale `tool_result` blocks is replaced with a one-line
+//! stub such as `[Read src/main.rs: 400 lines, elided]`. The blocks and their
+//! `tool_use_id`s stay, so every `tool_use` keeps its result.
+//!
+//! A result is stale when it is older than [`KEEP_RECENT_TURNS`] turns, or
+//! when the same file was read again later.
+
+use crate::context::{estimate_block_tokens, estimate_text_tokens};
+use chet_types::{ContentBlock, Message, Role, ToolResultContent};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Number of recent turns whose tool results are left alone.
+pub const KEEP_RECENT_TURNS: usize = 3;
+
+/// Results smaller than this are not worth a stub.
+const MIN_ELIDE_TOKENS: u64 = 100;
+
+/// Longest Bash command or search pattern quoted in a stub, in bytes.
+const MAX_SUBJECT_LEN: usize = 60;
+
+/// What micro-compaction elided — one pass, or a session's running total.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MicroCompaction {
+    pub runs: u32,
+    pub results_elided: usize,
+    /// Estimated tokens freed.
+    pub tokens_reclaimed: u64,
+}
+
+impl MicroCompaction {
+    pub fn is_empty(&self) -> bool {
+        self.runs == 0
+    }
+
+    /// Add a pass to a running total.
+    pub fn add(&mut self, pass: &MicroCompaction) {
+        self.runs += pass.runs;
+        self.results_elided += pass.results_elided;
+        self.tokens_reclaimed += pass.tokens_reclaimed;
+    }
+}
+
+/// The tool call a result answers.
+struct Call<'a> {
+    index: usize,
+    name: &'a str,
+    input: &'a serde_json::Value,
+}
+
+/// Replace stale tool results in `messages` with stubs. Returns `None` if
+/// nothing was worth eliding.
+pub fn micro_compact(messages: &mut [Message]) -> Option<MicroCompaction> {
+    let cutoff = recent_turns_start(messages);
+    let stubs = {
+        let calls = tool_calls(messages);
+        let mut stubs = Vec::new();
+        for (i, msg) in messages.iter().enumerate() {
+            for (j, block) in msg.content.iter().enumerate() {
+                let ContentBlock::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                } = block
+                else {
+                    continue;
+                };
+                if *is_error == Some(true) || is_stub(content) {
+                    continue;
+                }
+                let call = calls.get(tool_use_id.as_str());
+                let reread = call.is_some_and(|call| read_again(call, &calls));
+                if i >= cutoff && !reread {
+                    continue;
+                }
+                let stub = stub_for(call, content, reread);
+                let saved = estimate_block_tokens(block).saturating_sub(
+                    estimate_text_tokens(&stub) + 10, // tool_use_id overhead
+                );
+                if saved >= MIN_ELIDE_TOKENS {
+                    stubs.push((i, j, stub, saved));
+                }
+            }
+        }
+        stubs
+    };
+    if stubs.is_empty() {
+        return None;
+    }
+
+    let mut pass = MicroCompaction {
+        runs: 1,
+        ..MicroCompaction::default()
+    };
+    for (i, j, stub, saved) in stubs {
+        if let ContentBlock::ToolResult { content, .. } = &mut messages[i].content[j] {
+            *content = vec![ToolResultContent::Text { text: stub }];
+            pass.results_elided += 1;
+            pass.tokens_reclaimed += saved;
+        }
+    }
+    Some(pass)
+}
+
+/// Index of the first message in the last `KEEP_RECENT_TURNS` turns. A turn
+/// starts at a user message with text, not one carrying only tool results.
+fn recent_turns_start(messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, m)| {
+            m.role == Role::User
+                && m.content
+                    .iter()
+                    .any(|b| matches!(b, ContentBlock::Text { .. }))
+        })
+        .nth(KEEP_RECENT_TURNS - 1)
+        .map_or(0, |(i, _)| i)
+}
+
+fn tool_calls(messages: &[Message]) -> HashMap<&str, Call<'_>> {
+    let mut calls = HashMap::new();
+    for (index, msg) in messages.iter().enumerate() {
+        for block in &msg.content {
+            if let ContentBlock::ToolUse { id, name, input } = block {
+                calls.insert(id.as_str(), Call { index, name, input });
+            }
+        }
+    }
+    calls
+}
+
+/// Whether a later Read covers what `call` read: the same file, read whole or
+/// with the same range.
+fn read_again(call: &Call<'_>, calls: &HashMap<&str, Call<'_>>) -> bool {
+    if call.name != "Read" {
+        return false;
+    }
+    let Some(path) = call.input.get("file_path") else {
+        return false;
+    };
+    calls.values().any(|later| {
+        later.index > call.index
+            && later.name == "Read"
+            && later.input.get("file_path") == Some(path)
+            && (later.input == call.input
+                || (later.input.get("offset").is_none() && later.input.get("limit").is_none()))
+    })
+}
+
+/// Whether `content` is already a stub from an earlier pass.
+fn is_stub(content: &[ToolResultContent]) -> bool {
+    matches!(content, [ToolResultContent::Text { text }]
+        if text.starts_with('[') && (text.ends_with(", elided]") || text.ends_with("re-read later]")))
+}
+
+/// `[Read src/main.rs: 400 lines, elided]`
+fn stub_for(call: Option<&Call<'_>>, content: &[ToolResultContent], reread: bool) -> String {
+    let mut lines = 0;
+    let mut images = 0;
+    for c in content {
+        match c {
+            ToolResultContent::Text { text } => lines += text.lines().count(),
+            ToolResultContent::Image { .. } => images += 1,
+        }
+    }
+    let what = match call {
+        Some(call) => match subject(call) {
+            Some(subject) => format!("{} {subject}", call.name),
+            None => call.name.to_string(),
+        },
+        None => "Tool result".to_string(),
+    };
+    let mut size = format!("{lines} line{}", if lines == 1 { "" } else { "s" });
+    if images > 0 {
+        size.push_str(&format!(
+            ", {images} image{}",
+            if images == 1 { "" } else { "s" }
+        ));
+    }
+    if reread {
+        format!("[{what}: {size}, elided; re-read later]")
+    } else {
+        format!("[{what}: {size}, elided]")
+    }
+}
+
+/// The argument that identifies a call: its file, pattern or command.
+fn subject(call: &Call<'_>) -> Option<String> {
+    let key = match call.name {
+        "Read" | "Write" | "Edit" | "NotebookEdit" => "file_path",
+        "Grep" | "Glob" => "pattern",
+        "Bash" => "command",
+        _ => return None,
+    };
+    let value = call.input.get(key)?.as_str()?;
+    if key == "file_path" {
+        return Some(value.to_string());
+    }
+    let first_line = value.lines().next().unwrap_or_default();
+    if first_line.len() < value.len() || first_line.len() > MAX_SUBJECT_LEN {
+        let short = chet_types::truncate_str(first_line, MAX_SUBJECT_LEN);
+        Some(format!("`{short}...`"))
+    } else {
+        Some(format!("`{first_line}`"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn prompt(text: &str) -> Message {
+        Message {
+            role: Role::User,
+            content: vec![ContentBlock::Text {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn call(id: &str, name: &str, input: serde_json::Value) -> Message {
+        Message {
+            role: Role::Assistant,
+            content: vec![ContentBlock::ToolUse {
+                id: id.to_string(),
+                name: name.to_string(),
+                input,
+            }],
+        }
+    }
+
+    fn result(id: &str, lines: usize) -> Message {
+        let text = (0..lines)
+            .map(|i| format!("{i:>4}  some line of source code here"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Message {
+            role: Role::User,
+            content: vec![ContentBlock::ToolResult {
+                tool_use_id: id.to_string(),
+                content: vec![ToolResultContent::Text { text }],
+                is_error: None,
+            }],
+        }
+    }
+
+    fn result_text(msg: &Message) -> (&str, &str) {
+        match &msg.content[0] {
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                ..
+            } => match &content[0] {
+                ToolResultContent::Text { text } => (tool_use_id, text),
+                _ => panic!("expected text"),
+            },
+            _ => panic!("expected tool result"),
+        }
+    }
+
+    /// One turn: a prompt, a tool call and its result.
+    fn turn(n: usize, name: &str, input: serde_json::Value, lines: usize) -> Vec<Message> {
+        let id = format!("t{n}");
+        vec![
+            prompt(&format!("prompt {n}")),
+            call(&id, name, input),
+            result(&id, lines),
+        ]
+    }
+
+    #[test]
+    fn old_results_are_stubbed_and_pairs_kept() {
+        let mut messages = turn(0, "Read", json!({"file_path": "src/main.rs"}), 400);
+        messages.extend(turn(1, "Bash", json!({"command": "cargo test"}), 50));
+        for n in 2..2 + KEEP_RECENT_TURNS {
+            messages.extend(turn(n, "Grep", json!({"pattern": "fn main"}), 50));
+        }
+        let before = messages.len();
+
+        let pass = micro_compact(&mut messages).unwrap();
+        assert_eq!(pass.runs, 1);
+        assert_eq!(pass.results_elided, 2);
+        assert!(pass.tokens_reclaimed > 2_000);
+        assert_eq!(messages.len(), before);
+        assert_eq!(
+            result_text(&messages[2]),
+            ("t0", "[Read src/main.rs: 400 lines, elided]")
+        );
+        assert_eq!(
+            result_text(&messages[5]),
+            ("t1", "[Bash `cargo test`: 50 lines, elided]")
+        );
+        // Recent turns are untouched
+        assert!(result_text(&messages[8]).1.contains("some line"));
+
+        // A second pass finds nothing new
+        assert_eq!(micro_compact(&mut messages), None);
+    }
+
+    #[test]
+    fn reread_files_are_stubbed_even_when_recent() {
+        let mut messages = turn(0, "Read", json!({"file_path": "a.rs"}), 100);
+        messages.extend(turn(1, "Read", json!({"file_path": "a.rs"}), 100));
+        let pass = micro_compact(&mut messages).unwrap();
+        assert_eq!(pass.results_elided, 1);
+        assert_eq!(
+            result_text(&messages[2]).1,
+            "[Read a.rs: 100 lines, elided; re-read later]"
+        );
+        assert!(result_text(&messages[5]).1.contains("some line"));
+    }
+
+    #[test]
+    fn partial_rereads_and_errors_are_kept() {
+        let mut messages = turn(0, "Read", json!({"file_path": "a.rs"}), 100);
+        messages.extend(turn(
+            1,
+            "Read",
+            json!({"file_path": "a.rs", "offset": 50, "limit": 10}),
+            10,
+        ));
+        assert_eq!(micro_compact(&mut messages), None);
+
+        let mut messages = Vec::new();
+        for n in 0..=KEEP_RECENT_TURNS {
+            messages.extend(turn(n, "Bash", json!({"command": "make"}), 100));
+        }
+        if let ContentBlock::ToolResult { is_error, .. } = &mut messages[2].content[0] {
+            *is_error = Some(true);
+        }
+        assert_eq!(micro_compact(&mut messages), None);
+    }
+
+    #[test]
+    fn small_results_are_not_worth_a_stub() {
+        let mut messages = Vec::new();
+        for n in 0..=KEEP_RECENT_TURNS {
+            messages.extend(turn(n, "Glob", json!({"pattern": "*.rs"}), 2));
+        }
+        assert_eq!(micro_compact(&mut messages), None);
+    }
+}
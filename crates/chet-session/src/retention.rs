@@ -0,0 +1,115 @@
+//! Session retention — which saved sessions garbage collection removes.
+
+use crate::types::SessionSummary;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+/// Limits on how many sessions are kept, and for how long. `None` means no limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Retention {
+    /// Remove sessions not updated for this many days.
+    pub retention_days: Option<u32>,
+    /// Keep at most this many sessions, most recently updated first.
+    pub max_count: Option<usize>,
+}
+
+impl Retention {
+    pub fn is_unlimited(&self) -> bool {
+        self.retention_days.is_none() && self.max_count.is_none()
+    }
+}
+
+/// The sessions `retention` expires as of `now`. Sessions in `keep` are never
+/// expired but count toward `max_count`.
+pub fn expired<'a>(
+    summaries: &'a [SessionSummary],
+    retention: &Retention,
+    now: DateTime<Utc>,
+    keep: &[Uuid],
+) -> Vec<&'a SessionSummary> {
+    let cutoff = retention
+        .retention_days
+        .map(|days| now - Duration::days(i64::from(days)));
+    let mut by_recency: Vec<&SessionSummary> = summaries.iter().collect();
+    by_recency.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+
+    let mut kept = by_recency.iter().filter(|s| keep.contains(&s.id)).count();
+    let mut out = Vec::new();
+    for s in by_recency {
+        if keep.contains(&s.id) {
+            continue;
+        }
+        let too_old = cutoff.is_some_and(|cutoff| s.updated_at < cutoff);
+        let too_many = retention.max_count.is_some_and(|max| kept >= max);
+        if too_old || too_many {
+            out.push(s);
+        } else {
+            kept += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Session;
+
+    fn summaries(ages_in_days: &[i64], now: DateTime<Utc>) -> Vec<SessionSummary> {
+        ages_in_days
+            .iter()
+            .map(|days| {
+                let mut s = Session::new("m".into(), "/tmp".into()).to_summary();
+                s.updated_at = now - Duration::days(*days);
+                s
+            })
+            .collect()
+    }
+
+    fn ids(sessions: Vec<&SessionSummary>) -> Vec<Uuid> {
+        sessions.into_iter().map(|s| s.id).collect()
+    }
+
+    #[test]
+    fn unlimited_keeps_everything() {
+        let now = Utc::now();
+        let all = summaries(&[1, 400], now);
+        assert!(Retention::default().is_unlimited());
+        assert!(expired(&all, &Retention::default(), now, &[]).is_empty());
+    }
+
+    #[test]
+    fn expires_by_age_and_count() {
+        let now = Utc::now();
+        let all = summaries(&[40, 1, 10, 5], now);
+        let by_age = Retention {
+            retention_days: Some(30),
+            max_count: None,
+        };
+        assert_eq!(ids(expired(&all, &by_age, now, &[])), vec![all[0].id]);
+
+        let by_count = Retention {
+            retention_days: None,
+            max_count: Some(2),
+        };
+        assert_eq!(
+            ids(expired(&all, &by_count, now, &[])),
+            vec![all[2].id, all[0].id]
+        );
+    }
+
+    #[test]
+    fn kept_sessions_survive_but_count() {
+        let now = Utc::now();
+        let all = summaries(&[1, 90, 2], now);
+        let retention = Retention {
+            retention_days: Some(30),
+            max_count: Some(1),
+        };
+        // The old session is kept and fills the quota, so both others go
+        assert_eq!(
+            ids(expired(&all, &retention, now, &[all[1].id])),
+            vec![all[0].id, all[2].id]
+        );
+    }
+}
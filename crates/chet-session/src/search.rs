@@ -0,0 +1,341 @@
+//! Full-text session search.
+//!
+//! `sessions/search.jsonl` is a small inverted index. Each save appends the
+//! words that its new messages, tool inputs and label introduced, as
+//! `{"id": ..., "terms": [...]}`; searching folds the lines into a
+//! term → sessions map. A session matches when every query word is a prefix of
+//! one of its words, and its log is then read to cut a snippet around the match.
+
+use crate::event::SessionEvent;
+use crate::types::{Session, SessionSummary};
+use chet_types::{ContentBlock, Message};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ops::Range;
+use uuid::Uuid;
+
+pub(crate) const SEARCH_INDEX_FILE: &str = "search.jsonl";
+
+const MIN_WORD: usize = 2;
+const MAX_WORD: usize = 40;
+const SNIPPET_BEFORE: usize = 40;
+const SNIPPET_LEN: usize = 120;
+
+/// A session matching a search, with the text around the first match.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub summary: SessionSummary,
+    pub snippet: String,
+    /// Byte ranges of the query words in `snippet`.
+    pub highlights: Vec<Range<usize>>,
+}
+
+/// One line of the search index: words a save added to a session.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Postings {
+    pub id: Uuid,
+    pub terms: Vec<String>,
+}
+
+/// Term → sessions containing it.
+#[derive(Debug, Default)]
+pub(crate) struct SearchIndex {
+    terms: BTreeMap<String, HashSet<Uuid>>,
+    /// Index lines read, to tell when the file is worth compacting.
+    pub lines: usize,
+}
+
+impl SearchIndex {
+    pub fn add(&mut self, postings: Postings) {
+        self.lines += 1;
+        for term in postings.terms {
+            self.terms.entry(term).or_default().insert(postings.id);
+        }
+    }
+
+    pub fn remove(&mut self, id: Uuid) {
+        for ids in self.terms.values_mut() {
+            ids.remove(&id);
+        }
+        self.terms.retain(|_, ids| !ids.is_empty());
+    }
+
+    /// Sessions containing a word starting with each of `words`.
+    pub fn matches(&self, words: &[String]) -> HashSet<Uuid> {
+        let mut result: Option<HashSet<Uuid>> = None;
+        for word in words {
+            let ids: HashSet<Uuid> = self
+                .terms
+                .range(word.clone()..)
+                .take_while(|(term, _)| term.starts_with(word.as_str()))
+                .flat_map(|(_, ids)| ids.iter().copied())
+                .collect();
+            result = Some(match result {
+                Some(prev) => prev.intersection(&ids).copied().collect(),
+                None => ids,
+            });
+        }
+        result.unwrap_or_default()
+    }
+
+    /// One line per session, for rewriting the index.
+    pub fn to_postings(&self) -> Vec<Postings> {
+        let mut by_id: BTreeMap<Uuid, Vec<String>> = BTreeMap::new();
+        for (term, ids) in &self.terms {
+            for id in ids {
+                by_id.entry(*id).or_default().push(term.clone());
+            }
+        }
+        by_id
+            .into_iter()
+            .map(|(id, terms)| Postings { id, terms })
+            .collect()
+    }
+
+    pub fn session_count(&self) -> usize {
+        self.terms.values().flatten().collect::<HashSet<_>>().len()
+    }
+}
+
+/// Lowercased words of 2–40 letters, digits or underscores.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| (MIN_WORD..=MAX_WORD).contains(&w.chars().count()))
+        .map(str::to_lowercase)
+}
+
+/// The searchable text of a message: text blocks and tool inputs.
+fn message_texts(message: &Message, out: &mut Vec<String>) {
+    for block in &message.content {
+        match block {
+            ContentBlock::Text { text } => out.push(text.clone()),
+            ContentBlock::ToolUse { name, input, .. } => {
+                let mut text = name.clone();
+                json_strings(input, &mut text);
+                out.push(text);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Append the string and number values of a tool input.
+fn json_strings(value: &serde_json::Value, out: &mut String) {
+    match value {
+        serde_json::Value::String(s) => {
+            out.push(' ');
+            out.push_str(s);
+        }
+        serde_json::Value::Number(n) => {
+            out.push(' ');
+            out.push_str(&n.to_string());
+        }
+        serde_json::Value::Array(items) => items.iter().for_each(|v| json_strings(v, out)),
+        serde_json::Value::Object(map) => map.values().for_each(|v| json_strings(v, out)),
+        _ => {}
+    }
+}
+
+/// Label first, then messages in order.
+fn session_texts(session: &Session) -> Vec<String> {
+    let mut texts: Vec<String> = session.metadata.label.iter().cloned().collect();
+    for message in &session.messages {
+        message_texts(message, &mut texts);
+    }
+    texts
+}
+
+fn terms_of(texts: &[String]) -> BTreeSet<String> {
+    texts.iter().flat_map(|t| tokenize(t)).collect()
+}
+
+pub(crate) fn session_terms(session: &Session) -> BTreeSet<String> {
+    terms_of(&session_texts(session))
+}
+
+pub(crate) fn event_terms(events: &[SessionEvent]) -> BTreeSet<String> {
+    let mut texts = Vec::new();
+    for event in events {
+        match event {
+            SessionEvent::Snapshot { session } => texts.extend(session_texts(session)),
+            SessionEvent::Message { message } => message_texts(message, &mut texts),
+            SessionEvent::Compaction { messages, .. } => {
+                messages.iter().for_each(|m| message_texts(m, &mut texts))
+            }
+            SessionEvent::Label { label } => texts.extend(label.iter().cloned()),
+            SessionEvent::Usage { .. } => {}
+        }
+    }
+    terms_of(&texts)
+}
+
+/// Snippet around the text matching the most query words (the earliest on
+/// ties), or `None` if no text matches.
+pub(crate) fn snippet(session: &Session, words: &[String]) -> Option<(String, Vec<Range<usize>>)> {
+    let best = session_texts(session)
+        .into_iter()
+        .map(|text| {
+            let found = words
+                .iter()
+                .filter(|w| find_word(&text, w).is_some())
+                .count();
+            (found, text)
+        })
+        .filter(|(found, _)| *found > 0)
+        .rev()
+        .max_by_key(|(found, _)| *found)?
+        .1;
+
+    let first = words.iter().filter_map(|w| find_word(&best, w)).min()?;
+    let start = floor_char_boundary(&best, first.saturating_sub(SNIPPET_BEFORE));
+    let end = floor_char_boundary(&best, (start + SNIPPET_LEN).min(best.len()));
+    let body: String = best[start..end]
+        .chars()
+        .map(|c| if c.is_ascii_whitespace() { ' ' } else { c })
+        .collect();
+    let snippet = format!(
+        "{}{}{}",
+        if start > 0 { "…" } else { "" },
+        body,
+        if end < best.len() { "…" } else { "" }
+    );
+    let highlights = highlights(&snippet, words);
+    Some((snippet, highlights))
+}
+
+/// Byte ranges in `text` where one of `words` starts a word.
+pub fn highlights(text: &str, words: &[String]) -> Vec<Range<usize>> {
+    let lower = text.to_ascii_lowercase();
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for word in words {
+        let mut from = 0;
+        while let Some(pos) = lower[from..].find(word.as_str()) {
+            let at = from + pos;
+            if is_word_start(&lower, at) {
+                ranges.push(at..at + word.len());
+            }
+            from = at + word.len();
+        }
+    }
+    ranges.sort_by_key(|r| r.start);
+    // Overlapping matches (e.g. "auth" and "author") merge into one
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Byte offset of the first word in `text` starting with `word`.
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    let lower = text.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find(word) {
+        let at = from + pos;
+        if is_word_start(&lower, at) {
+            return Some(at);
+        }
+        from = at + word.len();
+    }
+    None
+}
+
+fn is_word_start(text: &str, at: usize) -> bool {
+    text[..at]
+        .chars()
+        .next_back()
+        .is_none_or(|c| !(c.is_alphanumeric() || c == '_'))
+}
+
+fn floor_char_boundary(s: &str, mut i: usize) -> usize {
+    while !s.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chet_types::Role;
+
+    fn words(query: &str) -> Vec<String> {
+        tokenize(query).collect()
+    }
+
+    #[test]
+    fn tokenize_splits_and_lowercases() {
+        assert_eq!(
+            words("Fix the OAuth token_refresh bug in src/auth.rs!"),
+            vec![
+                "fix",
+                "the",
+                "oauth",
+                "token_refresh",
+                "bug",
+                "in",
+                "src",
+                "auth",
+                "rs"
+            ]
+        );
+        assert!(words("a I").is_empty());
+    }
+
+    #[test]
+    fn index_matches_word_prefixes_of_every_query_word() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut index = SearchIndex::default();
+        index.add(Postings {
+            id: a,
+            terms: vec!["authentication".into(), "bug".into()],
+        });
+        index.add(Postings {
+            id: b,
+            terms: vec!["auth".into(), "feature".into()],
+        });
+        assert_eq!(index.matches(&words("auth")).len(), 2);
+        assert_eq!(index.matches(&words("auth bug")), HashSet::from([a]));
+        assert!(index.matches(&words("thentication")).is_empty());
+        index.remove(a);
+        assert_eq!(index.matches(&words("auth")), HashSet::from([b]));
+        assert_eq!(index.to_postings().len(), 1);
+    }
+
+    #[test]
+    fn tool_inputs_and_labels_are_searchable() {
+        let mut session = Session::new("m".into(), "/tmp".into());
+        session.metadata.label = Some("Deploy pipeline".into());
+        session.messages.push(Message {
+            role: Role::Assistant,
+            content: vec![ContentBlock::ToolUse {
+                id: "t1".into(),
+                name: "Bash".into(),
+                input: serde_json::json!({"command": "cargo nextest run"}),
+            }],
+        });
+        let terms = session_terms(&session);
+        assert!(terms.contains("deploy"));
+        assert!(terms.contains("bash"));
+        assert!(terms.contains("nextest"));
+    }
+
+    #[test]
+    fn snippet_highlights_matches() {
+        let mut session = Session::new("m".into(), "/tmp".into());
+        let long = format!("{} the Token refresh fails\nafter an hour", "x ".repeat(60));
+        session.messages.push(Message {
+            role: Role::User,
+            content: vec![ContentBlock::Text { text: long }],
+        });
+        let (snippet, ranges) = snippet(&session, &words("token refresh")).unwrap();
+        assert!(snippet.starts_with('…'));
+        assert!(!snippet.contains('\n'));
+        let marked: Vec<&str> = ranges.iter().map(|r| &snippet[r.clone()]).collect();
+        assert_eq!(marked, vec!["Token", "refresh"]);
+        assert!(super::snippet(&session, &words("missing")).is_none());
+    }
+}
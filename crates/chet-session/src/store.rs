@@ -1,62 +1,279 @@
-//! Persistent session storage backed by JSON files.
+//! Persistent session storage backed by append-only event logs.
+//!
+//! Layout under the config directory:
+//!
+//! ```text
+//! sessions/
+//!   index.jsonl            one SessionSummary per save; the last line per ID wins
+//!   search.jsonl           words added per save, for search (see `search`)
+//!   <id>/events.jsonl      the session's event log (see `event`)
+//!   <id>/compact-<n>.md    compaction archives
+//!   <id>/tool-output/      tool results too large to keep inline
+//! ```
+//!
+//! Saving appends only what changed since the last save. Every
+//! `SNAPSHOT_INTERVAL` events — and whenever the history was edited in a way
+//! events can't express — the log is rewritten atomically as a single
+//! snapshot. Sessions stored by older versions as `sessions/<id>.json` are
+//! migrated when the store is opened.
 
 use crate::error::SessionError;
+use crate::event::{self, EventRecord, Replay, SessionEvent};
+use crate::retention::{self, Retention};
+use crate::search::{self, Postings, SEARCH_INDEX_FILE, SearchHit, SearchIndex};
 use crate::types::{Session, SessionSummary};
-use std::path::PathBuf;
+use chet_types::Usage;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::io::AsyncWriteExt;
 use uuid::Uuid;
 
-/// File-based session store. Each session is a JSON file in `sessions_dir`.
+const EVENTS_FILE: &str = "events.jsonl";
+const INDEX_FILE: &str = "index.jsonl";
+const TOOL_OUTPUT_DIR: &str = "tool-output";
+
+/// Events appended after a snapshot before the log is rewritten.
+const SNAPSHOT_INTERVAL: usize = 200;
+
+/// File-based session store. Each session is a directory in `sessions_dir`.
 pub struct SessionStore {
     sessions_dir: PathBuf,
+    /// What each log on disk holds, for sessions loaded or saved by this store.
+    persisted: Mutex<HashMap<Uuid, Persisted>>,
+}
+
+/// The state recorded in a session's log.
+struct Persisted {
+    message_count: usize,
+    /// The last message, serialized, to notice history edited in place.
+    last_message: Option<String>,
+    usage: Usage,
+    label: Option<String>,
+    model: String,
+    cwd: String,
+    compaction_count: u32,
+    /// Micro-compaction edits old messages in place, which no event records.
+    micro_compactions: u32,
+    events_since_snapshot: usize,
+}
+
+impl Persisted {
+    fn of(session: &Session, events_since_snapshot: usize) -> Self {
+        Self {
+            message_count: session.messages.len(),
+            last_message: session
+                .messages
+                .last()
+                .and_then(|m| serde_json::to_string(m).ok()),
+            usage: session.total_usage.clone(),
+            label: session.metadata.label.clone(),
+            model: session.metadata.model.clone(),
+            cwd: session.metadata.cwd.clone(),
+            compaction_count: session.compaction_count,
+            micro_compactions: session.micro_compaction.runs,
+            events_since_snapshot,
+        }
+    }
+
+    /// Events that bring the log up to `session`, or `None` when only a
+    /// snapshot can (messages removed or edited, usage reset, ...).
+    fn diff(&self, session: &Session) -> Option<Vec<SessionEvent>> {
+        if session.metadata.model != self.model
+            || session.metadata.cwd != self.cwd
+            || session.micro_compaction.runs != self.micro_compactions
+        {
+            return None;
+        }
+        let mut events = Vec::new();
+        if session.compaction_count > self.compaction_count {
+            events.push(SessionEvent::Compaction {
+                compaction_count: session.compaction_count,
+                messages: session.messages.clone(),
+            });
+        } else if session.compaction_count < self.compaction_count
+            || session.messages.len() < self.message_count
+        {
+            return None;
+        } else {
+            if self.message_count > 0 {
+                let last = serde_json::to_string(&session.messages[self.message_count - 1]).ok();
+                if last != self.last_message {
+                    return None;
+                }
+            }
+            events.extend(
+                session.messages[self.message_count..]
+                    .iter()
+                    .map(|message| SessionEvent::Message {
+                        message: message.clone(),
+                    }),
+            );
+        }
+        if session.metadata.label != self.label {
+            events.push(SessionEvent::Label {
+                label: session.metadata.label.clone(),
+            });
+        }
+        let delta = usage_delta(&self.usage, &session.total_usage)?;
+        if delta.input_tokens
+            + delta.output_tokens
+            + delta.cache_creation_input_tokens
+            + delta.cache_read_input_tokens
+            > 0
+        {
+            events.push(SessionEvent::Usage { delta });
+        }
+        Some(events)
+    }
+}
+
+/// `new - old`, or `None` if any counter went down.
+fn usage_delta(old: &Usage, new: &Usage) -> Option<Usage> {
+    Some(Usage {
+        input_tokens: new.input_tokens.checked_sub(old.input_tokens)?,
+        output_tokens: new.output_tokens.checked_sub(old.output_tokens)?,
+        cache_creation_input_tokens: new
+            .cache_creation_input_tokens
+            .checked_sub(old.cache_creation_input_tokens)?,
+        cache_read_input_tokens: new
+            .cache_read_input_tokens
+            .checked_sub(old.cache_read_input_tokens)?,
+    })
 }
 
 impl SessionStore {
-    /// Create a new store, ensuring the sessions directory exists.
+    /// Create a new store, ensuring the sessions directory exists and
+    /// migrating sessions saved in the old single-file format.
     pub async fn new(config_dir: PathBuf) -> Result<Self, SessionError> {
-        let sessions_dir = config_dir.join("sessions");
+        let sessions_dir = Self::dir_in(&config_dir);
         tokio::fs::create_dir_all(&sessions_dir).await?;
-        Ok(Self { sessions_dir })
+        let store = Self {
+            sessions_dir,
+            persisted: Mutex::new(HashMap::new()),
+        };
+        store.migrate_legacy().await?;
+        Ok(store)
+    }
+
+    /// The directory sessions are stored in under `config_dir`.
+    pub fn dir_in(config_dir: &Path) -> PathBuf {
+        config_dir.join("sessions")
     }
 
-    /// Save a session to disk (atomic write: .tmp → rename).
+    /// Save a session, appending what changed since it was last loaded or
+    /// saved by this store.
     pub async fn save(&self, session: &Session) -> Result<(), SessionError> {
-        let path = self.session_path(session.id);
+        let plan = {
+            let persisted = self.persisted.lock().unwrap();
+            persisted.get(&session.id).and_then(|p| {
+                p.diff(session)
+                    .map(|events| (events, p.events_since_snapshot))
+            })
+        };
+        let terms = match plan {
+            Some((events, _)) if events.is_empty() => return Ok(()),
+            Some((events, since)) => {
+                let terms = search::event_terms(&events);
+                if since + events.len() < SNAPSHOT_INTERVAL {
+                    let records: Vec<EventRecord> = events
+                        .into_iter()
+                        .map(|event| EventRecord {
+                            at: session.updated_at,
+                            event,
+                        })
+                        .collect();
+                    self.append(session.id, &records).await?;
+                    self.persisted
+                        .lock()
+                        .unwrap()
+                        .insert(session.id, Persisted::of(session, since + records.len()));
+                } else {
+                    self.snapshot(session).await?;
+                }
+                terms
+            }
+            None => {
+                self.snapshot(session).await?;
+                search::session_terms(session)
+            }
+        };
+        self.index_append(&session.to_summary()).await;
+        self.search_append(session.id, terms).await;
+        Ok(())
+    }
+
+    /// Rewrite a session's log as a single snapshot (atomic: .tmp → rename).
+    async fn snapshot(&self, session: &Session) -> Result<(), SessionError> {
+        let dir = self.session_dir(session.id);
+        tokio::fs::create_dir_all(&dir).await?;
+        let record = EventRecord {
+            at: session.updated_at,
+            event: SessionEvent::Snapshot {
+                session: Box::new(session.clone()),
+            },
+        };
+        let path = dir.join(EVENTS_FILE);
         let tmp_path = path.with_extension("tmp");
-        let json = serde_json::to_string_pretty(session)?;
-        tokio::fs::write(&tmp_path, json).await?;
+        tokio::fs::write(&tmp_path, event::to_jsonl(&[record])?).await?;
         tokio::fs::rename(&tmp_path, &path).await?;
+        self.persisted
+            .lock()
+            .unwrap()
+            .insert(session.id, Persisted::of(session, 0));
+        Ok(())
+    }
+
+    async fn append(&self, id: Uuid, records: &[EventRecord]) -> Result<(), SessionError> {
+        let jsonl = event::to_jsonl(records)?;
+        let mut file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(self.session_dir(id).join(EVENTS_FILE))
+            .await?;
+        // One write so a crash leaves at most one partial line at the end
+        file.write_all(jsonl.as_bytes()).await?;
+        file.flush().await?;
         Ok(())
     }
 
     /// Load a session by exact UUID.
     pub async fn load(&self, id: Uuid) -> Result<Session, SessionError> {
-        let path = self.session_path(id);
-        if !path.exists() {
-            return Err(SessionError::NotFound { id });
+        let replay = self.read_log(id).await?;
+        let session = replay.session.ok_or_else(|| SessionError::Corrupt {
+            id,
+            message: "no snapshot in the event log".to_string(),
+        })?;
+        let mut persisted = self.persisted.lock().unwrap();
+        if replay.damaged {
+            tracing::warn!("Session {id}: event log was damaged; recovered what was readable");
+            // The next save rewrites the log instead of appending to it
+            persisted.remove(&id);
+        } else {
+            persisted.insert(id, Persisted::of(&session, replay.events_since_snapshot));
         }
-        let data = tokio::fs::read_to_string(&path).await?;
-        let session: Session = serde_json::from_str(&data)?;
         Ok(session)
     }
 
+    async fn read_log(&self, id: Uuid) -> Result<Replay, SessionError> {
+        let path = self.session_dir(id).join(EVENTS_FILE);
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => Ok(event::replay(&content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(SessionError::NotFound { id })
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Load a session by ID prefix. Errors if ambiguous (multiple matches).
     pub async fn load_by_prefix(&self, prefix: &str) -> Result<Session, SessionError> {
         let prefix_lower = prefix.to_lowercase();
-        let mut matches = Vec::new();
-
-        let mut entries = tokio::fs::read_dir(&self.sessions_dir).await?;
-        while let Some(entry) = entries.next_entry().await? {
-            let name = entry.file_name();
-            let name_str = name.to_string_lossy();
-            if name_str.ends_with(".json") {
-                let stem = name_str.trim_end_matches(".json");
-                if stem.to_lowercase().starts_with(&prefix_lower) {
-                    if let Ok(id) = Uuid::parse_str(stem) {
-                        matches.push(id);
-                    }
-                }
-            }
-        }
+        let matches: Vec<Uuid> = self
+            .session_ids()
+            .await?
+            .into_iter()
+            .filter(|id| id.to_string().starts_with(&prefix_lower))
+            .collect();
 
         match matches.len() {
             0 => Err(SessionError::PrefixNotFound {
@@ -71,52 +288,112 @@ impl SessionStore {
     }
 
     /// List all sessions, sorted by updated_at descending (most recent first).
+    /// Reads the index; only rebuilds it from the logs when it is missing.
     pub async fn list(&self) -> Result<Vec<SessionSummary>, SessionError> {
-        let mut summaries = Vec::new();
+        let mut summaries = match self.read_index().await? {
+            Some(summaries) => summaries,
+            None => self.rebuild_index().await?,
+        };
+        summaries.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        Ok(summaries)
+    }
 
-        let mut entries = tokio::fs::read_dir(&self.sessions_dir).await?;
-        while let Some(entry) = entries.next_entry().await? {
-            let name = entry.file_name();
-            let name_str = name.to_string_lossy();
-            if name_str.ends_with(".json") {
-                let path = entry.path();
-                match tokio::fs::read_to_string(&path).await {
-                    Ok(data) => match serde_json::from_str::<Session>(&data) {
-                        Ok(session) => summaries.push(session.to_summary()),
-                        Err(e) => {
-                            tracing::warn!("Failed to parse session {}: {}", name_str, e);
-                        }
-                    },
-                    Err(e) => {
-                        tracing::warn!("Failed to read session {}: {}", name_str, e);
-                    }
+    /// Delete a session and its archives.
+    pub async fn delete(&self, id: Uuid) -> Result<(), SessionError> {
+        let dir = self.session_dir(id);
+        if !dir.exists() {
+            return Err(SessionError::NotFound { id });
+        }
+        tokio::fs::remove_dir_all(&dir).await?;
+        self.forget(&HashSet::from([id])).await
+    }
+
+    /// Delete the sessions `retention` expires, except those in `keep`, and
+    /// return them. With `dry_run`, only report what would be deleted.
+    pub async fn gc(
+        &self,
+        retention: &Retention,
+        keep: &[Uuid],
+        dry_run: bool,
+    ) -> Result<Vec<SessionSummary>, SessionError> {
+        let summaries = self.list().await?;
+        let expired: Vec<SessionSummary> =
+            retention::expired(&summaries, retention, chrono::Utc::now(), keep)
+                .into_iter()
+                .cloned()
+                .collect();
+        if dry_run || expired.is_empty() {
+            return Ok(expired);
+        }
+        let mut removed = HashSet::new();
+        for s in &expired {
+            match tokio::fs::remove_dir_all(self.session_dir(s.id)).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    tracing::warn!("Failed to delete session {}: {e}", s.id);
+                    continue;
                 }
             }
+            removed.insert(s.id);
         }
+        self.forget(&removed).await?;
+        Ok(expired
+            .into_iter()
+            .filter(|s| removed.contains(&s.id))
+            .collect())
+    }
 
-        summaries.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
-        Ok(summaries)
+    /// Every session with the bytes its directory takes up — event log,
+    /// archives and tool outputs — most recent first.
+    pub async fn disk_usage(&self) -> Result<Vec<(SessionSummary, u64)>, SessionError> {
+        let summaries = self.list().await?;
+        let dirs: Vec<PathBuf> = summaries.iter().map(|s| self.session_dir(s.id)).collect();
+        let sizes = tokio::task::spawn_blocking(move || {
+            dirs.iter().map(|d| dir_size(d)).collect::<Vec<_>>()
+        })
+        .await
+        .map_err(std::io::Error::other)?;
+        Ok(summaries.into_iter().zip(sizes).collect())
     }
 
-    /// Delete a session file.
-    pub async fn delete(&self, id: Uuid) -> Result<(), SessionError> {
-        let path = self.session_path(id);
-        if !path.exists() {
-            return Err(SessionError::NotFound { id });
+    /// Drop deleted sessions from the cache and both indexes.
+    async fn forget(&self, ids: &HashSet<Uuid>) -> Result<(), SessionError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.persisted
+            .lock()
+            .unwrap()
+            .retain(|id, _| !ids.contains(id));
+        if let Some(mut summaries) = self.read_index().await? {
+            summaries.retain(|s| !ids.contains(&s.id));
+            self.write_index(&summaries).await?;
+        }
+        if let Some(mut index) = self.read_search_index().await? {
+            for id in ids {
+                index.remove(*id);
+            }
+            self.write_search_index(&index).await?;
         }
-        tokio::fs::remove_file(&path).await?;
         Ok(())
     }
 
-    /// Write a compaction archive as a markdown file alongside the session.
+    /// Where a session's tool results too large to keep inline are written.
+    pub fn tool_output_dir(&self, id: Uuid) -> PathBuf {
+        self.session_dir(id).join(TOOL_OUTPUT_DIR)
+    }
+
+    /// Write a compaction archive as a markdown file in the session directory.
     pub async fn write_compaction_archive(
         &self,
         session_id: Uuid,
         compaction_number: u32,
         markdown: &str,
     ) -> Result<PathBuf, SessionError> {
-        let filename = format!("{}-compact-{}.md", session_id, compaction_number);
-        let path = self.sessions_dir.join(filename);
+        let dir = self.session_dir(session_id);
+        tokio::fs::create_dir_all(&dir).await?;
+        let path = dir.join(format!("compact-{compaction_number}.md"));
         // Atomic write for consistency (even though archives are write-once)
         let tmp = path.with_extension("tmp");
         tokio::fs::write(&tmp, markdown).await?;
@@ -124,11 +401,326 @@ impl SessionStore {
         Ok(path)
     }
 
-    fn session_path(&self, id: Uuid) -> PathBuf {
-        self.sessions_dir.join(format!("{id}.json"))
+    fn session_dir(&self, id: Uuid) -> PathBuf {
+        self.sessions_dir.join(id.to_string())
+    }
+
+    /// IDs of every stored session.
+    async fn session_ids(&self) -> Result<Vec<Uuid>, SessionError> {
+        let mut ids = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.sessions_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_dir() {
+                continue;
+            }
+            if let Ok(id) = Uuid::parse_str(&entry.file_name().to_string_lossy()) {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    // --- Index ---
+
+    fn index_path(&self) -> PathBuf {
+        self.sessions_dir.join(INDEX_FILE)
+    }
+
+    /// Current summaries from the index, or `None` if there is no index.
+    /// Superseded lines are dropped by rewriting the index once they
+    /// outnumber the live ones.
+    async fn read_index(&self) -> Result<Option<Vec<SessionSummary>>, SessionError> {
+        let content = match tokio::fs::read_to_string(self.index_path()).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut lines = 0;
+        let mut latest: HashMap<Uuid, SessionSummary> = HashMap::new();
+        for line in content.lines() {
+            lines += 1;
+            match serde_json::from_str::<SessionSummary>(line) {
+                Ok(summary) => {
+                    latest.insert(summary.id, summary);
+                }
+                Err(e) => tracing::warn!("Skipping unreadable session index line: {e}"),
+            }
+        }
+        let summaries: Vec<SessionSummary> = latest.into_values().collect();
+        if lines > 2 * summaries.len() + 32 {
+            if let Err(e) = self.write_index(&summaries).await {
+                tracing::warn!("Failed to compact session index: {e}");
+            }
+        }
+        Ok(Some(summaries))
+    }
+
+    /// Replace the index (atomic: .tmp → rename).
+    async fn write_index(&self, summaries: &[SessionSummary]) -> Result<(), SessionError> {
+        let mut jsonl = String::new();
+        for summary in summaries {
+            jsonl.push_str(&serde_json::to_string(summary)?);
+            jsonl.push('\n');
+        }
+        let path = self.index_path();
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, jsonl).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    /// Rebuild the index by replaying every session log.
+    async fn rebuild_index(&self) -> Result<Vec<SessionSummary>, SessionError> {
+        let mut summaries = Vec::new();
+        for id in self.session_ids().await? {
+            match self.read_log(id).await {
+                Ok(Replay {
+                    session: Some(session),
+                    ..
+                }) => summaries.push(session.to_summary()),
+                Ok(_) => tracing::warn!("Session {id} has no snapshot; not indexed"),
+                Err(e) => tracing::warn!("Failed to read session {id}: {e}"),
+            }
+        }
+        self.write_index(&summaries).await?;
+        Ok(summaries)
+    }
+
+    /// Record a session's latest summary. The index is a cache, so failures
+    /// are logged rather than failing the save.
+    async fn index_append(&self, summary: &SessionSummary) {
+        let result = async {
+            if !self.index_path().exists() {
+                // Starting a fresh index with one line would hide every other session
+                self.rebuild_index().await?;
+                return Ok(());
+            }
+            let mut line = serde_json::to_string(summary)?;
+            line.push('\n');
+            let mut file = tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(self.index_path())
+                .await?;
+            file.write_all(line.as_bytes()).await?;
+            Ok::<_, SessionError>(())
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::warn!("Failed to update session index: {e}");
+        }
+    }
+
+    // --- Search index ---
+
+    fn search_index_path(&self) -> PathBuf {
+        self.sessions_dir.join(SEARCH_INDEX_FILE)
+    }
+
+    /// Sessions whose messages, tool inputs or label contain a word starting
+    /// with every word of `query`, most recently updated first.
+    pub async fn search(&self, query: &str) -> Result<Vec<SearchHit>, SessionError> {
+        let mut words: Vec<String> = search::tokenize(query).collect();
+        words.sort();
+        words.dedup();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+        let index = match self.read_search_index().await? {
+            Some(index) => index,
+            None => self.rebuild_search_index().await?,
+        };
+        let matches = index.matches(&words);
+
+        let mut hits = Vec::new();
+        // Deleted sessions can linger in the search index; the summary index
+        // is authoritative
+        for summary in self.list().await? {
+            if !matches.contains(&summary.id) {
+                continue;
+            }
+            let session = match self.read_log(summary.id).await {
+                Ok(Replay {
+                    session: Some(session),
+                    ..
+                }) => session,
+                _ => continue,
+            };
+            // Words only in compacted-away history have no snippet
+            let (snippet, highlights) = search::snippet(&session, &words)
+                .unwrap_or_else(|| (summary.preview.clone(), Vec::new()));
+            hits.push(SearchHit {
+                summary,
+                snippet,
+                highlights,
+            });
+        }
+        Ok(hits)
+    }
+
+    /// The search index, or `None` if there is none. Rewritten one line per
+    /// session once repeated saves have made it much longer than that.
+    async fn read_search_index(&self) -> Result<Option<SearchIndex>, SessionError> {
+        let content = match tokio::fs::read_to_string(self.search_index_path()).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut index = SearchIndex::default();
+        for line in content.lines() {
+            match serde_json::from_str::<Postings>(line) {
+                Ok(postings) => index.add(postings),
+                Err(e) => tracing::warn!("Skipping unreadable search index line: {e}"),
+            }
+        }
+        if index.lines > 2 * index.session_count() + 64 {
+            if let Err(e) = self.write_search_index(&index).await {
+                tracing::warn!("Failed to compact search index: {e}");
+            }
+        }
+        Ok(Some(index))
+    }
+
+    /// Replace the search index (atomic: .tmp → rename).
+    async fn write_search_index(&self, index: &SearchIndex) -> Result<(), SessionError> {
+        let mut jsonl = String::new();
+        for postings in index.to_postings() {
+            jsonl.push_str(&serde_json::to_string(&postings)?);
+            jsonl.push('\n');
+        }
+        let path = self.search_index_path();
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, jsonl).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    /// Rebuild the search index by replaying every session log.
+    async fn rebuild_search_index(&self) -> Result<SearchIndex, SessionError> {
+        let mut index = SearchIndex::default();
+        for id in self.session_ids().await? {
+            if let Ok(Replay {
+                session: Some(session),
+                ..
+            }) = self.read_log(id).await
+            {
+                index.add(Postings {
+                    id,
+                    terms: search::session_terms(&session).into_iter().collect(),
+                });
+            }
+        }
+        self.write_search_index(&index).await?;
+        Ok(index)
+    }
+
+    /// Record the words a save added. Like the summary index, failures are
+    /// logged rather than failing the save.
+    async fn search_append(&self, id: Uuid, terms: BTreeSet<String>) {
+        if terms.is_empty() {
+            return;
+        }
+        let result = async {
+            if !self.search_index_path().exists() {
+                self.rebuild_search_index().await?;
+                return Ok(());
+            }
+            let postings = Postings {
+                id,
+                terms: terms.into_iter().collect(),
+            };
+            let mut line = serde_json::to_string(&postings)?;
+            line.push('\n');
+            let mut file = tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(self.search_index_path())
+                .await?;
+            file.write_all(line.as_bytes()).await?;
+            Ok::<_, SessionError>(())
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::warn!("Failed to update search index: {e}");
+        }
+    }
+
+    // --- Migration ---
+
+    /// Convert `sessions/<id>.json` files (and their `<id>-compact-<n>.md`
+    /// archives) from the single-file format. Files that fail to parse are
+    /// left in place.
+    async fn migrate_legacy(&self) -> Result<(), SessionError> {
+        let mut legacy = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.sessions_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json")
+                && path
+                    .file_stem()
+                    .is_some_and(|stem| Uuid::parse_str(&stem.to_string_lossy()).is_ok())
+            {
+                legacy.push(path);
+            }
+        }
+        if legacy.is_empty() {
+            return Ok(());
+        }
+
+        for path in &legacy {
+            let session = match tokio::fs::read_to_string(path)
+                .await
+                .map_err(SessionError::from)
+                .and_then(|data| Ok(serde_json::from_str::<Session>(&data)?))
+            {
+                Ok(session) => session,
+                Err(e) => {
+                    tracing::warn!("Not migrating session {}: {e}", path.display());
+                    continue;
+                }
+            };
+            self.snapshot(&session).await?;
+            self.migrate_archives(session.id).await?;
+            tokio::fs::remove_file(path).await?;
+            tracing::info!("Migrated session {} to the event log format", session.id);
+        }
+        // Both indexes are rebuilt from the migrated logs on next use
+        for path in [self.index_path(), self.search_index_path()] {
+            match tokio::fs::remove_file(path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn migrate_archives(&self, id: Uuid) -> Result<(), SessionError> {
+        let prefix = format!("{id}-compact-");
+        let mut entries = tokio::fs::read_dir(&self.sessions_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(rest) = name.strip_prefix(&prefix) {
+                let target = self.session_dir(id).join(format!("compact-{rest}"));
+                tokio::fs::rename(entry.path(), target).await?;
+            }
+        }
+        Ok(())
     }
 }
 
+/// Total size of the files under `dir`; unreadable entries count as empty.
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -236,4 +828,250 @@ mod tests {
         let content = tokio::fs::read_to_string(&path).await.unwrap();
         assert!(content.contains("# Archive"));
     }
+
+    fn text(role: Role, text: &str) -> Message {
+        Message {
+            role,
+            content: vec![ContentBlock::Text { text: text.into() }],
+        }
+    }
+
+    fn log_lines(store: &SessionStore, id: Uuid) -> Vec<String> {
+        std::fs::read_to_string(store.session_dir(id).join(EVENTS_FILE))
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn save_appends_only_changes() {
+        let (store, _tmp) = test_store().await;
+        let mut session = test_session();
+        store.save(&session).await.unwrap();
+        assert_eq!(log_lines(&store, session.id).len(), 1);
+
+        session.messages.push(text(Role::Assistant, "Hi"));
+        session.total_usage.output_tokens += 5;
+        session.metadata.label = Some("greeting".into());
+        store.save(&session).await.unwrap();
+        let lines = log_lines(&store, session.id);
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].contains("\"type\":\"message\""));
+
+        // Nothing changed: nothing written
+        store.save(&session).await.unwrap();
+        assert_eq!(log_lines(&store, session.id).len(), 4);
+
+        let loaded = SessionStore::new(_tmp.path().to_path_buf())
+            .await
+            .unwrap()
+            .load(session.id)
+            .await
+            .unwrap();
+        assert_eq!(loaded.messages.len(), 2);
+        assert_eq!(loaded.total_usage.output_tokens, 5);
+        assert_eq!(loaded.metadata.label.as_deref(), Some("greeting"));
+    }
+
+    #[tokio::test]
+    async fn edited_history_is_snapshotted() {
+        let (store, _tmp) = test_store().await;
+        let mut session = test_session();
+        session.messages.push(text(Role::Assistant, "Hi"));
+        store.save(&session).await.unwrap();
+        session.messages.push(text(Role::User, "More"));
+        store.save(&session).await.unwrap();
+        assert_eq!(log_lines(&store, session.id).len(), 2);
+
+        session.messages.truncate(1);
+        store.save(&session).await.unwrap();
+        assert_eq!(log_lines(&store, session.id).len(), 1);
+        assert_eq!(store.load(session.id).await.unwrap().messages.len(), 1);
+
+        session.compaction_count = 1;
+        session.messages = vec![text(Role::User, "Summary")];
+        store.save(&session).await.unwrap();
+        assert!(log_lines(&store, session.id)[1].contains("\"type\":\"compaction\""));
+        let loaded = store.load(session.id).await.unwrap();
+        assert_eq!(loaded.compaction_count, 1);
+        assert_eq!(loaded.messages.len(), 1);
+
+        // Edits in place are only visible through the micro-compaction count
+        session.messages.push(text(Role::Assistant, "Answer"));
+        store.save(&session).await.unwrap();
+        session.messages[0] = text(Role::User, "[elided]");
+        session.micro_compaction.runs = 1;
+        store.save(&session).await.unwrap();
+        assert_eq!(log_lines(&store, session.id).len(), 1);
+        let loaded = store.load(session.id).await.unwrap();
+        assert_eq!(loaded.micro_compaction.runs, 1);
+        assert!(matches!(
+            &loaded.messages[0].content[0],
+            ContentBlock::Text { text } if text == "[elided]"
+        ));
+    }
+
+    #[tokio::test]
+    async fn long_logs_are_compacted_to_a_snapshot() {
+        let (store, _tmp) = test_store().await;
+        let mut session = test_session();
+        store.save(&session).await.unwrap();
+        for i in 0..SNAPSHOT_INTERVAL {
+            session.messages.push(text(Role::User, &format!("msg {i}")));
+            store.save(&session).await.unwrap();
+        }
+        assert!(log_lines(&store, session.id).len() < SNAPSHOT_INTERVAL);
+        let loaded = store.load(session.id).await.unwrap();
+        assert_eq!(loaded.messages.len(), SNAPSHOT_INTERVAL + 1);
+    }
+
+    #[tokio::test]
+    async fn truncated_log_is_recovered() {
+        let (store, tmp) = test_store().await;
+        let mut session = test_session();
+        store.save(&session).await.unwrap();
+        session.messages.push(text(Role::Assistant, "Hi"));
+        store.save(&session).await.unwrap();
+
+        // Simulate a crash mid-append
+        let path = store.session_dir(session.id).join(EVENTS_FILE);
+        let mut content = std::fs::read_to_string(&path).unwrap();
+        content.push_str("{\"at\":\"2026-01-01T00:00:00Z\",\"type\":\"mess");
+        std::fs::write(&path, content).unwrap();
+
+        let store = SessionStore::new(tmp.path().to_path_buf()).await.unwrap();
+        let mut loaded = store.load(session.id).await.unwrap();
+        assert_eq!(loaded.messages.len(), 2);
+
+        // The next save rewrites the damaged log
+        loaded.messages.push(text(Role::User, "Again"));
+        store.save(&loaded).await.unwrap();
+        assert_eq!(log_lines(&store, session.id).len(), 1);
+        assert_eq!(store.load(session.id).await.unwrap().messages.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn legacy_json_sessions_are_migrated() {
+        let tmp = TempDir::new().unwrap();
+        let sessions = tmp.path().join("sessions");
+        std::fs::create_dir_all(&sessions).unwrap();
+        let session = test_session();
+        std::fs::write(
+            sessions.join(format!("{}.json", session.id)),
+            serde_json::to_string_pretty(&session).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            sessions.join(format!("{}-compact-1.md", session.id)),
+            "# Old",
+        )
+        .unwrap();
+
+        let store = SessionStore::new(tmp.path().to_path_buf()).await.unwrap();
+        assert!(!sessions.join(format!("{}.json", session.id)).exists());
+        assert!(store.session_dir(session.id).join("compact-1.md").exists());
+        assert_eq!(store.load(session.id).await.unwrap().messages.len(), 1);
+        assert_eq!(store.list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn list_uses_and_rebuilds_the_index() {
+        let (store, _tmp) = test_store().await;
+        let s1 = test_session();
+        let mut s2 = test_session();
+        store.save(&s1).await.unwrap();
+        store.save(&s2).await.unwrap();
+        s2.messages.push(text(Role::User, "Latest question"));
+        store.save(&s2).await.unwrap();
+
+        let summaries = store.list().await.unwrap();
+        assert_eq!(summaries.len(), 2);
+        let summary = summaries.iter().find(|s| s.id == s2.id).unwrap();
+        assert_eq!(summary.message_count, 2);
+        assert_eq!(summary.preview, "Latest question");
+
+        std::fs::remove_file(store.index_path()).unwrap();
+        assert_eq!(store.list().await.unwrap().len(), 2);
+        assert!(store.index_path().exists());
+
+        store.delete(s1.id).await.unwrap();
+        let summaries = store.list().await.unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].id, s2.id);
+    }
+
+    #[tokio::test]
+    async fn search_finds_new_messages_and_labels() {
+        let (store, _tmp) = test_store().await;
+        let mut s1 = test_session();
+        s1.metadata.label = Some("Refactor parser".into());
+        store.save(&s1).await.unwrap();
+        let mut s2 = test_session();
+        store.save(&s2).await.unwrap();
+        s2.messages
+            .push(text(Role::User, "Why does the tokenizer drop unicode?"));
+        store.save(&s2).await.unwrap();
+
+        let hits = store.search("PARSER").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].summary.id, s1.id);
+
+        let hits = store.search("token unicode").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].summary.id, s2.id);
+        let marked: Vec<&str> = hits[0]
+            .highlights
+            .iter()
+            .map(|r| &hits[0].snippet[r.clone()])
+            .collect();
+        assert_eq!(marked, vec!["token", "unicode"]);
+        assert_eq!(store.search("hello").await.unwrap().len(), 2);
+        assert!(store.search("").await.unwrap().is_empty());
+
+        // Rebuilt when missing; deleted sessions drop out
+        std::fs::remove_file(store.search_index_path()).unwrap();
+        store.delete(s1.id).await.unwrap();
+        assert!(store.search("parser").await.unwrap().is_empty());
+        assert_eq!(store.search("tokenizer").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn gc_removes_expired_sessions_with_their_files() {
+        let (store, _tmp) = test_store().await;
+        let mut old = test_session();
+        old.updated_at -= chrono::Duration::days(60);
+        let current = test_session();
+        let recent = test_session();
+        for s in [&old, &current, &recent] {
+            store.save(s).await.unwrap();
+        }
+        let outputs = store.tool_output_dir(old.id);
+        std::fs::create_dir_all(&outputs).unwrap();
+        std::fs::write(outputs.join("Bash-1.txt"), "x".repeat(1000)).unwrap();
+
+        let usage = store.disk_usage().await.unwrap();
+        let (_, old_size) = usage.iter().find(|(s, _)| s.id == old.id).unwrap();
+        assert!(*old_size > 1000);
+
+        let retention = Retention {
+            retention_days: Some(30),
+            max_count: Some(1),
+        };
+        let dry = store.gc(&retention, &[current.id], true).await.unwrap();
+        assert_eq!(dry.len(), 2);
+        assert_eq!(store.list().await.unwrap().len(), 3);
+
+        let removed = store.gc(&retention, &[current.id], false).await.unwrap();
+        let mut removed: Vec<Uuid> = removed.iter().map(|s| s.id).collect();
+        removed.sort();
+        let mut expected = vec![old.id, recent.id];
+        expected.sort();
+        assert_eq!(removed, expected);
+        assert!(!outputs.exists());
+        let left = store.list().await.unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].id, current.id);
+        assert_eq!(store.search("hello").await.unwrap().len(), 1);
+    }
 }
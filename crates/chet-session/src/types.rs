@@ -1,5 +1,6 @@
 //! Session data types.
 
+use crate::microcompact::MicroCompaction;
 use chet_types::{Message, Usage};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -15,6 +16,24 @@ pub struct Session {
     pub total_usage: Usage,
     pub metadata: SessionMetadata,
     pub compaction_count: u32,
+    /// Tool results elided in place so far (see `microcompact`).
+    #[serde(default, skip_serializing_if = "MicroCompaction::is_empty")]
+    pub micro_compaction: MicroCompaction,
+    /// Token usage of each turn, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub turns: Vec<TurnUsage>,
+}
+
+/// Token usage of one turn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnUsage {
+    pub at: DateTime<Utc>,
+    /// Number of messages in the conversation when the turn ended.
+    pub messages: usize,
+    /// `compaction_count` when the turn ended; after a compaction, earlier
+    /// turns' message positions no longer apply.
+    pub compaction: u32,
+    pub usage: Usage,
 }
 
 impl Session {
@@ -31,11 +50,33 @@ impl Session {
                 model,
                 cwd,
                 label: None,
+                parent_id: None,
+                forked_at: None,
             },
             compaction_count: 0,
+            micro_compaction: MicroCompaction::default(),
+            turns: Vec::new(),
         }
     }
 
+    /// Add a turn's usage to the totals and the per-turn record.
+    pub fn record_turn(&mut self, usage: &Usage, at: DateTime<Utc>) {
+        self.total_usage.add(usage);
+        self.turns.push(TurnUsage {
+            at,
+            messages: self.messages.len(),
+            compaction: self.compaction_count,
+            usage: usage.clone(),
+        });
+    }
+
+    /// Turns whose message positions refer to the current history.
+    pub fn current_turns(&self) -> impl Iterator<Item = &TurnUsage> {
+        self.turns
+            .iter()
+            .filter(|t| t.compaction == self.compaction_count && t.messages <= self.messages.len())
+    }
+
     /// Short hex prefix of the session ID for display.
     pub fn short_id(&self) -> String {
         self.id.to_string()[..8].to_string()
@@ -86,6 +127,55 @@ impl Session {
         }
     }
 
+    /// User prompts (text messages, not tool results) as message index and
+    /// text, oldest first.
+    pub fn user_prompts(&self) -> Vec<(usize, String)> {
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.role == chet_types::Role::User)
+            .filter_map(|(i, m)| {
+                let text: Vec<&str> = m
+                    .content
+                    .iter()
+                    .filter_map(|b| match b {
+                        chet_types::ContentBlock::Text { text } => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                (!text.is_empty()).then(|| (i, text.join("\n")))
+            })
+            .collect()
+    }
+
+    /// Drop the message at `index` and everything after it, returning the
+    /// dropped prompt's text. `index` must come from [`Session::user_prompts`]
+    /// so the remaining history ends on a complete turn.
+    pub fn rewind_to(&mut self, index: usize) -> Option<String> {
+        let prompt = self
+            .user_prompts()
+            .into_iter()
+            .find(|(i, _)| *i == index)?
+            .1;
+        self.messages.truncate(index);
+        self.turns
+            .retain(|t| t.compaction != self.compaction_count || t.messages <= index);
+        self.updated_at = Utc::now();
+        Some(prompt)
+    }
+
+    /// A copy of this session under a new ID, recording where it came from.
+    pub fn fork(&self) -> Session {
+        let now = Utc::now();
+        let mut fork = self.clone();
+        fork.id = Uuid::new_v4();
+        fork.created_at = now;
+        fork.updated_at = now;
+        fork.metadata.parent_id = Some(self.id);
+        fork.metadata.forked_at = Some(now);
+        fork
+    }
+
     /// Build a summary for listing.
     pub fn to_summary(&self) -> SessionSummary {
         SessionSummary {
@@ -99,6 +189,7 @@ impl Session {
             total_output_tokens: self.total_usage.output_tokens,
             label: self.metadata.label.clone(),
             preview: self.preview(),
+            parent_id: self.metadata.parent_id,
         }
     }
 }
@@ -109,10 +200,15 @@ pub struct SessionMetadata {
     pub model: String,
     pub cwd: String,
     pub label: Option<String>,
+    /// The session this one was forked from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_at: Option<DateTime<Utc>>,
 }
 
-/// Lightweight summary for session listing.
-#[derive(Debug, Clone)]
+/// Lightweight summary for session listing, kept in the session index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionSummary {
     pub id: Uuid,
     pub created_at: DateTime<Utc>,
@@ -124,6 +220,8 @@ pub struct SessionSummary {
     pub total_output_tokens: u64,
     pub label: Option<String>,
     pub preview: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<Uuid>,
 }
 
 impl SessionSummary {
@@ -227,4 +325,37 @@ mod tests {
         let preview = session.preview();
         assert!(preview.ends_with("..."));
     }
+
+    #[test]
+    fn rewind_drops_the_prompt_and_everything_after() {
+        let mut session = Session::new("test".into(), "/tmp".into());
+        session.messages.push(text_msg(Role::User, "first"));
+        session.messages.push(text_msg(Role::Assistant, "one"));
+        session.record_turn(&Usage::default(), Utc::now());
+        session.messages.push(text_msg(Role::User, "second"));
+        session.messages.push(text_msg(Role::Assistant, "two"));
+        session.record_turn(&Usage::default(), Utc::now());
+
+        let prompts = session.user_prompts();
+        assert_eq!(prompts.len(), 2);
+        assert_eq!(prompts[1], (2, "second".to_string()));
+        // Only prompt positions are accepted
+        assert!(session.rewind_to(1).is_none());
+
+        assert_eq!(session.rewind_to(2).as_deref(), Some("second"));
+        assert_eq!(session.messages.len(), 2);
+        assert_eq!(session.turns.len(), 1);
+    }
+
+    #[test]
+    fn fork_records_parent() {
+        let mut session = Session::new("test".into(), "/tmp".into());
+        session.messages.push(text_msg(Role::User, "hello"));
+        let fork = session.fork();
+        assert_ne!(fork.id, session.id);
+        assert_eq!(fork.metadata.parent_id, Some(session.id));
+        assert!(fork.metadata.forked_at.is_some());
+        assert_eq!(fork.messages.len(), 1);
+        assert_eq!(fork.to_summary().parent_id, Some(session.id));
+    }
 }
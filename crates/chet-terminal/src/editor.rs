@@ -19,13 +19,16 @@ pub enum ReadLineResult {
     Eof,
     /// User pressed Ctrl+C.
     Interrupted,
+    /// User pressed Shift+Tab — the line contents so far, to restore with
+    /// [`LineEditor::set_pending`] after switching modes.
+    CycleMode(String),
 }
 
 /// RAII guard that disables raw mode on drop.
-struct RawModeGuard;
+pub(crate) struct RawModeGuard;
 
 impl RawModeGuard {
-    fn enable() -> io::Result<Self> {
+    pub(crate) fn enable() -> io::Result<Self> {
         terminal::enable_raw_mode()?;
         Ok(Self)
     }
@@ -42,6 +45,8 @@ pub struct LineEditor {
     history: History,
     completer: Option<Box<dyn Completer>>,
     history_loaded: bool,
+    /// Text to pre-fill the next `read_line` with.
+    pending: String,
 }
 
 impl LineEditor {
@@ -51,6 +56,7 @@ impl LineEditor {
             history: History::new(history_path),
             completer: None,
             history_loaded: false,
+            pending: String::new(),
         }
     }
 
@@ -59,6 +65,11 @@ impl LineEditor {
         self.completer = Some(completer);
     }
 
+    /// Pre-fill the next `read_line` with `text`.
+    pub fn set_pending(&mut self, text: String) {
+        self.pending = text;
+    }
+
     /// Read a line of input from the user, displaying `prompt`.
     ///
     /// Enters raw mode, reads key events, handles editing/history/completion.
@@ -74,10 +85,11 @@ impl LineEditor {
         let mut history = std::mem::replace(&mut self.history, History::new(PathBuf::new()));
         let completer = self.completer.take();
         let prompt = prompt.to_string();
+        let initial = std::mem::take(&mut self.pending);
 
         let (result, returned_history, returned_completer) =
             tokio::task::spawn_blocking(move || {
-                let result = read_line_sync(&prompt, &mut history, completer.as_deref());
+                let result = read_line_sync(&prompt, &initial, &mut history, completer.as_deref());
                 (result, history, completer)
             })
             .await
@@ -99,6 +111,7 @@ impl LineEditor {
 /// Synchronous read loop — runs inside `spawn_blocking`.
 fn read_line_sync(
     prompt: &str,
+    initial: &str,
     history: &mut History,
     completer: Option<&dyn Completer>,
 ) -> io::Result<ReadLineResult> {
@@ -107,6 +120,10 @@ fn read_line_sync(
     let mut buffer = LineBuffer::new();
 
     renderer.draw_prompt()?;
+    if !initial.is_empty() {
+        buffer.set(initial);
+        renderer.refresh(&buffer.as_str(), buffer.cursor())?;
+    }
 
     loop {
         let ev = event::read()?;
@@ -236,6 +253,12 @@ fn read_line_sync(
                         }
                         // Non-empty buffer: ignore Ctrl+D
                     }
+                    EditorAction::CycleMode => {
+                        use crossterm::{execute, style::Print};
+                        execute!(io::stderr(), Print("\r\n"))?;
+                        history.reset_navigation();
+                        return Ok(ReadLineResult::CycleMode(buffer.as_str()));
+                    }
                     EditorAction::ClearScreen => {
                         renderer.clear_and_redraw(&buffer.as_str(), buffer.cursor())?;
                     }
@@ -43,6 +43,8 @@ pub enum EditorAction {
     DeleteWord,
     /// Clear screen (Ctrl+L).
     ClearScreen,
+    /// Switch to the next permission mode (Shift+Tab).
+    CycleMode,
     /// No action — ignore this key event.
     Noop,
 }
@@ -94,6 +96,7 @@ pub fn map_key(event: KeyEvent) -> EditorAction {
         KeyCode::Backspace => EditorAction::Backspace,
         KeyCode::Delete => EditorAction::Delete,
         KeyCode::Tab => EditorAction::Complete,
+        KeyCode::BackTab => EditorAction::CycleMode,
         KeyCode::Enter => EditorAction::Submit,
 
         // Everything else
@@ -182,6 +185,7 @@ mod tests {
         assert_eq!(map_key(key(KeyCode::Backspace)), EditorAction::Backspace);
         assert_eq!(map_key(key(KeyCode::Delete)), EditorAction::Delete);
         assert_eq!(map_key(key(KeyCode::Tab)), EditorAction::Complete);
+        assert_eq!(map_key(key(KeyCode::BackTab)), EditorAction::CycleMode);
         assert_eq!(map_key(key(KeyCode::Enter)), EditorAction::Submit);
         assert_eq!(map_key(key(KeyCode::Home)), EditorAction::Home);
         assert_eq!(map_key(key(KeyCode::End)), EditorAction::End);
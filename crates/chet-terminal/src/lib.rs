@@ -8,6 +8,7 @@ mod history;
 mod inline;
 mod keys;
 pub mod markdown;
+pub mod picker;
 mod render;
 pub mod spinner;
 pub mod statusline;
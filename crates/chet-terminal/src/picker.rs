@@ -0,0 +1,213 @@
+//! Fuzzy picker — choose one of a list of lines by typing part of it.
+
+use crate::editor::RawModeGuard;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::{
+    cursor, execute,
+    style::Print,
+    terminal::{self, ClearType},
+};
+use std::io::{self, Write};
+
+/// Most items shown at once.
+const MAX_VISIBLE: usize = 10;
+
+/// Let the user pick one of `items` on stderr. Typing filters the list
+/// fuzzily, ↑/↓ move the selection, Enter picks and Esc or Ctrl+C cancels.
+/// Returns the index into `items`, or `None` if cancelled.
+pub async fn pick(prompt: &str, items: Vec<String>) -> io::Result<Option<usize>> {
+    let prompt = prompt.to_string();
+    tokio::task::spawn_blocking(move || pick_sync(&prompt, &items))
+        .await
+        .map_err(io::Error::other)?
+}
+
+fn pick_sync(prompt: &str, items: &[String]) -> io::Result<Option<usize>> {
+    let _guard = RawModeGuard::enable()?;
+    let mut query = String::new();
+    let mut selected = 0;
+    let mut matches = filter(&query, items);
+    draw(prompt, &query, items, &matches, selected)?;
+
+    let result = loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => break matches.get(selected).copied(),
+            KeyCode::Esc => break None,
+            KeyCode::Char('c' | 'd') if ctrl => break None,
+            KeyCode::Up => selected = selected.saturating_sub(1),
+            KeyCode::Char('p') if ctrl => selected = selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Tab => {
+                selected = (selected + 1).min(matches.len().saturating_sub(1))
+            }
+            KeyCode::Char('n') if ctrl => {
+                selected = (selected + 1).min(matches.len().saturating_sub(1))
+            }
+            KeyCode::Char('u') if ctrl => {
+                query.clear();
+                matches = filter(&query, items);
+                selected = 0;
+            }
+            KeyCode::Backspace => {
+                query.pop();
+                matches = filter(&query, items);
+                selected = 0;
+            }
+            KeyCode::Char(c) if !ctrl => {
+                query.push(c);
+                matches = filter(&query, items);
+                selected = 0;
+            }
+            _ => continue,
+        }
+        draw(prompt, &query, items, &matches, selected)?;
+    };
+
+    let mut stderr = io::stderr();
+    execute!(
+        stderr,
+        cursor::MoveToColumn(0),
+        terminal::Clear(ClearType::FromCursorDown)
+    )?;
+    Ok(result)
+}
+
+/// Redraw the query line and the visible matches below it, leaving the
+/// cursor at the end of the query.
+fn draw(
+    prompt: &str,
+    query: &str,
+    items: &[String],
+    matches: &[usize],
+    selected: usize,
+) -> io::Result<()> {
+    let mut stderr = io::stderr();
+    let (width, height) = terminal::size().unwrap_or((80, 24));
+    let visible = MAX_VISIBLE.min((height as usize).saturating_sub(2)).max(1);
+    // Scroll so the selection stays in view
+    let first = selected.saturating_sub(visible - 1);
+
+    execute!(
+        stderr,
+        cursor::MoveToColumn(0),
+        terminal::Clear(ClearType::FromCursorDown),
+        Print(prompt),
+        Print(query)
+    )?;
+    let shown = &matches[first..matches.len().min(first + visible)];
+    for (i, &index) in shown.iter().enumerate() {
+        let line = fit(&items[index], (width as usize).saturating_sub(2));
+        let row = if first + i == selected {
+            format!("\r\n> {}", crate::style::bold(line))
+        } else {
+            format!("\r\n  {line}")
+        };
+        execute!(stderr, Print(row))?;
+    }
+    if matches.is_empty() {
+        execute!(
+            stderr,
+            Print("\r\n  "),
+            Print(crate::style::dim("(no matches)"))
+        )?;
+    }
+    let rows = shown.len().max(1) as u16;
+    execute!(
+        stderr,
+        cursor::MoveUp(rows),
+        cursor::MoveToColumn((prompt.chars().count() + query.chars().count()) as u16)
+    )?;
+    stderr.flush()
+}
+
+/// The first `width` characters of `line`.
+fn fit(line: &str, width: usize) -> &str {
+    match line.char_indices().nth(width) {
+        Some((end, _)) => &line[..end],
+        None => line,
+    }
+}
+
+/// Indices of the items matching `query`, best first. Ties keep the
+/// items' order, so an empty query lists everything as given.
+pub fn filter(query: &str, items: &[String]) -> Vec<usize> {
+    let mut scored: Vec<(i64, usize)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| fuzzy_score(query, item).map(|score| (score, i)))
+        .collect();
+    scored.sort_by_key(|&(score, i)| (std::cmp::Reverse(score), i));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Score `candidate` against `query`, or `None` unless every query character
+/// (case-insensitive, spaces ignored) appears in order. Runs of consecutive
+/// characters and matches at word starts score higher.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut chars = candidate.chars().enumerate();
+    let mut prev: Option<char> = None;
+    let mut last_match: Option<usize> = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let q = q.to_lowercase().next().unwrap_or(q);
+        loop {
+            let (pos, c) = chars.next()?;
+            let before = prev.replace(c);
+            if c.to_lowercase().next() != Some(q) {
+                continue;
+            }
+            score += 1;
+            if last_match.is_some_and(|last| last + 1 == pos) {
+                score += 5;
+            }
+            if before.is_none_or(|b| !b.is_alphanumeric()) {
+                score += 3;
+            }
+            last_match = Some(pos);
+            break;
+        }
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn every_query_char_must_appear_in_order() {
+        assert!(fuzzy_score("ath", "fix auth bug").is_some());
+        assert!(fuzzy_score("AUTH", "fix auth bug").is_some());
+        assert!(fuzzy_score("hta", "fix auth bug").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn consecutive_and_word_start_matches_rank_first() {
+        let list = items(&["a-u-t-h scattered", "oauth flow", "auth refactor"]);
+        assert_eq!(filter("auth", &list), vec![2, 1, 0]);
+        assert_eq!(filter("auth ref", &list), vec![2]);
+    }
+
+    #[test]
+    fn empty_query_keeps_order() {
+        let list = items(&["b", "a", "c"]);
+        assert_eq!(filter("", &list), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn fit_truncates_by_chars() {
+        assert_eq!(fit("héllo", 2), "hé");
+        assert_eq!(fit("hi", 10), "hi");
+    }
+}
@@ -17,7 +17,8 @@ pub struct StatusLineData {
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub effort: Option<Effort>,
-    pub plan_mode: bool,
+    /// Permission mode badge (e.g. `PLAN`); `None` in the default mode.
+    pub mode: Option<String>,
     pub active_tool: Option<String>,
 }
 
@@ -32,7 +33,7 @@ impl Default for StatusLineData {
             input_tokens: 0,
             output_tokens: 0,
             effort: None,
-            plan_mode: false,
+            mode: None,
             active_tool: None,
         }
     }
@@ -276,9 +277,9 @@ fn render_segments(data: &StatusLineData) -> String {
         segments.push(format!("session:{}", data.session_id));
     }
 
-    // Plan mode badge
-    if data.plan_mode {
-        segments.push("PLAN".to_string());
+    // Permission mode badge
+    if let Some(mode) = &data.mode {
+        segments.push(mode.clone());
     }
 
     format!(" {} ", segments.join(" | "))
@@ -606,7 +607,7 @@ mod tests {
             input_tokens: 12_300,
             output_tokens: 4_500,
             effort: Some(Effort::High),
-            plan_mode: false,
+            mode: None,
             active_tool: None,
         };
         let rendered = render_segments(&data);
@@ -619,10 +620,10 @@ mod tests {
     }
 
     #[test]
-    fn render_segments_plan_mode() {
+    fn render_segments_mode_badge() {
         let data = StatusLineData {
             model: "claude-opus-4-6".to_string(),
-            plan_mode: true,
+            mode: Some("PLAN".to_string()),
             ..StatusLineData::default()
         };
         let rendered = render_segments(&data);
@@ -700,7 +701,7 @@ mod tests {
                 input_tokens: 12_300,
                 output_tokens: 4_500,
                 effort: Some(Effort::High),
-                plan_mode: true,
+                mode: Some("ACCEPT EDITS".to_string()),
                 active_tool: None,
             },
             terminal_width: 30,
@@ -729,7 +730,7 @@ mod tests {
         assert_eq!(d.input_tokens, 0);
         assert_eq!(d.output_tokens, 0);
         assert!(d.effort.is_none());
-        assert!(!d.plan_mode);
+        assert!(d.mode.is_none());
         assert!(d.active_tool.is_none());
     }
 }
@@ -0,0 +1,174 @@
+//! Line diffs for permission previews.
+
+/// Lines of unchanged context around each change.
+const CONTEXT_LINES: usize = 3;
+
+/// Above this many (old × new) lines in the changed region, the diff is shown
+/// as a plain remove-all / add-all instead of aligning lines.
+const MAX_ALIGN_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Keep,
+    Remove,
+    Add,
+}
+
+/// A unified diff of `old` → `new` with `@@` hunk headers, or an empty string
+/// when they are the same.
+pub(crate) fn unified_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let ops = diff_ops(&old, &new);
+    if ops.iter().all(|(op, _)| *op == Op::Keep) {
+        return String::new();
+    }
+
+    // Line numbers of each op in the old and new files
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut o, mut n) = (0, 0);
+    for (op, _) in &ops {
+        positions.push((o, n));
+        match op {
+            Op::Keep => {
+                o += 1;
+                n += 1;
+            }
+            Op::Remove => o += 1,
+            Op::Add => n += 1,
+        }
+    }
+
+    let mut out = String::new();
+    let mut i = 0;
+    while i < ops.len() {
+        let Some(first) = (i..ops.len()).find(|&j| ops[j].0 != Op::Keep) else {
+            break;
+        };
+        // Extend the hunk while changes are close enough to share context
+        let mut last = first;
+        let mut j = first;
+        while j < ops.len() {
+            if ops[j].0 != Op::Keep {
+                last = j;
+            } else if j - last > 2 * CONTEXT_LINES {
+                break;
+            }
+            j += 1;
+        }
+        let start = first.saturating_sub(CONTEXT_LINES).max(i);
+        let end = (last + 1 + CONTEXT_LINES).min(ops.len());
+        let hunk = &ops[start..end];
+        let old_len = hunk.iter().filter(|(op, _)| *op != Op::Add).count();
+        let new_len = hunk.iter().filter(|(op, _)| *op != Op::Remove).count();
+        let (old_start, new_start) = positions[start];
+        out.push_str(&format!(
+            "@@ -{},{old_len} +{},{new_len} @@\n",
+            old_start + usize::from(old_len > 0),
+            new_start + usize::from(new_len > 0),
+        ));
+        for (op, line) in hunk {
+            let sign = match op {
+                Op::Keep => ' ',
+                Op::Remove => '-',
+                Op::Add => '+',
+            };
+            out.push(sign);
+            out.push_str(line);
+            out.push('\n');
+        }
+        i = end;
+    }
+    out
+}
+
+/// The edit script from `old` to `new`: a longest-common-subsequence
+/// alignment of the region between their common prefix and suffix.
+fn diff_ops<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<(Op, &str)> = old[..prefix].iter().map(|l| (Op::Keep, *l)).collect();
+    if old_mid.len() * new_mid.len() > MAX_ALIGN_CELLS {
+        ops.extend(old_mid.iter().map(|l| (Op::Remove, *l)));
+        ops.extend(new_mid.iter().map(|l| (Op::Add, *l)));
+    } else {
+        // lcs[i][j]: length of the LCS of old_mid[i..] and new_mid[j..]
+        let (n, m) = (old_mid.len(), new_mid.len());
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old_mid[i] == new_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                ops.push((Op::Keep, old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                ops.push((Op::Remove, old_mid[i]));
+                i += 1;
+            } else {
+                ops.push((Op::Add, new_mid[j]));
+                j += 1;
+            }
+        }
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|l| (Op::Keep, *l)));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_text_has_no_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n"), "");
+        assert_eq!(unified_diff("", ""), "");
+    }
+
+    #[test]
+    fn changes_are_shown_with_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n";
+        assert_eq!(
+            unified_diff(old, new),
+            "@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n"
+        );
+    }
+
+    #[test]
+    fn new_file_is_all_additions() {
+        assert_eq!(unified_diff("", "a\nb\n"), "@@ -0,0 +1,2 @@\n+a\n+b\n");
+    }
+
+    #[test]
+    fn distant_changes_get_separate_hunks() {
+        let old: String = (1..=20).map(|i| format!("{i}\n")).collect();
+        let new: String = (1..=20)
+            .map(|i| match i {
+                2 => "two\n".to_string(),
+                19 => "nineteen\n".to_string(),
+                i => format!("{i}\n"),
+            })
+            .collect();
+        let diff = unified_diff(&old, &new);
+        assert_eq!(diff.matches("@@ -").count(), 2);
+        assert!(diff.contains("-2\n+two\n"));
+        assert!(diff.contains("-19\n+nineteen\n"));
+    }
+}
@@ -1,6 +1,7 @@
 //! Tool trait and built-in tool implementations for Chet.
 
 mod bash;
+mod diff;
 mod edit;
 mod glob;
 mod grep;
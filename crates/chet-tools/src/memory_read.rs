@@ -1,13 +1,18 @@
 //! MemoryRead tool — reads persistent memory.
 
-use chet_session::MemoryManager;
+use chet_session::{MemoryEntry, MemoryManager, MemoryScope};
 use chet_types::{Tool, ToolContext, ToolDefinition, ToolError, ToolOutput};
+use serde::Deserialize;
 use std::path::PathBuf;
 
-/// Tool for reading persistent memory (global + project).
+/// Most entries a search returns.
+const MAX_SEARCH_RESULTS: usize = 10;
+
+/// Tool for reading persistent memory (global, project and team).
 pub struct MemoryReadTool {
     memory_dir: PathBuf,
     project_id: Option<String>,
+    team_root: Option<PathBuf>,
 }
 
 impl MemoryReadTool {
@@ -15,10 +20,58 @@ impl MemoryReadTool {
         Self {
             memory_dir,
             project_id,
+            team_root: None,
+        }
+    }
+
+    /// Also read team memory: `.chet/memory.md` in the repo at `root`.
+    pub fn with_team_root(mut self, root: PathBuf) -> Self {
+        self.team_root = Some(root);
+        self
+    }
+
+    fn manager(&self) -> MemoryManager {
+        let mgr = MemoryManager::new(self.memory_dir.clone());
+        match &self.team_root {
+            Some(root) => mgr.with_team_root(root.clone()),
+            None => mgr,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct MemoryReadInput {
+    id: Option<String>,
+    query: Option<String>,
+}
+
+impl MemoryReadTool {
+    /// The scopes to read, with their parsed contents.
+    async fn docs(&self, mgr: &MemoryManager) -> Vec<(&'static str, chet_session::MemoryDoc)> {
+        let mut scopes = vec![MemoryScope::Global];
+        if let Some(id) = self.project_id.as_deref() {
+            scopes.push(MemoryScope::Project(id));
+        }
+        if let Some(root) = self.team_root.as_deref() {
+            scopes.push(MemoryScope::Team(root));
+        }
+        let mut docs = Vec::new();
+        for scope in scopes {
+            docs.push((scope.label(), mgr.load_doc(scope).await));
         }
+        docs
     }
 }
 
+fn format_entry(scope: &str, entry: &MemoryEntry) -> String {
+    let mut out = format!("[{scope}] {}", entry.headline());
+    if !entry.body.is_empty() {
+        out.push('\n');
+        out.push_str(&entry.body);
+    }
+    out
+}
+
 impl Tool for MemoryReadTool {
     fn name(&self) -> &str {
         "MemoryRead"
@@ -31,12 +84,23 @@ impl Tool for MemoryReadTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "MemoryRead".to_string(),
-            description: "Read persistent memory (global and project-specific). \
-                          Returns saved notes, preferences, and context that persist across sessions."
+            description: "Read persistent memory (global, project-specific and team). \
+                          Returns saved notes, preferences, and context that persist across sessions. \
+                          With no arguments, returns everything; pass `id` for one entry or \
+                          `query` to search entry titles, tags and text."
                 .to_string(),
             input_schema: serde_json::json!({
                 "type": "object",
-                "properties": {}
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Read one entry by ID (or exact title)"
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "Search entries; matches any word, best first"
+                    }
+                }
             }),
             cache_control: None,
         }
@@ -44,14 +108,53 @@ impl Tool for MemoryReadTool {
 
     fn execute(
         &self,
-        _input: serde_json::Value,
+        input: serde_json::Value,
         _ctx: ToolContext,
     ) -> std::pin::Pin<
         Box<dyn std::future::Future<Output = Result<ToolOutput, ToolError>> + Send + '_>,
     > {
         Box::pin(async move {
-            let mgr = MemoryManager::new(self.memory_dir.clone());
-            let combined = mgr.load_combined(self.project_id.as_deref()).await;
+            let input: MemoryReadInput = if input.is_null() {
+                MemoryReadInput::default()
+            } else {
+                serde_json::from_value(input).map_err(|e| ToolError::InvalidInput {
+                    tool: "MemoryRead".into(),
+                    message: e.to_string(),
+                })?
+            };
+            let mgr = self.manager();
+
+            if let Some(key) = input.id.as_deref() {
+                for (scope, doc) in self.docs(&mgr).await {
+                    if let Ok(i) = doc.find(key) {
+                        return Ok(ToolOutput::text(format_entry(scope, &doc.entries[i])));
+                    }
+                }
+                return Ok(ToolOutput::error(format!(
+                    "No memory entry with ID or title '{key}'."
+                )));
+            }
+
+            if let Some(query) = input.query.as_deref() {
+                let docs = self.docs(&mgr).await;
+                let hits: Vec<String> = docs
+                    .iter()
+                    .flat_map(|(scope, doc)| {
+                        doc.search(query)
+                            .into_iter()
+                            .map(move |entry| format_entry(scope, entry))
+                    })
+                    .take(MAX_SEARCH_RESULTS)
+                    .collect();
+                if hits.is_empty() {
+                    return Ok(ToolOutput::text(format!(
+                        "No memory entries match '{query}'."
+                    )));
+                }
+                return Ok(ToolOutput::text(hits.join("\n\n")));
+            }
+
+            let combined = mgr.load_all(self.project_id.as_deref()).await;
             if combined.is_empty() {
                 Ok(ToolOutput::text("No memory saved yet."))
             } else {
@@ -113,4 +216,58 @@ mod tests {
             _ => panic!("expected text output"),
         }
     }
+
+    #[tokio::test]
+    async fn read_one_entry_and_search() {
+        let dir = TempDir::new().unwrap();
+        let mgr = MemoryManager::new(dir.path().to_path_buf());
+        let style = mgr
+            .add_entry(
+                MemoryScope::Global,
+                "Style",
+                vec!["fmt".into()],
+                "Use rustfmt",
+            )
+            .await
+            .unwrap();
+        mgr.add_entry(
+            MemoryScope::Project("p1"),
+            "Deploy",
+            vec![],
+            "fmt before tagging",
+        )
+        .await
+        .unwrap();
+
+        let tool = MemoryReadTool::new(dir.path().to_path_buf(), Some("p1".into()));
+        let ctx = || ToolContext {
+            cwd: dir.path().to_path_buf(),
+            env: Default::default(),
+            sandboxed: false,
+        };
+        let text = |out: ToolOutput| match &out.content[0] {
+            chet_types::ToolOutputContent::Text { text } => text.clone(),
+            _ => panic!("expected text output"),
+        };
+
+        let out = tool
+            .execute(serde_json::json!({"id": style.id}), ctx())
+            .await
+            .unwrap();
+        assert!(text(out).starts_with("[global] Style (id: "));
+
+        let out = tool
+            .execute(serde_json::json!({"query": "fmt"}), ctx())
+            .await
+            .unwrap();
+        let found = text(out);
+        assert!(found.starts_with("[global] Style"));
+        assert!(found.contains("[project] Deploy"));
+
+        let out = tool
+            .execute(serde_json::json!({"id": "missing"}), ctx())
+            .await
+            .unwrap();
+        assert!(out.is_error);
+    }
 }
@@ -1,14 +1,17 @@
 //! MemoryWrite tool — writes to persistent memory.
 
-use chet_session::MemoryManager;
+use crate::diff::unified_diff;
+use chet_session::{MemoryDoc, MemoryEntry, MemoryError, MemoryManager, MemoryPatch, MemoryScope};
 use chet_types::{Tool, ToolContext, ToolDefinition, ToolError, ToolOutput};
+use chrono::Utc;
 use serde::Deserialize;
 use std::path::PathBuf;
 
-/// Tool for writing to persistent memory (global or project).
+/// Tool for writing to persistent memory (global, project or team).
 pub struct MemoryWriteTool {
     memory_dir: PathBuf,
     project_id: Option<String>,
+    team_root: Option<PathBuf>,
 }
 
 impl MemoryWriteTool {
@@ -16,14 +19,135 @@ impl MemoryWriteTool {
         Self {
             memory_dir,
             project_id,
+            team_root: None,
         }
     }
+
+    /// Allow the "team" scope: `.chet/memory.md` in the repo at `root`.
+    pub fn with_team_root(mut self, root: PathBuf) -> Self {
+        self.team_root = Some(root);
+        self
+    }
+
+    fn scope<'a>(&'a self, scope: &str) -> Result<MemoryScope<'a>, ToolError> {
+        match scope {
+            "global" => Ok(MemoryScope::Global),
+            "project" => {
+                let project_id = self
+                    .project_id
+                    .as_deref()
+                    .ok_or(ToolError::ExecutionFailed(
+                        "No project context available (not in a git repo or known directory)."
+                            .to_string(),
+                    ))?;
+                Ok(MemoryScope::Project(project_id))
+            }
+            "team" => {
+                let root = self.team_root.as_deref().ok_or(ToolError::ExecutionFailed(
+                    "No repository available for team memory.".to_string(),
+                ))?;
+                Ok(MemoryScope::Team(root))
+            }
+            other => Err(invalid(format!(
+                "Invalid scope '{other}'. Must be 'global', 'project' or 'team'."
+            ))),
+        }
+    }
+
+    /// Validate a call into the scope it writes and the change it makes.
+    fn parse(&self, input: serde_json::Value) -> Result<(MemoryScope<'_>, Edit), ToolError> {
+        let input: MemoryWriteInput =
+            serde_json::from_value(input).map_err(|e| invalid(e.to_string()))?;
+        let scope = self.scope(&input.scope)?;
+        let operation = match input.operation.as_deref() {
+            Some(op) => op,
+            None if input.title.is_some() => "add",
+            None => "replace",
+        };
+        let edit = match operation {
+            "add" => Edit::Add {
+                title: input
+                    .title
+                    .ok_or_else(|| invalid("'add' requires a title.".into()))?,
+                tags: input.tags.unwrap_or_default(),
+                body: input.content.unwrap_or_default(),
+            },
+            "update" => Edit::Update {
+                key: input
+                    .id
+                    .ok_or_else(|| invalid("'update' requires an id.".into()))?,
+                patch: MemoryPatch {
+                    title: input.title,
+                    tags: input.tags,
+                    body: input.content,
+                },
+            },
+            "delete" => Edit::Delete {
+                key: input
+                    .id
+                    .ok_or_else(|| invalid("'delete' requires an id.".into()))?,
+            },
+            "replace" => Edit::Replace {
+                content: input
+                    .content
+                    .ok_or_else(|| invalid("'replace' requires content.".into()))?,
+            },
+            other => {
+                return Err(invalid(format!(
+                    "Invalid operation '{other}'. Must be 'add', 'update', 'delete' or 'replace'."
+                )));
+            }
+        };
+        Ok((scope, edit))
+    }
 }
 
 #[derive(Deserialize)]
 struct MemoryWriteInput {
     scope: String,
-    content: String,
+    /// `add`, `update`, `delete` or `replace`. Defaults to `add` when a
+    /// title is given, else `replace`.
+    operation: Option<String>,
+    id: Option<String>,
+    title: Option<String>,
+    tags: Option<Vec<String>>,
+    content: Option<String>,
+}
+
+/// The change a MemoryWrite call makes.
+enum Edit {
+    Add {
+        title: String,
+        tags: Vec<String>,
+        body: String,
+    },
+    Update {
+        key: String,
+        patch: MemoryPatch,
+    },
+    Delete {
+        key: String,
+    },
+    Replace {
+        content: String,
+    },
+}
+
+/// `content` after `change`, or `None` if the change fails.
+fn changed(
+    content: &str,
+    change: impl FnOnce(&mut MemoryDoc) -> Result<(), MemoryError>,
+) -> Option<String> {
+    let mut doc = MemoryDoc::parse(content);
+    change(&mut doc).ok()?;
+    Some(doc.render())
+}
+
+fn invalid(message: String) -> ToolError {
+    ToolError::InvalidInput {
+        tool: "MemoryWrite".into(),
+        message,
+    }
 }
 
 impl Tool for MemoryWriteTool {
@@ -39,21 +163,41 @@ impl Tool for MemoryWriteTool {
         ToolDefinition {
             name: "MemoryWrite".to_string(),
             description: "Write to persistent memory. Saves notes, preferences, and context \
-                          that persist across sessions. Use MemoryRead first to see existing \
-                          content, then provide the complete updated content."
+                          that persist across sessions as titled entries. Use 'add' with a \
+                          title for a new entry, 'update' or 'delete' with an entry's id. \
+                          'replace' overwrites the whole file and should be rare. The 'team' \
+                          scope is committed to the repo and shared with teammates."
                 .to_string(),
             input_schema: serde_json::json!({
                 "type": "object",
-                "required": ["scope", "content"],
+                "required": ["scope"],
                 "properties": {
                     "scope": {
                         "type": "string",
-                        "enum": ["global", "project"],
-                        "description": "Which memory to write: 'global' for cross-project, 'project' for current project only"
+                        "enum": ["global", "project", "team"],
+                        "description": "Which memory to write: 'global' for cross-project, 'project' for current project only, 'team' for the repo's shared .chet/memory.md"
+                    },
+                    "operation": {
+                        "type": "string",
+                        "enum": ["add", "update", "delete", "replace"],
+                        "description": "Defaults to 'add' when a title is given, else 'replace'"
+                    },
+                    "id": {
+                        "type": "string",
+                        "description": "Entry ID (or exact title) for 'update' and 'delete'"
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "Entry title; required for 'add'"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Entry tags, for search"
                     },
                     "content": {
                         "type": "string",
-                        "description": "The complete memory content (replaces existing content for this scope)"
+                        "description": "Entry text, or for 'replace' the complete file content"
                     }
                 }
             }),
@@ -61,6 +205,34 @@ impl Tool for MemoryWriteTool {
         }
     }
 
+    fn preview<'a>(
+        &'a self,
+        input: &'a serde_json::Value,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Option<String>> + Send + 'a>> {
+        Box::pin(async move {
+            // Invalid calls fail in execute with a proper error
+            let (scope, edit) = self.parse(input.clone()).ok()?;
+            let mgr = MemoryManager::new(self.memory_dir.clone());
+            let before = mgr.load(scope).await;
+            let now = Utc::now();
+            let after = match edit {
+                Edit::Replace { content } => content,
+                Edit::Add { title, tags, body } => changed(&before, |doc| {
+                    doc.add(MemoryEntry::new(&title, tags, &body, now))
+                })?,
+                Edit::Update { key, patch } => {
+                    changed(&before, |doc| doc.update(&key, patch, now).map(drop))?
+                }
+                Edit::Delete { key } => changed(&before, |doc| doc.remove(&key).map(drop))?,
+            };
+            let diff = unified_diff(&before, &after);
+            if diff.is_empty() {
+                return None;
+            }
+            Some(format!("{}\n{diff}", mgr.path(scope).display()))
+        })
+    }
+
     fn execute(
         &self,
         input: serde_json::Value,
@@ -69,44 +241,40 @@ impl Tool for MemoryWriteTool {
         Box<dyn std::future::Future<Output = Result<ToolOutput, ToolError>> + Send + '_>,
     > {
         Box::pin(async move {
-            let input: MemoryWriteInput =
-                serde_json::from_value(input).map_err(|e| ToolError::InvalidInput {
-                    tool: "MemoryWrite".into(),
-                    message: e.to_string(),
-                })?;
-
+            let (scope, edit) = self.parse(input)?;
             let mgr = MemoryManager::new(self.memory_dir.clone());
-            let bytes = input.content.len();
+            let label = scope.label();
+            let failed = |e: MemoryError| ToolError::ExecutionFailed(e.to_string());
 
-            match input.scope.as_str() {
-                "global" => {
-                    mgr.write_global(&input.content)
+            let (verb, entry) = match edit {
+                Edit::Add { title, tags, body } => (
+                    "Added",
+                    mgr.add_entry(scope, &title, tags, &body)
                         .await
-                        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
-                    Ok(ToolOutput::text(format!(
-                        "Saved {bytes} bytes to global memory."
-                    )))
-                }
-                "project" => {
-                    let project_id =
-                        self.project_id
-                            .as_deref()
-                            .ok_or(ToolError::ExecutionFailed(
-                            "No project context available (not in a git repo or known directory)."
-                                .to_string(),
-                        ))?;
-                    mgr.write_project(project_id, &input.content)
+                        .map_err(failed)?,
+                ),
+                Edit::Update { key, patch } => (
+                    "Updated",
+                    mgr.update_entry(scope, &key, patch).await.map_err(failed)?,
+                ),
+                Edit::Delete { key } => (
+                    "Deleted",
+                    mgr.delete_entry(scope, &key).await.map_err(failed)?,
+                ),
+                Edit::Replace { content } => {
+                    mgr.write(scope, &content)
                         .await
                         .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
-                    Ok(ToolOutput::text(format!(
-                        "Saved {bytes} bytes to project memory."
-                    )))
+                    return Ok(ToolOutput::text(format!(
+                        "Saved {} bytes to {label} memory.",
+                        content.len()
+                    )));
                 }
-                other => Err(ToolError::InvalidInput {
-                    tool: "MemoryWrite".into(),
-                    message: format!("Invalid scope '{other}'. Must be 'global' or 'project'."),
-                }),
-            }
+            };
+            Ok(ToolOutput::text(format!(
+                "{verb} {label} memory entry '{}' (id: {}).",
+                entry.title, entry.id
+            )))
         })
     }
 }
@@ -200,4 +368,113 @@ mod tests {
             other => panic!("expected ExecutionFailed, got {other:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn add_update_and_delete_entries() {
+        let dir = TempDir::new().unwrap();
+        let tool = MemoryWriteTool::new(dir.path().to_path_buf(), Some("p1".into()));
+        let mgr = MemoryManager::new(dir.path().to_path_buf());
+
+        let result = tool
+            .execute(
+                serde_json::json!({
+                    "scope": "project",
+                    "title": "Build",
+                    "tags": ["cargo"],
+                    "content": "cargo build --release"
+                }),
+                make_ctx(&dir),
+            )
+            .await
+            .unwrap();
+        assert!(!result.is_error);
+        let doc = mgr.load_doc(MemoryScope::Project("p1")).await;
+        assert_eq!(doc.entries.len(), 1);
+        let id = doc.entries[0].id.clone();
+
+        tool.execute(
+            serde_json::json!({
+                "scope": "project",
+                "operation": "update",
+                "id": id,
+                "content": "make release"
+            }),
+            make_ctx(&dir),
+        )
+        .await
+        .unwrap();
+        let doc = mgr.load_doc(MemoryScope::Project("p1")).await;
+        assert_eq!(doc.entries[0].body, "make release");
+        assert_eq!(doc.entries[0].tags, vec!["cargo"]);
+
+        // A second add with the same title is refused
+        let result = tool
+            .execute(
+                serde_json::json!({"scope": "project", "operation": "add", "title": "build"}),
+                make_ctx(&dir),
+            )
+            .await;
+        assert!(matches!(result, Err(ToolError::ExecutionFailed(_))));
+
+        tool.execute(
+            serde_json::json!({"scope": "project", "operation": "delete", "id": "Build"}),
+            make_ctx(&dir),
+        )
+        .await
+        .unwrap();
+        assert!(
+            mgr.load_doc(MemoryScope::Project("p1"))
+                .await
+                .entries
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn operations_require_their_fields() {
+        let dir = TempDir::new().unwrap();
+        let tool = MemoryWriteTool::new(dir.path().to_path_buf(), None);
+        for input in [
+            serde_json::json!({"scope": "global", "operation": "add"}),
+            serde_json::json!({"scope": "global", "operation": "update"}),
+            serde_json::json!({"scope": "global"}),
+            serde_json::json!({"scope": "global", "operation": "merge"}),
+        ] {
+            let result = tool.execute(input, make_ctx(&dir)).await;
+            assert!(matches!(result, Err(ToolError::InvalidInput { .. })));
+        }
+    }
+
+    #[tokio::test]
+    async fn team_writes_go_to_the_repo_with_a_diff_preview() {
+        let dir = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        let input = serde_json::json!({
+            "scope": "team",
+            "title": "Release process",
+            "content": "Tag from main only"
+        });
+
+        let tool = MemoryWriteTool::new(dir.path().to_path_buf(), None);
+        let result = tool.execute(input.clone(), make_ctx(&dir)).await;
+        assert!(matches!(result, Err(ToolError::ExecutionFailed(_))));
+
+        let tool = tool.with_team_root(repo.path().to_path_buf());
+        let preview = tool.preview(&input).await.unwrap();
+        let path = repo.path().join(".chet/memory.md");
+        assert!(preview.starts_with(&format!("{}\n@@ -0,0 +1,", path.display())));
+        assert!(preview.contains("+## Release process\n"));
+        assert!(preview.contains("+Tag from main only\n"));
+        assert!(!path.exists(), "preview must not write");
+
+        tool.execute(input.clone(), make_ctx(&dir)).await.unwrap();
+        assert!(
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .contains("## Release process")
+        );
+
+        // Nothing to preview for a call that will fail
+        assert_eq!(tool.preview(&input).await, None);
+    }
 }
@@ -60,6 +60,11 @@ impl ToolRegistry {
         tool.execute(input, ctx).await
     }
 
+    /// A tool's preview of what a call would change (see [`Tool::preview`]).
+    pub async fn preview(&self, name: &str, input: &serde_json::Value) -> Option<String> {
+        self.tools.get(name)?.preview(input).await
+    }
+
     /// Check if a tool exists by name.
     pub fn has_tool(&self, name: &str) -> bool {
         self.tools.contains_key(name)
@@ -19,6 +19,16 @@ pub trait Provider: Send + Sync {
         request: &'a CreateMessageRequest,
     ) -> Pin<Box<dyn Future<Output = Result<EventStream, ApiError>> + Send + 'a>>;
 
+    /// Count the input tokens `request` would use (system prompt, tools and
+    /// messages) without running the model. `None` if the provider has no
+    /// counting endpoint; callers fall back to estimates.
+    fn count_tokens<'a>(
+        &'a self,
+        _request: &'a CreateMessageRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<u64>, ApiError>> + Send + 'a>> {
+        Box::pin(async { Ok(None) })
+    }
+
     /// Provider name for logging/display (e.g., "anthropic").
     fn name(&self) -> &str;
 }
@@ -73,6 +73,15 @@ pub trait Tool: Send + Sync {
         false
     }
 
+    /// What this call would change, e.g. a diff, shown when asking the user
+    /// for permission. `None` when there is nothing useful to show.
+    fn preview<'a>(
+        &'a self,
+        _input: &'a serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Option<String>> + Send + 'a>> {
+        Box::pin(async { None })
+    }
+
     /// Execute the tool with the given JSON input and context.
     ///
     /// The context is passed by value to avoid lifetime issues with dyn dispatch.